@@ -0,0 +1,79 @@
+use criterion::{BatchSize, Criterion, Throughput, criterion_group};
+use msi::{Column, Insert, Package, PackageType, Value};
+use std::io::Cursor;
+
+// ========================================================================= //
+
+const NUM_ROWS: usize = 100_000;
+
+// A single table can hold at most 65536 rows, so spread the rows out over
+// several tables.
+const NUM_TABLES: usize = 4;
+
+const ROWS_PER_QUERY: usize = 1000;
+
+fn create_package() -> Package<Cursor<Vec<u8>>> {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    for table_index in 0..NUM_TABLES {
+        let columns = vec![
+            Column::build("Key").primary_key().id_string(72),
+            Column::build("Group").string(32),
+            Column::build("Value").int32(),
+        ];
+        package.create_table(format!("Table{table_index}"), columns).unwrap();
+    }
+    package
+}
+
+/// Inserts `NUM_ROWS` rows, each with one unique string and one string
+/// shared with many other rows.
+fn insert_rows(package: &mut Package<Cursor<Vec<u8>>>, batch_size: usize) {
+    let rows_per_table = NUM_ROWS / NUM_TABLES;
+    for table_index in 0..NUM_TABLES {
+        let table_name = format!("Table{table_index}");
+        for start in (0..rows_per_table).step_by(batch_size) {
+            let end = (start + batch_size).min(rows_per_table);
+            let rows = (start..end)
+                .map(|row_index| {
+                    vec![
+                        Value::from(format!("Key{table_index}_{row_index}")),
+                        Value::from(format!("Group{}", row_index % 100)),
+                        Value::Int(row_index as i32),
+                    ]
+                })
+                .collect();
+            package.insert_rows(Insert::into(&table_name).rows(rows)).unwrap();
+        }
+    }
+}
+
+fn bench_insert(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("insert_100k_rows");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(NUM_ROWS as u64));
+    group.bench_function("one_query_per_table", |bencher| {
+        bencher.iter_batched(
+            create_package,
+            |mut package| insert_rows(&mut package, NUM_ROWS),
+            BatchSize::PerIteration,
+        )
+    });
+    group.bench_function("queries_of_1000_rows", |bencher| {
+        bencher.iter_batched(
+            create_package,
+            |mut package| insert_rows(&mut package, ROWS_PER_QUERY),
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_insert);
+
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+}
+
+// ========================================================================= //
@@ -0,0 +1,435 @@
+//! A derive macro for mapping Windows Installer database tables to Rust
+//! structs, for use with the [`msi`](https://docs.rs/msi) crate.
+//!
+//! Deriving `MsiTable` for a struct with named fields implements
+//! `msi::rows::TypedRow` and `msi::rows::MsiTable` for it (as well as
+//! `TryFrom<Row>` and `TryFrom<&Row>`), with one table column per field.  The
+//! struct can then be used with `Package::create_typed_table()`,
+//! `Package::typed_rows()`, `Package::insert_typed_rows()`, and
+//! `Package::update_typed_rows()`.  The macro is re-exported by the `msi`
+//! crate as `msi::rows::MsiTable` when its `derive` feature is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use msi::rows::MsiTable;
+//!
+//! #[derive(MsiTable)]
+//! #[msi(table = "MyTable")]
+//! struct MyRow {
+//!     #[msi(primary_key, id_string = 72)]
+//!     key: String,
+//!     #[msi(nullable)]
+//!     value: Option<i32>,
+//! }
+//!
+//! # use msi::{Package, PackageType};
+//! # use std::io::Cursor;
+//! let cursor = Cursor::new(Vec::new());
+//! let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+//! package.create_typed_table::<MyRow>().unwrap();
+//! package
+//!     .insert_typed_rows(vec![MyRow { key: "Foo".to_string(), value: None }])
+//!     .unwrap();
+//! let rows = package.typed_rows::<MyRow>().unwrap();
+//! assert_eq!(rows[0].key, "Foo");
+//! ```
+//!
+//! # Attributes
+//!
+//! The struct itself accepts `#[msi(table = "Name")]`, which sets the table
+//! name (by default, the struct's name is used).
+//!
+//! Each field accepts:
+//!
+//! * `name = "Name"`: the column name (by default, the field's name).
+//! * `primary_key`: the column is part of the table's primary key.
+//! * `nullable`: the column may hold null values.  The field must have an
+//!   `Option` type; fields with an `Option` type are always nullable.
+//! * `localizable`: the column holds localizable strings.
+//! * `int16`, `int32`, `string = N`, `id_string = N`, `text_string = N`, or
+//!   `formatted_string = N`: the column type, as with `msi::ColumnBuilder`.
+//!   Fields of type `i16` and `i32` default to `int16` and `int32`; string
+//!   fields have no default, since the maximum length must be chosen.
+//! * `category = "Name"`: the column's `msi::Category` (e.g. `"Guid"`).
+//! * `range(min = N, max = N)`: the range of valid integer values.
+//! * `foreign_key(table = "Name", column = N)`: the table and (1-based)
+//!   column index that the column's values refer to.
+//! * `description = "..."`: the column's `_Validation` description.
+//!
+//! Field types other than `String`, `i16`, `i32`, and `Option`s of them can be
+//! used by implementing `msi::rows::FieldValue` for them, and giving the
+//! column type explicitly.
+
+#![warn(missing_docs)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Error, Fields, GenericArgument, Ident, LitInt, LitStr,
+    PathArguments, Result, Type, parse_macro_input,
+};
+
+// ========================================================================= //
+
+/// Derives `msi::rows::TypedRow` and `msi::rows::MsiTable` for a struct.  See
+/// the crate documentation for the supported attributes.
+#[proc_macro_derive(MsiTable, attributes(msi))]
+pub fn derive_msi_table(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+// ========================================================================= //
+
+enum ColumnKind {
+    Int16,
+    Int32,
+    String(LitInt),
+    IdString(LitInt),
+    TextString(LitInt),
+    FormattedString(LitInt),
+}
+
+struct FieldSpec {
+    ident: Ident,
+    ty: Type,
+    name: String,
+    primary_key: bool,
+    nullable: bool,
+    localizable: bool,
+    kind: Option<ColumnKind>,
+    category: Option<Ident>,
+    range: Option<(LitInt, LitInt)>,
+    foreign_key: Option<(LitStr, LitInt)>,
+    description: Option<LitStr>,
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    input,
+                    "MsiTable can only be derived for structs with named \
+                     fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "MsiTable can only be derived for structs",
+            ));
+        }
+    };
+
+    let mut table_name = input.ident.to_string();
+    for attr in &input.attrs {
+        if !attr.path().is_ident("msi") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table_name = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("unsupported msi table attribute"))
+            }
+        })?;
+    }
+
+    let mut specs = Vec::new();
+    for field in fields {
+        specs.push(parse_field(field)?);
+    }
+    if !specs.iter().any(|spec| spec.primary_key) {
+        return Err(Error::new_spanned(
+            input,
+            "an MsiTable must have at least one #[msi(primary_key)] field",
+        ));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let field_idents: Vec<&Ident> = specs.iter().map(|s| &s.ident).collect();
+    let field_types: Vec<&Type> = specs.iter().map(|s| &s.ty).collect();
+    let column_names: Vec<&String> = specs.iter().map(|s| &s.name).collect();
+    let columns = specs.iter().map(column_tokens);
+
+    Ok(quote! {
+        impl #impl_generics ::msi::rows::TypedRow for #name #ty_generics
+            #where_clause
+        {
+            const TABLE_NAME: &'static str = #table_name;
+
+            fn from_row(row: &::msi::Row) -> ::std::io::Result<Self> {
+                ::std::result::Result::Ok(#name {
+                    #(
+                        #field_idents:
+                            <#field_types as ::msi::rows::FieldValue>
+                                ::from_field(row, #column_names)?,
+                    )*
+                })
+            }
+
+            fn to_values(
+                &self,
+            ) -> ::std::vec::Vec<(&'static str, ::msi::Value)> {
+                ::std::vec![
+                    #(
+                        (
+                            #column_names,
+                            ::msi::rows::FieldValue::to_field(
+                                &self.#field_idents,
+                            ),
+                        ),
+                    )*
+                ]
+            }
+        }
+
+        impl #impl_generics ::msi::rows::MsiTable for #name #ty_generics
+            #where_clause
+        {
+            fn columns() -> ::std::vec::Vec<::msi::Column> {
+                ::std::vec![#(#columns),*]
+            }
+        }
+
+        impl #impl_generics ::std::convert::TryFrom<&::msi::Row>
+            for #name #ty_generics #where_clause
+        {
+            type Error = ::std::io::Error;
+
+            fn try_from(row: &::msi::Row) -> ::std::io::Result<Self> {
+                <Self as ::msi::rows::TypedRow>::from_row(row)
+            }
+        }
+
+        impl #impl_generics ::std::convert::TryFrom<::msi::Row>
+            for #name #ty_generics #where_clause
+        {
+            type Error = ::std::io::Error;
+
+            fn try_from(row: ::msi::Row) -> ::std::io::Result<Self> {
+                <Self as ::msi::rows::TypedRow>::from_row(&row)
+            }
+        }
+    })
+}
+
+fn parse_field(field: &syn::Field) -> Result<FieldSpec> {
+    let ident = field.ident.clone().unwrap();
+    let mut spec = FieldSpec {
+        name: ident.to_string(),
+        ident,
+        ty: field.ty.clone(),
+        primary_key: false,
+        nullable: false,
+        localizable: false,
+        kind: None,
+        category: None,
+        range: None,
+        foreign_key: None,
+        description: None,
+    };
+    for attr in &field.attrs {
+        if !attr.path().is_ident("msi") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            let path = &meta.path;
+            let set_kind = |spec: &mut FieldSpec, kind: ColumnKind| {
+                if spec.kind.is_some() {
+                    return Err(meta.error("column type given more than once"));
+                }
+                spec.kind = Some(kind);
+                Ok(())
+            };
+            if path.is_ident("name") {
+                spec.name = meta.value()?.parse::<LitStr>()?.value();
+            } else if path.is_ident("primary_key") {
+                spec.primary_key = true;
+            } else if path.is_ident("nullable") {
+                spec.nullable = true;
+            } else if path.is_ident("localizable") {
+                spec.localizable = true;
+            } else if path.is_ident("int16") {
+                set_kind(&mut spec, ColumnKind::Int16)?;
+            } else if path.is_ident("int32") {
+                set_kind(&mut spec, ColumnKind::Int32)?;
+            } else if path.is_ident("string") {
+                set_kind(
+                    &mut spec,
+                    ColumnKind::String(meta.value()?.parse()?),
+                )?;
+            } else if path.is_ident("id_string") {
+                set_kind(
+                    &mut spec,
+                    ColumnKind::IdString(meta.value()?.parse()?),
+                )?;
+            } else if path.is_ident("text_string") {
+                set_kind(
+                    &mut spec,
+                    ColumnKind::TextString(meta.value()?.parse()?),
+                )?;
+            } else if path.is_ident("formatted_string") {
+                set_kind(
+                    &mut spec,
+                    ColumnKind::FormattedString(meta.value()?.parse()?),
+                )?;
+            } else if path.is_ident("category") {
+                let category = meta.value()?.parse::<LitStr>()?;
+                spec.category = Some(category.parse()?);
+            } else if path.is_ident("description") {
+                spec.description = Some(meta.value()?.parse()?);
+            } else if path.is_ident("range") {
+                let mut min = None;
+                let mut max = None;
+                meta.parse_nested_meta(|inner| {
+                    if inner.path.is_ident("min") {
+                        min = Some(inner.value()?.parse()?);
+                    } else if inner.path.is_ident("max") {
+                        max = Some(inner.value()?.parse()?);
+                    } else {
+                        return Err(inner.error("expected `min` or `max`"));
+                    }
+                    Ok(())
+                })?;
+                match (min, max) {
+                    (Some(min), Some(max)) => spec.range = Some((min, max)),
+                    _ => {
+                        return Err(meta.error(
+                            "range requires both `min` and `max` values",
+                        ));
+                    }
+                }
+            } else if path.is_ident("foreign_key") {
+                let mut table = None;
+                let mut column = None;
+                meta.parse_nested_meta(|inner| {
+                    if inner.path.is_ident("table") {
+                        table = Some(inner.value()?.parse()?);
+                    } else if inner.path.is_ident("column") {
+                        column = Some(inner.value()?.parse()?);
+                    } else {
+                        return Err(
+                            inner.error("expected `table` or `column`")
+                        );
+                    }
+                    Ok(())
+                })?;
+                match (table, column) {
+                    (Some(table), Some(column)) => {
+                        spec.foreign_key = Some((table, column))
+                    }
+                    _ => {
+                        return Err(meta.error(
+                            "foreign_key requires both `table` and `column` \
+                             values",
+                        ));
+                    }
+                }
+            } else {
+                return Err(meta.error("unsupported msi column attribute"));
+            }
+            Ok(())
+        })?;
+    }
+
+    let inner_ty = option_inner_type(&spec.ty);
+    if spec.nullable && inner_ty.is_none() {
+        return Err(Error::new_spanned(
+            &spec.ty,
+            "nullable columns must have an Option type",
+        ));
+    }
+    spec.nullable |= inner_ty.is_some();
+    if spec.kind.is_none() {
+        spec.kind = match type_name(inner_ty.unwrap_or(&spec.ty)) {
+            Some(name) if name == "i16" => Some(ColumnKind::Int16),
+            Some(name) if name == "i32" => Some(ColumnKind::Int32),
+            _ => {
+                return Err(Error::new_spanned(
+                    &spec.ty,
+                    "missing column type; add e.g. #[msi(string = 255)] or \
+                     #[msi(int32)]",
+                ));
+            }
+        };
+    }
+    Ok(spec)
+}
+
+fn column_tokens(spec: &FieldSpec) -> TokenStream2 {
+    let name = &spec.name;
+    let mut tokens = quote! { ::msi::Column::build(#name) };
+    if spec.primary_key {
+        tokens = quote! { #tokens.primary_key() };
+    }
+    if spec.nullable {
+        tokens = quote! { #tokens.nullable() };
+    }
+    if spec.localizable {
+        tokens = quote! { #tokens.localizable() };
+    }
+    if let Some((min, max)) = &spec.range {
+        tokens = quote! { #tokens.range(#min, #max) };
+    }
+    if let Some((table, column)) = &spec.foreign_key {
+        tokens = quote! { #tokens.foreign_key(#table, #column) };
+    }
+    if let Some(category) = &spec.category {
+        tokens = quote! { #tokens.category(::msi::Category::#category) };
+    }
+    if let Some(description) = &spec.description {
+        tokens = quote! { #tokens.description(#description) };
+    }
+    match spec.kind.as_ref().unwrap() {
+        ColumnKind::Int16 => quote! { #tokens.int16() },
+        ColumnKind::Int32 => quote! { #tokens.int32() },
+        ColumnKind::String(len) => quote! { #tokens.string(#len) },
+        ColumnKind::IdString(len) => quote! { #tokens.id_string(#len) },
+        ColumnKind::TextString(len) => quote! { #tokens.text_string(#len) },
+        ColumnKind::FormattedString(len) => {
+            quote! { #tokens.formatted_string(#len) }
+        }
+    }
+}
+
+/// If `ty` is `Option<T>`, returns `T`.
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else { return None };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(inner)) if args.args.len() == 1 => {
+            Some(inner)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the name of a simple type like `i32`, or `None` for any other
+/// kind of type.
+fn type_name(ty: &Type) -> Option<String> {
+    let Type::Path(type_path) = ty else { return None };
+    if type_path.qself.is_some() {
+        return None;
+    }
+    type_path.path.get_ident().map(Ident::to_string)
+}
+
+// ========================================================================= //
@@ -0,0 +1,162 @@
+use msi::rows::{MsiTable, TypedRow};
+use msi::{
+    Category, Column, ColumnValidation, Insert, Package, PackageType, Select,
+    Value,
+};
+use std::io::{Cursor, ErrorKind};
+
+// ========================================================================= //
+
+#[derive(Clone, Debug, MsiTable, PartialEq)]
+#[msi(table = "Settings")]
+struct SettingRow {
+    #[msi(primary_key, id_string = 72)]
+    key: String,
+    #[msi(nullable)]
+    value: Option<i32>,
+    #[msi(name = "Label", localizable, text_string = 64)]
+    label: Option<String>,
+    #[msi(range(min = 0, max = 10), description = "How important it is")]
+    priority: i16,
+    #[msi(category = "Guid", string = 38)]
+    guid: Option<String>,
+}
+
+#[derive(Debug, MsiTable, PartialEq)]
+struct Pair {
+    #[msi(primary_key)]
+    first: i16,
+    #[msi(primary_key)]
+    second: i32,
+    #[msi(
+        foreign_key(table = "Settings", column = 1),
+        category = "Identifier",
+        string = 72
+    )]
+    setting: String,
+}
+
+fn setting(key: &str, value: Option<i32>) -> SettingRow {
+    SettingRow {
+        key: key.to_string(),
+        value,
+        label: None,
+        priority: 5,
+        guid: None,
+    }
+}
+
+// ========================================================================= //
+
+#[test]
+fn derived_columns() {
+    assert_eq!(SettingRow::TABLE_NAME, "Settings");
+    let columns = SettingRow::columns();
+    let names: Vec<&str> =
+        columns.iter().map(|column| column.name()).collect();
+    assert_eq!(names, vec!["key", "value", "Label", "priority", "guid"]);
+    assert!(columns[0].is_primary_key());
+    assert!(!columns[0].is_nullable());
+    assert_eq!(columns[0].category(), Some(Category::Identifier));
+    assert!(columns[1].is_nullable());
+    assert!(columns[2].is_localizable());
+    assert_eq!(columns[2].category(), Some(Category::Text));
+    assert_eq!(columns[3].value_range(), Some((0, 10)));
+    assert_eq!(columns[3].description(), Some("How important it is"));
+    assert_eq!(columns[4].category(), Some(Category::Guid));
+
+    assert_eq!(Pair::TABLE_NAME, "Pair");
+    let columns = Pair::columns();
+    assert!(columns[0].is_primary_key());
+    assert!(columns[1].is_primary_key());
+    assert_eq!(
+        columns[2].validation(),
+        ColumnValidation::new()
+            .foreign_key(Some(("Settings", 1)))
+            .category(Some(Category::Identifier))
+    );
+}
+
+#[test]
+fn create_insert_select_update() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    package.create_typed_table::<SettingRow>().unwrap();
+    package
+        .insert_typed_rows(vec![
+            setting("Alpha", Some(1)),
+            setting("Beta", None),
+        ])
+        .unwrap();
+
+    let cursor = package.into_inner().unwrap();
+    let mut package = Package::open(cursor).unwrap();
+    let rows = package.typed_rows::<SettingRow>().unwrap();
+    assert_eq!(rows, vec![setting("Alpha", Some(1)), setting("Beta", None)]);
+
+    let mut beta = setting("Beta", Some(42));
+    beta.label = Some("The second letter".to_string());
+    package.update_typed_rows(vec![beta.clone()]).unwrap();
+    let row = package
+        .select_rows(Select::table("Settings"))
+        .unwrap()
+        .find(|row| row["key"] == Value::from("Beta"))
+        .unwrap();
+    assert_eq!(row["value"], Value::Int(42));
+    assert_eq!(SettingRow::try_from(row).unwrap(), beta);
+}
+
+#[test]
+fn failed_update_is_rolled_back() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    package.create_typed_table::<SettingRow>().unwrap();
+    package
+        .insert_typed_rows(vec![
+            setting("Alpha", Some(1)),
+            setting("Beta", None),
+        ])
+        .unwrap();
+    let mut beta = setting("Beta", Some(2));
+    beta.priority = 11;
+    let error = package
+        .update_typed_rows(vec![setting("Alpha", Some(2)), beta])
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    let rows = package.typed_rows::<SettingRow>().unwrap();
+    assert_eq!(rows, vec![setting("Alpha", Some(1)), setting("Beta", None)]);
+}
+
+#[test]
+fn wrong_value_type() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    package.create_typed_table::<SettingRow>().unwrap();
+    package.drop_table("Settings").unwrap();
+    let columns = vec![
+        Column::build("key").primary_key().id_string(72),
+        Column::build("value").nullable().string(16),
+        Column::build("priority").int16(),
+    ];
+    package.create_table("Settings", columns).unwrap();
+    package
+        .insert_rows(Insert::into("Settings").row(vec![
+            Value::from("Alpha"),
+            Value::from("one"),
+            Value::Int(1),
+        ]))
+        .unwrap();
+    let error = package.typed_rows::<SettingRow>().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert_eq!(
+        msi::Error::from_io(&error),
+        Some(&msi::Error::InvalidValue {
+            table: "Settings".to_string(),
+            column: "value".to_string(),
+            value: Value::from("one"),
+            row: None,
+        })
+    );
+}
+
+// ========================================================================= //
@@ -0,0 +1,41 @@
+use clap::Parser;
+use msi::builder::Manifest;
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    about = "Builds an MSI package from a TOML or JSON manifest"
+)]
+struct MsiBuild {
+    /// Path to the manifest (parsed as JSON if it ends in .json, and as TOML
+    /// otherwise)
+    manifest: PathBuf,
+    /// Path of the MSI file to create
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Directory that file sources are relative to (defaults to the
+    /// manifest's directory)
+    #[arg(long)]
+    source_dir: Option<PathBuf>,
+}
+
+fn main() -> io::Result<()> {
+    let cli = MsiBuild::parse();
+    let manifest = Manifest::from_file(&cli.manifest)?;
+    let source_dir = match cli.source_dir {
+        Some(dir) => dir,
+        None => cli
+            .manifest
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .to_path_buf(),
+    };
+    let package = manifest.build(&source_dir, Cursor::new(Vec::new()))?;
+    let data = package.into_inner()?.into_inner();
+    fs::write(&cli.output, &data)?;
+    println!("{} ({} bytes)", cli.output.display(), data.len());
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use clap::{Parser, Subcommand};
 use std::cmp;
-use std::io::{self, Read, Seek};
+use std::fs;
+use std::io::{self, Cursor, Read, Seek};
 use std::path::PathBuf;
 use time::OffsetDateTime;
 
@@ -11,6 +12,13 @@ fn pad(mut string: String, fill: char, width: usize) -> String {
     string
 }
 
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
 fn print_summary_info<F>(package: &msi::Package<F>) {
     println!("Package type: {:?}", package.package_type());
     let is_signed = package.has_digital_signature();
@@ -136,15 +144,46 @@ struct MsiInfo {
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Rewrites an MSI file without the unused space left behind by removed
+    /// tables and streams
+    Compact {
+        path: PathBuf,
+        /// Write the compacted file here instead of replacing the original
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Leave out the digital signature (if any)
+        #[arg(long)]
+        strip_signature: bool,
+    },
+
+    /// Decompiles an MSI file into WiX v4 source (Package.wxs) in a
+    /// directory, extracting binary data and embedded cabinets alongside it
+    Decompile { path: PathBuf, dir: PathBuf },
+
     /// Prints schema for a table in an MSI file
     Describe { path: PathBuf, table: String },
 
     /// Prints all rows for a table in an MSI file
-    Export { path: PathBuf, table: String },
+    Export {
+        path: PathBuf,
+        table: String,
+        /// The output format; csv and json (lines) can be read back in with
+        /// the library's import functions
+        #[arg(long, value_enum, default_value = "table")]
+        format: Format,
+    },
 
     /// Extract a binary stream from an MSI file
     Extract { path: PathBuf, stream: String },
 
+    /// Rebuilds an MSI file from a directory written by `unpack`
+    Pack {
+        dir: PathBuf,
+        /// The MSI file to create; a .msp or .mst extension makes a patch or
+        /// transform package
+        path: PathBuf,
+    },
+
     /// Lists binary streams in an MSI file
     Streams { path: PathBuf },
 
@@ -153,12 +192,53 @@ enum Commands {
 
     /// Lists database tables in an MSI file
     Tables { path: PathBuf },
+
+    /// Writes the tables, summary information, streams, and storages of an
+    /// MSI file into a directory, for reviewing changes with diff tools
+    Unpack { path: PathBuf, dir: PathBuf },
+
+    /// Runs validation rules over an MSI file, exiting with a nonzero status
+    /// if any errors are found
+    Validate {
+        path: PathBuf,
+        /// Only run these rules (e.g. ICE03); may be given multiple times
+        #[arg(long = "rule")]
+        rules: Vec<msi::Rule>,
+        /// Also fail if any warnings are found
+        #[arg(long)]
+        deny_warnings: bool,
+        /// Run the built-in rules named in this .cub file's _ICESequence
+        /// table (whose conditions hold) instead of the full rule set; the
+        /// .cub file's own custom-action rules are listed as skipped, not
+        /// run
+        #[arg(long, conflicts_with = "rules")]
+        cub: Option<PathBuf>,
+    },
 }
 
 fn main() -> io::Result<()> {
     let cli = MsiInfo::parse();
 
     match cli.command {
+        Commands::Compact { path, output, strip_signature } => {
+            let mut package = msi::open(&path)?;
+            let mut options = msi::SaveOptions::new();
+            if strip_signature {
+                options = options.strip_digital_signature();
+            }
+            let cursor =
+                options.save(&mut package, Cursor::new(Vec::new()))?;
+            drop(package);
+            let old_len = fs::metadata(&path)?.len();
+            let data = cursor.into_inner();
+            fs::write(output.as_ref().unwrap_or(&path), &data)?;
+            println!("{} bytes -> {} bytes", old_len, data.len());
+        }
+        Commands::Decompile { path, dir } => {
+            let mut package = msi::open(&path)?;
+            let wxs_path = package.decompile_wix(&dir)?;
+            println!("{}", wxs_path.display());
+        }
         Commands::Describe { path, table } => {
             let package = msi::open(&path)?;
 
@@ -168,15 +248,40 @@ fn main() -> io::Result<()> {
                 println!("No table {table:?} exists in the database.");
             }
         }
-        Commands::Export { path, table } => {
+        Commands::Export { path, table, format } => {
             let mut package = msi::open(&path)?;
-            print_table_contents(&mut package, &table);
+            match format {
+                Format::Table => print_table_contents(&mut package, &table),
+                Format::Csv => package
+                    .select_rows(msi::Select::table(&table))?
+                    .write_csv(io::stdout().lock())?,
+                Format::Json => package
+                    .select_rows(msi::Select::table(&table))?
+                    .write_json_lines(io::stdout().lock())?,
+            }
         }
         Commands::Extract { path, stream } => {
             let mut package = msi::open(&path)?;
             let mut input = package.read_stream(&stream)?;
             io::copy(&mut input, &mut io::stdout())?;
         }
+        Commands::Pack { dir, path } => {
+            let extension = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(str::to_ascii_lowercase);
+            let package_type = match extension.as_deref() {
+                Some("msp") => msi::PackageType::Patch,
+                Some("mst") => msi::PackageType::Transform,
+                _ => msi::PackageType::Installer,
+            };
+            let package = msi::Package::pack(
+                package_type,
+                &dir,
+                Cursor::new(Vec::new()),
+            )?;
+            fs::write(&path, package.into_inner()?.into_inner())?;
+        }
         Commands::Streams { path } => {
             let package = msi::open(&path)?;
 
@@ -195,6 +300,37 @@ fn main() -> io::Result<()> {
                 println!("{}", table.name());
             }
         }
+        Commands::Unpack { path, dir } => {
+            let mut package = msi::open(&path)?;
+            package.unpack(&dir)?;
+        }
+        Commands::Validate { path, rules, deny_warnings, cub } => {
+            let mut package = msi::open(&path)?;
+            let diagnostics = if let Some(cub_path) = cub {
+                let mut cub = msi::open(&cub_path)?;
+                let report = package.validate_rules_from_cub(&mut cub)?;
+                for skipped in report.skipped() {
+                    eprintln!("{skipped}");
+                }
+                report.diagnostics().to_vec()
+            } else if rules.is_empty() {
+                package.validate()?
+            } else {
+                package.validate_rules(&rules)?
+            };
+            let mut failed = false;
+            for diagnostic in &diagnostics {
+                println!("{diagnostic}");
+                failed |= match diagnostic.severity() {
+                    msi::Severity::Error => true,
+                    msi::Severity::Warning => deny_warnings,
+                    msi::Severity::Info => false,
+                };
+            }
+            if failed {
+                std::process::exit(1);
+            }
+        }
     }
 
     Ok(())
@@ -28,6 +28,16 @@ type Pairs<'a> = pest::iterators::Pairs<'a, Rule>;
 struct MsiQuery {
     path: PathBuf,
     query: String,
+    /// The output format for SELECT queries
+    #[arg(long, value_enum, default_value = "table")]
+    format: Format,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Format {
+    Table,
+    Csv,
+    Json,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -36,17 +46,17 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut package = msi::open_rw(&args.path)?;
 
     for pair in QueryParser::parse(Rule::QueryList, &args.query)? {
-        process_query(pair, &mut package);
+        process_query(pair, &mut package, args.format);
     }
 
     Ok(())
 }
 
-fn process_query(pair: Pair, package: &mut Package) {
+fn process_query(pair: Pair, package: &mut Package, format: Format) {
     match pair.as_rule() {
         Rule::QueryDelete => process_delete_query(pair, package),
         Rule::QueryInsert => process_insert_query(pair, package),
-        Rule::QuerySelect => process_select_query(pair, package),
+        Rule::QuerySelect => process_select_query(pair, package, format),
         Rule::QueryUpdate => process_update_query(pair, package),
         Rule::EOI => {}
         _ => unreachable!(),
@@ -63,9 +73,21 @@ fn process_insert_query(pair: Pair, package: &mut Package) {
     package.insert_rows(query).unwrap();
 }
 
-fn process_select_query(pair: Pair, package: &mut Package) {
+fn process_select_query(pair: Pair, package: &mut Package, format: Format) {
     let query = parse_select_query(pair);
-    println!("{query}");
+    match format {
+        Format::Table => println!("{query}"),
+        Format::Csv => {
+            let rows = package.select_rows(query).unwrap();
+            rows.write_csv(std::io::stdout().lock()).unwrap();
+            return;
+        }
+        Format::Json => {
+            let rows = package.select_rows(query).unwrap();
+            rows.write_json_lines(std::io::stdout().lock()).unwrap();
+            return;
+        }
+    }
     let rows = package.select_rows(query).unwrap();
     let columns = rows.columns().to_vec();
     let mut col_widths: Vec<usize> =
@@ -0,0 +1,15 @@
+//! Opens arbitrary data as a package, in both the default and lenient modes.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use msi::{OpenOptions, Package};
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Package::open(Cursor::new(data));
+    if let Ok(package) = OpenOptions::new().lenient().open(Cursor::new(data)) {
+        let _ = package.open_warnings();
+        let _ = package.summary_info().title();
+    }
+});
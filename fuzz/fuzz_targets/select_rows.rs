@@ -0,0 +1,29 @@
+//! Opens arbitrary data as a package, then reads every value from every
+//! table and checks the package's integrity.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use msi::{OpenOptions, Select};
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    let options = OpenOptions::new().lenient();
+    let mut package = match options.open(Cursor::new(data)) {
+        Ok(package) => package,
+        Err(_) => return,
+    };
+    let table_names: Vec<String> =
+        package.tables().map(|table| table.name().to_string()).collect();
+    for table_name in table_names {
+        if let Ok(rows) = package.select_rows(Select::table(&table_name)) {
+            for row in rows {
+                for index in 0..row.len() {
+                    let _ = row[index].to_string();
+                }
+            }
+        }
+    }
+    let _ = package.check_integrity();
+    let _ = package.validate();
+});
@@ -0,0 +1,27 @@
+//! Wraps arbitrary data as the summary information stream of an otherwise
+//! empty compound file, and opens it as a package; this exercises the
+//! property set parser without the fuzzer having to discover the compound
+//! file format first.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use msi::{OpenOptions, Package};
+use std::io::{Cursor, Write};
+
+fuzz_target!(|data: &[u8]| {
+    let cursor = Cursor::new(Vec::new());
+    let mut comp = cfb::CompoundFile::create(cursor).unwrap();
+    comp.create_stream("\u{5}SummaryInformation")
+        .unwrap()
+        .write_all(data)
+        .unwrap();
+    let cursor = comp.into_inner();
+    let _ = Package::open(cursor.clone());
+    if let Ok(package) = OpenOptions::new().lenient().open(cursor) {
+        let summary_info = package.summary_info();
+        let _ = summary_info.title();
+        let _ = summary_info.creation_time();
+        let _ = summary_info.languages();
+    }
+});
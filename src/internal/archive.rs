@@ -0,0 +1,177 @@
+use crate::internal::idt::{
+    self, FORCE_CODEPAGE_TABLE_NAME, SUMMARY_INFO_TABLE_NAME,
+};
+use crate::internal::package::Package;
+use crate::internal::streamname;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+// ========================================================================= //
+
+const COLUMNS_TABLE_NAME: &str = "_Columns";
+const TABLES_TABLE_NAME: &str = "_Tables";
+const VALIDATION_TABLE_NAME: &str = "_Validation";
+
+/// The subdirectory holding streams that aren't binary values of any table.
+const STREAMS_DIR_NAME: &str = "_Streams";
+/// The subdirectory holding embedded storages, as compound files.
+const STORAGES_DIR_NAME: &str = "_Storages";
+
+// ========================================================================= //
+
+pub(crate) fn unpack<F: Read + Seek>(
+    package: &mut Package<F>,
+    dir: &Path,
+) -> io::Result<()> {
+    let mut table_names = vec![
+        FORCE_CODEPAGE_TABLE_NAME.to_string(),
+        SUMMARY_INFO_TABLE_NAME.to_string(),
+    ];
+    table_names.extend(
+        package
+            .tables()
+            .map(|table| table.name())
+            .filter(|&name| {
+                name != COLUMNS_TABLE_NAME && name != TABLES_TABLE_NAME
+            })
+            .map(str::to_string),
+    );
+    let mut storage_names = package.storage_names();
+    storage_names.sort();
+    // Table, stream, and storage names (and the keys of rows with binary
+    // values) all become file names, so check that none of them can reach
+    // outside of `dir` before writing anything.
+    for name in table_names.iter().chain(&storage_names) {
+        idt::check_file_name(name)?;
+    }
+    for stream_name in package.streams() {
+        idt::check_file_name(&stream_name)?;
+    }
+    let mut idt_files = Vec::<(String, Vec<u8>, Vec<String>)>::new();
+    for table_name in table_names {
+        let mut data = Vec::new();
+        let stream_names = idt::export(package, &table_name, &mut data, None)?;
+        for stream_name in &stream_names {
+            idt::side_car_file_name(&table_name, stream_name)?;
+        }
+        idt_files.push((table_name, data, stream_names));
+    }
+
+    fs::create_dir_all(dir)?;
+    let mut binary_streams = HashSet::<String>::new();
+    for (table_name, data, stream_names) in idt_files {
+        fs::write(dir.join(format!("{table_name}.idt")), data)?;
+        idt::write_side_car_files(package, &table_name, &stream_names, dir)?;
+        binary_streams.extend(stream_names);
+    }
+
+    let mut stream_names: Vec<String> = package
+        .streams()
+        .filter(|name| !binary_streams.contains(name))
+        .collect();
+    stream_names.sort();
+    if !stream_names.is_empty() {
+        let streams_dir = dir.join(STREAMS_DIR_NAME);
+        fs::create_dir_all(&streams_dir)?;
+        for stream_name in stream_names {
+            let mut stream = package.read_stream(&stream_name)?;
+            let mut file = fs::File::create(streams_dir.join(&stream_name))?;
+            io::copy(&mut stream, &mut file)?;
+        }
+    }
+
+    if !storage_names.is_empty() {
+        let storages_dir = dir.join(STORAGES_DIR_NAME);
+        fs::create_dir_all(&storages_dir)?;
+        for storage_name in storage_names {
+            let data = package
+                .export_storage(&storage_name, io::Cursor::new(Vec::new()))?;
+            fs::write(storages_dir.join(&storage_name), data.into_inner())?;
+        }
+    }
+    Ok(())
+}
+
+// ========================================================================= //
+
+pub(crate) fn pack<F: Read + Write + Seek>(
+    package: &mut Package<F>,
+    dir: &Path,
+) -> io::Result<()> {
+    // The code page must be set before any strings are imported, and the
+    // _Validation table must come after the tables it describes, since
+    // creating a table adds _Validation rows for its columns.
+    let mut idt_paths: Vec<(u8, PathBuf)> = Vec::new();
+    for (name, path) in files_in(dir)? {
+        if let Some(table_name) = name.strip_suffix(".idt") {
+            let order = match table_name {
+                FORCE_CODEPAGE_TABLE_NAME => 0,
+                SUMMARY_INFO_TABLE_NAME => 1,
+                VALIDATION_TABLE_NAME => 3,
+                _ => 2,
+            };
+            idt_paths.push((order, path));
+        }
+    }
+    idt_paths.sort();
+    // Storages can't be imported during a transaction, so read them in (and
+    // make sure that they're valid compound files) up front, and only add
+    // them to the package once everything else has succeeded.
+    let mut storages = Vec::<(String, Vec<u8>)>::new();
+    let storages_dir = dir.join(STORAGES_DIR_NAME);
+    if storages_dir.is_dir() {
+        for (storage_name, path) in files_in(&storages_dir)? {
+            if !streamname::is_valid(&storage_name, false) {
+                invalid_input!(
+                    "{:?} is not a valid storage name",
+                    storage_name
+                );
+            }
+            let data = fs::read(&path)?;
+            cfb::CompoundFile::open(io::Cursor::new(data.as_slice()))?;
+            storages.push((storage_name, data));
+        }
+    }
+
+    package.transaction(|package| {
+        for (_, path) in idt_paths {
+            package.import_idt_file(&path)?;
+        }
+        let streams_dir = dir.join(STREAMS_DIR_NAME);
+        if streams_dir.is_dir() {
+            for (stream_name, path) in files_in(&streams_dir)? {
+                let data = fs::read(&path)?;
+                package.write_stream(&stream_name)?.write_all(&data)?;
+            }
+        }
+        Ok(())
+    })?;
+    for (storage_name, data) in storages {
+        package.import_storage(&storage_name, io::Cursor::new(data))?;
+    }
+    Ok(())
+}
+
+/// Returns the name and path of each regular file in the given directory,
+/// sorted by name.
+fn files_in(dir: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        match entry.file_name().into_string() {
+            Ok(name) => files.push((name, entry.path())),
+            Err(name) => {
+                invalid_data!("Unpacked file name {:?} is not UTF-8", name)
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// ========================================================================= //
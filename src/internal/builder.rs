@@ -0,0 +1,1023 @@
+use crate::internal::cabinet::{CabinetFile, write_cabinet};
+use crate::internal::codepage::CodePage;
+use crate::internal::language::LanguageId;
+use crate::internal::package::{Package, PackageType};
+use crate::internal::query::Insert;
+use crate::internal::rows::{
+    ComponentAttributes, ComponentRow, DirectoryRow, FeatureAttributes,
+    FeatureComponentsRow, FeatureRow, FileAttributes, FileRow, MediaRow,
+    PropertyRow,
+};
+use crate::internal::value::Value;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+// ========================================================================= //
+
+/// The name of the stream holding the package's embedded cabinet.
+const CABINET_NAME: &str = "product.cab";
+
+const TARGET_DIR: &str = "TARGETDIR";
+
+/// Directories whose locations Windows Installer sets, which may be used as
+/// parents of a manifest's directories.
+const STANDARD_DIRECTORIES: &[&str] = &[
+    "AdminToolsFolder",
+    "AppDataFolder",
+    "CommonAppDataFolder",
+    "CommonFiles64Folder",
+    "CommonFilesFolder",
+    "DesktopFolder",
+    "FavoritesFolder",
+    "FontsFolder",
+    "LocalAppDataFolder",
+    "MyPicturesFolder",
+    "PersonalFolder",
+    "ProgramFiles64Folder",
+    "ProgramFilesFolder",
+    "ProgramMenuFolder",
+    "SendToFolder",
+    "StartMenuFolder",
+    "StartupFolder",
+    "System64Folder",
+    "SystemFolder",
+    "TempFolder",
+    "TemplateFolder",
+    "WindowsFolder",
+    "WindowsVolume",
+];
+
+/// The standard actions scheduled in each sequence table, with their
+/// conventional sequence numbers.
+const SEQUENCES: &[(&str, &[(&str, i32)])] = &[
+    (
+        "InstallUISequence",
+        &[
+            ("LaunchConditions", 100),
+            ("CostInitialize", 800),
+            ("FileCost", 900),
+            ("CostFinalize", 1000),
+            ("ExecuteAction", 1300),
+        ],
+    ),
+    (
+        "InstallExecuteSequence",
+        &[
+            ("LaunchConditions", 100),
+            ("CostInitialize", 800),
+            ("FileCost", 900),
+            ("CostFinalize", 1000),
+            ("InstallValidate", 1400),
+            ("InstallInitialize", 1500),
+            ("ProcessComponents", 1600),
+            ("UnpublishFeatures", 1800),
+            ("RemoveFiles", 3500),
+            ("InstallFiles", 4000),
+            ("RegisterUser", 6000),
+            ("RegisterProduct", 6100),
+            ("PublishFeatures", 6300),
+            ("PublishProduct", 6400),
+            ("InstallFinalize", 6600),
+        ],
+    ),
+    (
+        "AdminUISequence",
+        &[
+            ("CostInitialize", 800),
+            ("FileCost", 900),
+            ("CostFinalize", 1000),
+            ("ExecuteAction", 1300),
+        ],
+    ),
+    (
+        "AdminExecuteSequence",
+        &[
+            ("CostInitialize", 800),
+            ("FileCost", 900),
+            ("CostFinalize", 1000),
+            ("InstallValidate", 1400),
+            ("InstallInitialize", 1500),
+            ("InstallAdminPackage", 3900),
+            ("InstallFiles", 4000),
+            ("InstallFinalize", 6600),
+        ],
+    ),
+    (
+        "AdvtExecuteSequence",
+        &[
+            ("CostInitialize", 800),
+            ("CostFinalize", 1000),
+            ("InstallValidate", 1400),
+            ("InstallInitialize", 1500),
+            ("PublishFeatures", 6300),
+            ("PublishProduct", 6400),
+            ("InstallFinalize", 6600),
+        ],
+    ),
+];
+
+/// Properties that the builder sets itself, which manifests may not set.
+const RESERVED_PROPERTIES: &[&str] = &[
+    "ALLUSERS",
+    "MSIINSTALLPERUSER",
+    "Manufacturer",
+    "ProductCode",
+    "ProductLanguage",
+    "ProductName",
+    "ProductVersion",
+    "UpgradeCode",
+];
+
+// ========================================================================= //
+
+/// A declarative description of a simple installer package, which can be
+/// read from TOML or JSON and built into a complete MSI file with
+/// `Manifest::build()`.
+///
+/// ```toml
+/// [product]
+/// name = "Widget"
+/// manufacturer = "Widgets Inc."
+/// version = "1.2.3"
+/// upgrade_code = "0a6d4c1e-55b2-4e1f-8e2b-6f3d2c1b0a02"
+///
+/// [[directories]]
+/// id = "INSTALLDIR"
+/// parent = "ProgramFilesFolder"
+/// name = "Widget"
+///
+/// [[components]]
+/// id = "MainExe"
+/// directory = "INSTALLDIR"
+/// files = [{ source = "target/release/widget.exe" }]
+///
+/// [[features]]
+/// id = "Complete"
+/// title = "Widget"
+/// components = ["MainExe"]
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// The product's metadata.
+    pub product: Product,
+    /// The directories to install into, in any order.
+    #[serde(default)]
+    pub directories: Vec<Directory>,
+    /// The components to install.
+    #[serde(default)]
+    pub components: Vec<Component>,
+    /// The top-level features; every component must belong to at least one
+    /// feature.
+    #[serde(default)]
+    pub features: Vec<Feature>,
+    /// Additional entries for the `Property` table.
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+/// Product metadata within a `Manifest`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Product {
+    /// The product's name (the `ProductName` property).
+    pub name: String,
+    /// The product's manufacturer (the `Manufacturer` property).
+    pub manufacturer: String,
+    /// The product version, in `major.minor.build` form.
+    pub version: String,
+    /// The code shared by every version of the product, for upgrades.  This
+    /// also seeds the GUIDs of components that don't specify one.
+    pub upgrade_code: Uuid,
+    /// The product code; a new one is generated for each build if this is
+    /// omitted.
+    #[serde(default)]
+    pub product_code: Option<Uuid>,
+    /// The package code; a new one is generated for each build if this is
+    /// omitted.
+    #[serde(default)]
+    pub package_code: Option<Uuid>,
+    /// The product's language (default 1033, for US English).
+    #[serde(default = "default_language")]
+    pub language: u16,
+    /// The database code page (default 1252, for Western European
+    /// languages).
+    #[serde(default = "default_codepage")]
+    pub codepage: i32,
+    /// The target platform (default `x86`).
+    #[serde(default)]
+    pub arch: Arch,
+    /// Whether the product is installed for all users (the default) or for
+    /// the current user only.
+    #[serde(default)]
+    pub scope: Scope,
+    /// The minimum Windows Installer version required, times 100 (default
+    /// 200 for x86 packages and 500 for others).  Per-user packages always
+    /// require at least 500.
+    #[serde(default)]
+    pub installer_version: Option<i32>,
+    /// A description of the package, for the summary information's
+    /// comments.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+fn default_language() -> u16 {
+    1033
+}
+
+fn default_codepage() -> i32 {
+    1252
+}
+
+/// The target platform of a `Product`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Arch {
+    /// 32-bit x86.
+    #[default]
+    X86,
+    /// 64-bit x86.
+    X64,
+    /// 64-bit ARM.
+    Arm64,
+}
+
+impl Arch {
+    fn template_name(self) -> &'static str {
+        match self {
+            Arch::X86 => "Intel",
+            Arch::X64 => "x64",
+            Arch::Arm64 => "Arm64",
+        }
+    }
+}
+
+/// The installation scope of a `Product`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scope {
+    /// Install for all users of the machine.
+    #[default]
+    PerMachine,
+    /// Install for the current user only.  This sets `ALLUSERS=2` and
+    /// `MSIINSTALLPERUSER=1`, which need Windows Installer 5.0, and marks the
+    /// package as not requiring elevated privileges.
+    PerUser,
+}
+
+/// A directory within a `Manifest`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Directory {
+    /// The directory's key in the `Directory` table.
+    pub id: String,
+    /// The parent directory: either another manifest directory, or a
+    /// standard directory such as `ProgramFilesFolder`.
+    pub parent: String,
+    /// The directory's name.
+    pub name: String,
+}
+
+/// A component within a `Manifest`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Component {
+    /// The component's key in the `Component` table.
+    pub id: String,
+    /// The directory that the component's files are installed into.
+    pub directory: String,
+    /// The component's GUID; if omitted, one is derived from the product's
+    /// upgrade code and the component's ID, so that it stays the same from
+    /// one build to the next.
+    #[serde(default)]
+    pub guid: Option<Uuid>,
+    /// The component's files.  The first file is the component's key path;
+    /// a component without files uses its directory as its key path.
+    #[serde(default)]
+    pub files: Vec<File>,
+}
+
+/// A file within a `Component`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct File {
+    /// The path of the file to install, relative to the source directory
+    /// passed to `Manifest::build()`.
+    pub source: PathBuf,
+    /// The installed file's name (default: the source's file name).
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The file's key in the `File` table (default: derived from its name).
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+/// A feature within a `Manifest`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Feature {
+    /// The feature's key in the `Feature` table.
+    pub id: String,
+    /// The feature's title, shown in the installer's user interface.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// The feature's description, shown in the installer's user interface.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The feature's install level (default 1, meaning that it's installed
+    /// by default).
+    #[serde(default = "default_level")]
+    pub level: i16,
+    /// Whether the user may choose not to install the feature (default
+    /// true).
+    #[serde(default = "default_allow_absent")]
+    pub allow_absent: bool,
+    /// The IDs of the feature's components.
+    #[serde(default)]
+    pub components: Vec<String>,
+    /// The feature's subfeatures.
+    #[serde(default)]
+    pub features: Vec<Feature>,
+}
+
+fn default_level() -> i16 {
+    1
+}
+
+fn default_allow_absent() -> bool {
+    true
+}
+
+// ========================================================================= //
+
+impl Manifest {
+    /// Parses a manifest from TOML.
+    pub fn from_toml(text: &str) -> io::Result<Manifest> {
+        match toml::from_str(text) {
+            Ok(manifest) => Ok(manifest),
+            Err(error) => invalid_data!("Invalid TOML manifest: {}", error),
+        }
+    }
+
+    /// Parses a manifest from JSON.
+    pub fn from_json(text: &str) -> io::Result<Manifest> {
+        match serde_json::from_str(text) {
+            Ok(manifest) => Ok(manifest),
+            Err(error) => invalid_data!("Invalid JSON manifest: {}", error),
+        }
+    }
+
+    /// Reads a manifest from a file, parsing it as JSON if its extension is
+    /// `.json`, and as TOML otherwise.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Manifest> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let is_json = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        if is_json {
+            Manifest::from_json(&text)
+        } else {
+            Manifest::from_toml(&text)
+        }
+    }
+
+    /// Builds an installer package from the manifest, reading files from
+    /// paths relative to `source_dir` and writing the package to the
+    /// underlying reader/writer (which should be initially empty).
+    ///
+    /// The package gets `Property`, `Directory`, `Component`, `File`,
+    /// `Feature`, `FeatureComponents`, and `Media` tables, the standard
+    /// sequence tables, and summary information with a package code,
+    /// template, and word count.  The files are stored uncompressed in a
+    /// cabinet embedded in the package, with short names generated for any
+    /// names that aren't valid 8.3 names.
+    ///
+    /// Returns an error if the manifest is inconsistent (e.g. a component
+    /// refers to a directory that doesn't exist, or doesn't belong to any
+    /// feature), or if a file can't be read.
+    pub fn build<F: Read + Write + Seek>(
+        &self,
+        source_dir: &Path,
+        inner: F,
+    ) -> io::Result<Package<F>> {
+        let plan = self.plan(source_dir)?;
+        let mut package = Package::create(PackageType::Installer, inner)?;
+        let codepage = match CodePage::from_id(self.product.codepage) {
+            Some(codepage) => codepage,
+            None => invalid_data!(
+                "Manifest has unknown code page {}",
+                self.product.codepage
+            ),
+        };
+        package.set_database_codepage(codepage);
+        package.summary_info_mut().set_codepage(codepage);
+        self.write_summary_info(&mut package);
+        for table_name in [
+            "Component",
+            "Directory",
+            "Feature",
+            "FeatureComponents",
+            "File",
+            "Media",
+            "Property",
+        ] {
+            package.create_standard_table(table_name)?;
+        }
+        package.insert_typed_rows(self.property_rows())?;
+        package.insert_typed_rows(plan.directories)?;
+        package.insert_typed_rows(plan.components)?;
+        package.insert_typed_rows(plan.features)?;
+        package.insert_typed_rows(plan.feature_components)?;
+        let num_files = plan.files.len();
+        package.insert_typed_rows(
+            plan.files.iter().map(|file| file.row.clone()),
+        )?;
+        package.insert_typed_rows(vec![MediaRow {
+            disk_id: 1,
+            last_sequence: num_files as i32,
+            disk_prompt: None,
+            cabinet: if num_files > 0 {
+                Some(format!("#{CABINET_NAME}"))
+            } else {
+                None
+            },
+            volume_label: None,
+            source: None,
+        }])?;
+        for &(table_name, actions) in SEQUENCES {
+            package.create_standard_table(table_name)?;
+            let mut query = Insert::into(table_name);
+            for &(action, sequence) in actions {
+                query = query.row(vec![
+                    Value::from(action),
+                    Value::Null,
+                    Value::Int(sequence),
+                ]);
+            }
+            package.insert_rows(query)?;
+        }
+        if num_files > 0 {
+            let mut cabinet_files = Vec::with_capacity(num_files);
+            for file in plan.files {
+                cabinet_files.push(CabinetFile {
+                    name: file.row.file,
+                    size: file.size,
+                    reader: fs::File::open(&file.path)?,
+                });
+            }
+            let stream = package.write_stream(CABINET_NAME)?;
+            let mut writer = io::BufWriter::new(stream);
+            write_cabinet(&mut writer, cabinet_files)?;
+            writer.flush()?;
+        }
+        package.flush()?;
+        Ok(package)
+    }
+
+    fn write_summary_info<F: Read + Write + Seek>(
+        &self,
+        package: &mut Package<F>,
+    ) {
+        let product = &self.product;
+        let mut installer_version = product
+            .installer_version
+            .unwrap_or(if product.arch == Arch::X86 { 200 } else { 500 });
+        // Long file names, compressed files.
+        let mut word_count = 2;
+        if product.scope == Scope::PerUser {
+            // MSIINSTALLPERUSER is only honored by Windows Installer 5.0 and
+            // later, and per-user installs don't need elevated privileges.
+            installer_version = installer_version.max(500);
+            word_count |= 8;
+        }
+        let summary_info = package.summary_info_mut();
+        summary_info.set_subject(product.name.clone());
+        summary_info.set_author(product.manufacturer.clone());
+        summary_info.set_keywords(&["Installer".to_string()]);
+        if let Some(ref description) = product.description {
+            summary_info.set_comments(description.clone());
+        }
+        summary_info.set_arch(product.arch.template_name());
+        summary_info.set_languages(&[LanguageId::from_id(product.language)]);
+        summary_info
+            .set_uuid(product.package_code.unwrap_or_else(Uuid::new_v4));
+        summary_info.set_page_count(installer_version);
+        summary_info.set_word_count(word_count);
+        summary_info.set_creating_application(format!(
+            "msi crate {}",
+            env!("CARGO_PKG_VERSION")
+        ));
+    }
+
+    fn property_rows(&self) -> Vec<PropertyRow> {
+        let product = &self.product;
+        let product_code = product.product_code.unwrap_or_else(Uuid::new_v4);
+        let mut properties = vec![
+            ("ProductName", product.name.clone()),
+            ("Manufacturer", product.manufacturer.clone()),
+            ("ProductVersion", product.version.clone()),
+            ("ProductLanguage", product.language.to_string()),
+            ("ProductCode", guid_string(product_code)),
+            ("UpgradeCode", guid_string(product.upgrade_code)),
+        ];
+        match product.scope {
+            Scope::PerMachine => properties.push(("ALLUSERS", "1".into())),
+            Scope::PerUser => {
+                properties.push(("ALLUSERS", "2".into()));
+                properties.push(("MSIINSTALLPERUSER", "1".into()));
+            }
+        }
+        let mut rows: Vec<PropertyRow> = properties
+            .into_iter()
+            .map(|(property, value)| PropertyRow {
+                property: property.to_string(),
+                value,
+            })
+            .collect();
+        rows.extend(self.properties.iter().map(|(property, value)| {
+            PropertyRow { property: property.clone(), value: value.clone() }
+        }));
+        rows
+    }
+
+    /// Checks the manifest for consistency and works out the rows of its
+    /// tables.
+    fn plan(&self, source_dir: &Path) -> io::Result<Plan> {
+        let product = &self.product;
+        if !is_valid_product_version(&product.version) {
+            invalid_data!(
+                "Manifest has invalid product version {:?} (expected \
+                 major.minor.build, at most 255.255.65535)",
+                product.version
+            );
+        }
+        for property in self.properties.keys() {
+            if RESERVED_PROPERTIES.contains(&property.as_str()) {
+                invalid_data!(
+                    "Manifest may not set the {:?} property, which comes from \
+                     the product metadata",
+                    property
+                );
+            }
+        }
+
+        let mut plan = Plan::default();
+        let mut short_names = ShortNames::default();
+        plan.directories.push(DirectoryRow {
+            directory: TARGET_DIR.to_string(),
+            parent: None,
+            default_dir: "SourceDir".to_string(),
+        });
+        let mut directory_ids: HashSet<&str> = HashSet::new();
+        directory_ids.insert(TARGET_DIR);
+        for directory in &self.directories {
+            check_identifier("directory", &directory.id)?;
+            if !directory_ids.insert(&directory.id) {
+                invalid_data!(
+                    "Manifest has more than one directory with ID {:?}",
+                    directory.id
+                );
+            }
+        }
+        for directory in &self.directories {
+            let parent = directory.parent.as_str();
+            if !directory_ids.contains(parent) {
+                if !STANDARD_DIRECTORIES.contains(&parent) {
+                    invalid_data!(
+                        "Directory {:?} has parent {:?}, which is neither a \
+                         directory in the manifest nor a standard directory",
+                        directory.id,
+                        parent
+                    );
+                }
+                directory_ids.insert(parent);
+                plan.directories.push(DirectoryRow {
+                    directory: parent.to_string(),
+                    parent: Some(TARGET_DIR.to_string()),
+                    default_dir: ".".to_string(),
+                });
+            }
+            let default_dir =
+                short_names.file_name(parent, &directory.name)?;
+            plan.directories.push(DirectoryRow {
+                directory: directory.id.clone(),
+                parent: Some(parent.to_string()),
+                default_dir,
+            });
+        }
+        // Every directory must descend from TARGETDIR.
+        let parents: HashMap<&str, &str> = self
+            .directories
+            .iter()
+            .map(|directory| {
+                (directory.id.as_str(), directory.parent.as_str())
+            })
+            .collect();
+        for directory in &self.directories {
+            let mut current = directory.id.as_str();
+            for _ in 0..=parents.len() {
+                match parents.get(current) {
+                    Some(parent) => current = parent,
+                    None => break,
+                }
+            }
+            if parents.contains_key(current) {
+                invalid_data!(
+                    "Directory {:?} is its own ancestor",
+                    directory.id
+                );
+            }
+        }
+
+        let component_attributes = if product.arch == Arch::X86 {
+            ComponentAttributes::empty()
+        } else {
+            ComponentAttributes::WIN64
+        };
+        let mut component_ids = HashSet::<&str>::new();
+        let mut file_ids = HashSet::<String>::new();
+        for component in &self.components {
+            check_identifier("component", &component.id)?;
+            if !component_ids.insert(&component.id) {
+                invalid_data!(
+                    "Manifest has more than one component with ID {:?}",
+                    component.id
+                );
+            }
+            let directory = component.directory.as_str();
+            if !directory_ids.contains(directory) {
+                if !STANDARD_DIRECTORIES.contains(&directory) {
+                    invalid_data!(
+                        "Component {:?} refers to directory {:?}, which is \
+                         neither a directory in the manifest nor a standard \
+                         directory",
+                        component.id,
+                        directory
+                    );
+                }
+                directory_ids.insert(directory);
+                plan.directories.push(DirectoryRow {
+                    directory: directory.to_string(),
+                    parent: Some(TARGET_DIR.to_string()),
+                    default_dir: ".".to_string(),
+                });
+            }
+            let mut key_path = None;
+            for file in &component.files {
+                let path = source_dir.join(&file.source);
+                let size = match fs::metadata(&path) {
+                    Ok(metadata) if metadata.is_file() => metadata.len(),
+                    Ok(_) => invalid_data!(
+                        "Source {:?} of component {:?} is not a file",
+                        path,
+                        component.id
+                    ),
+                    Err(error) => {
+                        return Err(io::Error::new(
+                            error.kind(),
+                            format!(
+                                "Can't read source {:?} of component {:?}: \
+                                 {}",
+                                path, component.id, error
+                            ),
+                        ));
+                    }
+                };
+                if size > i32::MAX as u64 {
+                    invalid_data!("Source {:?} is too large", path);
+                }
+                let name = match (&file.name, file.source.file_name()) {
+                    (Some(name), _) => name.clone(),
+                    (None, Some(name)) => name.to_string_lossy().into_owned(),
+                    (None, None) => invalid_data!(
+                        "Source {:?} of component {:?} has no file name",
+                        file.source,
+                        component.id
+                    ),
+                };
+                let id = match &file.id {
+                    Some(id) => {
+                        check_identifier("file", id)?;
+                        if file_ids.contains(id) {
+                            invalid_data!(
+                                "Manifest has more than one file with ID {:?}",
+                                id
+                            );
+                        }
+                        id.clone()
+                    }
+                    None => unique_identifier(&name, &file_ids),
+                };
+                file_ids.insert(id.clone());
+                let file_name =
+                    short_names.file_name(&component.directory, &name)?;
+                key_path.get_or_insert_with(|| id.clone());
+                plan.files.push(PlannedFile {
+                    row: FileRow {
+                        file: id,
+                        component: component.id.clone(),
+                        file_name,
+                        file_size: size as i32,
+                        version: None,
+                        language: None,
+                        attributes: FileAttributes::VITAL,
+                        sequence: plan.files.len() as i32 + 1,
+                    },
+                    path,
+                    size,
+                });
+            }
+            let guid = component.guid.unwrap_or_else(|| {
+                Uuid::new_v5(&product.upgrade_code, component.id.as_bytes())
+            });
+            plan.components.push(ComponentRow {
+                component: component.id.clone(),
+                component_id: Some(guid_string(guid)),
+                directory: component.directory.clone(),
+                attributes: component_attributes,
+                condition: None,
+                key_path,
+            });
+        }
+
+        let mut feature_ids = HashSet::<String>::new();
+        let mut featured_components = HashSet::<String>::new();
+        let mut display = 0;
+        let mut stack: Vec<(&Feature, Option<&str>)> = self
+            .features
+            .iter()
+            .rev()
+            .map(|feature| (feature, None))
+            .collect();
+        while let Some((feature, parent)) = stack.pop() {
+            check_identifier("feature", &feature.id)?;
+            if !feature_ids.insert(feature.id.clone()) {
+                invalid_data!(
+                    "Manifest has more than one feature with ID {:?}",
+                    feature.id
+                );
+            }
+            display += 2;
+            plan.features.push(FeatureRow {
+                feature: feature.id.clone(),
+                parent: parent.map(str::to_string),
+                title: feature.title.clone(),
+                description: feature.description.clone(),
+                display: Some(display),
+                level: feature.level,
+                directory: None,
+                attributes: if feature.allow_absent {
+                    FeatureAttributes::empty()
+                } else {
+                    FeatureAttributes::UI_DISALLOW_ABSENT
+                },
+            });
+            for component in &feature.components {
+                if !component_ids.contains(component.as_str()) {
+                    invalid_data!(
+                        "Feature {:?} refers to component {:?}, which isn't \
+                         in the manifest",
+                        feature.id,
+                        component
+                    );
+                }
+                featured_components.insert(component.clone());
+                plan.feature_components.push(FeatureComponentsRow {
+                    feature: feature.id.clone(),
+                    component: component.clone(),
+                });
+            }
+            stack.extend(
+                feature
+                    .features
+                    .iter()
+                    .rev()
+                    .map(|child| (child, Some(feature.id.as_str()))),
+            );
+        }
+        for component in &self.components {
+            if !featured_components.contains(&component.id) {
+                invalid_data!(
+                    "Component {:?} doesn't belong to any feature",
+                    component.id
+                );
+            }
+        }
+        Ok(plan)
+    }
+}
+
+#[derive(Default)]
+struct Plan {
+    directories: Vec<DirectoryRow>,
+    components: Vec<ComponentRow>,
+    features: Vec<FeatureRow>,
+    feature_components: Vec<FeatureComponentsRow>,
+    files: Vec<PlannedFile>,
+}
+
+struct PlannedFile {
+    row: FileRow,
+    path: PathBuf,
+    size: u64,
+}
+
+fn guid_string(uuid: Uuid) -> String {
+    format!("{{{}}}", uuid.hyphenated()).to_uppercase()
+}
+
+fn is_valid_product_version(version: &str) -> bool {
+    let parts: Vec<&str> = version.split('.').collect();
+    if parts.len() < 3 || parts.len() > 4 {
+        return false;
+    }
+    let limits = [255, 255, 65535, 65535];
+    parts.iter().zip(limits).all(|(part, limit)| {
+        !part.is_empty()
+            && part.chars().all(|chr| chr.is_ascii_digit())
+            && part.parse::<u32>().is_ok_and(|number| number <= limit)
+    })
+}
+
+/// Returns an error unless the string is a valid `Identifier` (a letter or
+/// underscore followed by letters, digits, underscores, and periods), of at
+/// most 72 characters.
+fn check_identifier(kind: &str, id: &str) -> io::Result<()> {
+    if !is_identifier(id) || id.len() > 72 {
+        invalid_data!("Manifest has invalid {} ID {:?}", kind, id);
+    }
+    Ok(())
+}
+
+fn is_identifier(id: &str) -> bool {
+    let mut chars = id.chars();
+    match chars.next() {
+        Some(chr) if chr.is_ascii_alphabetic() || chr == '_' => {}
+        _ => return false,
+    }
+    chars.all(|chr| chr.is_ascii_alphanumeric() || chr == '_' || chr == '.')
+}
+
+/// Makes a `File` table key from a file name, distinct from all the keys in
+/// `taken`.
+fn unique_identifier(name: &str, taken: &HashSet<String>) -> String {
+    let mut base: String =
+        name.chars()
+            .map(|chr| {
+                if chr.is_ascii_alphanumeric() || chr == '.' {
+                    chr
+                } else {
+                    '_'
+                }
+            })
+            .take(60)
+            .collect();
+    if !base.starts_with(|chr: char| chr.is_ascii_alphabetic()) {
+        base.insert(0, '_');
+    }
+    let mut id = base.clone();
+    let mut number = 1;
+    while taken.contains(&id) {
+        number += 1;
+        id = format!("{base}.{number}");
+    }
+    id
+}
+
+// ========================================================================= //
+
+/// The characters (besides ASCII letters and digits) that may appear in a
+/// short file name.
+const SHORT_NAME_PUNCTUATION: &str = "!#$%&'()-@^_`{}~";
+
+/// Generates short (8.3) names that are unique within each directory.
+#[derive(Default)]
+struct ShortNames {
+    taken: HashMap<String, HashSet<String>>,
+}
+
+impl ShortNames {
+    /// Returns the `Directory` table `DefaultDir` or `File` table `FileName`
+    /// value for an entry of the given directory: the name itself if it is a
+    /// valid short name, otherwise `SHORT~1.EXT|long name`.
+    fn file_name(
+        &mut self,
+        directory: &str,
+        name: &str,
+    ) -> io::Result<String> {
+        if name.is_empty()
+            || name == "."
+            || name == ".."
+            || name.contains(['\\', '/', ':', '*', '?', '"', '<', '>', '|'])
+        {
+            invalid_data!("Manifest has invalid file name {:?}", name);
+        }
+        let taken = self.taken.entry(directory.to_string()).or_default();
+        if is_short_name(name) && taken.insert(name.to_ascii_uppercase()) {
+            return Ok(name.to_string());
+        }
+        let filter = |string: &str| -> String {
+            string
+                .chars()
+                .filter(|&chr| is_short_name_char(chr))
+                .map(|chr| chr.to_ascii_uppercase())
+                .collect()
+        };
+        let (stem, extension) = match name.rfind('.') {
+            Some(index) if index > 0 => (&name[..index], &name[index + 1..]),
+            _ => (name, ""),
+        };
+        let stem = filter(stem);
+        let extension: String = filter(extension).chars().take(3).collect();
+        for number in 1.. {
+            let suffix = format!("~{number}");
+            let stem: String = stem.chars().take(8 - suffix.len()).collect();
+            let mut short_name = stem + &suffix;
+            if !extension.is_empty() {
+                short_name.push('.');
+                short_name.push_str(&extension);
+            }
+            if taken.insert(short_name.clone()) {
+                return Ok(format!("{short_name}|{name}"));
+            }
+        }
+        unreachable!()
+    }
+}
+
+fn is_short_name_char(chr: char) -> bool {
+    chr.is_ascii_alphanumeric() || SHORT_NAME_PUNCTUATION.contains(chr)
+}
+
+fn is_short_name(name: &str) -> bool {
+    let (stem, extension) = match name.split_once('.') {
+        Some((stem, extension)) => (stem, Some(extension)),
+        None => (name, None),
+    };
+    let is_valid_part = |part: &str, max_len: usize| {
+        part.len() <= max_len && part.chars().all(is_short_name_char)
+    };
+    !stem.is_empty()
+        && is_valid_part(stem, 8)
+        && extension.is_none_or(|extension| {
+            !extension.is_empty() && is_valid_part(extension, 3)
+        })
+}
+
+// ========================================================================= //
+
+#[cfg(test)]
+mod tests {
+    use super::{ShortNames, is_short_name, is_valid_product_version};
+
+    #[test]
+    fn short_names() {
+        assert!(is_short_name("widget.exe"));
+        assert!(is_short_name("README"));
+        assert!(!is_short_name("widgets.json"));
+        assert!(!is_short_name("my file.txt"));
+        assert!(!is_short_name("a.b.c"));
+
+        let mut names = ShortNames::default();
+        assert_eq!(names.file_name("D", "widget.exe").unwrap(), "widget.exe");
+        assert_eq!(
+            names.file_name("D", "Widget Helper.exe").unwrap(),
+            "WIDGET~1.EXE|Widget Helper.exe"
+        );
+        assert_eq!(
+            names.file_name("D", "Widget Helper 2.exe").unwrap(),
+            "WIDGET~2.EXE|Widget Helper 2.exe"
+        );
+        assert_eq!(
+            names.file_name("D", "WIDGET.EXE").unwrap(),
+            "WIDGET~3.EXE|WIDGET.EXE"
+        );
+        assert_eq!(
+            names.file_name("E", "Widget Helper.exe").unwrap(),
+            "WIDGET~1.EXE|Widget Helper.exe"
+        );
+        assert_eq!(
+            names.file_name("E", "settings.json").unwrap(),
+            "SETTIN~1.JSO|settings.json"
+        );
+        assert!(names.file_name("E", "a/b").is_err());
+    }
+
+    #[test]
+    fn product_versions() {
+        assert!(is_valid_product_version("1.2.3"));
+        assert!(is_valid_product_version("255.255.65535.1"));
+        assert!(!is_valid_product_version("1.2"));
+        assert!(!is_valid_product_version("256.0.0"));
+        assert!(!is_valid_product_version("1.2.x"));
+        assert!(!is_valid_product_version("1..3"));
+    }
+}
+
+// ========================================================================= //
@@ -0,0 +1,217 @@
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::io::{self, Read, Write};
+
+// ========================================================================= //
+
+const SIGNATURE: &[u8; 4] = b"MSCF";
+const VERSION_MINOR: u8 = 3;
+const VERSION_MAJOR: u8 = 1;
+
+const HEADER_LEN: u64 = 36;
+const FOLDER_LEN: u64 = 8;
+const FILE_ENTRY_LEN: u64 = 16;
+const DATA_HEADER_LEN: u64 = 8;
+
+/// The maximum number of uncompressed bytes in one CFDATA block.
+const MAX_BLOCK_LEN: usize = 0x8000;
+/// The maximum number of uncompressed bytes in one cabinet folder.
+const MAX_FOLDER_LEN: u64 = 0x7fff_8000;
+
+const COMPRESSION_NONE: u16 = 0;
+/// The `_A_ARCH` file attribute.
+const ATTRIBUTE_ARCHIVE: u16 = 0x20;
+/// The DOS date of 1980-01-01, used for every file so that cabinets are
+/// reproducible.
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+// ========================================================================= //
+
+/// A file to be stored in a cabinet: its name within the cabinet, its size,
+/// and a reader for its data.
+pub(crate) struct CabinetFile<R> {
+    pub name: String,
+    pub size: u64,
+    pub reader: R,
+}
+
+/// Writes a single-folder, uncompressed cabinet file containing the given
+/// files.  Returns an error if any file's reader yields a different number of
+/// bytes than its declared size.
+pub(crate) fn write_cabinet<W: Write, R: Read>(
+    mut writer: W,
+    files: Vec<CabinetFile<R>>,
+) -> io::Result<()> {
+    if files.len() > usize::from(u16::MAX) {
+        invalid_input!(
+            "A cabinet can hold at most {} files, not {}",
+            u16::MAX,
+            files.len()
+        );
+    }
+    let total_len: u64 = files.iter().map(|file| file.size).sum();
+    if total_len > MAX_FOLDER_LEN {
+        invalid_input!(
+            "Cabinet contents total {} bytes, but at most {} are allowed",
+            total_len,
+            MAX_FOLDER_LEN
+        );
+    }
+    let num_blocks = total_len.div_ceil(MAX_BLOCK_LEN as u64);
+    let files_len: u64 = files
+        .iter()
+        .map(|file| FILE_ENTRY_LEN + file.name.len() as u64 + 1)
+        .sum();
+    let data_start = HEADER_LEN + FOLDER_LEN + files_len;
+    let cabinet_len = data_start + num_blocks * DATA_HEADER_LEN + total_len;
+
+    // CFHEADER:
+    writer.write_all(SIGNATURE)?;
+    writer.write_u32::<LittleEndian>(0)?;
+    writer.write_u32::<LittleEndian>(cabinet_len as u32)?;
+    writer.write_u32::<LittleEndian>(0)?;
+    writer.write_u32::<LittleEndian>((HEADER_LEN + FOLDER_LEN) as u32)?;
+    writer.write_u32::<LittleEndian>(0)?;
+    writer.write_u8(VERSION_MINOR)?;
+    writer.write_u8(VERSION_MAJOR)?;
+    writer.write_u16::<LittleEndian>(1)?; // number of folders
+    writer.write_u16::<LittleEndian>(files.len() as u16)?;
+    writer.write_u16::<LittleEndian>(0)?; // flags
+    writer.write_u16::<LittleEndian>(0)?; // set ID
+    writer.write_u16::<LittleEndian>(0)?; // cabinet number within set
+
+    // CFFOLDER:
+    writer.write_u32::<LittleEndian>(data_start as u32)?;
+    writer.write_u16::<LittleEndian>(num_blocks as u16)?;
+    writer.write_u16::<LittleEndian>(COMPRESSION_NONE)?;
+
+    // CFFILE entries:
+    let mut offset: u64 = 0;
+    for file in &files {
+        writer.write_u32::<LittleEndian>(file.size as u32)?;
+        writer.write_u32::<LittleEndian>(offset as u32)?;
+        writer.write_u16::<LittleEndian>(0)?; // folder index
+        writer.write_u16::<LittleEndian>(DOS_DATE)?;
+        writer.write_u16::<LittleEndian>(0)?; // time
+        writer.write_u16::<LittleEndian>(ATTRIBUTE_ARCHIVE)?;
+        writer.write_all(file.name.as_bytes())?;
+        writer.write_u8(0)?;
+        offset += file.size;
+    }
+
+    // CFDATA blocks, which run on from one file into the next.  A zero
+    // checksum means that the checksum isn't used.
+    let mut block = Vec::with_capacity(MAX_BLOCK_LEN);
+    for file in files {
+        let mut reader = file.reader.take(file.size);
+        let mut remaining = file.size;
+        while remaining > 0 {
+            let wanted = (MAX_BLOCK_LEN - block.len()).min(remaining as usize);
+            let start = block.len();
+            block.resize(start + wanted, 0);
+            let num_read = read_fully(&mut reader, &mut block[start..])?;
+            if num_read < wanted {
+                invalid_data!(
+                    "File {:?} for cabinet is shorter than its expected \
+                     size of {} bytes",
+                    file.name,
+                    file.size
+                );
+            }
+            remaining -= wanted as u64;
+            if block.len() == MAX_BLOCK_LEN {
+                write_block(&mut writer, &block)?;
+                block.clear();
+            }
+        }
+        if reader.into_inner().read(&mut [0])? != 0 {
+            invalid_data!(
+                "File {:?} for cabinet is longer than its expected size of \
+                 {} bytes",
+                file.name,
+                file.size
+            );
+        }
+    }
+    if !block.is_empty() {
+        write_block(&mut writer, &block)?;
+    }
+    Ok(())
+}
+
+fn write_block<W: Write>(writer: &mut W, block: &[u8]) -> io::Result<()> {
+    writer.write_u32::<LittleEndian>(0)?; // checksum
+    writer.write_u16::<LittleEndian>(block.len() as u16)?;
+    writer.write_u16::<LittleEndian>(block.len() as u16)?;
+    writer.write_all(block)
+}
+
+fn read_fully<R: Read>(
+    reader: &mut R,
+    buffer: &mut [u8],
+) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buffer.len() {
+        match reader.read(&mut buffer[total..]) {
+            Ok(0) => break,
+            Ok(num_read) => total += num_read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(total)
+}
+
+// ========================================================================= //
+
+#[cfg(test)]
+mod tests {
+    use super::{CabinetFile, write_cabinet};
+    use std::io::ErrorKind;
+
+    #[test]
+    fn cabinet_layout() {
+        let files = vec![
+            CabinetFile {
+                name: "a".to_string(),
+                size: 3,
+                reader: &b"abc"[..],
+            },
+            CabinetFile {
+                name: "bc".to_string(),
+                size: 2,
+                reader: &b"de"[..],
+            },
+        ];
+        let mut output = Vec::new();
+        write_cabinet(&mut output, files).unwrap();
+        let expected: &[u8] = b"MSCF\0\0\0\0\
+            \x5e\0\0\0\0\0\0\0\
+            \x2c\0\0\0\0\0\0\0\
+            \x03\x01\x01\0\x02\0\0\0\0\0\0\0\
+            \x51\0\0\0\x01\0\0\0\
+            \x03\0\0\0\0\0\0\0\0\0\x21\0\0\0\x20\0a\0\
+            \x02\0\0\0\x03\0\0\0\0\0\x21\0\0\0\x20\0bc\0\
+            \0\0\0\0\x05\0\x05\0abcde";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn cabinet_file_size_mismatch() {
+        let files = vec![CabinetFile {
+            name: "a".to_string(),
+            size: 4,
+            reader: &b"abc"[..],
+        }];
+        let error = write_cabinet(Vec::new(), files).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let files = vec![CabinetFile {
+            name: "a".to_string(),
+            size: 2,
+            reader: &b"abc"[..],
+        }];
+        let error = write_cabinet(Vec::new(), files).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
+
+// ========================================================================= //
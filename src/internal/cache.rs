@@ -0,0 +1,273 @@
+use crate::internal::stringpool::StringPool;
+use crate::internal::table::Table;
+use crate::internal::value::{Value, ValueRef};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, Read, Seek, Write};
+use std::rc::Rc;
+
+// ========================================================================= //
+
+/// The decoded rows of a single table, as cached by a `RowCache`.
+///
+/// Rows read from a table's stream are kept in the order they were stored
+/// in.  Once the rows have been modified, they are kept sorted in the order
+/// that `Table::write_rows()` will write them out in, so that queries see
+/// the same order before and after the package is flushed.
+pub(crate) struct CachedRows {
+    rows: Vec<Vec<ValueRef>>,
+    // The primary keys of all rows, built on demand for checking inserts, and
+    // discarded whenever the rows are modified by anything other than an
+    // insert.
+    keys: Option<HashSet<Vec<Value>>>,
+    is_dirty: bool,
+}
+
+impl CachedRows {
+    /// Returns the cached rows.
+    pub(crate) fn rows(&self) -> &[Vec<ValueRef>] {
+        &self.rows
+    }
+
+    /// Calls the given function to modify the cached rows, then re-sorts the
+    /// rows and marks the table as needing to be written back out.
+    pub(crate) fn modify_rows<T, M>(&mut self, table: &Table, modify: M) -> T
+    where
+        M: FnOnce(&mut Vec<Vec<ValueRef>>) -> T,
+    {
+        self.keys = None;
+        self.is_dirty = true;
+        let result = modify(&mut self.rows);
+        self.rows.sort_by(|row, other| table.compare_rows(row, other));
+        result
+    }
+
+    /// Returns the set of primary keys of the cached rows.  Returns an error
+    /// if any two rows have the same key.
+    pub(crate) fn keys(
+        &mut self,
+        table: &Table,
+        string_pool: &StringPool,
+    ) -> io::Result<&HashSet<Vec<Value>>> {
+        if self.keys.is_none() {
+            let key_indices = table.primary_key_indices();
+            let mut keys = HashSet::with_capacity(self.rows.len());
+            for row in &self.rows {
+                let key: Vec<Value> = key_indices
+                    .iter()
+                    .map(|&index| row[index].to_value(string_pool))
+                    .collect();
+                if keys.contains(&key) {
+                    msi_error!(Corrupt {
+                        stream: table.name().to_string(),
+                        message: format!(
+                            "Malformed table {:?} contains multiple rows with \
+                             key {:?}",
+                            table.name(),
+                            key
+                        ),
+                    });
+                }
+                keys.insert(key);
+            }
+            self.keys = Some(keys);
+        }
+        Ok(self.keys.as_ref().unwrap())
+    }
+
+    /// Adds a new row with the given primary key, keeping the rows sorted,
+    /// and marks the table as needing to be written back out.  The caller is
+    /// responsible for making sure that the key isn't already present.
+    pub(crate) fn insert(
+        &mut self,
+        table: &Table,
+        key: Vec<Value>,
+        row: Vec<ValueRef>,
+    ) {
+        if !self.is_dirty {
+            self.rows.sort_by(|row, other| table.compare_rows(row, other));
+            self.is_dirty = true;
+        }
+        if let Some(ref mut keys) = self.keys {
+            keys.insert(key);
+        }
+        let index = self.rows.partition_point(|other| {
+            table.compare_rows(other, &row) != Ordering::Greater
+        });
+        self.rows.insert(index, row);
+    }
+}
+
+// ========================================================================= //
+
+/// An in-memory cache of decoded table rows for a package, so that queries
+/// don't need to re-read and re-write an entire table stream each time.
+/// Modified tables are written back out by `write_dirty()`, which the package
+/// calls when it is flushed.
+#[derive(Default)]
+pub(crate) struct RowCache {
+    entries: HashMap<String, CachedRows>,
+    // One undo log for each transaction in progress (innermost last).  Each
+    // maps the name of every table modified during that transaction to its
+    // cached rows from before the modification, or to `None` if the table's
+    // rows weren't cached yet.
+    undo_logs: Vec<HashMap<String, Option<CachedRows>>>,
+}
+
+impl RowCache {
+    /// Returns the cached rows for the given table, reading them from the
+    /// table's stream first if they aren't already cached.  Returns an error
+    /// if the stream refers to strings that aren't in the string pool.
+    pub(crate) fn load<F: Read + Seek>(
+        &mut self,
+        comp: &mut cfb::CompoundFile<F>,
+        table: &Table,
+        string_pool: &StringPool,
+    ) -> io::Result<&CachedRows> {
+        Ok(&*self.load_entry(comp, table, string_pool)?)
+    }
+
+    /// Like `load()`, but allows the rows to be modified.  If a transaction
+    /// is in progress, the table's current rows are first recorded, so that
+    /// they can be restored if the transaction is rolled back.
+    pub(crate) fn load_mut<F: Read + Seek>(
+        &mut self,
+        comp: &mut cfb::CompoundFile<F>,
+        table: &Table,
+        string_pool: &StringPool,
+    ) -> io::Result<&mut CachedRows> {
+        self.save(table.name());
+        self.load_entry(comp, table, string_pool)
+    }
+
+    fn load_entry<F: Read + Seek>(
+        &mut self,
+        comp: &mut cfb::CompoundFile<F>,
+        table: &Table,
+        string_pool: &StringPool,
+    ) -> io::Result<&mut CachedRows> {
+        if !self.entries.contains_key(table.name()) {
+            let stream_name = table.stream_name();
+            let rows = if comp.is_stream(&stream_name) {
+                table.read_rows(comp.open_stream(&stream_name)?)?
+            } else {
+                Vec::new()
+            };
+            table.check_string_refs(&rows, string_pool)?;
+            let entry = CachedRows { rows, keys: None, is_dirty: false };
+            self.entries.insert(table.name().to_string(), entry);
+        }
+        Ok(self.entries.get_mut(table.name()).unwrap())
+    }
+
+    /// Returns the cached rows for the given table, if they have been modified
+    /// since they were last written out.
+    pub(crate) fn dirty_rows(
+        &self,
+        table_name: &str,
+    ) -> Option<&[Vec<ValueRef>]> {
+        match self.entries.get(table_name) {
+            Some(entry) if entry.is_dirty => Some(entry.rows()),
+            _ => None,
+        }
+    }
+
+    /// Discards the cached rows for the given table (without writing them
+    /// out).
+    pub(crate) fn remove(&mut self, table_name: &str) {
+        self.save(table_name);
+        self.entries.remove(table_name);
+    }
+
+    /// Discards all cached rows (without writing them out).  This must not
+    /// be called during a transaction.
+    pub(crate) fn clear(&mut self) {
+        debug_assert!(self.undo_logs.is_empty());
+        self.entries.clear();
+    }
+
+    /// Starts recording changes to the cache, so that they can be undone by
+    /// `rollback_transaction()`.  Transactions may be nested.
+    pub(crate) fn begin_transaction(&mut self) {
+        self.undo_logs.push(HashMap::new());
+    }
+
+    /// Keeps the changes made since the innermost transaction began.  If
+    /// there is an enclosing transaction, they can still be undone by rolling
+    /// that one back.
+    pub(crate) fn commit_transaction(&mut self) {
+        let undo_log = self.undo_logs.pop().unwrap();
+        if let Some(outer_log) = self.undo_logs.last_mut() {
+            // Records already in the enclosing log are older, and so take
+            // precedence.
+            for (table_name, original) in undo_log {
+                outer_log.entry(table_name).or_insert(original);
+            }
+        }
+    }
+
+    /// Puts the rows of every table modified since the innermost transaction
+    /// began back the way they were.
+    pub(crate) fn rollback_transaction(&mut self) {
+        let undo_log = self.undo_logs.pop().unwrap();
+        for (table_name, original) in undo_log {
+            match original {
+                Some(entry) => {
+                    self.entries.insert(table_name, entry);
+                }
+                None => {
+                    self.entries.remove(&table_name);
+                }
+            }
+        }
+    }
+
+    /// If a transaction is in progress, records the current cached rows for
+    /// the given table, unless they have already been recorded.  This must be
+    /// called before the rows are modified or discarded.
+    fn save(&mut self, table_name: &str) {
+        if let Some(undo_log) = self.undo_logs.last_mut() {
+            if !undo_log.contains_key(table_name) {
+                let original =
+                    self.entries.get(table_name).map(|entry| CachedRows {
+                        rows: entry.rows.clone(),
+                        keys: None,
+                        is_dirty: entry.is_dirty,
+                    });
+                undo_log.insert(table_name.to_string(), original);
+            }
+        }
+    }
+
+    /// Writes the rows of each modified table back out to its stream.  This
+    /// must not be called during a transaction.
+    pub(crate) fn write_dirty<F: Read + Write + Seek>(
+        &mut self,
+        comp: &mut cfb::CompoundFile<F>,
+        tables: &BTreeMap<String, Rc<Table>>,
+    ) -> io::Result<()> {
+        debug_assert!(self.undo_logs.is_empty());
+        let mut table_names: Vec<&String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.is_dirty)
+            .map(|(table_name, _)| table_name)
+            .collect();
+        // Write tables out in a consistent order, so that the layout of the
+        // compound file doesn't depend on hash order.
+        table_names.sort();
+        for table_name in table_names {
+            if let Some(table) = tables.get(table_name) {
+                let rows = self.entries[table_name].rows.clone();
+                let stream = comp.create_stream(table.stream_name())?;
+                table.write_rows(stream, rows)?;
+            }
+        }
+        for entry in self.entries.values_mut() {
+            entry.is_dirty = false;
+        }
+        Ok(())
+    }
+}
+
+// ========================================================================= //
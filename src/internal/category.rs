@@ -484,6 +484,28 @@ impl str::FromStr for Category {
     }
 }
 
+/// Categories serialize as their names in the `_Validation` table (e.g.
+/// `"Identifier"`).
+#[cfg(feature = "serde")]
+impl serde::Serialize for Category {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Category {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let string = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        string.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 // ========================================================================= //
 
 #[cfg(test)]
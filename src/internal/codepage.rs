@@ -275,6 +275,29 @@ fn ascii_decode(bytes: &[u8]) -> String {
     chars.into_iter().collect()
 }
 
+/// Code pages serialize as their ID numbers (e.g. `1252`).
+#[cfg(feature = "serde")]
+impl serde::Serialize for CodePage {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(self.id())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CodePage {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let id = i32::deserialize(deserializer)?;
+        Self::from_id(id).ok_or_else(|| {
+            serde::de::Error::custom(format!("unknown code page: {id}"))
+        })
+    }
+}
+
 // ========================================================================= //
 
 #[cfg(test)]
@@ -24,6 +24,7 @@ const COL_NONBINARY_BIT: i32 = 0x400;
 
 /// A database column data type.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColumnType {
     /// A 16-bit integer.
     Int16,
@@ -194,7 +195,17 @@ impl fmt::Display for ColumnType {
 // ========================================================================= //
 
 /// A database column.
+///
+/// With the `serde` feature enabled, a column serializes as a struct holding
+/// its name, type, flags, and any `_Validation` metadata.  Deserializing a
+/// column builds it with a `ColumnBuilder`, and fails if the column name is
+/// invalid.
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "ColumnData", try_from = "ColumnData")
+)]
 pub struct Column {
     name: String,
     coltype: ColumnType,
@@ -204,7 +215,10 @@ pub struct Column {
     value_range: Option<(i32, i32)>,
     foreign_key: Option<(String, i32)>,
     category: Option<Category>,
+    unknown_category: Option<String>,
     enum_values: Vec<String>,
+    description: Option<String>,
+    type_bits: Option<i32>,
 }
 
 impl Column {
@@ -235,17 +249,28 @@ impl Column {
                 value_range: self.value_range,
                 foreign_key: self.foreign_key.clone(),
                 category: self.category,
+                unknown_category: self.unknown_category.clone(),
                 enum_values: self.enum_values.clone(),
+                description: self.description.clone(),
+                type_bits: self.type_bits,
             }
         }
     }
 
     pub(crate) fn but_nullable(mut self) -> Self {
         self.is_nullable = true;
+        self.type_bits = self.type_bits.map(|bits| bits | COL_NULLABLE_BIT);
         self
     }
 
+    /// Returns the `_Columns` type bitfield for this column.  For a column
+    /// read from an existing package, this is the bitfield exactly as it was
+    /// stored, including any bits this library doesn't understand, so that
+    /// copying the column into another table doesn't alter it.
     pub(crate) fn bitfield(&self) -> i32 {
+        if let Some(bits) = self.type_bits {
+            return bits;
+        }
         let mut bits = self.coltype.bitfield() | COL_VALID_BIT;
         if self.is_localizable {
             bits |= COL_LOCALIZABLE_BIT;
@@ -319,6 +344,23 @@ impl Column {
         self.category
     }
 
+    /// Returns the category string from the `_Validation` table for this
+    /// column, if it is one that this library doesn't recognize (in which
+    /// case `category()` returns `None`).  Such a category is preserved when
+    /// the column is used to create a new table, but isn't used to validate
+    /// values.
+    #[must_use]
+    pub fn unknown_category(&self) -> Option<&str> {
+        self.unknown_category.as_deref()
+    }
+
+    /// Returns the human-readable description of this column from the
+    /// `_Validation` table, if any.
+    #[must_use]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
     /// Returns the list of valid enum values for this column, if any.
     #[must_use]
     pub fn enum_values(&self) -> Option<&[String]> {
@@ -329,6 +371,40 @@ impl Column {
         }
     }
 
+    /// Returns this column's validation metadata, which can be modified and
+    /// then applied to the column with `Package::set_validation()`.
+    #[must_use]
+    pub fn validation(&self) -> ColumnValidation {
+        ColumnValidation {
+            value_range: self.value_range,
+            foreign_key: self.foreign_key.clone(),
+            category: self.category,
+            unknown_category: self.unknown_category.clone(),
+            enum_values: self.enum_values.clone(),
+            description: self.description.clone(),
+        }
+    }
+
+    pub(crate) fn with_validation(
+        &self,
+        validation: ColumnValidation,
+    ) -> Self {
+        Self {
+            name: self.name.clone(),
+            coltype: self.coltype,
+            is_localizable: self.is_localizable,
+            is_nullable: self.is_nullable,
+            is_primary_key: self.is_primary_key,
+            value_range: validation.value_range,
+            foreign_key: validation.foreign_key,
+            category: validation.category,
+            unknown_category: validation.unknown_category,
+            enum_values: validation.enum_values,
+            description: validation.description,
+            type_bits: self.type_bits,
+        }
+    }
+
     /// Returns true if the given value is valid for this column.
     #[must_use]
     pub fn is_valid_value(&self, value: &Value) -> bool {
@@ -385,7 +461,9 @@ pub struct ColumnBuilder {
     value_range: Option<(i32, i32)>,
     foreign_key: Option<(String, i32)>,
     category: Option<Category>,
+    unknown_category: Option<String>,
     enum_values: Vec<String>,
+    description: Option<String>,
 }
 
 impl ColumnBuilder {
@@ -398,7 +476,9 @@ impl ColumnBuilder {
             value_range: None,
             foreign_key: None,
             category: None,
+            unknown_category: None,
             enum_values: Vec::new(),
+            description: None,
         }
     }
 
@@ -444,6 +524,20 @@ impl ColumnBuilder {
         self
     }
 
+    pub(crate) fn unknown_category(mut self, category: &str) -> Self {
+        self.unknown_category = Some(category.to_string());
+        self
+    }
+
+    /// Gives the column a human-readable description, which is stored in the
+    /// `Description` column of the `_Validation` table (where tools such as
+    /// Orca display it).
+    #[must_use]
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
     /// Makes the column only permit the given values.
     #[must_use]
     pub fn enum_values(mut self, values: &[&str]) -> Self {
@@ -508,7 +602,10 @@ impl ColumnBuilder {
             value_range: self.value_range,
             foreign_key: self.foreign_key,
             category: self.category,
+            unknown_category: self.unknown_category,
             enum_values: self.enum_values,
+            description: self.description,
+            type_bits: None,
         }
     }
 
@@ -523,13 +620,199 @@ impl ColumnBuilder {
             value_range: self.value_range,
             foreign_key: self.foreign_key,
             category: self.category,
+            unknown_category: self.unknown_category,
             enum_values: self.enum_values,
+            description: self.description,
+            type_bits: Some(type_bits),
         })
     }
 }
 
 // ========================================================================= //
 
+/// The serialized form of a `Column`, holding only its public metadata.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ColumnData {
+    name: String,
+    #[serde(rename = "type")]
+    coltype: ColumnType,
+    #[serde(default)]
+    localizable: bool,
+    #[serde(default)]
+    nullable: bool,
+    #[serde(default)]
+    primary_key: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value_range: Option<(i32, i32)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    foreign_key: Option<(String, i32)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    category: Option<Category>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    enum_values: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Column> for ColumnData {
+    fn from(column: Column) -> ColumnData {
+        ColumnData {
+            name: column.name,
+            coltype: column.coltype,
+            localizable: column.is_localizable,
+            nullable: column.is_nullable,
+            primary_key: column.is_primary_key,
+            value_range: column.value_range,
+            foreign_key: column.foreign_key,
+            category: column.category,
+            enum_values: column.enum_values,
+            description: column.description,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ColumnData> for Column {
+    type Error = String;
+
+    fn try_from(data: ColumnData) -> Result<Column, String> {
+        if !Column::is_valid_name(&data.name) {
+            return Err(format!("{:?} is not a valid column name", data.name));
+        }
+        let mut builder = Column::build(data.name);
+        if data.localizable {
+            builder = builder.localizable();
+        }
+        if data.nullable {
+            builder = builder.nullable();
+        }
+        if data.primary_key {
+            builder = builder.primary_key();
+        }
+        if let Some((min, max)) = data.value_range {
+            builder = builder.range(min, max);
+        }
+        if let Some((table_name, column_index)) = data.foreign_key {
+            builder = builder.foreign_key(&table_name, column_index);
+        }
+        if let Some(category) = data.category {
+            builder = builder.category(category);
+        }
+        if !data.enum_values.is_empty() {
+            let values: Vec<&str> =
+                data.enum_values.iter().map(String::as_str).collect();
+            builder = builder.enum_values(&values);
+        }
+        if let Some(description) = data.description {
+            builder = builder.description(&description);
+        }
+        Ok(match data.coltype {
+            ColumnType::Int16 => builder.int16(),
+            ColumnType::Int32 => builder.int32(),
+            ColumnType::Str(max_len) => builder.string(max_len),
+            ColumnType::Binary => builder.binary(),
+        })
+    }
+}
+
+// ========================================================================= //
+
+/// The validation metadata for a column of an existing table, as stored in
+/// the `_Validation` table.
+///
+/// # Examples
+///
+/// ```
+/// use msi::{Column, ColumnValidation, Package, PackageType};
+/// use std::io::Cursor;
+///
+/// let cursor = Cursor::new(Vec::new());
+/// let mut package = Package::create(PackageType::Installer, cursor)
+///     .unwrap();
+/// let columns = vec![
+///     Column::build("Key").primary_key().int16(),
+///     Column::build("Color").string(16),
+/// ];
+/// package.create_table("Paint", columns).unwrap();
+/// let validation = ColumnValidation::new()
+///     .enum_values(&["Red", "Green", "Blue"])
+///     .description("The color of the paint");
+/// package.set_validation("Paint", "Color", validation).unwrap();
+/// let table = package.get_table("Paint").unwrap();
+/// let column = table.get_column("Color").unwrap();
+/// assert_eq!(column.description(), Some("The color of the paint"));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ColumnValidation {
+    value_range: Option<(i32, i32)>,
+    foreign_key: Option<(String, i32)>,
+    category: Option<Category>,
+    unknown_category: Option<String>,
+    enum_values: Vec<String>,
+    description: Option<String>,
+}
+
+impl ColumnValidation {
+    /// Creates empty validation metadata, which places no restrictions on
+    /// the column's values.  To modify a column's existing metadata instead,
+    /// start from `Column::validation()`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the column only permit values in the given range, or in any
+    /// range if `None`.
+    #[must_use]
+    pub fn range(mut self, range: Option<(i32, i32)>) -> Self {
+        self.value_range = range;
+        self
+    }
+
+    /// Makes the column refer to a key column in another table, or to none if
+    /// `None`.
+    #[must_use]
+    pub fn foreign_key(mut self, foreign_key: Option<(&str, i32)>) -> Self {
+        self.foreign_key = foreign_key
+            .map(|(table_name, index)| (table_name.to_string(), index));
+        self
+    }
+
+    /// For string columns, makes the column use the specified data format, or
+    /// none if `None`.
+    #[must_use]
+    pub fn category(mut self, category: Option<Category>) -> Self {
+        self.category = category;
+        self.unknown_category = None;
+        self
+    }
+
+    /// Makes the column only permit the given values (which are stored in the
+    /// `Set` column of the `_Validation` table), or any values if `values` is
+    /// empty.
+    #[must_use]
+    pub fn enum_values(mut self, values: &[&str]) -> Self {
+        self.enum_values = values.iter().map(|val| val.to_string()).collect();
+        self
+    }
+
+    /// Sets the column's human-readable description, or removes it if
+    /// `description` is empty.
+    #[must_use]
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = if description.is_empty() {
+            None
+        } else {
+            Some(description.to_string())
+        };
+        self
+    }
+}
+
+// ========================================================================= //
+
 #[cfg(test)]
 mod tests {
     use super::{Column, ColumnType};
@@ -587,7 +870,8 @@ mod tests {
         );
 
         let mut string_pool = StringPool::new(CodePage::default());
-        let string_ref = string_pool.incref("Hello, world!".to_string());
+        let string_ref =
+            string_pool.incref("Hello, world!".to_string()).unwrap();
         assert_eq!(string_ref.number(), 1);
 
         let mut input: &[u8] = b"\x00\x00";
@@ -654,7 +938,8 @@ mod tests {
         assert_eq!(&output as &[u8], b"\xff\xff\xff\x7f");
 
         let mut string_pool = StringPool::new(CodePage::default());
-        let string_ref = string_pool.incref("Hello, world!".to_string());
+        let string_ref =
+            string_pool.incref("Hello, world!".to_string()).unwrap();
         assert_eq!(string_ref.number(), 1);
 
         let mut output = Vec::<u8>::new();
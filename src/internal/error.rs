@@ -0,0 +1,213 @@
+use crate::internal::value::Value;
+use std::fmt;
+use std::io;
+
+// ========================================================================= //
+
+/// A specific error from operating on a package, with enough context to tell
+/// exactly which table, column, or row was at fault.
+///
+/// For compatibility, this crate's APIs still return `io::Error`s; when an
+/// error has one of these causes, it is stored as the `io::Error`'s inner
+/// error, and can be retrieved with `Error::from_io()`.  Errors without a
+/// more specific cause (such as errors from the underlying reader/writer) are
+/// plain `io::Error`s.
+///
+/// # Examples
+///
+/// ```
+/// use msi::{Column, Insert, Package, PackageType, Value};
+/// use std::io::Cursor;
+///
+/// let cursor = Cursor::new(Vec::new());
+/// let mut package = Package::create(PackageType::Installer, cursor)
+///     .unwrap();
+/// let columns = vec![
+///     Column::build("Key").primary_key().int16(),
+///     Column::build("Name").string(4),
+/// ];
+/// package.create_table("Things", columns).unwrap();
+/// let query = Insert::into("Things")
+///     .row(vec![Value::Int(1), Value::from("Fine")])
+///     .row(vec![Value::Int(2), Value::from("Too long")]);
+/// let error = package.insert_rows(query).unwrap_err();
+/// match msi::Error::from_io(&error) {
+///     Some(msi::Error::InvalidValue { table, column, row, .. }) => {
+///         assert_eq!(table, "Things");
+///         assert_eq!(column, "Name");
+///         assert_eq!(*row, Some(1));
+///     }
+///     _ => panic!("unexpected error: {}", error),
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The database has no table with the given name.
+    TableNotFound {
+        /// The name of the table.
+        table: String,
+    },
+    /// The database already has a table with the given name.
+    TableAlreadyExists {
+        /// The name of the table.
+        table: String,
+    },
+    /// A table has no column with the given name.
+    ColumnNotFound {
+        /// The name of the table.
+        table: String,
+        /// The name of the missing column.
+        column: String,
+    },
+    /// The package has no binary stream with the given name.
+    StreamNotFound {
+        /// The name of the stream.
+        stream: String,
+    },
+    /// A row being inserted has the wrong number of values for its table.
+    WrongNumberOfValues {
+        /// The name of the table.
+        table: String,
+        /// The 0-based index of the row within the `Insert` query.
+        row: usize,
+        /// The number of columns in the table.
+        expected: usize,
+        /// The number of values in the row.
+        actual: usize,
+    },
+    /// A value can't be stored in a column (e.g. because it is of the wrong
+    /// type, or too long, or null in a non-nullable column), or a value read
+    /// from a column isn't of the type that a typed row needs.
+    InvalidValue {
+        /// The name of the table.
+        table: String,
+        /// The name of the column.
+        column: String,
+        /// The invalid value.
+        value: Value,
+        /// For an `Insert` query, the 0-based index of the row within the
+        /// query; otherwise (e.g. for an `Update` query, which applies the
+        /// value to every matching row), `None`.
+        row: Option<usize>,
+    },
+    /// A row being inserted has the same primary key as a row already in the
+    /// table.
+    DuplicateKey {
+        /// The name of the table.
+        table: String,
+        /// The primary key values.
+        key: Vec<Value>,
+        /// The 0-based index of the row within the `Insert` query.
+        row: usize,
+    },
+    /// Two rows within the same `Insert` query have the same primary key.
+    RepeatedKey {
+        /// The name of the table.
+        table: String,
+        /// The primary key values.
+        key: Vec<Value>,
+        /// The 0-based index of the second such row within the query.
+        row: usize,
+    },
+    /// The data in one of the package's streams is malformed.
+    Corrupt {
+        /// The name of the damaged stream (or, for a table's stream, the name
+        /// of the table).
+        stream: String,
+        /// A description of the problem.
+        message: String,
+    },
+}
+
+impl Error {
+    /// Returns the specific cause of the given `io::Error`, if it has one.
+    #[must_use]
+    pub fn from_io(error: &io::Error) -> Option<&Error> {
+        // Some errors wrap a more specific one to add context (such as a line
+        // number), so look through their sources too.
+        let mut inner: &(dyn std::error::Error + 'static) = error.get_ref()?;
+        loop {
+            if let Some(error) = inner.downcast_ref::<Error>() {
+                return Some(error);
+            }
+            inner = match inner.downcast_ref::<io::Error>() {
+                Some(error) => error.get_ref()?,
+                None => inner.source()?,
+            };
+        }
+    }
+
+    /// Returns the kind of `io::Error` that this error converts into.
+    #[must_use]
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            Self::TableNotFound { .. } | Self::StreamNotFound { .. } => {
+                io::ErrorKind::NotFound
+            }
+            Self::TableAlreadyExists { .. } | Self::DuplicateKey { .. } => {
+                io::ErrorKind::AlreadyExists
+            }
+            Self::ColumnNotFound { .. }
+            | Self::WrongNumberOfValues { .. }
+            | Self::InvalidValue { .. }
+            | Self::RepeatedKey { .. } => io::ErrorKind::InvalidInput,
+            Self::Corrupt { .. } => io::ErrorKind::InvalidData,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TableNotFound { table } => {
+                write!(formatter, "Table {:?} does not exist", table)
+            }
+            Self::TableAlreadyExists { table } => {
+                write!(formatter, "Table {:?} already exists", table)
+            }
+            Self::ColumnNotFound { table, column } => write!(
+                formatter,
+                "Table {:?} has no column named {:?}",
+                table, column
+            ),
+            Self::StreamNotFound { stream } => {
+                write!(formatter, "Stream {:?} does not exist", stream)
+            }
+            Self::WrongNumberOfValues { table, expected, actual, .. } => {
+                write!(
+                    formatter,
+                    "Table {:?} has {} columns, but a row with {} values was \
+                     provided",
+                    table, expected, actual
+                )
+            }
+            Self::InvalidValue { column, value, .. } => write!(
+                formatter,
+                "{} is not a valid value for column {:?}",
+                value, column
+            ),
+            Self::DuplicateKey { table, key, .. } => write!(
+                formatter,
+                "Table {:?} already contains a row with key {:?}",
+                table, key
+            ),
+            Self::RepeatedKey { key, .. } => write!(
+                formatter,
+                "Cannot insert multiple rows with key {:?}",
+                key
+            ),
+            Self::Corrupt { message, .. } => formatter.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> io::Error {
+        io::Error::new(error.kind(), error)
+    }
+}
+
+// ========================================================================= //
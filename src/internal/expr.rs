@@ -148,7 +148,13 @@ impl Expr {
     /// operator to a string) will result in a null value.
     #[must_use]
     pub fn eval(&self, row: &Row) -> Value {
-        self.ast.eval(row)
+        self.ast.eval(&|name| row[name].clone())
+    }
+
+    /// Evaluates the expression, using the given function to look up the
+    /// value of each column it refers to.
+    pub(crate) fn eval_with<F: Fn(&str) -> Value>(&self, column: F) -> Value {
+        self.ast.eval(&column)
     }
 
     /// Returns the set of all column names referenced by this expression.
@@ -292,26 +298,26 @@ enum Ast {
 }
 
 impl Ast {
-    fn eval(&self, row: &Row) -> Value {
+    fn eval(&self, column: &dyn Fn(&str) -> Value) -> Value {
         match *self {
             Self::Literal(ref value) => value.clone(),
-            Self::Column(ref name) => row[name.as_str()].clone(),
-            Self::UnOp(op, ref arg) => op.eval(arg.eval(row)),
+            Self::Column(ref name) => column(name.as_str()),
+            Self::UnOp(op, ref arg) => op.eval(arg.eval(column)),
             Self::BinOp(op, ref arg1, ref arg2) => {
-                op.eval(arg1.eval(row), arg2.eval(row))
+                op.eval(arg1.eval(column), arg2.eval(column))
             }
             Self::And(ref arg1, ref arg2) => {
-                if arg1.eval(row).to_bool() {
-                    Value::from_bool(arg2.eval(row).to_bool())
+                if arg1.eval(column).to_bool() {
+                    Value::from_bool(arg2.eval(column).to_bool())
                 } else {
                     Value::from_bool(false)
                 }
             }
             Self::Or(ref arg1, ref arg2) => {
-                if arg1.eval(row).to_bool() {
+                if arg1.eval(column).to_bool() {
                     Value::from_bool(true)
                 } else {
-                    Value::from_bool(arg2.eval(row).to_bool())
+                    Value::from_bool(arg2.eval(column).to_bool())
                 }
             }
         }
@@ -0,0 +1,567 @@
+use crate::internal::codepage::CodePage;
+use crate::internal::column::{Column, ColumnType};
+use crate::internal::expr::Expr;
+use crate::internal::package::Package;
+use crate::internal::query::{Delete, Insert, Select};
+use crate::internal::summary::SummaryInfo;
+use crate::internal::table::Row;
+use crate::internal::value::Value;
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::Path;
+
+// ========================================================================= //
+
+/// The name of the pseudo-table whose IDT file sets the database code page.
+pub(crate) const FORCE_CODEPAGE_TABLE_NAME: &str = "_ForceCodepage";
+
+/// The name of the pseudo-table whose IDT file holds the summary information.
+pub(crate) const SUMMARY_INFO_TABLE_NAME: &str = "_SummaryInformation";
+
+const VALIDATION_TABLE_NAME: &str = "_Validation";
+
+// In IDT files, tabs, carriage returns, and line feeds within values are
+// replaced with these control characters.
+const ESCAPED_TAB: char = '\x10';
+const ESCAPED_CR: char = '\x11';
+const ESCAPED_LF: char = '\x19';
+
+// ========================================================================= //
+
+/// Returns the IDT type code for a column (e.g. `s72` or `I2`).
+pub(crate) fn column_type_code(column: &Column) -> String {
+    let (letter, size) = match column.coltype() {
+        ColumnType::Int16 => ('i', 2),
+        ColumnType::Int32 => ('i', 4),
+        ColumnType::Str(max_len) => {
+            (if column.is_localizable() { 'l' } else { 's' }, max_len)
+        }
+        ColumnType::Binary => ('v', 0),
+    };
+    let letter = if column.is_nullable() {
+        letter.to_ascii_uppercase()
+    } else {
+        letter
+    };
+    format!("{letter}{size}")
+}
+
+/// Parses an IDT type code into a column.
+pub(crate) fn parse_column(
+    name: &str,
+    code: &str,
+    is_primary_key: bool,
+) -> io::Result<Column> {
+    let mut chars = code.chars();
+    let letter = chars.next().unwrap_or(' ');
+    let size: Option<usize> = chars.as_str().parse().ok();
+    let mut builder = Column::build(name);
+    if is_primary_key {
+        builder = builder.primary_key();
+    }
+    if letter.is_ascii_uppercase() {
+        builder = builder.nullable();
+    }
+    match (letter.to_ascii_lowercase(), size) {
+        ('i', Some(2)) => Ok(builder.int16()),
+        ('i', Some(4)) => Ok(builder.int32()),
+        ('s', Some(max_len)) => Ok(builder.string(max_len)),
+        ('l', Some(max_len)) => Ok(builder.localizable().string(max_len)),
+        ('v', Some(_)) => Ok(builder.binary()),
+        _ => invalid_data!(
+            "Invalid IDT column type {:?} for column {:?}",
+            code,
+            name
+        ),
+    }
+}
+
+fn escape(string: &str) -> String {
+    string
+        .chars()
+        .map(|chr| match chr {
+            '\t' => ESCAPED_TAB,
+            '\r' => ESCAPED_CR,
+            '\n' => ESCAPED_LF,
+            _ => chr,
+        })
+        .collect()
+}
+
+fn unescape(string: &str) -> String {
+    string
+        .chars()
+        .map(|chr| match chr {
+            ESCAPED_TAB => '\t',
+            ESCAPED_CR => '\r',
+            ESCAPED_LF => '\n',
+            _ => chr,
+        })
+        .collect()
+}
+
+/// Returns the name of the stream holding a binary value, given the values
+/// of its row's primary key columns.
+pub(crate) fn binary_stream_name(table_name: &str, keys: &[&Value]) -> String {
+    let mut name = table_name.to_string();
+    for key in keys {
+        name.push('.');
+        match key {
+            Value::Int(number) => name.push_str(&number.to_string()),
+            Value::Str(string) => name.push_str(string),
+            Value::Null | Value::Binary => {}
+        }
+    }
+    name
+}
+
+/// Returns an error unless `name` (which comes from the package or from an
+/// IDT file, and so can't be trusted) is usable as a single file name within
+/// a directory.  Names that are empty, `.` or `..`, or that contain path
+/// separators, drive prefixes, or NUL characters are rejected, so that they
+/// can't be used to reach files outside that directory.
+pub(crate) fn check_file_name(name: &str) -> io::Result<()> {
+    if name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains(['/', '\\', ':', '\0'])
+    {
+        invalid_data!("{:?} is not a valid file name", name);
+    }
+    Ok(())
+}
+
+/// Returns true if the two columns would be written identically to an IDT
+/// file.
+fn same_idt_column(column1: &Column, column2: &Column) -> bool {
+    column1.name() == column2.name()
+        && column1.is_primary_key() == column2.is_primary_key()
+        && column_type_code(column1) == column_type_code(column2)
+}
+
+// ========================================================================= //
+
+/// Writes the given table to `writer` as an IDT file, and returns the names
+/// of the streams holding the table's binary values.  If `binary_dir` is
+/// given, the data of those streams is written to side-car files there.
+pub(crate) fn export<F, W>(
+    package: &mut Package<F>,
+    table_name: &str,
+    mut writer: W,
+    binary_dir: Option<&Path>,
+) -> io::Result<Vec<String>>
+where
+    F: Read + Seek,
+    W: Write,
+{
+    let codepage = package.database_codepage();
+    if table_name == FORCE_CODEPAGE_TABLE_NAME {
+        let text = format!("\r\n\r\n{}\t{}\r\n", codepage.id(), table_name);
+        writer.write_all(text.as_bytes())?;
+        return Ok(Vec::new());
+    }
+    if table_name == SUMMARY_INFO_TABLE_NAME {
+        export_summary_info(package.summary_info(), codepage, writer)?;
+        return Ok(Vec::new());
+    }
+    let table = match package.get_table(table_name) {
+        Some(table) => table.clone(),
+        None => msi_error!(TableNotFound { table: table_name.to_string() }),
+    };
+    let rows: Vec<Row> =
+        package.select_rows(Select::table(table_name))?.collect();
+    let key_indices = table.primary_key_indices();
+
+    let mut lines = Vec::<String>::with_capacity(rows.len());
+    let mut stream_names = Vec::<String>::new();
+    let mut needs_codepage = false;
+    for row in &rows {
+        let keys: Vec<&Value> =
+            key_indices.iter().map(|&index| &row[index]).collect();
+        let mut fields = Vec::<String>::with_capacity(row.len());
+        for index in 0..row.len() {
+            fields.push(match &row[index] {
+                Value::Null => String::new(),
+                Value::Int(number) => number.to_string(),
+                Value::Str(string) => {
+                    needs_codepage |= !string.is_ascii();
+                    escape(string)
+                }
+                Value::Binary => {
+                    let stream_name = binary_stream_name(table_name, &keys);
+                    let file_name = format!(
+                        "{}.ibd",
+                        &stream_name[table_name.len() + 1..]
+                    );
+                    stream_names.push(stream_name);
+                    file_name
+                }
+            });
+        }
+        lines.push(fields.join("\t"));
+    }
+    if let Some(dir) = binary_dir {
+        write_side_car_files(package, table_name, &stream_names, dir)?;
+    }
+
+    let mut text = String::new();
+    let names: Vec<&str> = table.columns().iter().map(Column::name).collect();
+    text.push_str(&names.join("\t"));
+    text.push_str("\r\n");
+    let codes: Vec<String> =
+        table.columns().iter().map(column_type_code).collect();
+    text.push_str(&codes.join("\t"));
+    text.push_str("\r\n");
+    if needs_codepage {
+        text.push_str(&format!("{}\t", codepage.id()));
+    }
+    text.push_str(table_name);
+    for &index in &key_indices {
+        text.push('\t');
+        text.push_str(table.columns()[index].name());
+    }
+    text.push_str("\r\n");
+    for line in lines {
+        text.push_str(&line);
+        text.push_str("\r\n");
+    }
+    writer.write_all(&codepage.encode(&text))?;
+    Ok(stream_names)
+}
+
+/// Returns the name of the side-car file holding the data of the given
+/// binary stream of a table, or an error if that name (or the table's name)
+/// can't safely be used as a file name.
+pub(crate) fn side_car_file_name(
+    table_name: &str,
+    stream_name: &str,
+) -> io::Result<String> {
+    check_file_name(table_name)?;
+    let file_name = format!("{}.ibd", &stream_name[table_name.len() + 1..]);
+    check_file_name(&file_name)?;
+    Ok(file_name)
+}
+
+/// Writes the data of the given binary streams of a table to side-car files
+/// in a subdirectory (named after the table) of `dir`.  All of the file
+/// names are checked before anything is written.
+pub(crate) fn write_side_car_files<F: Read + Seek>(
+    package: &mut Package<F>,
+    table_name: &str,
+    stream_names: &[String],
+    dir: &Path,
+) -> io::Result<()> {
+    let file_names = stream_names
+        .iter()
+        .map(|stream_name| side_car_file_name(table_name, stream_name))
+        .collect::<io::Result<Vec<String>>>()?;
+    let dir = dir.join(table_name);
+    for (stream_name, file_name) in stream_names.iter().zip(file_names) {
+        let mut stream = package.read_stream(stream_name)?;
+        fs::create_dir_all(&dir)?;
+        let mut file = fs::File::create(dir.join(file_name))?;
+        io::copy(&mut stream, &mut file)?;
+    }
+    Ok(())
+}
+
+fn export_summary_info<W: Write>(
+    summary_info: &SummaryInfo,
+    codepage: CodePage,
+    mut writer: W,
+) -> io::Result<()> {
+    let properties = summary_info.idt_properties();
+    let mut text = String::from("PropertyId\tValue\r\ni2\tl255\r\n");
+    if properties.iter().any(|(_, value)| !value.is_ascii()) {
+        text.push_str(&format!("{}\t", codepage.id()));
+    }
+    text.push_str(SUMMARY_INFO_TABLE_NAME);
+    text.push_str("\tPropertyId\r\n");
+    for (property, value) in properties {
+        text.push_str(&format!("{}\t{}\r\n", property, escape(&value)));
+    }
+    writer.write_all(&codepage.encode(&text))
+}
+
+// ========================================================================= //
+
+pub(crate) fn import<F, R>(
+    package: &mut Package<F>,
+    mut reader: R,
+    binary_dir: Option<&Path>,
+) -> io::Result<String>
+where
+    F: Read + Write + Seek,
+    R: Read,
+{
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let mut lines: Vec<&[u8]> = data
+        .split(|&byte| byte == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    if lines.len() < 3 {
+        invalid_data!("IDT file must start with three header lines");
+    }
+
+    // The third line holds the table name and primary key column names,
+    // optionally preceded by the code page of the file.
+    let header = String::from_utf8_lossy(lines[2]);
+    let mut header_fields: Vec<&str> = header.split('\t').collect();
+    let mut file_codepage = None;
+    if let Ok(id) = header_fields[0].parse::<i32>() {
+        match CodePage::from_id(id) {
+            Some(codepage) => file_codepage = Some(codepage),
+            None => invalid_data!("IDT file has unknown code page {}", id),
+        }
+        header_fields.remove(0);
+    }
+    let table_name = match header_fields.first() {
+        Some(&name) if !name.is_empty() => name.to_string(),
+        _ => invalid_data!("IDT file is missing its table name"),
+    };
+    if table_name == FORCE_CODEPAGE_TABLE_NAME {
+        match file_codepage {
+            Some(codepage) => package.set_database_codepage(codepage),
+            None => invalid_data!("_ForceCodepage IDT file has no code page"),
+        }
+        return Ok(table_name);
+    }
+    let codepage = file_codepage.unwrap_or(package.database_codepage());
+    if table_name == SUMMARY_INFO_TABLE_NAME {
+        let mut summary_info = SummaryInfo::new();
+        for (index, &line) in lines.iter().enumerate().skip(3) {
+            let line = codepage.decode(line);
+            let property = line
+                .split_once('\t')
+                .and_then(|(id, value)| Some((id.parse().ok()?, value)));
+            match property {
+                Some((id, value)) => {
+                    summary_info.set_idt_property(id, &unescape(value))?
+                }
+                None => invalid_data!(
+                    "Line {} of IDT file for table {:?} is not a valid \
+                     property",
+                    index + 1,
+                    table_name
+                ),
+            }
+        }
+        *package.summary_info_mut() = summary_info;
+        return Ok(table_name);
+    }
+    let column_names: Vec<String> =
+        codepage.decode(lines[0]).split('\t').map(str::to_string).collect();
+    let column_codes: Vec<String> =
+        codepage.decode(lines[1]).split('\t').map(str::to_string).collect();
+    if column_names.len() != column_codes.len() {
+        invalid_data!(
+            "IDT file for table {:?} has {} column names but {} column types",
+            table_name,
+            column_names.len(),
+            column_codes.len()
+        );
+    }
+    let key_names = &header_fields[1..];
+    for &key_name in key_names {
+        if !column_names.iter().any(|name| name == key_name) {
+            invalid_data!(
+                "IDT file for table {:?} has primary key {:?}, which is not \
+                 one of its columns",
+                table_name,
+                key_name
+            );
+        }
+    }
+    let mut columns = Vec::with_capacity(column_names.len());
+    for (name, code) in column_names.iter().zip(&column_codes) {
+        let is_key = key_names.contains(&name.as_str());
+        columns.push(parse_column(name, code, is_key)?);
+    }
+
+    // Parse the rows, reading any binary values from side-car files.
+    let mut rows = Vec::<Vec<Value>>::with_capacity(lines.len() - 3);
+    let mut streams = Vec::<(String, Vec<u8>)>::new();
+    for (index, &line) in lines.iter().enumerate().skip(3) {
+        let line_number = index + 1;
+        let line = codepage.decode(line);
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != columns.len() {
+            invalid_data!(
+                "Line {} of IDT file for table {:?} has {} fields, but the \
+                 table has {} columns",
+                line_number,
+                table_name,
+                fields.len(),
+                columns.len()
+            );
+        }
+        let mut values = Vec::with_capacity(columns.len());
+        let mut binary_files = Vec::<&str>::new();
+        for (column, &field) in columns.iter().zip(&fields) {
+            let value = if field.is_empty() {
+                Value::Null
+            } else {
+                match column.coltype() {
+                    ColumnType::Int16 | ColumnType::Int32 => {
+                        match field.parse::<i32>() {
+                            Ok(number) => Value::Int(number),
+                            Err(_) => invalid_data!(
+                                "Line {} of IDT file for table {:?} has \
+                                 non-integer value {:?} in column {:?}",
+                                line_number,
+                                table_name,
+                                field,
+                                column.name()
+                            ),
+                        }
+                    }
+                    ColumnType::Str(_) => Value::Str(unescape(field)),
+                    ColumnType::Binary => {
+                        binary_files.push(field);
+                        Value::Binary
+                    }
+                }
+            };
+            values.push(value);
+        }
+        if !binary_files.is_empty() {
+            let dir = match binary_dir {
+                Some(dir) => {
+                    check_file_name(&table_name)?;
+                    dir.join(&table_name)
+                }
+                None => invalid_input!(
+                    "IDT file for table {:?} refers to binary data files, \
+                     which can only be imported from a file on disk",
+                    table_name
+                ),
+            };
+            let keys: Vec<&Value> = columns
+                .iter()
+                .zip(&values)
+                .filter(|(column, _)| column.is_primary_key())
+                .map(|(_, value)| value)
+                .collect();
+            let stream_name = binary_stream_name(&table_name, &keys);
+            for file_name in binary_files {
+                check_file_name(file_name)?;
+                streams.push((
+                    stream_name.clone(),
+                    fs::read(dir.join(file_name))?,
+                ));
+            }
+        }
+        rows.push(values);
+    }
+
+    package.transaction(|package| {
+        replace_table(package, &table_name, columns)?;
+        package.insert_rows(Insert::into(table_name.as_str()).rows(rows))?;
+        for (stream_name, data) in streams {
+            package.write_stream(&stream_name)?.write_all(&data)?;
+        }
+        Ok(())
+    })?;
+    Ok(table_name)
+}
+
+/// Makes sure that the package has an empty table with the given columns,
+/// either by clearing out an existing table with the same columns, or by
+/// (re)creating it.  Any `_Validation` rows already present for a newly
+/// created table (e.g. from an earlier `_Validation` import) are kept.
+pub(crate) fn replace_table<F: Read + Write + Seek>(
+    package: &mut Package<F>,
+    table_name: &str,
+    columns: Vec<Column>,
+) -> io::Result<()> {
+    if let Some(table) = package.get_table(table_name) {
+        let same_columns =
+            table.columns().len() == columns.len()
+                && table.columns().iter().zip(&columns).all(
+                    |(column1, column2)| same_idt_column(column1, column2),
+                );
+        if same_columns {
+            return package.delete_rows(Delete::from(table_name));
+        }
+        package.drop_table(table_name)?;
+    }
+    let for_this_table = || Expr::col("Table").eq(Expr::string(table_name));
+    let has_validation = package
+        .get_table(VALIDATION_TABLE_NAME)
+        .is_some_and(|table| table.has_column("Table"));
+    let saved_rows: Vec<Vec<Value>> = if has_validation {
+        package
+            .select_rows(
+                Select::table(VALIDATION_TABLE_NAME).with(for_this_table()),
+            )?
+            .map(|row| {
+                (0..row.len()).map(|index| row[index].clone()).collect()
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    if !saved_rows.is_empty() {
+        package.delete_rows(
+            Delete::from(VALIDATION_TABLE_NAME).with(for_this_table()),
+        )?;
+    }
+    package.create_table(table_name, columns)?;
+    if !saved_rows.is_empty() {
+        package.delete_rows(
+            Delete::from(VALIDATION_TABLE_NAME).with(for_this_table()),
+        )?;
+        package.insert_rows(
+            Insert::into(VALIDATION_TABLE_NAME).rows(saved_rows).unchecked(),
+        )?;
+    }
+    Ok(())
+}
+
+// ========================================================================= //
+
+#[cfg(test)]
+mod tests {
+    use super::{column_type_code, escape, parse_column, unescape};
+    use crate::internal::column::Column;
+
+    #[test]
+    fn column_type_codes() {
+        let columns = [
+            (Column::build("A").primary_key().id_string(72), "s72"),
+            (Column::build("B").nullable().string(0), "S0"),
+            (Column::build("C").localizable().text_string(255), "l255"),
+            (Column::build("D").nullable().localizable().string(64), "L64"),
+            (Column::build("E").int16(), "i2"),
+            (Column::build("F").nullable().int32(), "I4"),
+            (Column::build("G").binary(), "v0"),
+            (Column::build("H").nullable().binary(), "V0"),
+        ];
+        for (column, code) in columns {
+            assert_eq!(column_type_code(&column), code);
+            let parsed =
+                parse_column(column.name(), code, column.is_primary_key())
+                    .unwrap();
+            assert_eq!(parsed.coltype(), column.coltype());
+            assert_eq!(parsed.is_nullable(), column.is_nullable());
+            assert_eq!(parsed.is_localizable(), column.is_localizable());
+            assert_eq!(parsed.is_primary_key(), column.is_primary_key());
+        }
+        assert!(parse_column("X", "x4", false).is_err());
+        assert!(parse_column("X", "i3", false).is_err());
+        assert!(parse_column("X", "s", false).is_err());
+    }
+
+    #[test]
+    fn escaping() {
+        let string = "one\ttwo\r\nthree";
+        assert_eq!(escape(string), "one\x10two\x11\x19three");
+        assert_eq!(unescape(&escape(string)), string);
+    }
+}
+
+// ========================================================================= //
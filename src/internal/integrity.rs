@@ -0,0 +1,269 @@
+use crate::internal::cache::RowCache;
+use crate::internal::streamname;
+use crate::internal::stringpool::StringPool;
+use crate::internal::table::Table;
+use crate::internal::value::{Value, ValueRef};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::io::{self, Read, Seek, Write};
+use std::rc::Rc;
+
+// ========================================================================= //
+
+const STRING_DATA_TABLE_NAME: &str = "_StringData";
+const STRING_POOL_TABLE_NAME: &str = "_StringPool";
+
+// ========================================================================= //
+
+/// A structural problem with a package's database, as found by
+/// `Package::check_integrity()`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IntegrityProblem {
+    /// The refcount recorded in the string pool for a string doesn't match the
+    /// number of references to it in the database's tables.
+    WrongRefcount {
+        /// The 1-based index of the string in the string pool.
+        string_ref: i32,
+        /// The string stored at that index.
+        string: String,
+        /// The refcount recorded in the string pool.
+        recorded: u16,
+        /// The number of references actually present in the tables.
+        actual: u32,
+    },
+    /// A table row refers to a string that isn't in the string pool.
+    InvalidStringRef {
+        /// The name of the table.
+        table: String,
+        /// The 0-based index of the row within the table's stream.
+        row: usize,
+        /// The name of the column containing the invalid reference.
+        column: String,
+        /// The invalid string pool index.
+        string_ref: i32,
+    },
+    /// A table contains more than one row with the same primary key.
+    DuplicateKey {
+        /// The name of the table.
+        table: String,
+        /// The repeated primary key values.
+        key: Vec<Value>,
+    },
+    /// The length of a table's stream isn't a whole number of rows, which
+    /// usually means that the table has columns that are missing from the
+    /// `_Columns` table.
+    IncompleteRows {
+        /// The name of the table.
+        table: String,
+        /// The length of the table's stream, in bytes.
+        stream_len: u64,
+        /// The length of one row, according to the table's known columns.
+        row_size: u64,
+    },
+    /// A table stream exists that doesn't belong to any table in the
+    /// database.
+    OrphanedStream {
+        /// The decoded name of the stream.
+        name: String,
+    },
+}
+
+impl IntegrityProblem {
+    /// Returns true if `Package::repair()` is able to fix this problem.
+    #[must_use]
+    pub fn is_repairable(&self) -> bool {
+        !matches!(self, Self::IncompleteRows { .. })
+    }
+}
+
+impl fmt::Display for IntegrityProblem {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::WrongRefcount { string_ref, string, recorded, actual } => {
+                write!(
+                    formatter,
+                    "String #{string_ref} ({string:?}) has refcount \
+                     {recorded}, but is referenced {actual} times"
+                )
+            }
+            Self::InvalidStringRef { table, row, column, string_ref } => {
+                write!(
+                    formatter,
+                    "Row {row} of table {table:?} has invalid string ref \
+                     #{string_ref} in column {column:?}"
+                )
+            }
+            Self::DuplicateKey { table, key } => {
+                write!(formatter, "Table {table:?} has repeated key {key:?}")
+            }
+            Self::IncompleteRows { table, stream_len, row_size } => {
+                write!(
+                    formatter,
+                    "Table {table:?} has {stream_len} bytes of data, which \
+                     is not a whole number of {row_size}-byte rows"
+                )
+            }
+            Self::OrphanedStream { name } => {
+                write!(
+                    formatter,
+                    "Table stream {name:?} doesn't belong to any table"
+                )
+            }
+        }
+    }
+}
+
+// ========================================================================= //
+
+/// The results of scanning every table in a database.
+struct Scan {
+    problems: Vec<IntegrityProblem>,
+    /// Tables that had rows dropped, along with their remaining rows.
+    damaged_tables: Vec<(Rc<Table>, Vec<Vec<ValueRef>>)>,
+    /// Encoded names of table streams that don't belong to any table.
+    orphaned_streams: Vec<String>,
+    /// The actual number of references to each string pool entry.
+    refcounts: Vec<u32>,
+}
+
+fn scan<F: Read + Seek>(
+    comp: &mut cfb::CompoundFile<F>,
+    cache: Option<&RowCache>,
+    string_pool: &StringPool,
+    tables: &BTreeMap<String, Rc<Table>>,
+) -> io::Result<Scan> {
+    let mut problems = Vec::<IntegrityProblem>::new();
+    let mut damaged_tables = Vec::new();
+    let mut refcounts = vec![0u32; string_pool.num_strings() as usize];
+    for table in tables.values() {
+        let stream_name = table.stream_name();
+        let dirty_rows =
+            cache.and_then(|cache| cache.dirty_rows(table.name()));
+        let rows = if let Some(rows) = dirty_rows {
+            // The table's stream is out of date, and will be rewritten from
+            // these rows when the package is flushed.
+            rows.to_vec()
+        } else if comp.is_stream(&stream_name) {
+            let stream_len = comp.entry(&stream_name)?.len();
+            let row_size = table.row_size();
+            if row_size > 0 && stream_len % row_size != 0 {
+                problems.push(IntegrityProblem::IncompleteRows {
+                    table: table.name().to_string(),
+                    stream_len,
+                    row_size,
+                });
+            }
+            table.read_rows(comp.open_stream(&stream_name)?)?
+        } else {
+            continue;
+        };
+        let num_rows = rows.len();
+        let key_indices = table.primary_key_indices();
+        let mut keys = HashSet::<Vec<Value>>::new();
+        let mut kept_rows = Vec::with_capacity(num_rows);
+        'rows: for (row_index, row) in rows.into_iter().enumerate() {
+            for (column, value_ref) in table.columns().iter().zip(&row) {
+                if let ValueRef::Str(string_ref) = *value_ref {
+                    if !string_pool.contains(string_ref) {
+                        problems.push(IntegrityProblem::InvalidStringRef {
+                            table: table.name().to_string(),
+                            row: row_index,
+                            column: column.name().to_string(),
+                            string_ref: string_ref.number(),
+                        });
+                        continue 'rows;
+                    }
+                }
+            }
+            let key: Vec<Value> = key_indices
+                .iter()
+                .map(|&index| row[index].to_value(string_pool))
+                .collect();
+            if keys.contains(&key) {
+                problems.push(IntegrityProblem::DuplicateKey {
+                    table: table.name().to_string(),
+                    key,
+                });
+                continue;
+            }
+            keys.insert(key);
+            for value_ref in &row {
+                if let ValueRef::Str(string_ref) = *value_ref {
+                    refcounts[(string_ref.number() - 1) as usize] += 1;
+                }
+            }
+            kept_rows.push(row);
+        }
+        if kept_rows.len() < num_rows {
+            damaged_tables.push((table.clone(), kept_rows));
+        }
+    }
+    for (string_ref, &actual) in string_pool.refs().zip(&refcounts) {
+        let recorded = string_pool.refcount(string_ref);
+        if u32::from(recorded) != actual.min(u32::from(u16::MAX)) {
+            problems.push(IntegrityProblem::WrongRefcount {
+                string_ref: string_ref.number(),
+                string: string_pool.get(string_ref).to_string(),
+                recorded,
+                actual,
+            });
+        }
+    }
+    let table_streams: HashSet<String> =
+        tables.values().map(|table| table.stream_name()).collect();
+    let mut orphaned_streams = Vec::new();
+    for entry in comp.read_root_storage() {
+        if !entry.is_stream() || table_streams.contains(entry.name()) {
+            continue;
+        }
+        let (name, is_table) = streamname::decode(entry.name());
+        if !is_table
+            || name == STRING_POOL_TABLE_NAME
+            || name == STRING_DATA_TABLE_NAME
+        {
+            continue;
+        }
+        orphaned_streams.push(entry.name().to_string());
+        problems.push(IntegrityProblem::OrphanedStream { name });
+    }
+    Ok(Scan { problems, damaged_tables, orphaned_streams, refcounts })
+}
+
+/// Checks the database's tables and string pool for structural problems.
+pub(crate) fn check<F: Read + Seek>(
+    comp: &mut cfb::CompoundFile<F>,
+    cache: &RowCache,
+    string_pool: &StringPool,
+    tables: &BTreeMap<String, Rc<Table>>,
+) -> io::Result<Vec<IntegrityProblem>> {
+    Ok(scan(comp, Some(cache), string_pool, tables)?.problems)
+}
+
+/// Fixes whatever structural problems can be fixed, and returns the list of
+/// all problems that were found (including any that couldn't be fixed).  Any
+/// cached table rows must be written out before calling this, and discarded
+/// afterwards.
+pub(crate) fn repair<F: Read + Write + Seek>(
+    comp: &mut cfb::CompoundFile<F>,
+    string_pool: &mut StringPool,
+    tables: &BTreeMap<String, Rc<Table>>,
+) -> io::Result<Vec<IntegrityProblem>> {
+    let scan = scan(comp, None, string_pool, tables)?;
+    for (table, rows) in scan.damaged_tables {
+        let stream = comp.create_stream(table.stream_name())?;
+        table.write_rows(stream, rows)?;
+    }
+    let string_refs: Vec<_> = string_pool.refs().collect();
+    for (string_ref, actual) in string_refs.into_iter().zip(scan.refcounts) {
+        let actual = u16::try_from(actual).unwrap_or(u16::MAX);
+        if string_pool.refcount(string_ref) != actual {
+            string_pool.set_refcount(string_ref, actual);
+        }
+    }
+    for stream_name in scan.orphaned_streams {
+        comp.remove_stream(stream_name)?;
+    }
+    Ok(scan.problems)
+}
+
+// ========================================================================= //
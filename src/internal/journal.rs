@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+use std::io::{self, Read, Seek, Write};
+
+// ========================================================================= //
+
+/// An undo log for the streams of a compound file, used to roll back a
+/// package transaction.  Before a stream is first overwritten or removed
+/// during the transaction, its original contents (if any) are saved here, so
+/// that they can be put back if the transaction fails.
+#[derive(Default)]
+pub(crate) struct Journal {
+    // Maps each (encoded) stream name to the stream's contents from before the
+    // transaction started, or to `None` if the stream didn't exist then.
+    originals: BTreeMap<String, Option<Vec<u8>>>,
+}
+
+impl Journal {
+    /// Records the current contents of the given stream, unless they have
+    /// already been recorded.  This must be called before the stream is
+    /// created, overwritten, or removed.
+    pub(crate) fn save<F: Read + Seek>(
+        &mut self,
+        comp: &mut cfb::CompoundFile<F>,
+        stream_name: &str,
+    ) -> io::Result<()> {
+        if self.originals.contains_key(stream_name) {
+            return Ok(());
+        }
+        let original = if comp.is_stream(stream_name) {
+            let mut data = Vec::new();
+            comp.open_stream(stream_name)?.read_to_end(&mut data)?;
+            Some(data)
+        } else {
+            None
+        };
+        self.originals.insert(stream_name.to_string(), original);
+        Ok(())
+    }
+
+    /// Folds the records of a committed nested transaction into this
+    /// (enclosing) transaction's journal.  Records already present here are
+    /// older, and so take precedence.
+    pub(crate) fn merge(&mut self, inner: Journal) {
+        for (stream_name, original) in inner.originals {
+            self.originals.entry(stream_name).or_insert(original);
+        }
+    }
+
+    /// Puts every recorded stream back the way it was before the transaction
+    /// started.
+    pub(crate) fn restore<F: Read + Write + Seek>(
+        self,
+        comp: &mut cfb::CompoundFile<F>,
+    ) -> io::Result<()> {
+        for (stream_name, original) in self.originals {
+            match original {
+                Some(data) => {
+                    comp.create_stream(&stream_name)?.write_all(&data)?;
+                }
+                None => {
+                    if comp.is_stream(&stream_name) {
+                        comp.remove_stream(&stream_name)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// ========================================================================= //
+
+/// The error from a transaction that failed, and then also failed to roll
+/// back.  It keeps the transaction's original error as its source (so that
+/// `Error::from_io()` still finds the specific cause), and also records why
+/// the rollback failed.
+#[derive(Debug)]
+pub(crate) struct RollbackError {
+    error: io::Error,
+    restore_error: io::Error,
+}
+
+impl RollbackError {
+    /// Attaches the given rollback failure to the transaction's original
+    /// error, keeping the original error's kind.
+    pub(crate) fn wrap(
+        error: io::Error,
+        restore_error: io::Error,
+    ) -> io::Error {
+        let kind = error.kind();
+        io::Error::new(kind, RollbackError { error, restore_error })
+    }
+}
+
+impl fmt::Display for RollbackError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{} (and then rolling back the transaction failed: {})",
+            self.error, self.restore_error
+        )
+    }
+}
+
+impl error::Error for RollbackError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+// ========================================================================= //
@@ -21,7 +21,15 @@ const SUBLANG_NEUTRAL: u16 = 0x0;
 /// [language]: https://learn.microsoft.com/windows/win32/intl/locales-and-languages
 /// [Language Identifier Constants and Strings]: https://learn.microsoft.com/windows/win32/intl/language-identifier-constants-and-strings
 /// [`Value::from()`]: crate::Value
+///
+/// With the `serde` feature enabled, a [`LanguageId`] serializes as its
+/// numeric identifier (e.g. `1033`).
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct LanguageId(u16);
 
 impl LanguageId {
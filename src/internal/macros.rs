@@ -1,16 +1,5 @@
 // ========================================================================= //
 
-macro_rules! already_exists {
-    ($e:expr) => {
-        return Err(::std::io::Error::new(::std::io::ErrorKind::AlreadyExists,
-                                         $e))
-    };
-    ($fmt:expr, $($arg:tt)+) => {
-        return Err(::std::io::Error::new(::std::io::ErrorKind::AlreadyExists,
-                                         format!($fmt, $($arg)+)))
-    };
-}
-
 macro_rules! invalid_data {
     ($e:expr) => {
         return Err(::std::io::Error::new(::std::io::ErrorKind::InvalidData,
@@ -33,13 +22,11 @@ macro_rules! invalid_input {
     };
 }
 
-macro_rules! not_found {
-    ($e:expr) => {
-        return Err(::std::io::Error::new(::std::io::ErrorKind::NotFound, $e))
-    };
-    ($fmt:expr, $($arg:tt)+) => {
-        return Err(::std::io::Error::new(::std::io::ErrorKind::NotFound,
-                                         format!($fmt, $($arg)+)))
+macro_rules! msi_error {
+    ($variant:ident { $($field:tt)* }) => {
+        return Err(::std::io::Error::from(
+            $crate::internal::error::Error::$variant { $($field)* },
+        ))
     };
 }
 
@@ -1,18 +1,35 @@
 #[macro_use]
 mod macros;
 
+pub mod archive;
+#[cfg(feature = "builder")]
+pub mod builder;
+#[cfg(feature = "builder")]
+pub mod cabinet;
+pub mod cache;
 pub mod category;
 pub mod codepage;
 pub mod column;
+pub mod error;
 pub mod expr;
+pub mod idt;
+pub mod integrity;
+pub mod journal;
 pub mod language;
 pub mod package;
 pub mod propset;
 pub mod query;
+pub mod rows;
+pub mod schema;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 pub mod stream;
 pub mod streamname;
 pub mod stringpool;
 pub mod summary;
 pub mod table;
+pub mod textrows;
 pub mod timestamp;
+pub mod validate;
 pub mod value;
+pub mod wix;
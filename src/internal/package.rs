@@ -1,22 +1,41 @@
+use crate::internal::archive;
+use crate::internal::cache::RowCache;
 use crate::internal::category::Category;
 use crate::internal::codepage::CodePage;
-use crate::internal::column::Column;
+use crate::internal::column::{Column, ColumnValidation};
 use crate::internal::expr::Expr;
+use crate::internal::idt;
+use crate::internal::integrity::{self, IntegrityProblem};
+use crate::internal::journal::{Journal, RollbackError};
 use crate::internal::query::{Delete, Insert, Select, Update};
+use crate::internal::rows::{
+    ComponentRow, CustomActionRow, DirectoryRow, FeatureRow, FileRow,
+    MsiTable, RegistryRow, TypedRow,
+};
+use crate::internal::schema::{self, SchemaVersion};
+#[cfg(feature = "sqlite")]
+use crate::internal::sqlite;
 use crate::internal::stream::{StreamReader, StreamWriter, Streams};
 use crate::internal::streamname::{
     self, DIGITAL_SIGNATURE_STREAM_NAME, MSI_DIGITAL_SIGNATURE_EX_STREAM_NAME,
     SUMMARY_INFO_STREAM_NAME,
 };
-use crate::internal::stringpool::{StringPool, StringPoolBuilder};
+use crate::internal::stringpool::{
+    MAX_SHORT_STRING_REFS, StringPool, StringPoolBuilder, StringPoolLayout,
+};
 use crate::internal::summary::SummaryInfo;
 use crate::internal::table::{Rows, Table};
+use crate::internal::textrows;
+use crate::internal::validate::{self, CubReport, Diagnostic, Rule};
 use crate::internal::value::{Value, ValueRef};
+use crate::internal::wix;
 use cfb;
 use std::borrow::Borrow;
 use std::collections::{BTreeMap, HashMap, HashSet, btree_map};
 use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
 // ========================================================================= //
@@ -34,6 +53,8 @@ const STRING_POOL_TABLE_NAME: &str = "_StringPool";
 
 const MAX_NUM_TABLE_COLUMNS: usize = 32;
 
+const SOURCE_DATE_EPOCH_VAR: &str = "SOURCE_DATE_EPOCH";
+
 // ========================================================================= //
 
 fn make_columns_table(long_string_refs: bool) -> Rc<Table> {
@@ -90,6 +111,132 @@ fn is_reserved_table_name(table_name: &str) -> bool {
         || table_name == VALIDATION_TABLE_NAME
 }
 
+/// Returns the `_Validation` table row describing the given column.
+fn make_validation_row(table_name: &str, column: &Column) -> Vec<Value> {
+    let (min_value, max_value) = if let Some((min, max)) = column.value_range()
+    {
+        (Value::Int(min), Value::Int(max))
+    } else {
+        (Value::Null, Value::Null)
+    };
+    let (key_table, key_column) =
+        if let Some((table, column)) = column.foreign_key() {
+            (Value::Str(table.to_string()), Value::Int(column))
+        } else {
+            (Value::Null, Value::Null)
+        };
+    vec![
+        Value::Str(table_name.to_string()),
+        Value::Str(column.name().to_string()),
+        Value::Str(if column.is_nullable() {
+            "Y".to_string()
+        } else {
+            "N".to_string()
+        }),
+        min_value,
+        max_value,
+        key_table,
+        key_column,
+        if let Some(category) = column.category() {
+            Value::Str(category.to_string())
+        } else if let Some(category) = column.unknown_category() {
+            Value::Str(category.to_string())
+        } else {
+            Value::Null
+        },
+        if let Some(values) = column.enum_values() {
+            Value::Str(values.join(";"))
+        } else {
+            Value::Null
+        },
+        match column.description() {
+            Some(description) => Value::Str(description.to_string()),
+            None => Value::Null,
+        },
+    ]
+}
+
+/// Checks that the `_Columns` entries for the `_Validation` table match the
+/// hard-coded validation table definition, returning a description of the
+/// first mismatch found (if any).
+fn check_validation_columns(
+    expected: &[Column],
+    column_specs: &BTreeMap<i32, (String, i32)>,
+) -> Result<(), String> {
+    if column_specs.len() != expected.len() {
+        return Err(format!(
+            "{:?} table has {} columns, but should have {}",
+            VALIDATION_TABLE_NAME,
+            column_specs.len(),
+            expected.len()
+        ));
+    }
+    for (column, (&index, (name, bitfield))) in
+        expected.iter().zip(column_specs.iter())
+    {
+        let actual = Column::build(name.as_str())
+            .with_bitfield(*bitfield)
+            .map_err(|error| error.to_string())?;
+        if name != column.name()
+            || actual.coltype() != column.coltype()
+            || actual.is_primary_key() != column.is_primary_key()
+        {
+            return Err(format!(
+                "Column {} of {:?} table should be {:?} ({}{}), but is {:?} \
+                 ({}{})",
+                index,
+                VALIDATION_TABLE_NAME,
+                column.name(),
+                if column.is_primary_key() { "key " } else { "" },
+                column.coltype(),
+                name,
+                if actual.is_primary_key() { "key " } else { "" },
+                actual.coltype(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn read_string_pool<F: Read + Seek>(
+    comp: &mut cfb::CompoundFile<F>,
+) -> io::Result<StringPool> {
+    let builder = {
+        let name = streamname::encode(STRING_POOL_TABLE_NAME, true);
+        let stream = comp.open_stream(name)?;
+        StringPoolBuilder::read_from_pool(stream)?
+    };
+    let name = streamname::encode(STRING_DATA_TABLE_NAME, true);
+    let stream = comp.open_stream(name)?;
+    builder.build_from_data(stream)
+}
+
+/// Reads the raw rows of one of the system tables, returning an empty list if
+/// the table's stream doesn't exist.
+fn read_system_table<F: Read + Seek>(
+    comp: &mut cfb::CompoundFile<F>,
+    table: &Table,
+    string_pool: &StringPool,
+    report: &mut OpenReport,
+) -> io::Result<Vec<Vec<ValueRef>>> {
+    let stream_name = table.stream_name();
+    if !comp.exists(&stream_name) {
+        return Ok(Vec::new());
+    }
+    let rows = comp.open_stream(&stream_name).and_then(|stream| {
+        let rows = table.read_rows(stream)?;
+        table.check_string_refs(&rows, string_pool)?;
+        Ok(rows)
+    });
+    match rows {
+        Ok(rows) => Ok(rows),
+        Err(error) => {
+            report.recover(error)?;
+            Ok(Vec::new())
+        }
+    }
+}
+
 // ========================================================================= //
 
 /// The type of MSI package (e.g. installer or patch).
@@ -138,6 +285,211 @@ impl PackageType {
     }
 }
 
+/// Returns the time given by the `SOURCE_DATE_EPOCH` environment variable (see
+/// https://reproducible-builds.org/specs/source-date-epoch/), or the Unix
+/// epoch if it isn't set.
+fn source_date_epoch() -> io::Result<SystemTime> {
+    match std::env::var(SOURCE_DATE_EPOCH_VAR) {
+        Ok(value) => parse_source_date_epoch(&value),
+        Err(std::env::VarError::NotPresent) => Ok(UNIX_EPOCH),
+        Err(std::env::VarError::NotUnicode(value)) => {
+            invalid_input!(
+                "Invalid {} value: {:?}",
+                SOURCE_DATE_EPOCH_VAR,
+                value
+            )
+        }
+    }
+}
+
+/// Parses a `SOURCE_DATE_EPOCH` value, which must be a whole number of
+/// seconds since the Unix epoch.
+fn parse_source_date_epoch(value: &str) -> io::Result<SystemTime> {
+    match value
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|secs| UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+    {
+        Some(timestamp) => Ok(timestamp),
+        None => {
+            invalid_input!(
+                "Invalid {} value: {:?}",
+                SOURCE_DATE_EPOCH_VAR,
+                value
+            )
+        }
+    }
+}
+
+// ========================================================================= //
+
+/// Where to get the fixed time used for timestamps in reproducible packages.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ReproducibleTime {
+    FromEnvironment,
+    Fixed(SystemTime),
+}
+
+// ========================================================================= //
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum OpenMode {
+    Default,
+    Strict,
+    Lenient,
+}
+
+/// Options for opening an existing package, with different levels of
+/// tolerance for inconsistencies in the database's system tables.
+///
+/// # Examples
+///
+/// ```
+/// use msi::{OpenOptions, Package, PackageType};
+/// use std::io::Cursor;
+///
+/// let cursor = Cursor::new(Vec::new());
+/// let package = Package::create(PackageType::Installer, cursor).unwrap();
+/// let cursor = package.into_inner().unwrap();
+///
+/// let package = OpenOptions::new().lenient().open(cursor).unwrap();
+/// assert!(package.open_warnings().is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct OpenOptions {
+    mode: OpenMode,
+}
+
+impl OpenOptions {
+    /// Creates a new set of options with the default behavior, which is the
+    /// same as that of `Package::open()`: structural problems in the system
+    /// tables (such as repeated keys) are errors, while less serious
+    /// inconsistencies (such as unknown column categories) are ignored.
+    #[must_use]
+    pub fn new() -> Self {
+        Self { mode: OpenMode::Default }
+    }
+
+    /// Makes opening fail on any inconsistency in the system tables,
+    /// including ones that are normally ignored, such as a `_Validation`
+    /// table that doesn't match the standard definition, unknown column
+    /// categories, or `_Validation` entries for nonexistent columns.
+    #[must_use]
+    pub fn strict(mut self) -> Self {
+        self.mode = OpenMode::Strict;
+        self
+    }
+
+    /// Makes opening succeed whenever possible, even for damaged databases.
+    /// Instead of returning an error, inconsistent entries are skipped (or
+    /// replaced with defaults), and a description of each problem is recorded
+    /// in `Package::open_warnings()`.
+    #[must_use]
+    pub fn lenient(mut self) -> Self {
+        self.mode = OpenMode::Lenient;
+        self
+    }
+
+    /// Opens an existing MSI file with these options, using the underlying
+    /// reader.  If the underlying reader also supports the `Write` trait, then
+    /// the `Package` object will be writable as well.
+    pub fn open<F: Read + Seek>(&self, inner: F) -> io::Result<Package<F>> {
+        Package::open_with_options(inner, self)
+    }
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ========================================================================= //
+
+/// Options for writing a copy of a package with `Package::save_as()`.
+#[derive(Clone, Debug)]
+pub struct SaveOptions {
+    strip_digital_signature: bool,
+}
+
+impl SaveOptions {
+    /// Creates a new set of options with the default behavior, which is the
+    /// same as that of `Package::save_as()`: everything in the package is
+    /// copied, including any digital signature.
+    #[must_use]
+    pub fn new() -> Self {
+        Self { strip_digital_signature: false }
+    }
+
+    /// Leaves the package's digital signature (if any) out of the copy.
+    #[must_use]
+    pub fn strip_digital_signature(mut self) -> Self {
+        self.strip_digital_signature = true;
+        self
+    }
+
+    /// Writes a copy of the package with these options to the given
+    /// reader/writer, which should be initially empty, and returns the
+    /// reader/writer when done.  See `Package::save_as()`.
+    pub fn save<F: Read + Seek, G: Read + Write + Seek>(
+        &self,
+        package: &mut Package<F>,
+        out: G,
+    ) -> io::Result<G> {
+        package.save_with_options(out, self)
+    }
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Tracks problems found while opening a package, according to the
+/// `OpenMode`.
+struct OpenReport {
+    mode: OpenMode,
+    warnings: Vec<String>,
+}
+
+impl OpenReport {
+    /// Reports a problem that is an error unless opening leniently.
+    fn error(&mut self, message: String) -> io::Result<()> {
+        if self.mode == OpenMode::Lenient {
+            self.warnings.push(message);
+            Ok(())
+        } else {
+            invalid_data!(message)
+        }
+    }
+
+    /// Reports a problem that is only an error when opening strictly, and that
+    /// is otherwise ignored unless opening leniently.
+    fn quirk(&mut self, message: String) -> io::Result<()> {
+        match self.mode {
+            OpenMode::Default => Ok(()),
+            OpenMode::Strict => invalid_data!(message),
+            OpenMode::Lenient => {
+                self.warnings.push(message);
+                Ok(())
+            }
+        }
+    }
+
+    /// Reports an I/O error that can be recovered from when opening
+    /// leniently.
+    fn recover(&mut self, error: io::Error) -> io::Result<()> {
+        if self.mode == OpenMode::Lenient {
+            self.warnings.push(error.to_string());
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+}
+
 // ========================================================================= //
 
 /// An MSI package file, backed by an underlying reader/writer (such as a
@@ -193,7 +545,12 @@ pub struct Package<F> {
     summary_info: SummaryInfo,
     is_summary_info_modified: bool,
     string_pool: StringPool,
+    string_pool_layout: StringPoolLayout,
+    reproducible: Option<ReproducibleTime>,
     tables: BTreeMap<String, Rc<Table>>,
+    cache: RowCache,
+    journal: Option<Journal>,
+    open_warnings: Vec<String>,
     finisher: Option<Box<dyn Finish<F>>>,
 }
 
@@ -216,6 +573,20 @@ impl<F> Package<F> {
         self.string_pool.codepage()
     }
 
+    /// Returns how the string pool will be laid out when the package is
+    /// flushed.
+    #[must_use]
+    pub fn string_pool_layout(&self) -> StringPoolLayout {
+        self.string_pool_layout
+    }
+
+    /// Returns true if the package has been put into reproducible mode (see
+    /// `Package::set_reproducible()`).
+    #[must_use]
+    pub fn is_reproducible(&self) -> bool {
+        self.reproducible.is_some()
+    }
+
     /// Returns true if the database has a table with the given name.
     #[must_use]
     pub fn has_table(&self, table_name: &str) -> bool {
@@ -246,6 +617,25 @@ impl<F> Package<F> {
         Streams::new(self.comp().read_root_storage())
     }
 
+    /// Returns the names of the storages embedded in this package (such as
+    /// nested installer packages or transforms).
+    pub(crate) fn storage_names(&self) -> Vec<String> {
+        self.comp()
+            .read_root_storage()
+            .filter(|entry| entry.is_storage())
+            .map(|entry| streamname::decode(entry.name()).0)
+            .collect()
+    }
+
+    /// Returns descriptions of any problems that were skipped over when this
+    /// package was opened in lenient mode (see `OpenOptions::lenient()`).
+    /// This is always empty for packages opened in other modes, or created
+    /// with `Package::create()`.
+    #[must_use]
+    pub fn open_warnings(&self) -> &[String] {
+        &self.open_warnings
+    }
+
     /// Returns true if the package has been digitally signed.  Note that this
     /// method only checks whether a signature is present; it does *not* verify
     /// that the signature is actually valid.
@@ -262,6 +652,19 @@ impl<F> Package<F> {
         Ok(self.comp.take().unwrap().into_inner())
     }
 
+    /// Writes any buffered changes out to the underlying compound file.  If
+    /// that fails, the changes stay buffered, so that a later flush can try
+    /// again.
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(finisher) = self.finisher.take() {
+            if let Err(error) = finisher.finish(self) {
+                self.finisher = Some(finisher);
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
     fn comp(&self) -> &cfb::CompoundFile<F> {
         self.comp.as_ref().unwrap()
     }
@@ -276,54 +679,77 @@ impl<F: Read + Seek> Package<F> {
     /// underlying reader also supports the `Write` trait, then the `Package`
     /// object will be writable as well.
     pub fn open(inner: F) -> io::Result<Self> {
+        OpenOptions::new().open(inner)
+    }
+
+    fn open_with_options(inner: F, options: &OpenOptions) -> io::Result<Self> {
+        let mut report =
+            OpenReport { mode: options.mode, warnings: Vec::new() };
         let mut comp = cfb::CompoundFile::open(inner)?;
         let package_type = {
             let root_entry = comp.root_entry();
             let clsid = root_entry.clsid();
             match PackageType::from_clsid(clsid) {
                 Some(ptype) => ptype,
-                None => invalid_data!(
-                    "Unrecognized package CLSID ({})",
-                    clsid.hyphenated()
-                ),
+                None => {
+                    report.error(format!(
+                        "Unrecognized package CLSID ({})",
+                        clsid.hyphenated()
+                    ))?;
+                    PackageType::Installer
+                }
             }
         };
-        let summary_info =
-            SummaryInfo::read(comp.open_stream(SUMMARY_INFO_STREAM_NAME)?)?;
-        let string_pool = {
-            let builder = {
-                let name = streamname::encode(STRING_POOL_TABLE_NAME, true);
-                let stream = comp.open_stream(name)?;
-                StringPoolBuilder::read_from_pool(stream)?
-            };
-            let name = streamname::encode(STRING_DATA_TABLE_NAME, true);
-            let stream = comp.open_stream(name)?;
-            builder.build_from_data(stream)?
+        let summary_info = match comp
+            .open_stream(SUMMARY_INFO_STREAM_NAME)
+            .and_then(SummaryInfo::read)
+        {
+            Ok(summary_info) => summary_info,
+            Err(error) => {
+                report.recover(error)?;
+                SummaryInfo::new()
+            }
+        };
+        let string_pool = match read_string_pool(&mut comp) {
+            Ok(string_pool) => string_pool,
+            Err(error) => {
+                report.recover(error)?;
+                let mut string_pool = StringPool::new(summary_info.codepage());
+                string_pool.mark_unmodified();
+                string_pool
+            }
         };
         let mut all_tables = BTreeMap::<String, Rc<Table>>::new();
         // Read in _Tables table:
         let table_names: HashSet<String> = {
             let table = make_tables_table(string_pool.long_string_refs());
-            let stream_name = table.stream_name();
             let mut names = HashSet::<String>::new();
-            if comp.exists(&stream_name) {
-                let stream = comp.open_stream(&stream_name)?;
-                let rows = Rows::new(
-                    &string_pool,
-                    table.clone(),
-                    table.read_rows(stream)?,
-                );
-                for row in rows {
-                    let table_name = row[0].as_str().unwrap().to_string();
-                    if names.contains(&table_name) {
-                        invalid_data!(
-                            "Repeated key in {:?} table: {:?}",
-                            TABLES_TABLE_NAME,
-                            table_name
-                        );
+            for row in read_system_table(
+                &mut comp,
+                &table,
+                &string_pool,
+                &mut report,
+            )? {
+                let table_name = match row[0] {
+                    ValueRef::Str(string_ref) => {
+                        string_pool.get(string_ref).to_string()
                     }
-                    names.insert(table_name);
+                    _ => {
+                        report.error(format!(
+                            "Null key in {:?} table",
+                            TABLES_TABLE_NAME
+                        ))?;
+                        continue;
+                    }
+                };
+                if names.contains(&table_name) {
+                    report.error(format!(
+                        "Repeated key in {:?} table: {:?}",
+                        TABLES_TABLE_NAME, table_name
+                    ))?;
+                    continue;
                 }
+                names.insert(table_name);
             }
             all_tables.insert(table.name().to_string(), table);
             names
@@ -336,129 +762,178 @@ impl<F: Read + Seek> Package<F> {
                 .collect();
         {
             let table = make_columns_table(string_pool.long_string_refs());
-            let stream_name = table.stream_name();
-            if comp.exists(&stream_name) {
-                let stream = comp.open_stream(&stream_name)?;
-                let rows = Rows::new(
-                    &string_pool,
-                    table.clone(),
-                    table.read_rows(stream)?,
-                );
-                for row in rows {
-                    let table_name = row[0].as_str().unwrap();
-                    if let Some(cols) = columns_map.get_mut(table_name) {
-                        let col_index = row[1].as_int().unwrap();
-                        if cols.contains_key(&col_index) {
-                            invalid_data!(
-                                "Repeated key in {:?} table: {:?}",
-                                COLUMNS_TABLE_NAME,
-                                (table_name, col_index)
-                            );
+            for row in read_system_table(
+                &mut comp,
+                &table,
+                &string_pool,
+                &mut report,
+            )? {
+                let (table_name, col_index, col_name, type_bits) =
+                    match (row[0], row[1], row[2], row[3]) {
+                        (
+                            ValueRef::Str(table_name),
+                            ValueRef::Int(col_index),
+                            ValueRef::Str(col_name),
+                            ValueRef::Int(type_bits),
+                        ) => (
+                            string_pool.get(table_name),
+                            col_index,
+                            string_pool.get(col_name),
+                            type_bits,
+                        ),
+                        _ => {
+                            report.error(format!(
+                                "Null value in {:?} table",
+                                COLUMNS_TABLE_NAME
+                            ))?;
+                            continue;
                         }
-                        let col_name = row[2].as_str().unwrap().to_string();
-                        let type_bits = row[3].as_int().unwrap();
-                        cols.insert(col_index, (col_name, type_bits));
-                    } else {
-                        invalid_data!(
-                            "_Columns mentions table {:?}, which isn't in \
-                             _Tables",
-                            table_name
-                        );
+                    };
+                if let Some(cols) = columns_map.get_mut(table_name) {
+                    if cols.contains_key(&col_index) {
+                        report.error(format!(
+                            "Repeated key in {:?} table: {:?}",
+                            COLUMNS_TABLE_NAME,
+                            (table_name, col_index)
+                        ))?;
+                        continue;
                     }
+                    cols.insert(col_index, (col_name.to_string(), type_bits));
+                } else {
+                    report.error(format!(
+                        "_Columns mentions table {:?}, which isn't in _Tables",
+                        table_name
+                    ))?;
                 }
             }
             all_tables.insert(table.name().to_string(), table);
         }
         // Read in _Validation table:
         let mut validation_map =
-            HashMap::<(String, String), Vec<ValueRef>>::new();
+            HashMap::<(String, String), Vec<Value>>::new();
         {
             let table = make_validation_table(string_pool.long_string_refs());
-            // TODO: Ensure that columns_map["_Validation"].columns() matches
-            // the hard-coded validation table definition.
-            let stream_name = table.stream_name();
-            if comp.exists(&stream_name) {
-                let stream = comp.open_stream(&stream_name)?;
-                for value_refs in table.read_rows(stream)? {
-                    let table_name = value_refs[0]
-                        .to_value(&string_pool)
-                        .as_str()
-                        .unwrap()
-                        .to_string();
-                    let column_name = value_refs[1]
-                        .to_value(&string_pool)
-                        .as_str()
-                        .unwrap()
-                        .to_string();
-                    let key = (table_name, column_name);
-                    if validation_map.contains_key(&key) {
-                        invalid_data!(
-                            "Repeated key in {:?} table: {:?}",
-                            VALIDATION_TABLE_NAME,
-                            key
-                        );
+            let matches_definition = match columns_map.get(table.name()) {
+                Some(column_specs) => {
+                    check_validation_columns(table.columns(), column_specs)
+                }
+                None => Ok(()),
+            };
+            let should_read = match matches_definition {
+                Ok(()) => true,
+                Err(message) => {
+                    report.quirk(message)?;
+                    report.mode != OpenMode::Lenient
+                }
+            };
+            let rows = if should_read {
+                read_system_table(
+                    &mut comp,
+                    &table,
+                    &string_pool,
+                    &mut report,
+                )?
+            } else {
+                Vec::new()
+            };
+            for value_refs in rows {
+                let values: Vec<Value> = value_refs
+                    .into_iter()
+                    .map(|value_ref| value_ref.to_value(&string_pool))
+                    .collect();
+                let key = match (values[0].as_str(), values[1].as_str()) {
+                    (Some(table_name), Some(column_name)) => {
+                        (table_name.to_string(), column_name.to_string())
+                    }
+                    _ => {
+                        report.error(format!(
+                            "Null key in {:?} table",
+                            VALIDATION_TABLE_NAME
+                        ))?;
+                        continue;
                     }
-                    validation_map.insert(key, value_refs);
+                };
+                if validation_map.contains_key(&key) {
+                    report.error(format!(
+                        "Repeated key in {:?} table: {:?}",
+                        VALIDATION_TABLE_NAME, key
+                    ))?;
+                    continue;
                 }
+                validation_map.insert(key, values);
             }
         }
         // Construct Table objects from column/validation data:
         for (table_name, column_specs) in columns_map {
             if column_specs.is_empty() {
-                invalid_data!("No columns found for table {:?}", table_name);
+                report.error(format!(
+                    "No columns found for table {:?}",
+                    table_name
+                ))?;
+                continue;
             }
             let num_columns = column_specs.len() as i32;
             if column_specs.keys().next() != Some(&1)
                 || column_specs.keys().next_back() != Some(&num_columns)
             {
-                invalid_data!(
+                report.error(format!(
                     "Table {:?} does not have a complete set of columns",
                     table_name
-                );
+                ))?;
+                continue;
             }
             let mut columns = Vec::<Column>::with_capacity(column_specs.len());
             for (_, (column_name, bitfield)) in column_specs {
                 let mut builder = Column::build(column_name.as_str());
                 let key = (table_name.clone(), column_name);
-                if let Some(value_refs) = validation_map.get(&key) {
-                    let is_nullable = value_refs[2].to_value(&string_pool);
-                    if is_nullable.as_str().unwrap() == "Y" {
+                if let Some(values) = validation_map.remove(&key) {
+                    if values[2].as_str() == Some("Y") {
                         builder = builder.nullable();
                     }
-                    let min_value = value_refs[3].to_value(&string_pool);
-                    let max_value = value_refs[4].to_value(&string_pool);
-                    if !min_value.is_null() && !max_value.is_null() {
-                        let min = min_value.as_int().unwrap();
-                        let max = max_value.as_int().unwrap();
+                    if let (Some(min), Some(max)) =
+                        (values[3].as_int(), values[4].as_int())
+                    {
                         builder = builder.range(min, max);
                     }
-                    let key_table = value_refs[5].to_value(&string_pool);
-                    let key_column = value_refs[6].to_value(&string_pool);
-                    if !key_table.is_null() && !key_column.is_null() {
-                        builder = builder.foreign_key(
-                            key_table.as_str().unwrap(),
-                            key_column.as_int().unwrap(),
-                        );
+                    if let (Some(key_table), Some(key_column)) =
+                        (values[5].as_str(), values[6].as_int())
+                    {
+                        builder = builder.foreign_key(key_table, key_column);
                     }
-                    let category_value = value_refs[7].to_value(&string_pool);
-                    if !category_value.is_null() {
-                        let category = category_value
-                            .as_str()
-                            .unwrap()
-                            .parse::<Category>()
-                            .ok();
-                        if let Some(category) = category {
-                            builder = builder.category(category);
+                    if let Some(category) = values[7].as_str() {
+                        match category.parse::<Category>() {
+                            Ok(category) => {
+                                builder = builder.category(category);
+                            }
+                            Err(_) => {
+                                report.quirk(format!(
+                                    "Unknown category {:?} for column {:?} \
+                                     of table {:?}",
+                                    category, key.1, table_name
+                                ))?;
+                                builder = builder.unknown_category(category);
+                            }
                         }
                     }
-                    let enum_values = value_refs[8].to_value(&string_pool);
-                    if !enum_values.is_null() {
+                    if let Some(enum_values) = values[8].as_str() {
                         let enum_values: Vec<&str> =
-                            enum_values.as_str().unwrap().split(';').collect();
+                            enum_values.split(';').collect();
                         builder = builder.enum_values(&enum_values);
                     }
+                    if let Some(description) = values[9].as_str() {
+                        builder = builder.description(description);
+                    }
+                }
+                match builder.with_bitfield(bitfield) {
+                    Ok(column) => columns.push(column),
+                    Err(error) => {
+                        report.recover(error)?;
+                        break;
+                    }
                 }
-                columns.push(builder.with_bitfield(bitfield)?);
+            }
+            if columns.len() != num_columns as usize {
+                continue;
             }
             let table = Table::new(
                 table_name,
@@ -467,13 +942,28 @@ impl<F: Read + Seek> Package<F> {
             );
             all_tables.insert(table.name().to_string(), table);
         }
+        let mut leftover: Vec<&(String, String)> =
+            validation_map.keys().collect();
+        leftover.sort();
+        for (table_name, column_name) in leftover {
+            report.quirk(format!(
+                "_Validation mentions column {:?} of table {:?}, which \
+                 doesn't exist",
+                column_name, table_name
+            ))?;
+        }
         Ok(Self {
             comp: Some(comp),
             package_type,
             summary_info,
             is_summary_info_modified: false,
             string_pool,
+            string_pool_layout: StringPoolLayout::default(),
+            reproducible: None,
             tables: all_tables,
+            cache: RowCache::default(),
+            journal: None,
+            open_warnings: report.warnings,
             finisher: None,
         })
     }
@@ -484,11 +974,70 @@ impl<F: Read + Seek> Package<F> {
     pub fn select_rows(&mut self, query: Select) -> io::Result<Rows<'_>> {
         query.exec(
             self.comp.as_mut().unwrap(),
+            &mut self.cache,
             &self.string_pool,
             &self.tables,
         )
     }
 
+    /// Reads every row of the table for the given typed row type (see the
+    /// `rows` module).  Returns an error if the table doesn't exist, or if
+    /// any of its rows can't be converted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use msi::rows::PropertyRow;
+    /// # use msi::{Insert, Package, PackageType, Value};
+    /// # use std::io::Cursor;
+    /// # let cursor = Cursor::new(Vec::new());
+    /// # let mut package = Package::create(PackageType::Installer, cursor)
+    /// #     .unwrap();
+    /// # package.create_standard_table("Property").unwrap();
+    /// # package.insert_rows(Insert::into("Property").row(vec![
+    /// #     Value::from("ProductName"),
+    /// #     Value::from("Widget"),
+    /// # ])).unwrap();
+    /// let properties = package.typed_rows::<PropertyRow>().unwrap();
+    /// assert_eq!(properties[0].property, "ProductName");
+    /// assert_eq!(properties[0].value, "Widget");
+    /// ```
+    pub fn typed_rows<T: TypedRow>(&mut self) -> io::Result<Vec<T>> {
+        self.select_rows(Select::table(T::TABLE_NAME))?
+            .map(|row| T::from_row(&row))
+            .collect()
+    }
+
+    /// Reads every row of the `Component` table.
+    pub fn components(&mut self) -> io::Result<Vec<ComponentRow>> {
+        self.typed_rows()
+    }
+
+    /// Reads every row of the `CustomAction` table.
+    pub fn custom_actions(&mut self) -> io::Result<Vec<CustomActionRow>> {
+        self.typed_rows()
+    }
+
+    /// Reads every row of the `Directory` table.
+    pub fn directories(&mut self) -> io::Result<Vec<DirectoryRow>> {
+        self.typed_rows()
+    }
+
+    /// Reads every row of the `Feature` table.
+    pub fn features(&mut self) -> io::Result<Vec<FeatureRow>> {
+        self.typed_rows()
+    }
+
+    /// Reads every row of the `File` table.
+    pub fn files(&mut self) -> io::Result<Vec<FileRow>> {
+        self.typed_rows()
+    }
+
+    /// Reads every row of the `Registry` table.
+    pub fn registry_entries(&mut self) -> io::Result<Vec<RegistryRow>> {
+        self.typed_rows()
+    }
+
     /// Opens an existing binary stream in the package for reading.
     pub fn read_stream(
         &mut self,
@@ -499,11 +1048,266 @@ impl<F: Read + Seek> Package<F> {
         }
         let encoded_name = streamname::encode(stream_name, false);
         if !self.comp().is_stream(&encoded_name) {
-            not_found!("Stream {:?} does not exist", stream_name);
+            msi_error!(StreamNotFound { stream: stream_name.to_string() });
         }
         Ok(StreamReader::new(self.comp_mut().open_stream(&encoded_name)?))
     }
 
+    /// Writes the given table to `writer` in the tab-delimited IDT archive
+    /// format used by `msidb.exe`.  The pseudo-table names `"_ForceCodepage"`
+    /// and `"_SummaryInformation"` write IDT files recording the database
+    /// code page and the summary information instead.
+    ///
+    /// Binary values are written as the names of the side-car files that
+    /// would hold their data; use `Package::export_idt_to_dir()` to write
+    /// those files as well.
+    pub fn export_idt<W: Write>(
+        &mut self,
+        table_name: &str,
+        writer: W,
+    ) -> io::Result<()> {
+        idt::export(self, table_name, writer, None)?;
+        Ok(())
+    }
+
+    /// Writes the given table to an IDT file named after the table (e.g.
+    /// `File.idt`) within the given directory, and the data of any binary
+    /// values to side-car files in a subdirectory named after the table (as
+    /// `msidb.exe` does).  Returns the path of the IDT file.
+    ///
+    /// Returns an error without writing anything if the table name, or the
+    /// primary key of a row with a binary value, can't safely be used as a
+    /// file name (e.g. because it contains a path separator).
+    pub fn export_idt_to_dir<P: AsRef<Path>>(
+        &mut self,
+        table_name: &str,
+        dir: P,
+    ) -> io::Result<PathBuf> {
+        let dir = dir.as_ref();
+        idt::check_file_name(table_name)?;
+        let path = dir.join(format!("{table_name}.idt"));
+        let mut data = Vec::new();
+        idt::export(self, table_name, &mut data, Some(dir))?;
+        std::fs::write(&path, data)?;
+        Ok(path)
+    }
+
+    /// Writes the whole package into the given directory (creating it if
+    /// necessary), in a form suited to reviewing changes with ordinary diff
+    /// tools:
+    ///
+    /// * an IDT file for each table, with side-car files for binary values
+    ///   (as written by `Package::export_idt_to_dir()`);
+    /// * `_SummaryInformation.idt` and `_ForceCodepage.idt`, holding the
+    ///   summary information and the database code page;
+    /// * a `_Streams` subdirectory holding any streams that aren't binary
+    ///   values of a table (such as cabinets);
+    /// * a `_Storages` subdirectory holding each embedded storage as a
+    ///   standalone compound file.
+    ///
+    /// Use `Package::pack()` to rebuild a package from the directory.
+    ///
+    /// Returns an error without writing anything if a table, stream, or
+    /// storage name can't safely be used as a file name (e.g. because it
+    /// contains a path separator).
+    pub fn unpack<P: AsRef<Path>>(&mut self, dir: P) -> io::Result<()> {
+        archive::unpack(self, dir.as_ref())
+    }
+
+    /// Decompiles the package into WiX v4 source, written to `Package.wxs`
+    /// within the given directory (which is created if necessary), and
+    /// returns the path of that file.  The `Directory`, `Component`, `File`,
+    /// `Registry`, `Shortcut`, `Feature`, `FeatureComponents`, `Property`,
+    /// `Media`, `CustomAction`, and sequence tables are converted to the
+    /// corresponding WiX elements; other tables are ignored.
+    ///
+    /// The data of `Binary` and `Icon` table rows is extracted to `Binary`
+    /// and `Icon` subdirectories, and embedded cabinets to the directory
+    /// itself.  `File` elements refer to their sources as `File\<key>`, which
+    /// is where extracting each cabinet into a `File` subdirectory puts them
+    /// (since cabinet entries are named after their `File` table keys).
+    pub fn decompile_wix<P: AsRef<Path>>(
+        &mut self,
+        dir: P,
+    ) -> io::Result<PathBuf> {
+        wix::decompile(self, dir.as_ref())
+    }
+
+    /// Writes the whole package to a new SQLite database file at the given
+    /// path, for running queries that `Select` can't express.  Each table
+    /// becomes an SQLite table with the same columns and primary key, with
+    /// binary values stored as blobs holding the stream data.  The package
+    /// type and code page, the column types, the summary information, and
+    /// any other streams and embedded storages go in the `_Database`,
+    /// `_Columns`, `_SummaryInformation`, `_Streams`, and `_Storages` tables.
+    /// Use `Package::import_sqlite()` to rebuild the package.
+    ///
+    /// Returns an error without writing anything if the file already holds
+    /// any of these tables.  Requires the `sqlite` feature.
+    #[cfg(feature = "sqlite")]
+    pub fn export_sqlite<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> io::Result<()> {
+        sqlite::export(self, path.as_ref())
+    }
+
+    /// Writes a copy of the given embedded storage (and everything within
+    /// it) to `out` as a standalone compound file.
+    pub(crate) fn export_storage<G: Read + Write + Seek>(
+        &mut self,
+        storage_name: &str,
+        out: G,
+    ) -> io::Result<G> {
+        let path =
+            Path::new("/").join(streamname::encode(storage_name, false));
+        let comp = self.comp.as_mut().unwrap();
+        let entries: Vec<cfb::Entry> = comp.walk_storage(&path)?.collect();
+        let mut out =
+            cfb::CompoundFile::create_with_version(comp.version(), out)?;
+        for entry in &entries {
+            let out_path =
+                Path::new("/").join(entry.path().strip_prefix(&path).unwrap());
+            if entry.is_stream() {
+                let mut stream = comp.open_stream(entry.path())?;
+                io::copy(&mut stream, &mut out.create_stream(&out_path)?)?;
+            } else {
+                // The storage itself becomes the copy's root storage.
+                if out_path != Path::new("/") {
+                    out.create_storage(&out_path)?;
+                }
+                out.set_storage_clsid(&out_path, *entry.clsid())?;
+            }
+            out.set_state_bits(&out_path, entry.state_bits())?;
+        }
+        out.flush()?;
+        Ok(out.into_inner())
+    }
+
+    /// Runs all of the built-in validation rules (see `Rule::all()`) over the
+    /// package, and returns a list of any problems found.  Returns an error
+    /// only if the package's tables could not be read.
+    pub fn validate(&mut self) -> io::Result<Vec<Diagnostic>> {
+        self.validate_rules(&Rule::all())
+    }
+
+    /// Runs the given validation rules over the package, and returns a list of
+    /// any problems found.
+    pub fn validate_rules(
+        &mut self,
+        rules: &[Rule],
+    ) -> io::Result<Vec<Diagnostic>> {
+        let database = validate::Database::load(self)?;
+        Ok(validate::run_rules(&database, rules))
+    }
+
+    /// Runs the built-in validation rules (see `Rule`) that are named in a
+    /// .cub file's `_ICESequence` table over this package.  The .cub file's
+    /// tables (such as its `_Validation` table) are merged into a temporary
+    /// view of this package first; this package itself is not modified.
+    ///
+    /// Each `_ICESequence` entry's condition, if any, is evaluated against
+    /// the merged `Property` table, and the entry is skipped if it is false.
+    /// No code from the .cub file is ever executed: entries that name a rule
+    /// with no built-in implementation (such as ones implemented by custom
+    /// actions), or whose conditions refer to things that only exist during
+    /// an installation (such as component states), are skipped.  Every
+    /// skipped entry is listed in the returned report, with the reason why.
+    ///
+    /// Note that this only ever runs rules from the fixed set of built-in
+    /// ones (ICE02, ICE03, ICE06, ICE08, ICE27, and `DirectoryTree`).  The
+    /// .cub file's own rules, including any organization-specific ones, are
+    /// always custom actions, and are reported as skipped rather than being
+    /// evaluated, even if their logic could be expressed as a condition.
+    pub fn validate_rules_from_cub<G: Read + Seek>(
+        &mut self,
+        cub: &mut Package<G>,
+    ) -> io::Result<CubReport> {
+        let database = validate::Database::load(self)?;
+        let cub_database = validate::Database::load(cub)?;
+        Ok(validate::run_rules_from_cub(database, cub_database))
+    }
+
+    /// Writes a copy of the package to the given reader/writer (which should
+    /// be initially empty), and returns the reader/writer when done.
+    ///
+    /// Note that any changes buffered in this package are first flushed to
+    /// its own underlying writer, just as `flush()` would, so saving a
+    /// modified package updates the original in place as well as writing the
+    /// copy.  If that flush fails, the changes stay buffered.
+    ///
+    /// The copy has the same tables, streams, storages, and summary
+    /// information as this package, but is written out from scratch, so it
+    /// doesn't contain any of the unused space that removing tables and
+    /// streams leaves behind in the original.  To leave out the digital
+    /// signature, use `SaveOptions::strip_digital_signature()`.  Returns an
+    /// error if called during a transaction.
+    pub fn save_as<G: Read + Write + Seek>(
+        &mut self,
+        out: G,
+    ) -> io::Result<G> {
+        self.save_with_options(out, &SaveOptions::new())
+    }
+
+    fn save_with_options<G: Read + Write + Seek>(
+        &mut self,
+        out: G,
+        options: &SaveOptions,
+    ) -> io::Result<G> {
+        if self.journal.is_some() {
+            invalid_input!("Cannot save a package during a transaction");
+        }
+        self.finish()?;
+        let comp = self.comp.as_mut().unwrap();
+        let entries: Vec<cfb::Entry> = comp
+            .walk()
+            .filter(|entry| {
+                !(options.strip_digital_signature
+                    && entry.path().parent() == Some(Path::new("/"))
+                    && (entry.name() == DIGITAL_SIGNATURE_STREAM_NAME
+                        || entry.name()
+                            == MSI_DIGITAL_SIGNATURE_EX_STREAM_NAME))
+            })
+            .collect();
+        let mut out =
+            cfb::CompoundFile::create_with_version(comp.version(), out)?;
+        for entry in &entries {
+            let path = entry.path();
+            if entry.is_stream() {
+                let mut stream = comp.open_stream(path)?;
+                io::copy(&mut stream, &mut out.create_stream(path)?)?;
+            } else {
+                if !entry.is_root() {
+                    out.create_storage(path)?;
+                }
+                out.set_storage_clsid(path, *entry.clsid())?;
+            }
+            out.set_state_bits(path, entry.state_bits())?;
+        }
+        // Creating entries within a storage updates its modified time, so
+        // copy the storage timestamps only once everything is in place.
+        for entry in entries.iter().filter(|entry| !entry.is_stream()) {
+            out.set_created_time(entry.path(), entry.created())?;
+            out.set_modified_time(entry.path(), entry.modified())?;
+        }
+        out.flush()?;
+        Ok(out.into_inner())
+    }
+
+    /// Checks the package's database for structural problems, such as string
+    /// pool refcounts that don't match the references actually present in the
+    /// tables, references to nonexistent strings, repeated primary keys, or
+    /// table streams that don't belong to any table.  Returns an empty list if
+    /// no problems are found.  See also `Package::repair()`.
+    pub fn check_integrity(&mut self) -> io::Result<Vec<IntegrityProblem>> {
+        integrity::check(
+            self.comp.as_mut().unwrap(),
+            &self.cache,
+            &self.string_pool,
+            &self.tables,
+        )
+    }
+
     // TODO: pub fn has_valid_digital_signature(&mut self) -> io::Result<bool>
 }
 
@@ -530,7 +1334,12 @@ impl<F: Read + Write + Seek> Package<F> {
             summary_info,
             is_summary_info_modified: true,
             string_pool,
+            string_pool_layout: StringPoolLayout::default(),
+            reproducible: None,
             tables,
+            cache: RowCache::default(),
+            journal: None,
+            open_warnings: Vec::new(),
             finisher: None,
         };
         package
@@ -541,6 +1350,37 @@ impl<F: Read + Write + Seek> Package<F> {
         Ok(package)
     }
 
+    /// Creates a new package of the given type from a directory written by
+    /// `Package::unpack()`, using the underlying reader/writer (which should
+    /// be initially empty).  Tables are imported as with
+    /// `Package::import_idt_file()`, with the `_Validation` table imported
+    /// last so that its rows replace the ones generated for the other tables.
+    /// The tables and streams are imported in a single transaction, so if any
+    /// of them fails, none of them is added.
+    pub fn pack<P: AsRef<Path>>(
+        package_type: PackageType,
+        dir: P,
+        inner: F,
+    ) -> io::Result<Self> {
+        let mut package = Self::create(package_type, inner)?;
+        archive::pack(&mut package, dir.as_ref())?;
+        package.flush()?;
+        Ok(package)
+    }
+
+    /// Creates a new package from an SQLite database file written by
+    /// `Package::export_sqlite()`, using the underlying reader/writer (which
+    /// should be initially empty).  Returns an error if any row is invalid
+    /// for its table (e.g. after editing the database).  Requires the
+    /// `sqlite` feature.
+    #[cfg(feature = "sqlite")]
+    pub fn import_sqlite<P: AsRef<Path>>(
+        path: P,
+        inner: F,
+    ) -> io::Result<Self> {
+        sqlite::import(path.as_ref(), inner)
+    }
+
     /// Returns a mutable reference to the summary information for this
     /// package.  Call `flush()` or drop the `Package` object to persist any
     /// changes made to the underlying writer.
@@ -556,6 +1396,59 @@ impl<F: Read + Write + Seek> Package<F> {
         self.string_pool.set_codepage(codepage)
     }
 
+    /// Sets how the string pool will be laid out when the package is flushed.
+    /// With `StringPoolLayout::Sorted`, two packages with the same database
+    /// contents will have identical string pools and table streams, no matter
+    /// the order in which the contents were added.
+    pub fn set_string_pool_layout(&mut self, layout: StringPoolLayout) {
+        self.set_finisher();
+        self.string_pool_layout = layout;
+    }
+
+    /// Turns reproducible mode on or off.  In reproducible mode, each flush
+    /// normalizes everything in the package that would otherwise depend on
+    /// when it was built, or on the order in which its contents were added:
+    ///
+    /// * The string pool is compacted and sorted (as with
+    ///   `StringPoolLayout::Sorted`), so the string pool and table streams
+    ///   depend only on the database contents.
+    /// * Any date/time properties set in the summary information, and the
+    ///   timestamps of all storages in the underlying compound file, are set
+    ///   to the time given by the `SOURCE_DATE_EPOCH` environment variable,
+    ///   or to the Unix epoch (1970-01-01 00:00:00 UTC) if it isn't set.
+    /// * The root storage's CLSID is set to the standard one for the package
+    ///   type, and the state bits of all compound file entries are cleared.
+    ///   (The CLSIDs of any other storages are part of the package contents,
+    ///   and are left as they are.)
+    ///
+    /// The one thing that flushing in place can't normalize is where in the
+    /// underlying file the compound file's sectors end up, which depends on
+    /// the order in which streams were written, overwritten, and removed (and
+    /// on what the file held before).  Two packages with the same contents
+    /// are byte-identical if they were built with the same sequence of
+    /// operations; to get byte-identical output regardless of how a package
+    /// was built, write it out with `Package::save_as()`, which lays out a
+    /// fresh compound file with its entries in a fixed order.
+    ///
+    /// Flushing returns an error if `SOURCE_DATE_EPOCH` is set to something
+    /// other than a whole number of seconds.
+    pub fn set_reproducible(&mut self, reproducible: bool) {
+        self.set_finisher();
+        self.reproducible = if reproducible {
+            Some(ReproducibleTime::FromEnvironment)
+        } else {
+            None
+        };
+    }
+
+    /// Turns on reproducible mode (see `Package::set_reproducible()`), using
+    /// the given time for all timestamps instead of the `SOURCE_DATE_EPOCH`
+    /// environment variable.
+    pub fn set_reproducible_with_time(&mut self, timestamp: SystemTime) {
+        self.set_finisher();
+        self.reproducible = Some(ReproducibleTime::Fixed(timestamp));
+    }
+
     /// Creates a new database table.  Returns an error without modifying the
     /// database if the table name or columns are invalid, or if a table with
     /// that name already exists.
@@ -607,79 +1500,104 @@ impl<F: Read + Write + Seek> Package<F> {
             }
         }
         if self.tables.contains_key(&table_name) {
-            already_exists!("Table {:?} already exists", table_name);
-        }
-        self.insert_rows(
-            Insert::into(COLUMNS_TABLE_NAME).rows(
-                columns
-                    .iter()
-                    .enumerate()
-                    .map(|(index, column)| {
-                        vec![
-                            Value::Str(table_name.clone()),
-                            Value::Int(1 + index as i32),
-                            Value::Str(column.name().to_string()),
-                            Value::Int(column.bitfield()),
-                        ]
-                    })
-                    .collect(),
+            msi_error!(TableAlreadyExists { table: table_name });
+        }
+        // Either all of the table's metadata gets written, or none of it does.
+        self.transaction(|package| {
+            package.insert_rows(
+                Insert::into(COLUMNS_TABLE_NAME).rows(
+                    columns
+                        .iter()
+                        .enumerate()
+                        .map(|(index, column)| {
+                            vec![
+                                Value::Str(table_name.clone()),
+                                Value::Int(1 + index as i32),
+                                Value::Str(column.name().to_string()),
+                                Value::Int(column.bitfield()),
+                            ]
+                        })
+                        .collect(),
+                ),
+            )?;
+            package.insert_rows(
+                Insert::into(TABLES_TABLE_NAME)
+                    .row(vec![Value::Str(table_name.clone())]),
+            )?;
+            let validation_rows: Vec<Vec<Value>> = columns
+                .iter()
+                .map(|column| make_validation_row(&table_name, column))
+                .collect();
+            let columns_have_unknown_categories = columns
+                .iter()
+                .any(|column| column.unknown_category().is_some());
+            let long_string_refs = package.string_pool.long_string_refs();
+            let table =
+                Table::new(table_name.clone(), columns, long_string_refs);
+            package.tables.insert(table_name, table);
+            let mut insert = Insert::into(VALIDATION_TABLE_NAME);
+            if columns_have_unknown_categories {
+                insert = insert.unchecked();
+            }
+            package.insert_rows(insert.rows(validation_rows))?;
+            Ok(())
+        })
+    }
+
+    /// Creates a new database table with the name and columns of one of the
+    /// standard Windows Installer tables (e.g. `"File"`), as defined in this
+    /// package's schema version.  The schema version is taken from the "Page
+    /// Count" property of the package's summary information, or is the newest
+    /// supported version if that property isn't set; to use the definition
+    /// from a specific schema version instead, pass the columns returned by
+    /// `schema::table_columns()` to `create_table()`.
+    ///
+    /// Returns an error without modifying the database if there's no such
+    /// standard table in the package's schema version, or if a table with
+    /// that name already exists.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use msi::{Insert, Package, PackageType, Value};
+    /// use std::io::Cursor;
+    ///
+    /// let cursor = Cursor::new(Vec::new());
+    /// let mut package = Package::create(PackageType::Installer, cursor)
+    ///     .unwrap();
+    /// package.create_standard_table("Property").unwrap();
+    /// package.insert_rows(Insert::into("Property").row(vec![
+    ///     Value::from("ProductName"),
+    ///     Value::from("Widget"),
+    /// ])).unwrap();
+    /// ```
+    pub fn create_standard_table(
+        &mut self,
+        table_name: &str,
+    ) -> io::Result<()> {
+        let version = match self.summary_info.page_count() {
+            Some(number) => SchemaVersion::from_number(number),
+            None => SchemaVersion::default(),
+        };
+        match schema::table_columns(table_name, version) {
+            Some(columns) => self.create_table(table_name, columns),
+            None => invalid_input!(
+                "{:?} is not a standard table in schema version {}",
+                table_name,
+                version
             ),
-        )?;
-        self.insert_rows(
-            Insert::into(TABLES_TABLE_NAME)
-                .row(vec![Value::Str(table_name.clone())]),
-        )?;
-        let validation_rows: Vec<Vec<Value>> = columns
-            .iter()
-            .map(|column| {
-                let (min_value, max_value) =
-                    if let Some((min, max)) = column.value_range() {
-                        (Value::Int(min), Value::Int(max))
-                    } else {
-                        (Value::Null, Value::Null)
-                    };
-                let (key_table, key_column) =
-                    if let Some((table, column)) = column.foreign_key() {
-                        (Value::Str(table.to_string()), Value::Int(column))
-                    } else {
-                        (Value::Null, Value::Null)
-                    };
-                vec![
-                    Value::Str(table_name.clone()),
-                    Value::Str(column.name().to_string()),
-                    Value::Str(if column.is_nullable() {
-                        "Y".to_string()
-                    } else {
-                        "N".to_string()
-                    }),
-                    min_value,
-                    max_value,
-                    key_table,
-                    key_column,
-                    if let Some(category) = column.category() {
-                        Value::Str(category.to_string())
-                    } else {
-                        Value::Null
-                    },
-                    if let Some(values) = column.enum_values() {
-                        Value::Str(values.join(";"))
-                    } else {
-                        Value::Null
-                    },
-                    Value::Null,
-                ]
-            })
-            .collect();
-        let long_string_refs = self.string_pool.long_string_refs();
-        let table = Table::new(table_name.clone(), columns, long_string_refs);
-        self.tables.insert(table_name, table);
-        self.insert_rows(
-            Insert::into(VALIDATION_TABLE_NAME).rows(validation_rows),
-        )?;
-        Ok(())
+        }
     }
 
-    /// Removes an existing database table.  Returns an error without modifying
+    /// Creates a new database table for a row type that knows its own schema
+    /// (see the `MsiTable` trait).  Returns an error without modifying the
+    /// database if the table could not be created (see
+    /// `Package::create_table()`).
+    pub fn create_typed_table<T: MsiTable>(&mut self) -> io::Result<()> {
+        self.create_table(T::TABLE_NAME, T::columns())
+    }
+
+    /// Removes an existing database table.  Returns an error without modifying
     /// the database if the table name is invalid, or if no such table exists.
     pub fn drop_table(&mut self, table_name: &str) -> io::Result<()> {
         if is_reserved_table_name(table_name) {
@@ -689,10 +1607,17 @@ impl<F: Read + Write + Seek> Package<F> {
             invalid_input!("{:?} is not a valid table name", table_name);
         }
         if !self.tables.contains_key(table_name) {
-            not_found!("Table {:?} does not exist", table_name);
+            msi_error!(TableNotFound { table: table_name.to_string() });
         }
-        let stream_name = self.tables.get(table_name).unwrap().stream_name();
+        let table = self.tables.get(table_name).unwrap().clone();
+        self.set_finisher();
+        let comp = self.comp.as_mut().unwrap();
+        let rows = self.cache.load(comp, &table, &self.string_pool)?.rows();
+        ValueRef::remove_all(rows.iter().flatten(), &mut self.string_pool)?;
+        self.cache.remove(table_name);
+        let stream_name = table.stream_name();
         if self.comp().exists(&stream_name) {
+            self.journal_stream(&stream_name)?;
             self.comp_mut().remove_stream(&stream_name)?;
         }
         self.delete_rows(
@@ -711,12 +1636,71 @@ impl<F: Read + Write + Seek> Package<F> {
         Ok(())
     }
 
+    /// Replaces the validation metadata (value range, foreign key, category,
+    /// set of permitted values, and description) of a column in an existing
+    /// table, updating both the `_Validation` table and the column returned by
+    /// `get_table()`.  Returns an error without modifying the database if the
+    /// table or column doesn't exist, or if the table is one of the special
+    /// system tables.
+    ///
+    /// Rows already in the table are not checked against the new metadata;
+    /// use `validate()` for that.
+    pub fn set_validation(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+        validation: ColumnValidation,
+    ) -> io::Result<()> {
+        if is_reserved_table_name(table_name) {
+            invalid_input!(
+                "Cannot change validation of special {:?} table",
+                table_name
+            );
+        }
+        let table = match self.tables.get(table_name) {
+            Some(table) => table.clone(),
+            None => {
+                msi_error!(TableNotFound { table: table_name.to_string() })
+            }
+        };
+        let index = match table.index_for_column_name(column_name) {
+            Some(index) => index,
+            None => msi_error!(ColumnNotFound {
+                table: table_name.to_string(),
+                column: column_name.to_string(),
+            }),
+        };
+        let mut columns = table.columns().to_vec();
+        columns[index] = columns[index].with_validation(validation);
+        let row = make_validation_row(table_name, &columns[index]);
+        let mut insert = Insert::into(VALIDATION_TABLE_NAME);
+        if columns[index].unknown_category().is_some() {
+            insert = insert.unchecked();
+        }
+        self.transaction(|package| {
+            package.delete_rows(
+                Delete::from(VALIDATION_TABLE_NAME).with(
+                    Expr::col("Table").eq(Expr::string(table_name)).and(
+                        Expr::col("Column").eq(Expr::string(column_name)),
+                    ),
+                ),
+            )?;
+            package.insert_rows(insert.row(row))?;
+            let long_string_refs = table.long_string_refs();
+            let table =
+                Table::new(table_name.to_string(), columns, long_string_refs);
+            package.tables.insert(table_name.to_string(), table);
+            Ok(())
+        })
+    }
+
     /// Attempts to execute a delete query.  Returns an error without modifying
     /// the database if the query fails (e.g. due to the table not existing).
     pub fn delete_rows(&mut self, query: Delete) -> io::Result<()> {
         self.set_finisher();
         query.exec(
             self.comp.as_mut().unwrap(),
+            &mut self.cache,
             &mut self.string_pool,
             &self.tables,
         )
@@ -727,20 +1711,169 @@ impl<F: Read + Write + Seek> Package<F> {
     /// invalid, or keys not being unique, or the table not existing).
     pub fn insert_rows(&mut self, query: Insert) -> io::Result<()> {
         self.set_finisher();
+        if self.tables.contains_key(query.table_name()) {
+            self.reserve_strings(query.max_new_strings())?;
+        }
         query.exec(
             self.comp.as_mut().unwrap(),
+            &mut self.cache,
             &mut self.string_pool,
             &self.tables,
         )
     }
 
+    /// Inserts typed rows (see the `rows` module) into their table.  Values
+    /// are matched to the table's columns by name, and any column of the table
+    /// that the row type doesn't provide is set to null.  Returns an error
+    /// without modifying the database if the table doesn't exist, if it lacks
+    /// a column that one of the rows has a non-null value for, or if the
+    /// insert itself fails.
+    pub fn insert_typed_rows<T, I>(&mut self, rows: I) -> io::Result<()>
+    where
+        T: TypedRow,
+        I: IntoIterator<Item = T>,
+    {
+        let table = match self.tables.get(T::TABLE_NAME) {
+            Some(table) => table.clone(),
+            None => {
+                msi_error!(TableNotFound { table: T::TABLE_NAME.to_string() })
+            }
+        };
+        let mut query = Insert::into(T::TABLE_NAME);
+        for row in rows {
+            let mut values = vec![Value::Null; table.columns().len()];
+            for (column_name, value) in row.to_values() {
+                match table.index_for_column_name(column_name) {
+                    Some(index) => values[index] = value,
+                    None if value.is_null() => {}
+                    None => msi_error!(ColumnNotFound {
+                        table: T::TABLE_NAME.to_string(),
+                        column: column_name.to_string(),
+                    }),
+                }
+            }
+            query = query.row(values);
+        }
+        self.insert_rows(query)
+    }
+
+    /// Updates existing rows with typed rows (see the `rows` module).  Each
+    /// typed row overwrites the non-key columns of the table row with the same
+    /// primary key; typed rows with no matching table row are ignored.
+    /// Returns an error without modifying the database if the table doesn't
+    /// exist, if it lacks a column that one of the rows has a non-null value
+    /// for, or if any of the updates fails.
+    pub fn update_typed_rows<T, I>(&mut self, rows: I) -> io::Result<()>
+    where
+        T: TypedRow,
+        I: IntoIterator<Item = T>,
+    {
+        let table = match self.tables.get(T::TABLE_NAME) {
+            Some(table) => table.clone(),
+            None => {
+                msi_error!(TableNotFound { table: T::TABLE_NAME.to_string() })
+            }
+        };
+        let mut queries = Vec::new();
+        for row in rows {
+            let mut query = Update::table(T::TABLE_NAME);
+            let mut condition = Expr::boolean(true);
+            for (column_name, value) in row.to_values() {
+                match table.get_column(column_name) {
+                    Some(column) if column.is_primary_key() => {
+                        let value = match value {
+                            Value::Null | Value::Binary => Expr::null(),
+                            Value::Int(number) => Expr::integer(number),
+                            Value::Str(string) => Expr::string(string),
+                        };
+                        condition =
+                            condition.and(Expr::col(column_name).eq(value));
+                    }
+                    Some(_) => query = query.set(column_name, value),
+                    None if value.is_null() => {}
+                    None => msi_error!(ColumnNotFound {
+                        table: T::TABLE_NAME.to_string(),
+                        column: column_name.to_string(),
+                    }),
+                }
+            }
+            queries.push(query.with(condition));
+        }
+        self.transaction(|package| {
+            for query in queries {
+                package.update_rows(query)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Reads a table from the tab-delimited IDT archive format used by
+    /// `msidb.exe`, and returns the table's name.  If the package already has
+    /// a table with that name, its rows are replaced (and if its columns
+    /// differ, the table is recreated).  IDT files for the `_ForceCodepage`
+    /// and `_SummaryInformation` pseudo-tables set the database code page and
+    /// replace the summary information instead.
+    ///
+    /// Returns an error without modifying the database if the IDT data is
+    /// malformed, if any row is invalid for the table, or if the data refers
+    /// to binary side-car files (which require `Package::import_idt_file()`).
+    pub fn import_idt<R: Read>(&mut self, reader: R) -> io::Result<String> {
+        idt::import(self, reader, None)
+    }
+
+    /// Reads a table from an IDT file on disk, as with `Package::import_idt()`.
+    /// Binary values are read from side-car files in a subdirectory (named
+    /// after the table) of the IDT file's directory; an IDT file that names
+    /// side-car files anywhere else is rejected.
+    pub fn import_idt_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> io::Result<String> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        idt::import(self, file, Some(dir))
+    }
+
+    /// Reads rows from CSV data written by `Rows::write_csv()` and inserts
+    /// them into the given (existing) table, returning the number of rows
+    /// inserted.  The file's columns may be a subset of the table's, in any
+    /// order; missing columns are left null.
+    ///
+    /// Returns an error without modifying the database if the CSV data is
+    /// malformed, if its column names or types don't match the table, or if
+    /// any row is invalid for the table; errors for a particular row give the
+    /// line number it started on.
+    pub fn import_csv<R: Read>(
+        &mut self,
+        table_name: &str,
+        reader: R,
+    ) -> io::Result<usize> {
+        textrows::import_csv(self, table_name, reader)
+    }
+
+    /// Reads rows from JSON lines data written by `Rows::write_json_lines()`
+    /// and inserts them into the given (existing) table, as with
+    /// `Package::import_csv()`.
+    pub fn import_json_lines<R: Read>(
+        &mut self,
+        table_name: &str,
+        reader: R,
+    ) -> io::Result<usize> {
+        textrows::import_json_lines(self, table_name, reader)
+    }
+
     /// Attempts to execute an update query.  Returns an error without
     /// modifying the database if the query fails (e.g. due to values being
     /// invalid, or column names being incorrect, or the table not existing).
     pub fn update_rows(&mut self, query: Update) -> io::Result<()> {
         self.set_finisher();
+        if self.tables.contains_key(query.table_name()) {
+            self.reserve_strings(query.max_new_strings())?;
+        }
         query.exec(
             self.comp.as_mut().unwrap(),
+            &mut self.cache,
             &mut self.string_pool,
             &self.tables,
         )
@@ -755,9 +1888,50 @@ impl<F: Read + Write + Seek> Package<F> {
             invalid_input!("{:?} is not a valid stream name", stream_name);
         }
         let encoded_name = streamname::encode(stream_name, false);
+        self.journal_stream(&encoded_name)?;
         Ok(StreamWriter::new(self.comp_mut().create_stream(&encoded_name)?))
     }
 
+    /// Creates (or replaces) an embedded storage in the package, holding a
+    /// copy of the contents of the given compound file.  Returns an error if
+    /// called during a transaction, since storages aren't journaled.
+    pub(crate) fn import_storage<G: Read + Seek>(
+        &mut self,
+        storage_name: &str,
+        source: G,
+    ) -> io::Result<()> {
+        if self.journal.is_some() {
+            invalid_input!("Cannot import a storage during a transaction");
+        }
+        if !streamname::is_valid(storage_name, false) {
+            invalid_input!("{:?} is not a valid storage name", storage_name);
+        }
+        let mut source = cfb::CompoundFile::open(source)?;
+        let path =
+            Path::new("/").join(streamname::encode(storage_name, false));
+        let comp = self.comp.as_mut().unwrap();
+        if comp.is_storage(&path) {
+            comp.remove_storage_all(&path)?;
+        } else if comp.is_stream(&path) {
+            comp.remove_stream(&path)?;
+        }
+        let entries: Vec<cfb::Entry> = source.walk().collect();
+        for entry in &entries {
+            let relative = entry.path().strip_prefix("/").unwrap();
+            let out_path = path.join(relative);
+            if entry.is_stream() {
+                let mut stream = source.open_stream(entry.path())?;
+                io::copy(&mut stream, &mut comp.create_stream(&out_path)?)?;
+            } else {
+                // The source's root storage becomes the new storage itself.
+                comp.create_storage(&out_path)?;
+                comp.set_storage_clsid(&out_path, *entry.clsid())?;
+            }
+            comp.set_state_bits(&out_path, entry.state_bits())?;
+        }
+        Ok(())
+    }
+
     /// Removes an existing binary stream from the package.
     pub fn remove_stream(&mut self, stream_name: &str) -> io::Result<()> {
         if !streamname::is_valid(stream_name, false) {
@@ -765,11 +1939,31 @@ impl<F: Read + Write + Seek> Package<F> {
         }
         let encoded_name = streamname::encode(stream_name, false);
         if !self.comp().is_stream(&encoded_name) {
-            not_found!("Stream {:?} does not exist", stream_name);
+            msi_error!(StreamNotFound { stream: stream_name.to_string() });
         }
+        self.journal_stream(&encoded_name)?;
         self.comp_mut().remove_stream(&encoded_name)
     }
 
+    /// Fixes any structural problems found by `Package::check_integrity()`
+    /// that can be fixed: string pool refcounts are recomputed from the
+    /// tables, rows with invalid string references or repeated keys are
+    /// dropped (keeping the first row for each key), and orphaned table
+    /// streams are removed.  Returns the list of problems that were found,
+    /// including any that could not be fixed (see
+    /// `IntegrityProblem::is_repairable()`).  Returns an error if called
+    /// during a transaction.
+    pub fn repair(&mut self) -> io::Result<Vec<IntegrityProblem>> {
+        if self.journal.is_some() {
+            invalid_input!("Cannot repair a package during a transaction");
+        }
+        self.set_finisher();
+        let comp = self.comp.as_mut().unwrap();
+        self.cache.write_dirty(comp, &self.tables)?;
+        self.cache.clear();
+        integrity::repair(comp, &mut self.string_pool, &self.tables)
+    }
+
     // TODO: pub fn add_digital_signature(&mut self, ...) -> io::Result<()>
 
     /// Removes any existing digital signature from the package.  This can be
@@ -777,23 +1971,272 @@ impl<F: Read + Write + Seek> Package<F> {
     /// the signature).
     pub fn remove_digital_signature(&mut self) -> io::Result<()> {
         if self.comp().is_stream(DIGITAL_SIGNATURE_STREAM_NAME) {
+            self.journal_stream(DIGITAL_SIGNATURE_STREAM_NAME)?;
             self.comp_mut().remove_stream(DIGITAL_SIGNATURE_STREAM_NAME)?;
         }
         if self.comp().is_stream(MSI_DIGITAL_SIGNATURE_EX_STREAM_NAME) {
+            self.journal_stream(MSI_DIGITAL_SIGNATURE_EX_STREAM_NAME)?;
             self.comp_mut()
                 .remove_stream(MSI_DIGITAL_SIGNATURE_EX_STREAM_NAME)?;
         }
         Ok(())
     }
 
-    /// Flushes any buffered changes to the underlying writer.
+    /// Flushes any buffered changes to the underlying writer.  Returns an
+    /// error if called during a transaction.
     pub fn flush(&mut self) -> io::Result<()> {
-        if let Some(finisher) = self.finisher.take() {
-            finisher.finish(self)?;
+        if self.journal.is_some() {
+            invalid_input!("Cannot flush a package during a transaction");
         }
+        self.finish()?;
         self.comp_mut().flush()
     }
 
+    /// Runs the given closure as a single atomic edit of the package.  If the
+    /// closure returns an error, every change that it made to the package's
+    /// tables, string pool, summary information, and binary streams is rolled
+    /// back, leaving the package as it was before the transaction began, and
+    /// the error is returned.  Otherwise, the changes are committed, and will
+    /// be written to the underlying writer by the next flush, just like
+    /// changes made outside of a transaction.
+    ///
+    /// Transactions may be nested; rolling back an inner transaction only
+    /// undoes the changes made within it.
+    ///
+    /// If putting the binary streams back fails (because the underlying
+    /// writer fails), the closure's error is still returned, with the same
+    /// kind and specific cause, but its message also describes the rollback
+    /// failure.  In that case the in-memory state is still rolled back, but
+    /// the underlying file may be left partially modified.
+    ///
+    /// If the closure panics, the transaction is rolled back in the same way
+    /// before the panic continues.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use msi::{Column, Insert, Package, PackageType, Select, Value};
+    /// use std::io::Cursor;
+    ///
+    /// let cursor = Cursor::new(Vec::new());
+    /// let mut package = Package::create(PackageType::Installer, cursor)
+    ///     .unwrap();
+    /// let columns = vec![Column::build("Number").primary_key().int16()];
+    /// package.create_table("Numbers", columns).unwrap();
+    /// let result = package.transaction(|package| {
+    ///     package.insert_rows(
+    ///         Insert::into("Numbers").row(vec![Value::Int(1)]),
+    ///     )?;
+    ///     // This fails, because the table already has a row with key 1:
+    ///     package.insert_rows(
+    ///         Insert::into("Numbers").row(vec![Value::Int(1)]),
+    ///     )
+    /// });
+    /// assert!(result.is_err());
+    /// // The first insert was rolled back too:
+    /// let rows = package.select_rows(Select::table("Numbers")).unwrap();
+    /// assert_eq!(rows.len(), 0);
+    /// ```
+    pub fn transaction<T, E>(&mut self, edit: E) -> io::Result<T>
+    where
+        E: FnOnce(&mut Self) -> io::Result<T>,
+    {
+        let mut guard = TransactionGuard {
+            snapshot: Some(self.begin_transaction()),
+            package: self,
+        };
+        let result = edit(guard.package);
+        let snapshot = guard.snapshot.take().unwrap();
+        match result {
+            Ok(value) => {
+                guard.package.commit_transaction(snapshot);
+                Ok(value)
+            }
+            Err(error) => match guard.package.rollback_transaction(snapshot) {
+                Ok(()) => Err(error),
+                Err(restore_error) => {
+                    Err(RollbackError::wrap(error, restore_error))
+                }
+            },
+        }
+    }
+
+    /// Starts a new (possibly nested) transaction, returning the state needed
+    /// to commit or roll it back.  Tables and strings are recorded lazily, as
+    /// they are changed.
+    fn begin_transaction(&mut self) -> Snapshot {
+        self.string_pool.begin_transaction();
+        self.cache.begin_transaction();
+        Snapshot {
+            outer_journal: self.journal.replace(Journal::default()),
+            summary_info: self.summary_info.clone(),
+            is_summary_info_modified: self.is_summary_info_modified,
+            string_pool_layout: self.string_pool_layout,
+            reproducible: self.reproducible,
+            tables: self.tables.clone(),
+            has_finisher: self.finisher.is_some(),
+        }
+    }
+
+    fn commit_transaction(&mut self, snapshot: Snapshot) {
+        let journal = self.journal.take().unwrap();
+        self.journal = snapshot.outer_journal.map(|mut outer_journal| {
+            outer_journal.merge(journal);
+            outer_journal
+        });
+        self.string_pool.commit_transaction();
+        self.cache.commit_transaction();
+    }
+
+    /// Undoes every change made since the given transaction began.  The
+    /// in-memory state is always restored, even if putting the binary streams
+    /// back fails.
+    fn rollback_transaction(&mut self, snapshot: Snapshot) -> io::Result<()> {
+        let journal = self.journal.take().unwrap();
+        self.journal = snapshot.outer_journal;
+        let restored = journal.restore(self.comp_mut());
+        self.summary_info = snapshot.summary_info;
+        self.is_summary_info_modified = snapshot.is_summary_info_modified;
+        self.string_pool.rollback_transaction();
+        self.string_pool_layout = snapshot.string_pool_layout;
+        self.reproducible = snapshot.reproducible;
+        self.tables = snapshot.tables;
+        self.cache.rollback_transaction();
+        if !snapshot.has_finisher {
+            self.finisher = None;
+        }
+        restored
+    }
+
+    /// If a transaction is in progress, records the current contents of the
+    /// given (encoded) stream name, so that they can be restored if the
+    /// transaction is rolled back.
+    fn journal_stream(&mut self, encoded_name: &str) -> io::Result<()> {
+        if let Some(ref mut journal) = self.journal {
+            journal.save(self.comp.as_mut().unwrap(), encoded_name)?;
+        }
+        Ok(())
+    }
+
+    /// Makes sure that the string pool has room for the given number of new
+    /// strings, switching the database to long string refs if necessary.
+    fn reserve_strings(&mut self, num_new_strings: usize) -> io::Result<()> {
+        if self.string_pool.long_string_refs()
+            || self.string_pool.num_strings() as usize + num_new_strings
+                <= MAX_SHORT_STRING_REFS
+        {
+            return Ok(());
+        }
+        self.upgrade_to_long_string_refs()
+    }
+
+    /// Rewrites every table in the database to use three-byte string refs
+    /// instead of two-byte string refs, which allows the string pool to grow
+    /// beyond 65535 entries.
+    fn upgrade_to_long_string_refs(&mut self) -> io::Result<()> {
+        self.set_finisher();
+        let comp = self.comp.as_mut().unwrap();
+        let mut tables = BTreeMap::<String, Rc<Table>>::new();
+        for (table_name, table) in &self.tables {
+            // Make sure the table's rows are cached (and will be written back
+            // out at the next flush) before we lose the ability to decode its
+            // stream.
+            self.cache
+                .load_mut(comp, table, &self.string_pool)?
+                .modify_rows(table, |_| ());
+            let long_table =
+                Table::new(table_name.clone(), table.columns().to_vec(), true);
+            tables.insert(table_name.clone(), long_table);
+        }
+        self.tables = tables;
+        self.string_pool.set_long_string_refs();
+        Ok(())
+    }
+
+    /// Rewrites the string pool according to the given layout, updating every
+    /// table's string references to match.
+    fn apply_string_pool_layout(
+        &mut self,
+        layout: StringPoolLayout,
+    ) -> io::Result<()> {
+        let sorted = match layout {
+            StringPoolLayout::Preserve => return Ok(()),
+            StringPoolLayout::Compact => false,
+            StringPoolLayout::Sorted => true,
+        };
+        if self.string_pool.is_compact(sorted) {
+            return Ok(());
+        }
+        // Check every table before changing anything, so that we can bail
+        // out cleanly if the database turns out to be malformed.
+        let comp = self.comp.as_mut().unwrap();
+        for table in self.tables.values() {
+            for value_ref in self
+                .cache
+                .load(comp, table, &self.string_pool)?
+                .rows()
+                .iter()
+                .flatten()
+            {
+                if let ValueRef::Str(string_ref) = *value_ref {
+                    if self.string_pool.refcount(string_ref) == 0 {
+                        msi_error!(Corrupt {
+                            stream: table.name().to_string(),
+                            message: format!(
+                                "Table {:?} refers to string #{}, which is \
+                                 not in the string pool",
+                                table.name(),
+                                string_ref.number()
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        let remap = self.string_pool.compact(sorted);
+        for table in self.tables.values() {
+            self.cache.load_mut(comp, table, &self.string_pool)?.modify_rows(
+                table,
+                |rows| {
+                    for value_ref in rows.iter_mut().flatten() {
+                        if let ValueRef::Str(string_ref) = *value_ref {
+                            let index = (string_ref.number() - 1) as usize;
+                            *value_ref = ValueRef::Str(remap[index].unwrap());
+                        }
+                    }
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Sets the created and modified times of every storage in the compound
+    /// file (including the root storage) to the given time, or to zero.
+    /// Normalizes the parts of the compound file's directory that don't come
+    /// from the package contents: sets the timestamps of all storages to the
+    /// given time, sets the root storage's CLSID to the one for this type of
+    /// package, and clears the state bits of every entry.
+    fn normalize_compound_file(
+        &mut self,
+        timestamp: SystemTime,
+    ) -> io::Result<()> {
+        let clsid = self.package_type.clsid();
+        let comp = self.comp_mut();
+        let entries: Vec<(PathBuf, bool)> = comp
+            .walk()
+            .map(|entry| (entry.path().to_path_buf(), entry.is_stream()))
+            .collect();
+        comp.set_storage_clsid("/", clsid)?;
+        for (path, is_stream) in entries {
+            comp.set_state_bits(&path, 0)?;
+            if !is_stream {
+                comp.set_created_time(&path, timestamp)?;
+                comp.set_modified_time(&path, timestamp)?;
+            }
+        }
+        Ok(())
+    }
+
     fn set_finisher(&mut self) {
         if self.finisher.is_none() {
             let finisher: Box<dyn Finish<F>> = Box::new(FinishImpl {});
@@ -836,6 +2279,37 @@ impl<'a> ExactSizeIterator for Tables<'a> {}
 
 // ========================================================================= //
 
+/// The in-memory state of a package from before a transaction began, used to
+/// roll the transaction back.  (The string pool and row cache keep their own
+/// undo logs.)
+struct Snapshot {
+    outer_journal: Option<Journal>,
+    summary_info: SummaryInfo,
+    is_summary_info_modified: bool,
+    string_pool_layout: StringPoolLayout,
+    reproducible: Option<ReproducibleTime>,
+    tables: BTreeMap<String, Rc<Table>>,
+    has_finisher: bool,
+}
+
+/// Rolls back a transaction if the closure running it panics, so that the
+/// package isn't left with a transaction that never ends.
+struct TransactionGuard<'a, F: Read + Write + Seek> {
+    package: &'a mut Package<F>,
+    // This is `None` once the transaction has been committed or rolled back.
+    snapshot: Option<Snapshot>,
+}
+
+impl<'a, F: Read + Write + Seek> Drop for TransactionGuard<'a, F> {
+    fn drop(&mut self) {
+        if let Some(snapshot) = self.snapshot.take() {
+            let _ = self.package.rollback_transaction(snapshot);
+        }
+    }
+}
+
+// ========================================================================= //
+
 trait Finish<F> {
     fn finish(&self, package: &mut Package<F>) -> io::Result<()>;
 }
@@ -844,6 +2318,17 @@ struct FinishImpl {}
 
 impl<F: Read + Write + Seek> Finish<F> for FinishImpl {
     fn finish(&self, package: &mut Package<F>) -> io::Result<()> {
+        let timestamp = match package.reproducible {
+            Some(ReproducibleTime::FromEnvironment) => {
+                Some(source_date_epoch()?)
+            }
+            Some(ReproducibleTime::Fixed(timestamp)) => Some(timestamp),
+            None => None,
+        };
+        if let Some(timestamp) = timestamp {
+            package.summary_info.normalize_times(timestamp);
+            package.is_summary_info_modified = true;
+        }
         if package.is_summary_info_modified {
             let stream = package
                 .comp
@@ -853,6 +2338,15 @@ impl<F: Read + Write + Seek> Finish<F> for FinishImpl {
             package.summary_info.write(stream)?;
             package.is_summary_info_modified = false;
         }
+        let layout = if timestamp.is_some() {
+            StringPoolLayout::Sorted
+        } else {
+            package.string_pool_layout
+        };
+        package.apply_string_pool_layout(layout)?;
+        package
+            .cache
+            .write_dirty(package.comp.as_mut().unwrap(), &package.tables)?;
         if package.string_pool.is_modified() {
             {
                 let name = streamname::encode(STRING_POOL_TABLE_NAME, true);
@@ -868,6 +2362,9 @@ impl<F: Read + Write + Seek> Finish<F> for FinishImpl {
             }
             package.string_pool.mark_unmodified();
         }
+        if let Some(timestamp) = timestamp {
+            package.normalize_compound_file(timestamp)?;
+        }
         Ok(())
     }
 }
@@ -876,13 +2373,39 @@ impl<F: Read + Write + Seek> Finish<F> for FinishImpl {
 
 #[cfg(test)]
 mod tests {
-    use super::{Package, PackageType};
+    use super::{
+        Package, PackageType, STRING_DATA_TABLE_NAME, STRING_POOL_TABLE_NAME,
+        parse_source_date_epoch,
+    };
     use crate::internal::codepage::CodePage;
     use crate::internal::column::Column;
     use crate::internal::expr::Expr;
-    use crate::internal::query::{Insert, Select, Update};
-    use crate::internal::value::Value;
-    use std::io::Cursor;
+    use crate::internal::integrity::IntegrityProblem;
+    use crate::internal::query::{Delete, Insert, Select, Update};
+    use crate::internal::streamname;
+    use crate::internal::stringpool::{
+        MAX_SHORT_STRING_REFS, StringPoolBuilder, StringPoolLayout, StringRef,
+    };
+    use crate::internal::value::{Value, ValueRef};
+    use std::io::{Cursor, ErrorKind, Read, Write};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn parse_source_date_epochs() {
+        assert_eq!(parse_source_date_epoch("0").unwrap(), UNIX_EPOCH);
+        assert_eq!(
+            parse_source_date_epoch(" 1700000000\n").unwrap(),
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        );
+        for value in ["", "-1", "1.5", "yesterday", "99999999999999999999"] {
+            let error = parse_source_date_epoch(value).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+            assert_eq!(
+                error.to_string(),
+                format!("Invalid SOURCE_DATE_EPOCH value: {value:?}")
+            );
+        }
+    }
 
     #[test]
     fn set_database_codepage() {
@@ -980,6 +2503,327 @@ mod tests {
             .collect();
         assert_eq!(values, vec![(1, -5), (2, 42), (3, -5)]);
     }
+
+    fn create_numbers_package() -> Package<Cursor<Vec<u8>>> {
+        let cursor = Cursor::new(Vec::new());
+        let mut package =
+            Package::create(PackageType::Installer, cursor).expect("create");
+        let columns = vec![
+            Column::build("Number").primary_key().int16(),
+            Column::build("Word").nullable().string(50),
+        ];
+        package.create_table("Numbers", columns).expect("create_table");
+        package
+    }
+
+    #[test]
+    fn repair_wrong_refcount() {
+        let mut package = create_numbers_package();
+        package
+            .insert_rows(
+                Insert::into("Numbers")
+                    .row(vec![Value::Int(1), Value::from("One")]),
+            )
+            .expect("insert_rows");
+        assert_eq!(package.check_integrity().unwrap(), vec![]);
+        let string_ref =
+            package.string_pool.incref("One".to_string()).unwrap();
+
+        let problems = package.check_integrity().unwrap();
+        assert_eq!(
+            problems,
+            vec![IntegrityProblem::WrongRefcount {
+                string_ref: string_ref.number(),
+                string: "One".to_string(),
+                recorded: 2,
+                actual: 1,
+            }]
+        );
+        assert_eq!(package.repair().unwrap(), problems);
+        assert_eq!(package.check_integrity().unwrap(), vec![]);
+
+        let cursor = package.into_inner().expect("into_inner");
+        let mut package = Package::open(cursor).expect("open");
+        assert_eq!(package.check_integrity().unwrap(), vec![]);
+        assert_eq!(package.string_pool.refcount(string_ref), 1);
+    }
+
+    #[test]
+    fn repair_invalid_string_ref_and_duplicate_key() {
+        let mut package = create_numbers_package();
+        let one = package.string_pool.incref("One".to_string()).unwrap();
+        let bogus =
+            StringRef::read(&mut &b"\xff\x7f"[..], false).unwrap().unwrap();
+        let table = package.tables["Numbers"].clone();
+        let stream =
+            package.comp_mut().create_stream(table.stream_name()).unwrap();
+        table
+            .write_rows(
+                stream,
+                vec![
+                    vec![ValueRef::Int(1), ValueRef::Null],
+                    vec![ValueRef::Int(1), ValueRef::Str(one)],
+                    vec![ValueRef::Int(3), ValueRef::Str(bogus)],
+                ],
+            )
+            .unwrap();
+
+        let problems = package.repair().unwrap();
+        assert_eq!(
+            problems,
+            vec![
+                IntegrityProblem::DuplicateKey {
+                    table: "Numbers".to_string(),
+                    key: vec![Value::Int(1)],
+                },
+                IntegrityProblem::InvalidStringRef {
+                    table: "Numbers".to_string(),
+                    row: 2,
+                    column: "Word".to_string(),
+                    string_ref: 0x7fff,
+                },
+                IntegrityProblem::WrongRefcount {
+                    string_ref: one.number(),
+                    string: "One".to_string(),
+                    recorded: 1,
+                    actual: 0,
+                },
+            ]
+        );
+        assert_eq!(package.check_integrity().unwrap(), vec![]);
+        let rows = package.select_rows(Select::table("Numbers")).unwrap();
+        let values: Vec<(Value, Value)> =
+            rows.map(|row| (row[0].clone(), row[1].clone())).collect();
+        assert_eq!(values, vec![(Value::Int(1), Value::Null)]);
+    }
+
+    #[test]
+    fn incomplete_rows_are_not_repairable() {
+        let mut package = create_numbers_package();
+        let table = package.tables["Numbers"].clone();
+        let mut stream =
+            package.comp_mut().create_stream(table.stream_name()).unwrap();
+        stream.write_all(b"\x01\x80\x00\x00\xff").unwrap();
+        drop(stream);
+
+        let problems = package.repair().unwrap();
+        assert_eq!(
+            problems,
+            vec![IntegrityProblem::IncompleteRows {
+                table: "Numbers".to_string(),
+                stream_len: 5,
+                row_size: 4,
+            }]
+        );
+        assert!(!problems[0].is_repairable());
+        assert_eq!(package.check_integrity().unwrap(), problems);
+    }
+
+    #[test]
+    fn upgrade_to_long_string_refs() {
+        let mut package = create_numbers_package();
+        package
+            .insert_rows(
+                Insert::into("Numbers")
+                    .row(vec![Value::Int(1), Value::from("One")]),
+            )
+            .expect("insert_rows");
+        // Fill up the string pool, so that there is room for just one more
+        // string before we run out of short string refs.
+        let mut pool = Vec::<u8>::new();
+        package.string_pool.write_pool(&mut pool).unwrap();
+        let mut data = Vec::<u8>::new();
+        package.string_pool.write_data(&mut data).unwrap();
+        let num_strings = package.string_pool.num_strings() as usize;
+        for index in num_strings..(MAX_SHORT_STRING_REFS - 1) {
+            pool.extend_from_slice(b"\x05\x00\x01\x00");
+            data.extend_from_slice(format!("{index:05}").as_bytes());
+        }
+        package.string_pool = StringPoolBuilder::read_from_pool(&pool[..])
+            .unwrap()
+            .build_from_data(&data[..])
+            .unwrap();
+        assert!(!package.string_pool.long_string_refs());
+
+        package
+            .insert_rows(
+                Insert::into("Numbers")
+                    .row(vec![Value::Int(2), Value::from("Two")])
+                    .row(vec![Value::Int(3), Value::from("Three")]),
+            )
+            .expect("insert_rows");
+        assert!(package.string_pool.long_string_refs());
+        assert!(package.tables.values().all(|table| table.long_string_refs()));
+        assert_eq!(
+            package.string_pool.num_strings() as usize,
+            MAX_SHORT_STRING_REFS + 1
+        );
+
+        let cursor = package.into_inner().expect("into_inner");
+        let mut package = Package::open(cursor).expect("open");
+        assert!(package.string_pool.long_string_refs());
+        let rows = package.select_rows(Select::table("Numbers")).unwrap();
+        let values: Vec<(i32, String)> = rows
+            .map(|row| {
+                (
+                    row[0].as_int().unwrap(),
+                    row[1].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                (1, "One".to_string()),
+                (2, "Two".to_string()),
+                (3, "Three".to_string()),
+            ]
+        );
+        assert!(package.has_table("Numbers"));
+        assert!(package.get_table("Numbers").unwrap().long_string_refs());
+    }
+
+    #[test]
+    fn update_reserves_strings_once_per_query() {
+        let mut package = create_numbers_package();
+        package
+            .insert_rows(
+                Insert::into("Numbers")
+                    .row(vec![Value::Int(1), Value::from("One")])
+                    .row(vec![Value::Int(2), Value::from("Two")])
+                    .row(vec![Value::Int(3), Value::from("Three")]),
+            )
+            .expect("insert_rows");
+        // Fill up the string pool, so that there is room for just one more
+        // string before we run out of short string refs.
+        let mut pool = Vec::<u8>::new();
+        package.string_pool.write_pool(&mut pool).unwrap();
+        let mut data = Vec::<u8>::new();
+        package.string_pool.write_data(&mut data).unwrap();
+        let num_strings = package.string_pool.num_strings() as usize;
+        for index in num_strings..(MAX_SHORT_STRING_REFS - 1) {
+            pool.extend_from_slice(b"\x05\x00\x01\x00");
+            data.extend_from_slice(format!("{index:05}").as_bytes());
+        }
+        package.string_pool = StringPoolBuilder::read_from_pool(&pool[..])
+            .unwrap()
+            .build_from_data(&data[..])
+            .unwrap();
+
+        // Setting every row to the same new string only needs one new entry,
+        // so the package should keep using short string refs.
+        package
+            .update_rows(
+                Update::table("Numbers").set("Word", Value::from("Same")),
+            )
+            .expect("update_rows");
+        assert!(!package.string_pool.long_string_refs());
+        let words: Vec<Value> = package
+            .select_rows(Select::table("Numbers").columns(&["Word"]))
+            .unwrap()
+            .map(|row| row[0].clone())
+            .collect();
+        assert_eq!(words, vec![Value::from("Same"); 3]);
+    }
+
+    fn read_table_stream(
+        package: &mut Package<Cursor<Vec<u8>>>,
+        table_name: &str,
+    ) -> Vec<u8> {
+        let name = streamname::encode(table_name, true);
+        let mut data = Vec::new();
+        let mut stream = package.comp_mut().open_stream(name).unwrap();
+        stream.read_to_end(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn compact_string_pool_on_flush() {
+        let mut package = create_numbers_package();
+        package
+            .insert_rows(
+                Insert::into("Numbers")
+                    .row(vec![Value::Int(1), Value::from("One")])
+                    .row(vec![Value::Int(2), Value::from("Two")])
+                    .row(vec![Value::Int(3), Value::from("Three")]),
+            )
+            .expect("insert_rows");
+        package
+            .delete_rows(
+                Delete::from("Numbers")
+                    .with(Expr::col("Number").lt(Expr::integer(3))),
+            )
+            .expect("delete_rows");
+        package.flush().unwrap();
+        let num_strings = package.string_pool.num_strings();
+        assert!(!package.string_pool.is_compact(false));
+
+        package.set_string_pool_layout(StringPoolLayout::Compact);
+        package.flush().unwrap();
+        assert!(package.string_pool.is_compact(false));
+        assert_eq!(package.string_pool.num_strings(), num_strings - 2);
+        assert_eq!(package.check_integrity().unwrap(), vec![]);
+
+        let cursor = package.into_inner().expect("into_inner");
+        let mut package = Package::open(cursor).expect("open");
+        assert_eq!(package.check_integrity().unwrap(), vec![]);
+        let rows = package.select_rows(Select::table("Numbers")).unwrap();
+        let values: Vec<(Value, Value)> =
+            rows.map(|row| (row[0].clone(), row[1].clone())).collect();
+        assert_eq!(values, vec![(Value::Int(3), Value::from("Three"))]);
+    }
+
+    #[test]
+    fn sorted_string_pool_is_deterministic() {
+        let mut package1 = create_numbers_package();
+        package1
+            .insert_rows(
+                Insert::into("Numbers")
+                    .row(vec![Value::Int(1), Value::from("One")])
+                    .row(vec![Value::Int(2), Value::from("Two")]),
+            )
+            .expect("insert_rows");
+        let mut package2 = create_numbers_package();
+        package2
+            .insert_rows(
+                Insert::into("Numbers")
+                    .row(vec![Value::Int(5), Value::from("Five")])
+                    .row(vec![Value::Int(2), Value::from("Two")]),
+            )
+            .expect("insert_rows");
+        package2
+            .insert_rows(
+                Insert::into("Numbers")
+                    .row(vec![Value::Int(1), Value::from("One")]),
+            )
+            .expect("insert_rows");
+        package2
+            .delete_rows(
+                Delete::from("Numbers")
+                    .with(Expr::col("Number").eq(Expr::integer(5))),
+            )
+            .expect("delete_rows");
+
+        for package in [&mut package1, &mut package2] {
+            package.set_string_pool_layout(StringPoolLayout::Sorted);
+            package.flush().unwrap();
+            assert!(package.string_pool.is_compact(true));
+        }
+        for table_name in [
+            STRING_POOL_TABLE_NAME,
+            STRING_DATA_TABLE_NAME,
+            "_Columns",
+            "_Tables",
+            "_Validation",
+            "Numbers",
+        ] {
+            assert_eq!(
+                read_table_stream(&mut package1, table_name),
+                read_table_stream(&mut package2, table_name),
+                "{table_name} streams differ"
+            );
+        }
+    }
 }
 
 // ========================================================================= //
@@ -35,6 +35,7 @@ impl PropertyFormatVersion {
 
 // ========================================================================= //
 
+#[derive(Clone, Copy)]
 pub enum OperatingSystem {
     Win16,
     Macintosh,
@@ -43,7 +44,7 @@ pub enum OperatingSystem {
 
 // ========================================================================= //
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum PropertyValue {
     Empty,
     Null,
@@ -66,9 +67,18 @@ impl PropertyValue {
             30 => {
                 let length = reader.read_u32::<LittleEndian>()?;
                 let length = if length == 0 { 0 } else { length - 1 };
-                let mut bytes: Vec<u8> = Vec::with_capacity(length as usize);
-                for _ in 0..length {
-                    bytes.push(reader.read_u8()?);
+                // Don't trust the length enough to preallocate a buffer for
+                // it; a corrupt file could claim up to 4 GB.
+                let mut bytes = Vec::new();
+                (&mut reader)
+                    .take(u64::from(length))
+                    .read_to_end(&mut bytes)?;
+                if bytes.len() < length as usize {
+                    invalid_data!(
+                        "Property set string is truncated ({} of {} bytes)",
+                        bytes.len(),
+                        length
+                    );
                 }
                 if reader.read_u8()? != 0 {
                     invalid_data!("Property set string not null-terminated");
@@ -174,6 +184,7 @@ impl PropertyValue {
 
 // ========================================================================= //
 
+#[derive(Clone)]
 pub struct PropertySet {
     os: OperatingSystem,
     os_version: u16,
@@ -375,6 +386,10 @@ impl PropertySet {
     pub fn remove(&mut self, property_name: u32) {
         self.properties.remove(&property_name);
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &PropertyValue)> {
+        self.properties.iter().map(|(&name, value)| (name, value))
+    }
 }
 
 // ========================================================================= //
@@ -384,7 +399,7 @@ mod tests {
     use super::{OperatingSystem, PropertySet, PropertyValue};
     use crate::internal::codepage::CodePage;
     use crate::internal::timestamp::Timestamp;
-    use std::io::Cursor;
+    use std::io::{Cursor, ErrorKind};
     use std::time::{Duration, UNIX_EPOCH};
 
     #[test]
@@ -430,6 +445,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn read_truncated_property_value() {
+        // This string claims to be 4 GB long.
+        let input: &[u8] = b"\x1e\x00\x00\x00\xff\xff\xff\xffHello\x00";
+        let error = PropertyValue::read(input, CodePage::Utf8).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "Property set string is truncated (6 of 4294967294 bytes)"
+        );
+    }
+
     #[test]
     fn write_property_value() {
         let value = PropertyValue::Empty;
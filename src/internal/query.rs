@@ -1,3 +1,4 @@
+use crate::internal::cache::RowCache;
 use crate::internal::expr::Expr;
 use crate::internal::stringpool::StringPool;
 use crate::internal::table::{Row, Rows, Table};
@@ -5,7 +6,7 @@ use crate::internal::value::{Value, ValueRef};
 use cfb;
 use std::collections::{BTreeMap, HashSet};
 use std::fmt;
-use std::io::{self, Read, Seek, Write};
+use std::io::{self, Read, Seek};
 use std::rc::Rc;
 
 // ========================================================================= //
@@ -39,39 +40,36 @@ impl Delete {
     pub(crate) fn exec<F>(
         self,
         comp: &mut cfb::CompoundFile<F>,
+        cache: &mut RowCache,
         string_pool: &mut StringPool,
         tables: &BTreeMap<String, Rc<Table>>,
     ) -> io::Result<()>
     where
-        F: Read + Write + Seek,
+        F: Read + Seek,
     {
         let table = match tables.get(&self.table_name) {
             Some(table) => table,
-            None => not_found!("Table {:?} does not exist", self.table_name),
+            None => {
+                msi_error!(TableNotFound { table: self.table_name.clone() })
+            }
         };
         // Validate the condition.
         if let Some(ref expr) = self.condition {
             for column_name in expr.column_names() {
                 if !table.has_column(column_name) {
-                    invalid_input!(
-                        "Table {:?} has no column named {:?}",
-                        self.table_name,
-                        column_name
-                    );
+                    msi_error!(ColumnNotFound {
+                        table: self.table_name.clone(),
+                        column: column_name.to_string(),
+                    });
                 }
             }
         }
-        // Read in the rows from the table.
-        let stream_name = table.stream_name();
-        let mut rows = if comp.exists(&stream_name) {
-            let stream = comp.open_stream(&stream_name)?;
-            table.read_rows(stream)?
-        } else {
-            Vec::new()
-        };
-        // Delete rows from the table.
-        rows.retain(|value_refs| {
-            let should_delete = match self.condition {
+        // Figure out which rows to delete.
+        let cached = cache.load_mut(comp, table, string_pool)?;
+        let should_delete: Vec<bool> = cached
+            .rows()
+            .iter()
+            .map(|value_refs| match self.condition {
                 Some(ref expr) => {
                     let values: Vec<Value> = value_refs
                         .iter()
@@ -81,20 +79,23 @@ impl Delete {
                     expr.eval(&row).to_bool()
                 }
                 None => true,
-            };
-            // TODO: Handle deleting rows referred to by foreign keys.
-            if should_delete {
-                for value_ref in value_refs {
-                    value_ref.remove(string_pool);
-                }
-                false
-            } else {
-                true
-            }
+            })
+            .collect();
+        // TODO: Handle deleting rows referred to by foreign keys.
+        ValueRef::remove_all(
+            cached
+                .rows()
+                .iter()
+                .zip(should_delete.iter())
+                .filter(|&(_, &delete)| delete)
+                .flat_map(|(value_refs, _)| value_refs),
+            string_pool,
+        )?;
+        // Delete rows from the table.
+        let mut should_delete = should_delete.into_iter();
+        cached.modify_rows(table, |rows| {
+            rows.retain(|_| !should_delete.next().unwrap())
         });
-        // Write the table back out to the file.
-        let stream = comp.create_stream(&stream_name)?;
-        table.write_rows(stream, rows)?;
         Ok(())
     }
 }
@@ -117,12 +118,17 @@ impl fmt::Display for Delete {
 pub struct Insert {
     table_name: String,
     new_rows: Vec<Vec<Value>>,
+    check_values: bool,
 }
 
 impl Insert {
     /// Starts building a query that will insert rows into the specified table.
     pub fn into<S: Into<String>>(table_name: S) -> Self {
-        Self { table_name: table_name.into(), new_rows: Vec::new() }
+        Self {
+            table_name: table_name.into(),
+            new_rows: Vec::new(),
+            check_values: true,
+        }
     }
 
     /// Adds a new row to be inserted into the table.
@@ -139,102 +145,119 @@ impl Insert {
         self
     }
 
+    /// Skips checking that each new value is valid for its column (the number
+    /// of values and uniqueness of keys are still checked).  This is used for
+    /// copying metadata from an existing package that this library can't
+    /// validate, such as unknown `_Validation` categories.
+    pub(crate) fn unchecked(mut self) -> Self {
+        self.check_values = false;
+        self
+    }
+
+    pub(crate) fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Returns the maximum number of new entries that executing this query
+    /// could add to the string pool.
+    pub(crate) fn max_new_strings(&self) -> usize {
+        self.new_rows
+            .iter()
+            .flatten()
+            .filter(|value| matches!(value, Value::Str(_)))
+            .count()
+    }
+
     pub(crate) fn exec<F>(
         self,
         comp: &mut cfb::CompoundFile<F>,
+        cache: &mut RowCache,
         string_pool: &mut StringPool,
         tables: &BTreeMap<String, Rc<Table>>,
     ) -> io::Result<()>
     where
-        F: Read + Write + Seek,
+        F: Read + Seek,
     {
         let table = match tables.get(&self.table_name) {
             Some(table) => table,
-            None => not_found!("Table {:?} does not exist", self.table_name),
+            None => {
+                msi_error!(TableNotFound { table: self.table_name.clone() })
+            }
         };
         // Validate the new rows.
-        for values in &self.new_rows {
+        for (row, values) in self.new_rows.iter().enumerate() {
             if values.len() != table.columns().len() {
-                invalid_input!(
-                    "Table {:?} has {} columns, but a row with {} values was \
-                     provided",
-                    self.table_name,
-                    table.columns().len(),
-                    values.len()
-                );
+                msi_error!(WrongNumberOfValues {
+                    table: self.table_name.clone(),
+                    row,
+                    expected: table.columns().len(),
+                    actual: values.len(),
+                });
             }
             for (column, value) in table.columns().iter().zip(values.iter()) {
-                if !column.is_valid_value(value) {
-                    invalid_input!(
-                        "{} is not a valid value for column {:?}",
-                        value,
-                        column.name()
-                    );
+                if self.check_values && !column.is_valid_value(value) {
+                    msi_error!(InvalidValue {
+                        table: self.table_name.clone(),
+                        column: column.name().to_string(),
+                        value: value.clone(),
+                        row: Some(row),
+                    });
                 }
                 // TODO: Validate foreign keys.
             }
         }
-        // Read in the rows from the table.
-        let stream_name = table.stream_name();
+        // Check if any of the new rows already exist in the table (or conflict
+        // with each other).
         let key_indices = table.primary_key_indices();
-        let mut rows_map = BTreeMap::<Vec<Value>, Vec<ValueRef>>::new();
-        if comp.exists(&stream_name) {
-            let stream = comp.open_stream(&stream_name)?;
-            for row in table.read_rows(stream)? {
+        let cached = cache.load_mut(comp, table, string_pool)?;
+        {
+            let existing_keys = cached.keys(table, string_pool)?;
+            let mut new_keys_set = HashSet::<Vec<Value>>::new();
+            for (row, values) in self.new_rows.iter().enumerate() {
                 let keys: Vec<Value> = key_indices
                     .iter()
-                    .map(|&index| row[index].to_value(string_pool))
+                    .map(|&index| values[index].clone())
                     .collect();
-                if rows_map.contains_key(&keys) {
-                    invalid_data!(
-                        "Malformed table {:?} contains multiple rows with \
-                         key {:?}",
-                        self.table_name,
-                        keys
-                    );
+                if existing_keys.contains(&keys) {
+                    msi_error!(DuplicateKey {
+                        table: self.table_name.clone(),
+                        key: keys,
+                        row,
+                    });
                 }
-                rows_map.insert(keys, row);
-            }
-        }
-        // Check if any of the new rows already exist in the table (or conflict
-        // with each other).
-        let mut new_keys_set = HashSet::<Vec<Value>>::new();
-        for values in &self.new_rows {
-            let keys: Vec<Value> = key_indices
-                .iter()
-                .map(|&index| values[index].clone())
-                .collect();
-            if rows_map.contains_key(&keys) {
-                already_exists!(
-                    "Table {:?} already contains a row with key {:?}",
-                    self.table_name,
-                    keys
-                );
-            }
-            if new_keys_set.contains(&keys) {
-                invalid_input!(
-                    "Cannot insert multiple rows with key {:?}",
-                    keys
-                );
+                if new_keys_set.contains(&keys) {
+                    msi_error!(RepeatedKey {
+                        table: self.table_name.clone(),
+                        key: keys,
+                        row,
+                    });
+                }
+                new_keys_set.insert(keys);
             }
-            new_keys_set.insert(keys);
         }
+        // Intern all the new values before changing the table, so that
+        // nothing has changed if that fails.
+        let all_keys: Vec<Vec<Value>> = self
+            .new_rows
+            .iter()
+            .map(|values| {
+                key_indices
+                    .iter()
+                    .map(|&index| values[index].clone())
+                    .collect()
+            })
+            .collect();
+        let value_refs = ValueRef::create_all(
+            self.new_rows.into_iter().flatten(),
+            string_pool,
+        )?;
         // Insert the new rows into the table.
-        for values in self.new_rows {
-            let keys: Vec<Value> = key_indices
-                .iter()
-                .map(|&index| values[index].clone())
-                .collect();
-            let row: Vec<ValueRef> = values
-                .into_iter()
-                .map(|value| ValueRef::create(value, string_pool))
-                .collect();
-            rows_map.insert(keys, row);
+        let num_columns = table.columns().len();
+        for (keys, row) in
+            all_keys.into_iter().zip(value_refs.chunks(num_columns))
+        {
+            cached.insert(table, keys, row.to_vec());
         }
-        // Write the table back out to the file.
-        let rows: Vec<Vec<ValueRef>> = rows_map.into_values().collect();
-        let stream = comp.create_stream(&stream_name)?;
-        table.write_rows(stream, rows)?;
         Ok(())
     }
 }
@@ -281,6 +304,7 @@ impl Join {
     fn exec<'a, F>(
         self,
         comp: &mut cfb::CompoundFile<F>,
+        cache: &mut RowCache,
         string_pool: &'a StringPool,
         tables: &BTreeMap<String, Rc<Table>>,
     ) -> io::Result<Rows<'a>>
@@ -292,24 +316,19 @@ impl Join {
                 let table = match tables.get(&table_name) {
                     Some(table) => table,
                     None => {
-                        not_found!("Table {:?} does not exist", table_name)
+                        msi_error!(TableNotFound { table: table_name.clone() })
                     }
                 };
-                let stream_name = table.stream_name();
-                let rows = if comp.exists(&stream_name) {
-                    let stream = comp.open_stream(&stream_name)?;
-                    table.read_rows(stream)?
-                } else {
-                    Vec::new()
-                };
+                let rows =
+                    cache.load(comp, table, string_pool)?.rows().to_vec();
                 Ok(Rows::new(string_pool, table.clone(), rows))
             }
             Self::Inner(select1, select2, condition) => {
                 let (table1, rows1) = select1
-                    .exec(comp, string_pool, tables)?
+                    .exec(comp, cache, string_pool, tables)?
                     .into_table_and_values();
                 let (table2, rows2) = select2
-                    .exec(comp, string_pool, tables)?
+                    .exec(comp, cache, string_pool, tables)?
                     .into_table_and_values();
                 let columns =
                     table1
@@ -347,10 +366,10 @@ impl Join {
             }
             Self::Left(select1, select2, condition) => {
                 let (table1, rows1) = select1
-                    .exec(comp, string_pool, tables)?
+                    .exec(comp, cache, string_pool, tables)?
                     .into_table_and_values();
                 let (table2, rows2) = select2
-                    .exec(comp, string_pool, tables)?
+                    .exec(comp, cache, string_pool, tables)?
                     .into_table_and_values();
                 let columns = table1
                     .columns()
@@ -499,6 +518,7 @@ impl Select {
     pub(crate) fn exec<'a, F>(
         self,
         comp: &mut cfb::CompoundFile<F>,
+        cache: &mut RowCache,
         string_pool: &'a StringPool,
         tables: &BTreeMap<String, Rc<Table>>,
     ) -> io::Result<Rows<'a>>
@@ -506,7 +526,7 @@ impl Select {
         F: Read + Seek,
     {
         // Join the table(s) to be queried.
-        let rows = self.from.exec(comp, string_pool, tables)?;
+        let rows = self.from.exec(comp, cache, string_pool, tables)?;
         let (mut table, mut rows) = rows.into_table_and_values();
         // Validate the selected column names.
         let mut column_indices =
@@ -515,11 +535,10 @@ impl Select {
             match table.index_for_column_name(column_name.as_str()) {
                 Some(index) => column_indices.push(index),
                 None => {
-                    invalid_input!(
-                        "Table {:?} has no column named {:?}",
-                        table.name(),
-                        column_name
-                    );
+                    msi_error!(ColumnNotFound {
+                        table: table.name().to_string(),
+                        column: column_name.clone(),
+                    });
                 }
             }
         }
@@ -527,11 +546,10 @@ impl Select {
         if let Some(ref expr) = self.condition {
             for column_name in expr.column_names() {
                 if !table.has_column(column_name) {
-                    invalid_input!(
-                        "Table {:?} has no column named {:?}",
-                        table.name(),
-                        column_name
-                    );
+                    msi_error!(ColumnNotFound {
+                        table: table.name().to_string(),
+                        column: column_name.to_string(),
+                    });
                 }
             }
         }
@@ -650,35 +668,52 @@ impl Update {
         self
     }
 
+    pub(crate) fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Returns the maximum number of new entries that executing this query
+    /// could add to the string pool.  Every updated row gets the same values,
+    /// so this doesn't depend on how many rows are updated.
+    pub(crate) fn max_new_strings(&self) -> usize {
+        self.updates
+            .iter()
+            .filter(|(_, value)| matches!(value, Value::Str(_)))
+            .count()
+    }
+
     pub(crate) fn exec<F>(
         self,
         comp: &mut cfb::CompoundFile<F>,
+        cache: &mut RowCache,
         string_pool: &mut StringPool,
         tables: &BTreeMap<String, Rc<Table>>,
     ) -> io::Result<()>
     where
-        F: Read + Write + Seek,
+        F: Read + Seek,
     {
         let table = match tables.get(&self.table_name) {
             Some(table) => table,
-            None => not_found!("Table {:?} does not exist", self.table_name),
+            None => {
+                msi_error!(TableNotFound { table: self.table_name.clone() })
+            }
         };
         // Validate the updates.
         for (column_name, value) in &self.updates {
             if !table.has_column(column_name.as_str()) {
-                invalid_input!(
-                    "Table {:?} has no column named {:?}",
-                    self.table_name,
-                    column_name
-                );
+                msi_error!(ColumnNotFound {
+                    table: self.table_name.clone(),
+                    column: column_name.clone(),
+                });
             }
             let column = table.get_column(column_name).unwrap();
             if !column.is_valid_value(value) {
-                invalid_input!(
-                    "{} is not a valid value for column {:?}",
-                    value,
-                    column_name
-                );
+                msi_error!(InvalidValue {
+                    table: self.table_name.clone(),
+                    column: column_name.clone(),
+                    value: value.clone(),
+                    row: None,
+                });
             }
             // TODO: Validate foreign keys.
         }
@@ -686,25 +721,28 @@ impl Update {
         if let Some(ref expr) = self.condition {
             for column_name in expr.column_names() {
                 if !table.has_column(column_name) {
-                    invalid_input!(
-                        "Table {:?} has no column named {:?}",
-                        self.table_name,
-                        column_name
-                    );
+                    msi_error!(ColumnNotFound {
+                        table: self.table_name.clone(),
+                        column: column_name.to_string(),
+                    });
                 }
             }
         }
-        // Read in the rows from the table.
-        let stream_name = table.stream_name();
-        let mut rows = if comp.exists(&stream_name) {
-            let stream = comp.open_stream(&stream_name)?;
-            table.read_rows(stream)?
-        } else {
-            Vec::new()
-        };
-        // Update the rows.
-        for value_refs in &mut rows {
-            let should_update = match self.condition {
+        // Figure out which rows and columns to update.  (If the same column
+        // is set more than once, the last value wins.)
+        let updates: BTreeMap<usize, &Value> = self
+            .updates
+            .iter()
+            .map(|(column_name, value)| {
+                (table.index_for_column_name(column_name).unwrap(), value)
+            })
+            .collect();
+        let cached = cache.load_mut(comp, table, string_pool)?;
+        let row_indices: Vec<usize> = cached
+            .rows()
+            .iter()
+            .enumerate()
+            .filter(|(_, value_refs)| match self.condition {
                 Some(ref expr) => {
                     let values: Vec<Value> = value_refs
                         .iter()
@@ -714,20 +752,37 @@ impl Update {
                     expr.eval(&row).to_bool()
                 }
                 None => true,
-            };
-            if should_update {
-                for (column_name, value) in &self.updates {
-                    let index =
-                        table.index_for_column_name(column_name).unwrap();
-                    let value_ref = &mut value_refs[index];
-                    value_ref.remove(string_pool);
-                    *value_ref = ValueRef::create(value.clone(), string_pool);
+            })
+            .map(|(row_index, _)| row_index)
+            .collect();
+        // Intern the new values before releasing the old ones, so that the
+        // string pool is left unchanged if either step fails.
+        let new_refs = ValueRef::create_all(
+            row_indices
+                .iter()
+                .flat_map(|_| updates.values().map(|&value| value.clone())),
+            string_pool,
+        )?;
+        let rows = cached.rows();
+        let removed = ValueRef::remove_all(
+            row_indices.iter().flat_map(|&row_index| {
+                updates.keys().map(move |&index| &rows[row_index][index])
+            }),
+            string_pool,
+        );
+        if let Err(error) = removed {
+            ValueRef::remove_all(&new_refs, string_pool).unwrap();
+            return Err(error);
+        }
+        // Update the rows.
+        let mut new_refs = new_refs.into_iter();
+        cached.modify_rows(table, |rows| {
+            for row_index in row_indices {
+                for &index in updates.keys() {
+                    rows[row_index][index] = new_refs.next().unwrap();
                 }
             }
-        }
-        // Write the table back out to the file.
-        let stream = comp.create_stream(&stream_name)?;
-        table.write_rows(stream, rows)?;
+        });
         Ok(())
     }
 }
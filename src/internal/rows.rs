@@ -0,0 +1,950 @@
+use crate::internal::column::Column;
+use crate::internal::error::Error;
+use crate::internal::table::Row;
+use crate::internal::value::Value;
+use std::fmt;
+use std::io;
+use std::ops::{BitOr, BitOrAssign};
+
+// ========================================================================= //
+
+/// A strongly typed row of one of the standard Windows Installer tables.
+///
+/// Typed rows can be read from a package with `Package::typed_rows()` (or
+/// one of its shorthands, such as `Package::files()`), and added to a
+/// package with `Package::insert_typed_rows()`.
+pub trait TypedRow: Sized {
+    /// The name of the table that rows of this type belong to.
+    const TABLE_NAME: &'static str;
+
+    /// Converts a row of the table into this type.  Returns an error if a
+    /// required column is missing, or if a column's value has the wrong type.
+    fn from_row(row: &Row) -> io::Result<Self>;
+
+    /// Returns the row's values, each paired with the name of its column.
+    /// Columns that were added in later schema versions are only included if
+    /// they have a non-null value.
+    fn to_values(&self) -> Vec<(&'static str, Value)>;
+}
+
+/// A typed row type that also knows the full schema of its table, so that
+/// the table can be created with `Package::create_typed_table()`.
+///
+/// This trait is normally implemented with `#[derive(MsiTable)]` (which
+/// requires the `derive` feature); see the `msi_derive` crate for the
+/// supported attributes.
+pub trait MsiTable: TypedRow {
+    /// Returns the columns of the table, in order.
+    fn columns() -> Vec<Column>;
+}
+
+/// A Rust type that can be stored in a single table column.  This is used by
+/// `#[derive(MsiTable)]` to convert struct fields to and from row values.
+pub trait FieldValue: Sized {
+    /// Reads the value of the named column from the row.  Returns an error if
+    /// the row has no such column, or if the value has the wrong type.
+    fn from_field(row: &Row, column: &str) -> io::Result<Self>;
+
+    /// Converts the field into a column value.
+    fn to_field(&self) -> Value;
+}
+
+impl FieldValue for String {
+    fn from_field(row: &Row, column: &str) -> io::Result<Self> {
+        string(row, column)
+    }
+
+    fn to_field(&self) -> Value {
+        Value::from(self.clone())
+    }
+}
+
+impl FieldValue for i16 {
+    fn from_field(row: &Row, column: &str) -> io::Result<Self> {
+        short(row, column)
+    }
+
+    fn to_field(&self) -> Value {
+        Value::from(*self)
+    }
+}
+
+impl FieldValue for i32 {
+    fn from_field(row: &Row, column: &str) -> io::Result<Self> {
+        int(row, column)
+    }
+
+    fn to_field(&self) -> Value {
+        Value::Int(*self)
+    }
+}
+
+/// A null value (or a missing column) is read as `None`.
+impl<T: FieldValue> FieldValue for Option<T> {
+    fn from_field(row: &Row, column: &str) -> io::Result<Self> {
+        match row.get_by_name(column) {
+            None | Some(Value::Null) => Ok(None),
+            Some(_) => T::from_field(row, column).map(Some),
+        }
+    }
+
+    fn to_field(&self) -> Value {
+        match self {
+            Some(value) => value.to_field(),
+            None => Value::Null,
+        }
+    }
+}
+
+// ========================================================================= //
+
+fn get<'a>(row: &'a Row, column: &str) -> io::Result<&'a Value> {
+    match row.get_by_name(column) {
+        Some(value) => Ok(value),
+        None => msi_error!(ColumnNotFound {
+            table: row.table_name().to_string(),
+            column: column.to_string(),
+        }),
+    }
+}
+
+fn wrong_type(row: &Row, column: &str) -> io::Error {
+    let value = row.get_by_name(column).cloned().unwrap_or(Value::Null);
+    io::Error::from(Error::InvalidValue {
+        table: row.table_name().to_string(),
+        column: column.to_string(),
+        value,
+        row: None,
+    })
+}
+
+fn string(row: &Row, column: &str) -> io::Result<String> {
+    match get(row, column)? {
+        Value::Str(string) => Ok(string.clone()),
+        _ => Err(wrong_type(row, column)),
+    }
+}
+
+fn opt_string(row: &Row, column: &str) -> io::Result<Option<String>> {
+    match row.get_by_name(column) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Str(string)) => Ok(Some(string.clone())),
+        Some(_) => Err(wrong_type(row, column)),
+    }
+}
+
+fn int(row: &Row, column: &str) -> io::Result<i32> {
+    match get(row, column)? {
+        Value::Int(number) => Ok(*number),
+        _ => Err(wrong_type(row, column)),
+    }
+}
+
+fn opt_int(row: &Row, column: &str) -> io::Result<Option<i32>> {
+    match row.get_by_name(column) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Int(number)) => Ok(Some(*number)),
+        Some(_) => Err(wrong_type(row, column)),
+    }
+}
+
+fn short(row: &Row, column: &str) -> io::Result<i16> {
+    i16::try_from(int(row, column)?).map_err(|_| wrong_type(row, column))
+}
+
+fn opt_short(row: &Row, column: &str) -> io::Result<Option<i16>> {
+    match opt_int(row, column)? {
+        None => Ok(None),
+        Some(number) => i16::try_from(number)
+            .map(Some)
+            .map_err(|_| wrong_type(row, column)),
+    }
+}
+
+fn opt_value<T: Clone + Into<Value>>(value: &Option<T>) -> Value {
+    match value {
+        Some(value) => value.clone().into(),
+        None => Value::Null,
+    }
+}
+
+// ========================================================================= //
+
+macro_rules! attribute_flags {
+    (
+        $(#[$attr:meta])*
+        $name:ident {
+            $(
+                $(#[$flag_attr:meta])*
+                const $flag:ident = $bits:expr;
+            )*
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
+        pub struct $name(i32);
+
+        impl $name {
+            $(
+                $(#[$flag_attr])*
+                pub const $flag: $name = $name($bits);
+            )*
+
+            /// Returns a value with no flags set.
+            #[must_use]
+            pub fn empty() -> Self {
+                Self(0)
+            }
+
+            /// Creates a value from a raw bitfield.  Bits without a named
+            /// constant are preserved.
+            #[must_use]
+            pub fn from_bits(bits: i32) -> Self {
+                Self(bits)
+            }
+
+            /// Returns the raw bitfield.
+            #[must_use]
+            pub fn bits(self) -> i32 {
+                self.0
+            }
+
+            /// Returns true if no flags are set.
+            #[must_use]
+            pub fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+
+            /// Returns true if all the flags in `other` are set.
+            #[must_use]
+            pub fn contains(self, other: Self) -> bool {
+                (self.0 & other.0) == other.0
+            }
+
+            /// Sets the flags in `other`.
+            pub fn insert(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+
+            /// Clears the flags in `other`.
+            pub fn remove(&mut self, other: Self) {
+                self.0 &= !other.0;
+            }
+        }
+
+        impl BitOr for $name {
+            type Output = Self;
+
+            fn bitor(self, other: Self) -> Self {
+                Self(self.0 | other.0)
+            }
+        }
+
+        impl BitOrAssign for $name {
+            fn bitor_assign(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                let mut remaining = self.0;
+                let mut names = Vec::<&str>::new();
+                $(
+                    if $bits != 0 && (remaining & $bits) == $bits {
+                        names.push(stringify!($flag));
+                        remaining &= !$bits;
+                    }
+                )*
+                write!(formatter, "{}(", stringify!($name))?;
+                for (index, name) in names.iter().enumerate() {
+                    if index > 0 {
+                        formatter.write_str(" | ")?;
+                    }
+                    formatter.write_str(name)?;
+                }
+                if remaining != 0 || names.is_empty() {
+                    if !names.is_empty() {
+                        formatter.write_str(" | ")?;
+                    }
+                    write!(formatter, "{:#x}", remaining)?;
+                }
+                formatter.write_str(")")
+            }
+        }
+    };
+}
+
+attribute_flags! {
+    /// The bit flags in the `Attributes` column of the `Component` table.
+    ComponentAttributes {
+        /// The component can only be run from source.
+        const SOURCE_ONLY = 0x1;
+        /// The component can be run locally or from source.
+        const OPTIONAL = 0x2;
+        /// The component's key path is a `Registry` table key, rather than a
+        /// `File` table key.
+        const REGISTRY_KEY_PATH = 0x4;
+        /// The component's key file should be reference counted in the
+        /// registry's shared DLL list.
+        const SHARED_DLL_REF_COUNT = 0x8;
+        /// The component is never uninstalled.
+        const PERMANENT = 0x10;
+        /// The component's key path is an `ODBCDataSource` table key.
+        const ODBC_DATA_SOURCE = 0x20;
+        /// The component's condition is reevaluated on reinstall.
+        const TRANSITIVE = 0x40;
+        /// Existing files and registry keys are never overwritten.
+        const NEVER_OVERWRITE = 0x80;
+        /// The component is a 64-bit component.
+        const WIN64 = 0x100;
+        /// Registry reflection is disabled for the component.
+        const DISABLE_REGISTRY_REFLECTION = 0x200;
+        /// The component is uninstalled when superseded by a patch.
+        const UNINSTALL_ON_SUPERSEDENCE = 0x400;
+        /// The component's files are shared between patches.
+        const SHARED = 0x800;
+    }
+}
+
+attribute_flags! {
+    /// The bit flags in the `Type` column of the `CustomAction` table.
+    ///
+    /// The low bits of the column hold the action's base type (see
+    /// `base_type()`) and source type (see `source_type()`); the remaining
+    /// bits are flags.  Note that the meaning of some flags depends on
+    /// others (e.g. `ROLLBACK` and `FIRST_SEQUENCE` share a bit, and only
+    /// mean "rollback" when `IN_SCRIPT` is also set).
+    CustomActionType {
+        /// Ignore the action's exit code.
+        const CONTINUE = 0x40;
+        /// Run the action asynchronously.
+        const ASYNC = 0x80;
+        /// Run the action only the first time it appears in a sequence.
+        const FIRST_SEQUENCE = 0x100;
+        /// Run the action only once per process.
+        const ONCE_PER_PROCESS = 0x200;
+        /// Run the action only if it runs on the client and the server.
+        const CLIENT_REPEAT = 0x300;
+        /// Defer the action to the installation script.
+        const IN_SCRIPT = 0x400;
+        /// For a deferred action, run it only on rollback.
+        const ROLLBACK = 0x100;
+        /// For a deferred action, run it only on commit.
+        const COMMIT = 0x200;
+        /// For a deferred action, run it in the system context.
+        const NO_IMPERSONATE = 0x800;
+        /// For a script action, use the 64-bit script engine.
+        const WIN64 = 0x1000;
+        /// Hide the action's target from the log.
+        const HIDE_TARGET = 0x2000;
+        /// Impersonate the user in terminal server sessions.
+        const TS_AWARE = 0x4000;
+    }
+}
+
+impl CustomActionType {
+    /// Returns the base type of the action (e.g. 1 for a DLL stored in the
+    /// `Binary` table, or 6 for a JScript stored in the `Binary` table).
+    #[must_use]
+    pub fn base_type(self) -> i32 {
+        self.0 & 0x7
+    }
+
+    /// Returns the source type bits of the action (0x0 for `Binary` table
+    /// data, 0x10 for a `File`, 0x20 for a `Directory`, and 0x30 for a
+    /// `Property`).
+    #[must_use]
+    pub fn source_type(self) -> i32 {
+        self.0 & 0x30
+    }
+}
+
+attribute_flags! {
+    /// The bit flags in the `Attributes` column of the `Feature` table.
+    FeatureAttributes {
+        /// The feature runs from source by default.
+        const FAVOR_SOURCE = 0x1;
+        /// The feature follows its parent's install state.
+        const FOLLOW_PARENT = 0x2;
+        /// The feature is advertised by default.
+        const FAVOR_ADVERTISE = 0x4;
+        /// The feature cannot be advertised.
+        const DISALLOW_ADVERTISE = 0x8;
+        /// The user interface doesn't offer to make the feature absent.
+        const UI_DISALLOW_ABSENT = 0x10;
+        /// The feature is only advertised if the shell supports it.
+        const NO_UNSUPPORTED_ADVERTISE = 0x20;
+    }
+}
+
+attribute_flags! {
+    /// The bit flags in the `Attributes` column of the `File` table.
+    FileAttributes {
+        /// The file is read-only.
+        const READ_ONLY = 0x1;
+        /// The file is hidden.
+        const HIDDEN = 0x2;
+        /// The file is a system file.
+        const SYSTEM = 0x4;
+        /// The installation fails if the file can't be installed.
+        const VITAL = 0x200;
+        /// The file has a valid checksum.
+        const CHECKSUM = 0x400;
+        /// The file was added by a patch.
+        const PATCH_ADDED = 0x1000;
+        /// The file's source is uncompressed.
+        const NONCOMPRESSED = 0x2000;
+        /// The file's source is compressed.
+        const COMPRESSED = 0x4000;
+    }
+}
+
+// ========================================================================= //
+
+/// A row of the `Component` table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ComponentRow {
+    /// The component's key.
+    pub component: String,
+    /// The component's GUID, in uppercase registry format.
+    pub component_id: Option<String>,
+    /// The key of the component's directory in the `Directory` table.
+    pub directory: String,
+    /// The component's attributes.
+    pub attributes: ComponentAttributes,
+    /// The condition controlling whether the component is installed.
+    pub condition: Option<String>,
+    /// The key of the component's key path (in the `File`, `Registry`, or
+    /// `ODBCDataSource` table, depending on `attributes`).
+    pub key_path: Option<String>,
+}
+
+impl TypedRow for ComponentRow {
+    const TABLE_NAME: &'static str = "Component";
+
+    fn from_row(row: &Row) -> io::Result<Self> {
+        Ok(ComponentRow {
+            component: string(row, "Component")?,
+            component_id: opt_string(row, "ComponentId")?,
+            directory: string(row, "Directory_")?,
+            attributes: ComponentAttributes(int(row, "Attributes")?),
+            condition: opt_string(row, "Condition")?,
+            key_path: opt_string(row, "KeyPath")?,
+        })
+    }
+
+    fn to_values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("Component", Value::from(self.component.clone())),
+            ("ComponentId", opt_value(&self.component_id)),
+            ("Directory_", Value::from(self.directory.clone())),
+            ("Attributes", Value::Int(self.attributes.bits())),
+            ("Condition", opt_value(&self.condition)),
+            ("KeyPath", opt_value(&self.key_path)),
+        ]
+    }
+}
+
+/// A row of the `CustomAction` table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CustomActionRow {
+    /// The action's name.
+    pub action: String,
+    /// The action's type and flags.
+    pub action_type: CustomActionType,
+    /// The action's source (whose meaning depends on the action's type).
+    pub source: Option<String>,
+    /// The action's target (whose meaning depends on the action's type).
+    pub target: Option<String>,
+    /// The action's extended type flags (Windows Installer 4.5 and later).
+    pub extended_type: Option<i32>,
+}
+
+impl TypedRow for CustomActionRow {
+    const TABLE_NAME: &'static str = "CustomAction";
+
+    fn from_row(row: &Row) -> io::Result<Self> {
+        Ok(CustomActionRow {
+            action: string(row, "Action")?,
+            action_type: CustomActionType(int(row, "Type")?),
+            source: opt_string(row, "Source")?,
+            target: opt_string(row, "Target")?,
+            extended_type: opt_int(row, "ExtendedType")?,
+        })
+    }
+
+    fn to_values(&self) -> Vec<(&'static str, Value)> {
+        let mut values = vec![
+            ("Action", Value::from(self.action.clone())),
+            ("Type", Value::Int(self.action_type.bits())),
+            ("Source", opt_value(&self.source)),
+            ("Target", opt_value(&self.target)),
+        ];
+        if let Some(extended_type) = self.extended_type {
+            values.push(("ExtendedType", Value::Int(extended_type)));
+        }
+        values
+    }
+}
+
+/// A row of the `Directory` table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DirectoryRow {
+    /// The directory's key.
+    pub directory: String,
+    /// The key of the parent directory, or `None` for a root directory.
+    pub parent: Option<String>,
+    /// The directory's name, in `DefaultDir` format (e.g. `"SHORT~1|Long
+    /// Name"`, or `"target:source"`).
+    pub default_dir: String,
+}
+
+impl TypedRow for DirectoryRow {
+    const TABLE_NAME: &'static str = "Directory";
+
+    fn from_row(row: &Row) -> io::Result<Self> {
+        Ok(DirectoryRow {
+            directory: string(row, "Directory")?,
+            parent: opt_string(row, "Directory_Parent")?,
+            default_dir: string(row, "DefaultDir")?,
+        })
+    }
+
+    fn to_values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("Directory", Value::from(self.directory.clone())),
+            ("Directory_Parent", opt_value(&self.parent)),
+            ("DefaultDir", Value::from(self.default_dir.clone())),
+        ]
+    }
+}
+
+/// A row of the `Feature` table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeatureRow {
+    /// The feature's key.
+    pub feature: String,
+    /// The key of the parent feature, or `None` for a root feature.
+    pub parent: Option<String>,
+    /// The feature's short, human-readable title.
+    pub title: Option<String>,
+    /// The feature's longer, human-readable description.
+    pub description: Option<String>,
+    /// The feature's display position and initial expansion state.
+    pub display: Option<i16>,
+    /// The feature's install level.
+    pub level: i16,
+    /// The key of the feature's configurable directory, if any.
+    pub directory: Option<String>,
+    /// The feature's attributes.
+    pub attributes: FeatureAttributes,
+}
+
+impl TypedRow for FeatureRow {
+    const TABLE_NAME: &'static str = "Feature";
+
+    fn from_row(row: &Row) -> io::Result<Self> {
+        Ok(FeatureRow {
+            feature: string(row, "Feature")?,
+            parent: opt_string(row, "Feature_Parent")?,
+            title: opt_string(row, "Title")?,
+            description: opt_string(row, "Description")?,
+            display: opt_short(row, "Display")?,
+            level: short(row, "Level")?,
+            directory: opt_string(row, "Directory_")?,
+            attributes: FeatureAttributes(int(row, "Attributes")?),
+        })
+    }
+
+    fn to_values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("Feature", Value::from(self.feature.clone())),
+            ("Feature_Parent", opt_value(&self.parent)),
+            ("Title", opt_value(&self.title)),
+            ("Description", opt_value(&self.description)),
+            ("Display", opt_value(&self.display)),
+            ("Level", Value::from(self.level)),
+            ("Directory_", opt_value(&self.directory)),
+            ("Attributes", Value::Int(self.attributes.bits())),
+        ]
+    }
+}
+
+/// A row of the `FeatureComponents` table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeatureComponentsRow {
+    /// The key of the feature.
+    pub feature: String,
+    /// The key of a component belonging to the feature.
+    pub component: String,
+}
+
+impl TypedRow for FeatureComponentsRow {
+    const TABLE_NAME: &'static str = "FeatureComponents";
+
+    fn from_row(row: &Row) -> io::Result<Self> {
+        Ok(FeatureComponentsRow {
+            feature: string(row, "Feature_")?,
+            component: string(row, "Component_")?,
+        })
+    }
+
+    fn to_values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("Feature_", Value::from(self.feature.clone())),
+            ("Component_", Value::from(self.component.clone())),
+        ]
+    }
+}
+
+/// A row of the `File` table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileRow {
+    /// The file's key.
+    pub file: String,
+    /// The key of the component that the file belongs to.
+    pub component: String,
+    /// The file's name, in `Filename` format (e.g. `"SHORT~1.TXT|Long
+    /// Name.txt"`).
+    pub file_name: String,
+    /// The size of the file, in bytes.
+    pub file_size: i32,
+    /// The file's version string, or the key of its companion file.
+    pub version: Option<String>,
+    /// The file's language IDs, separated by commas.
+    pub language: Option<String>,
+    /// The file's attributes.  A null value in the table is read as empty
+    /// attributes, and empty attributes are written as null.
+    pub attributes: FileAttributes,
+    /// The file's position in the media sequence.
+    pub sequence: i32,
+}
+
+impl TypedRow for FileRow {
+    const TABLE_NAME: &'static str = "File";
+
+    fn from_row(row: &Row) -> io::Result<Self> {
+        Ok(FileRow {
+            file: string(row, "File")?,
+            component: string(row, "Component_")?,
+            file_name: string(row, "FileName")?,
+            file_size: int(row, "FileSize")?,
+            version: opt_string(row, "Version")?,
+            language: opt_string(row, "Language")?,
+            attributes: FileAttributes(
+                opt_int(row, "Attributes")?.unwrap_or(0),
+            ),
+            sequence: int(row, "Sequence")?,
+        })
+    }
+
+    fn to_values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("File", Value::from(self.file.clone())),
+            ("Component_", Value::from(self.component.clone())),
+            ("FileName", Value::from(self.file_name.clone())),
+            ("FileSize", Value::Int(self.file_size)),
+            ("Version", opt_value(&self.version)),
+            ("Language", opt_value(&self.language)),
+            (
+                "Attributes",
+                if self.attributes.is_empty() {
+                    Value::Null
+                } else {
+                    Value::Int(self.attributes.bits())
+                },
+            ),
+            ("Sequence", Value::Int(self.sequence)),
+        ]
+    }
+}
+
+/// A row of the `Media` table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MediaRow {
+    /// The disk's key.
+    pub disk_id: i16,
+    /// The sequence number of the last file on the disk.
+    pub last_sequence: i32,
+    /// The human-readable name of the disk, for prompting the user.
+    pub disk_prompt: Option<String>,
+    /// The name of the disk's cabinet file; if it starts with `#`, the
+    /// cabinet is stored in a stream within the package.
+    pub cabinet: Option<String>,
+    /// The disk's volume label.
+    pub volume_label: Option<String>,
+    /// The property holding the disk's source location (for patches).
+    pub source: Option<String>,
+}
+
+impl TypedRow for MediaRow {
+    const TABLE_NAME: &'static str = "Media";
+
+    fn from_row(row: &Row) -> io::Result<Self> {
+        Ok(MediaRow {
+            disk_id: short(row, "DiskId")?,
+            last_sequence: int(row, "LastSequence")?,
+            disk_prompt: opt_string(row, "DiskPrompt")?,
+            cabinet: opt_string(row, "Cabinet")?,
+            volume_label: opt_string(row, "VolumeLabel")?,
+            source: opt_string(row, "Source")?,
+        })
+    }
+
+    fn to_values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("DiskId", Value::from(self.disk_id)),
+            ("LastSequence", Value::Int(self.last_sequence)),
+            ("DiskPrompt", opt_value(&self.disk_prompt)),
+            ("Cabinet", opt_value(&self.cabinet)),
+            ("VolumeLabel", opt_value(&self.volume_label)),
+            ("Source", opt_value(&self.source)),
+        ]
+    }
+}
+
+/// A row of the `Property` table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PropertyRow {
+    /// The property's name.
+    pub property: String,
+    /// The property's value.
+    pub value: String,
+}
+
+impl TypedRow for PropertyRow {
+    const TABLE_NAME: &'static str = "Property";
+
+    fn from_row(row: &Row) -> io::Result<Self> {
+        Ok(PropertyRow {
+            property: string(row, "Property")?,
+            value: string(row, "Value")?,
+        })
+    }
+
+    fn to_values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("Property", Value::from(self.property.clone())),
+            ("Value", Value::from(self.value.clone())),
+        ]
+    }
+}
+
+/// A row of the `Registry` table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RegistryRow {
+    /// The registry entry's key.
+    pub registry: String,
+    /// The predefined root key (-1 for per-user or per-machine depending on
+    /// the installation, 0 for `HKEY_CLASSES_ROOT`, 1 for
+    /// `HKEY_CURRENT_USER`, 2 for `HKEY_LOCAL_MACHINE`, or 3 for
+    /// `HKEY_USERS`).
+    pub root: i16,
+    /// The registry key path, relative to the root.
+    pub key: String,
+    /// The registry value name, or a special `+`/`-`/`*` action.
+    pub name: Option<String>,
+    /// The registry value.
+    pub value: Option<String>,
+    /// The key of the component that the entry belongs to.
+    pub component: String,
+}
+
+impl TypedRow for RegistryRow {
+    const TABLE_NAME: &'static str = "Registry";
+
+    fn from_row(row: &Row) -> io::Result<Self> {
+        Ok(RegistryRow {
+            registry: string(row, "Registry")?,
+            root: short(row, "Root")?,
+            key: string(row, "Key")?,
+            name: opt_string(row, "Name")?,
+            value: opt_string(row, "Value")?,
+            component: string(row, "Component_")?,
+        })
+    }
+
+    fn to_values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("Registry", Value::from(self.registry.clone())),
+            ("Root", Value::from(self.root)),
+            ("Key", Value::from(self.key.clone())),
+            ("Name", opt_value(&self.name)),
+            ("Value", opt_value(&self.value)),
+            ("Component_", Value::from(self.component.clone())),
+        ]
+    }
+}
+
+/// A row of the `Shortcut` table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ShortcutRow {
+    /// The shortcut's key.
+    pub shortcut: String,
+    /// The key of the directory that the shortcut is created in.
+    pub directory: String,
+    /// The shortcut's file name, in `Filename` format.
+    pub name: String,
+    /// The key of the component that the shortcut belongs to.
+    pub component: String,
+    /// The shortcut's target: a feature key (for an advertised shortcut) or
+    /// a formatted path.
+    pub target: String,
+    /// The command-line arguments for the shortcut.
+    pub arguments: Option<String>,
+    /// The shortcut's description.
+    pub description: Option<String>,
+    /// The shortcut's hotkey.
+    pub hotkey: Option<i16>,
+    /// The key of the shortcut's icon in the `Icon` table.
+    pub icon: Option<String>,
+    /// The index of the icon within its icon file.
+    pub icon_index: Option<i16>,
+    /// The window mode to start the target in.
+    pub show_cmd: Option<i16>,
+    /// The property holding the shortcut's working directory.
+    pub working_dir: Option<String>,
+    /// The DLL holding the shortcut's localized display name (Windows
+    /// Installer 4.0 and later).
+    pub display_resource_dll: Option<String>,
+    /// The resource ID of the shortcut's localized display name.
+    pub display_resource_id: Option<i32>,
+    /// The DLL holding the shortcut's localized description (Windows
+    /// Installer 4.0 and later).
+    pub description_resource_dll: Option<String>,
+    /// The resource ID of the shortcut's localized description.
+    pub description_resource_id: Option<i32>,
+}
+
+impl TypedRow for ShortcutRow {
+    const TABLE_NAME: &'static str = "Shortcut";
+
+    fn from_row(row: &Row) -> io::Result<Self> {
+        Ok(ShortcutRow {
+            shortcut: string(row, "Shortcut")?,
+            directory: string(row, "Directory_")?,
+            name: string(row, "Name")?,
+            component: string(row, "Component_")?,
+            target: string(row, "Target")?,
+            arguments: opt_string(row, "Arguments")?,
+            description: opt_string(row, "Description")?,
+            hotkey: opt_short(row, "Hotkey")?,
+            icon: opt_string(row, "Icon_")?,
+            icon_index: opt_short(row, "IconIndex")?,
+            show_cmd: opt_short(row, "ShowCmd")?,
+            working_dir: opt_string(row, "WkDir")?,
+            display_resource_dll: opt_string(row, "DisplayResourceDLL")?,
+            display_resource_id: opt_int(row, "DisplayResourceId")?,
+            description_resource_dll: opt_string(
+                row,
+                "DescriptionResourceDLL",
+            )?,
+            description_resource_id: opt_int(row, "DescriptionResourceId")?,
+        })
+    }
+
+    fn to_values(&self) -> Vec<(&'static str, Value)> {
+        let mut values = vec![
+            ("Shortcut", Value::from(self.shortcut.clone())),
+            ("Directory_", Value::from(self.directory.clone())),
+            ("Name", Value::from(self.name.clone())),
+            ("Component_", Value::from(self.component.clone())),
+            ("Target", Value::from(self.target.clone())),
+            ("Arguments", opt_value(&self.arguments)),
+            ("Description", opt_value(&self.description)),
+            ("Hotkey", opt_value(&self.hotkey)),
+            ("Icon_", opt_value(&self.icon)),
+            ("IconIndex", opt_value(&self.icon_index)),
+            ("ShowCmd", opt_value(&self.show_cmd)),
+            ("WkDir", opt_value(&self.working_dir)),
+        ];
+        let optional = [
+            ("DisplayResourceDLL", opt_value(&self.display_resource_dll)),
+            ("DisplayResourceId", opt_value(&self.display_resource_id)),
+            (
+                "DescriptionResourceDLL",
+                opt_value(&self.description_resource_dll),
+            ),
+            (
+                "DescriptionResourceId",
+                opt_value(&self.description_resource_id),
+            ),
+        ];
+        values.extend(
+            optional.into_iter().filter(|(_, value)| !value.is_null()),
+        );
+        values
+    }
+}
+
+macro_rules! impl_try_from_row {
+    ($($name:ident),*) => {
+        $(
+            impl TryFrom<&Row> for $name {
+                type Error = io::Error;
+
+                fn try_from(row: &Row) -> io::Result<Self> {
+                    $name::from_row(row)
+                }
+            }
+
+            impl TryFrom<Row> for $name {
+                type Error = io::Error;
+
+                fn try_from(row: Row) -> io::Result<Self> {
+                    $name::from_row(&row)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_row!(
+    ComponentRow,
+    CustomActionRow,
+    DirectoryRow,
+    FeatureRow,
+    FeatureComponentsRow,
+    FileRow,
+    MediaRow,
+    PropertyRow,
+    RegistryRow,
+    ShortcutRow
+);
+
+// ========================================================================= //
+
+#[cfg(test)]
+mod tests {
+    use super::{ComponentAttributes, CustomActionType, FileAttributes};
+
+    #[test]
+    fn attribute_flags() {
+        let mut attributes = FileAttributes::READ_ONLY | FileAttributes::VITAL;
+        assert_eq!(attributes.bits(), 0x201);
+        assert!(attributes.contains(FileAttributes::VITAL));
+        assert!(!attributes.contains(FileAttributes::HIDDEN));
+        attributes.remove(FileAttributes::READ_ONLY);
+        attributes.insert(FileAttributes::from_bits(0x8000));
+        assert_eq!(
+            format!("{:?}", attributes),
+            "FileAttributes(VITAL | 0x8000)"
+        );
+        assert_eq!(
+            format!("{:?}", ComponentAttributes::empty()),
+            "ComponentAttributes(0x0)"
+        );
+    }
+
+    #[test]
+    fn custom_action_type() {
+        let action_type = CustomActionType::from_bits(0x0c22);
+        assert_eq!(action_type.base_type(), 2);
+        assert_eq!(action_type.source_type(), 0x20);
+        assert!(action_type.contains(CustomActionType::IN_SCRIPT));
+        assert!(action_type.contains(CustomActionType::NO_IMPERSONATE));
+    }
+}
+
+// ========================================================================= //
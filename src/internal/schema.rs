@@ -0,0 +1,1289 @@
+use crate::internal::category::Category;
+use crate::internal::column::{Column, ColumnType};
+use std::fmt;
+
+// ========================================================================= //
+
+/// A version of the standard Windows Installer database schema.
+///
+/// Each version of Windows Installer added new standard tables (and, in a few
+/// cases, new columns to existing tables).  A package's schema version is
+/// normally recorded in the "Page Count" property of its summary information
+/// (e.g. 200 for Windows Installer 2.0).
+#[derive(
+    Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd,
+)]
+pub enum SchemaVersion {
+    /// The schema for Windows Installer 2.0.
+    V200,
+    /// The schema for Windows Installer 3.0 and 3.1.
+    V300,
+    /// The schema for Windows Installer 4.0.
+    V400,
+    /// The schema for Windows Installer 4.5.
+    V450,
+    /// The schema for Windows Installer 5.0.
+    #[default]
+    V500,
+}
+
+impl SchemaVersion {
+    /// Returns a list of all supported schema versions, from oldest to
+    /// newest.
+    #[must_use]
+    pub fn all() -> Vec<SchemaVersion> {
+        vec![
+            SchemaVersion::V200,
+            SchemaVersion::V300,
+            SchemaVersion::V400,
+            SchemaVersion::V450,
+            SchemaVersion::V500,
+        ]
+    }
+
+    /// Returns the schema version number, as stored in the "Page Count"
+    /// summary information property (e.g. 450 for Windows Installer 4.5).
+    #[must_use]
+    pub fn number(self) -> i32 {
+        match self {
+            SchemaVersion::V200 => 200,
+            SchemaVersion::V300 => 300,
+            SchemaVersion::V400 => 400,
+            SchemaVersion::V450 => 450,
+            SchemaVersion::V500 => 500,
+        }
+    }
+
+    /// Returns the newest supported schema version that is no newer than the
+    /// given schema version number (or the oldest supported version, if the
+    /// number is older than that).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use msi::schema::SchemaVersion;
+    /// assert_eq!(SchemaVersion::from_number(301), SchemaVersion::V300);
+    /// assert_eq!(SchemaVersion::from_number(110), SchemaVersion::V200);
+    /// assert_eq!(SchemaVersion::from_number(999), SchemaVersion::V500);
+    /// ```
+    #[must_use]
+    pub fn from_number(number: i32) -> SchemaVersion {
+        SchemaVersion::all()
+            .into_iter()
+            .rev()
+            .find(|version| version.number() <= number)
+            .unwrap_or(SchemaVersion::V200)
+    }
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let number = self.number();
+        write!(formatter, "{}.{}", number / 100, (number % 100) / 10)
+    }
+}
+
+// ========================================================================= //
+
+/// Returns the names of all the standard tables in the given schema version,
+/// in alphabetical order.
+#[must_use]
+pub fn table_names(version: SchemaVersion) -> Vec<&'static str> {
+    TABLES
+        .iter()
+        .filter(|table| table.since <= version)
+        .map(|table| table.name)
+        .collect()
+}
+
+/// Returns true if the given name is the name of a standard table in any
+/// schema version.
+#[must_use]
+pub fn is_standard_table(table_name: &str) -> bool {
+    find_table(table_name).is_some()
+}
+
+/// Returns the columns of the given standard table, as defined in the given
+/// schema version, or `None` if that schema version has no such table.  The
+/// columns carry the standard validation metadata (nullability, value
+/// ranges, foreign keys, categories, and permitted values), and can be passed
+/// directly to `Package::create_table()`.
+///
+/// # Examples
+///
+/// ```
+/// use msi::schema::{self, SchemaVersion};
+///
+/// let columns = schema::table_columns("File", SchemaVersion::V500).unwrap();
+/// assert_eq!(columns[0].name(), "File");
+/// assert!(columns[0].is_primary_key());
+/// assert_eq!(columns[1].name(), "Component_");
+/// assert_eq!(columns.len(), 8);
+///
+/// // The MsiEmbeddedUI table was introduced in Windows Installer 4.5:
+/// assert!(schema::table_columns("MsiEmbeddedUI", SchemaVersion::V400)
+///     .is_none());
+/// ```
+#[must_use]
+pub fn table_columns(
+    table_name: &str,
+    version: SchemaVersion,
+) -> Option<Vec<Column>> {
+    let table = find_table(table_name)?;
+    if table.since > version {
+        return None;
+    }
+    Some(
+        table
+            .columns
+            .iter()
+            .filter(|column| column.since <= version)
+            .map(ColumnDef::to_column)
+            .collect(),
+    )
+}
+
+fn find_table(table_name: &str) -> Option<&'static TableDef> {
+    TABLES
+        .binary_search_by(|table| table.name.cmp(table_name))
+        .ok()
+        .map(|index| &TABLES[index])
+}
+
+// ========================================================================= //
+
+struct TableDef {
+    name: &'static str,
+    since: SchemaVersion,
+    columns: &'static [ColumnDef],
+}
+
+const fn table(name: &'static str, columns: &'static [ColumnDef]) -> TableDef {
+    TableDef { name, since: SchemaVersion::V200, columns }
+}
+
+impl TableDef {
+    const fn since(mut self, version: SchemaVersion) -> Self {
+        self.since = version;
+        self
+    }
+}
+
+struct ColumnDef {
+    name: &'static str,
+    coltype: ColumnType,
+    since: SchemaVersion,
+    is_primary_key: bool,
+    is_nullable: bool,
+    is_localizable: bool,
+    value_range: Option<(i32, i32)>,
+    foreign_key: Option<(&'static str, i32)>,
+    category: Option<Category>,
+    enum_values: &'static [&'static str],
+}
+
+const fn column(name: &'static str, coltype: ColumnType) -> ColumnDef {
+    ColumnDef {
+        name,
+        coltype,
+        since: SchemaVersion::V200,
+        is_primary_key: false,
+        is_nullable: false,
+        is_localizable: false,
+        value_range: None,
+        foreign_key: None,
+        category: None,
+        enum_values: &[],
+    }
+}
+
+/// A 16-bit integer column.
+const fn i2(name: &'static str) -> ColumnDef {
+    column(name, ColumnType::Int16)
+}
+
+/// A 32-bit integer column.
+const fn i4(name: &'static str) -> ColumnDef {
+    column(name, ColumnType::Int32)
+}
+
+/// A string column with the given maximum length (or zero for no max).
+const fn s(name: &'static str, max_len: usize) -> ColumnDef {
+    column(name, ColumnType::Str(max_len))
+}
+
+/// A binary stream column.
+const fn v(name: &'static str) -> ColumnDef {
+    column(name, ColumnType::Binary).cat(Category::Binary)
+}
+
+/// A string column holding an identifier, with the given maximum length.
+const fn id(name: &'static str, max_len: usize) -> ColumnDef {
+    s(name, max_len).cat(Category::Identifier)
+}
+
+/// A column holding a GUID.
+const fn guid(name: &'static str) -> ColumnDef {
+    s(name, 38).cat(Category::Guid)
+}
+
+/// A column referring to the first (key) column of another table.  Such
+/// columns conventionally have a trailing underscore in their names.
+const fn fk(
+    name: &'static str,
+    table: &'static str,
+    max_len: usize,
+) -> ColumnDef {
+    id(name, max_len).refs(table, 1)
+}
+
+/// A column referring to the `Component` table.
+const fn component(name: &'static str) -> ColumnDef {
+    fk(name, "Component", 72)
+}
+
+/// A column referring to the `Feature` table.
+const fn feature(name: &'static str) -> ColumnDef {
+    fk(name, "Feature", 38)
+}
+
+/// A column referring to the `File` table.
+const fn file(name: &'static str) -> ColumnDef {
+    fk(name, "File", 72)
+}
+
+/// A 16-bit screen coordinate or size, for the user interface tables.
+const fn coord(name: &'static str) -> ColumnDef {
+    i2(name).range(0, 0x7fff)
+}
+
+impl ColumnDef {
+    const fn key(mut self) -> Self {
+        self.is_primary_key = true;
+        self
+    }
+
+    const fn null(mut self) -> Self {
+        self.is_nullable = true;
+        self
+    }
+
+    const fn loc(mut self) -> Self {
+        self.is_localizable = true;
+        self
+    }
+
+    const fn range(mut self, min: i32, max: i32) -> Self {
+        self.value_range = Some((min, max));
+        self
+    }
+
+    const fn refs(mut self, table: &'static str, column: i32) -> Self {
+        self.foreign_key = Some((table, column));
+        self
+    }
+
+    const fn cat(mut self, category: Category) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    const fn set(mut self, values: &'static [&'static str]) -> Self {
+        self.enum_values = values;
+        self
+    }
+
+    const fn since(mut self, version: SchemaVersion) -> Self {
+        self.since = version;
+        self
+    }
+
+    fn to_column(&self) -> Column {
+        let mut builder = Column::build(self.name);
+        if self.is_primary_key {
+            builder = builder.primary_key();
+        }
+        if self.is_nullable {
+            builder = builder.nullable();
+        }
+        if self.is_localizable {
+            builder = builder.localizable();
+        }
+        if let Some((min, max)) = self.value_range {
+            builder = builder.range(min, max);
+        }
+        if let Some((table, column)) = self.foreign_key {
+            builder = builder.foreign_key(table, column);
+        }
+        if let Some(category) = self.category {
+            builder = builder.category(category);
+        }
+        if !self.enum_values.is_empty() {
+            builder = builder.enum_values(self.enum_values);
+        }
+        match self.coltype {
+            ColumnType::Int16 => builder.int16(),
+            ColumnType::Int32 => builder.int32(),
+            ColumnType::Str(max_len) => builder.string(max_len),
+            ColumnType::Binary => builder.binary(),
+        }
+    }
+}
+
+// ========================================================================= //
+
+const MAX_I2: i32 = 0x7fff;
+const MAX_I4: i32 = 0x7fff_ffff;
+
+// The columns shared by all of the sequence tables:
+const SEQUENCE_COLUMNS: &[ColumnDef] = &[
+    id("Action", 72).key(),
+    s("Condition", 255).null().cat(Category::Condition),
+    i2("Sequence").null().range(-4, MAX_I2),
+];
+
+// The definitions of the standard tables, which must be kept sorted by name
+// (so that they can be binary searched).  Where a column can refer to any of
+// several tables (e.g. `Component.KeyPath`), no foreign key is given.
+const TABLES: &[TableDef] = &[
+    table(
+        "ActionText",
+        &[
+            id("Action", 72).key(),
+            s("Description", 0).null().loc().cat(Category::Text),
+            s("Template", 0).null().loc().cat(Category::Template),
+        ],
+    ),
+    table("AdminExecuteSequence", SEQUENCE_COLUMNS),
+    table("AdminUISequence", SEQUENCE_COLUMNS),
+    table("AdvtExecuteSequence", SEQUENCE_COLUMNS),
+    table("AdvtUISequence", SEQUENCE_COLUMNS),
+    table(
+        "AppId",
+        &[
+            guid("AppId").key(),
+            s("RemoteServerName", 255).null().cat(Category::Formatted),
+            s("LocalService", 255).null().cat(Category::Text),
+            s("ServiceParameters", 255).null().cat(Category::Text),
+            s("DllSurrogate", 255).null().cat(Category::Text),
+            i2("ActivateAtStorage").null().range(0, 1),
+            i2("RunAsInteractiveUser").null().range(0, 1),
+        ],
+    ),
+    table(
+        "AppSearch",
+        &[id("Property", 72).key(), id("Signature_", 72).key()],
+    ),
+    table(
+        "BBControl",
+        &[
+            fk("Billboard_", "Billboard", 50).key(),
+            id("BBControl", 50).key(),
+            id("Type", 50),
+            coord("X"),
+            coord("Y"),
+            coord("Width"),
+            coord("Height"),
+            i4("Attributes").null().range(0, MAX_I4),
+            s("Text", 50).null().loc().cat(Category::Text),
+        ],
+    ),
+    table(
+        "Billboard",
+        &[
+            id("Billboard", 50).key(),
+            feature("Feature_"),
+            id("Action", 50).null(),
+            i2("Ordering").null().range(0, MAX_I2),
+        ],
+    ),
+    table("Binary", &[id("Name", 72).key(), v("Data")]),
+    table(
+        "BindImage",
+        &[file("File_").key(), s("Path", 255).null().cat(Category::Paths)],
+    ),
+    table("CCPSearch", &[id("Signature_", 72).key()]),
+    table(
+        "CheckBox",
+        &[
+            id("Property", 72).key(),
+            s("Value", 64).null().cat(Category::Formatted),
+        ],
+    ),
+    table(
+        "Class",
+        &[
+            guid("CLSID").key(),
+            id("Context", 32).key(),
+            component("Component_").key(),
+            s("ProgId_Default", 255)
+                .null()
+                .refs("ProgId", 1)
+                .cat(Category::Text),
+            s("Description", 255).null().loc().cat(Category::Text),
+            guid("AppId_").null().refs("AppId", 1),
+            s("FileTypeMask", 255).null().cat(Category::Text),
+            fk("Icon_", "Icon", 72).null(),
+            i2("IconIndex").null().range(-MAX_I2, MAX_I2),
+            s("DefInprocHandler", 32).null().cat(Category::Filename),
+            s("Argument", 255).null().cat(Category::Formatted),
+            feature("Feature_"),
+            i2("Attributes").null().range(0, MAX_I2),
+        ],
+    ),
+    table(
+        "ComboBox",
+        &[
+            id("Property", 72).key(),
+            i2("Order").key().range(1, MAX_I2),
+            s("Value", 64).cat(Category::Formatted),
+            s("Text", 64).null().loc().cat(Category::Formatted),
+        ],
+    ),
+    table(
+        "CompLocator",
+        &[
+            id("Signature_", 72).key(),
+            guid("ComponentId"),
+            i2("Type").null().range(0, 1),
+        ],
+    ),
+    table(
+        "Complus",
+        &[
+            component("Component_").key(),
+            i2("ExpType").key().null().range(0, MAX_I2),
+        ],
+    ),
+    table(
+        "Component",
+        &[
+            id("Component", 72).key(),
+            guid("ComponentId").null(),
+            fk("Directory_", "Directory", 72),
+            i2("Attributes"),
+            s("Condition", 255).null().cat(Category::Condition),
+            id("KeyPath", 72).null(),
+        ],
+    ),
+    table(
+        "Condition",
+        &[
+            feature("Feature_").key(),
+            i2("Level").key().range(0, MAX_I2),
+            s("Condition", 255).null().cat(Category::Condition),
+        ],
+    ),
+    table(
+        "Control",
+        &[
+            fk("Dialog_", "Dialog", 72).key(),
+            id("Control", 50).key(),
+            id("Type", 20),
+            coord("X"),
+            coord("Y"),
+            coord("Width"),
+            coord("Height"),
+            i4("Attributes").null().range(0, MAX_I4),
+            id("Property", 72).null(),
+            s("Text", 0).null().loc().cat(Category::Formatted),
+            id("Control_Next", 50).null().refs("Control", 2),
+            s("Help", 50).null().loc().cat(Category::Text),
+        ],
+    ),
+    table(
+        "ControlCondition",
+        &[
+            fk("Dialog_", "Dialog", 72).key(),
+            id("Control_", 50).key().refs("Control", 2),
+            s("Action", 50)
+                .key()
+                .set(&["Default", "Disable", "Enable", "Hide", "Show"]),
+            s("Condition", 255).key().cat(Category::Condition),
+        ],
+    ),
+    table(
+        "ControlEvent",
+        &[
+            fk("Dialog_", "Dialog", 72).key(),
+            id("Control_", 50).key().refs("Control", 2),
+            s("Event", 50).key().cat(Category::Formatted),
+            s("Argument", 255).key().cat(Category::Formatted),
+            s("Condition", 255).key().null().cat(Category::Condition),
+            i2("Ordering").null().range(0, MAX_I2),
+        ],
+    ),
+    table(
+        "CreateFolder",
+        &[
+            fk("Directory_", "Directory", 72).key(),
+            component("Component_").key(),
+        ],
+    ),
+    table(
+        "CustomAction",
+        &[
+            id("Action", 72).key(),
+            i2("Type").range(1, MAX_I2),
+            s("Source", 72).null().cat(Category::CustomSource),
+            s("Target", 255).null().cat(Category::Formatted),
+            i4("ExtendedType")
+                .null()
+                .range(0, MAX_I4)
+                .since(SchemaVersion::V450),
+        ],
+    ),
+    table(
+        "Dialog",
+        &[
+            id("Dialog", 72).key(),
+            i2("HCentering").range(0, 100),
+            i2("VCentering").range(0, 100),
+            coord("Width"),
+            coord("Height"),
+            i4("Attributes").null().range(0, MAX_I4),
+            s("Title", 128).null().loc().cat(Category::Formatted),
+            id("Control_First", 50).refs("Control", 2),
+            id("Control_Default", 50).null().refs("Control", 2),
+            id("Control_Cancel", 50).null().refs("Control", 2),
+        ],
+    ),
+    table(
+        "Directory",
+        &[
+            id("Directory", 72).key(),
+            fk("Directory_Parent", "Directory", 72).null(),
+            s("DefaultDir", 255).loc().cat(Category::DefaultDir),
+        ],
+    ),
+    table(
+        "DrLocator",
+        &[
+            id("Signature_", 72).key(),
+            id("Parent", 72).key().null(),
+            s("Path", 255).key().null().cat(Category::AnyPath),
+            i2("Depth").null().range(0, MAX_I2),
+        ],
+    ),
+    table(
+        "DuplicateFile",
+        &[
+            id("FileKey", 72).key(),
+            component("Component_"),
+            file("File_"),
+            s("DestName", 255).null().loc().cat(Category::Filename),
+            id("DestFolder", 72).null(),
+        ],
+    ),
+    table(
+        "Environment",
+        &[
+            id("Environment", 72).key(),
+            s("Name", 255).loc().cat(Category::Text),
+            s("Value", 255).null().loc().cat(Category::Formatted),
+            component("Component_"),
+        ],
+    ),
+    table(
+        "Error",
+        &[
+            i2("Error").key().range(0, MAX_I2),
+            s("Message", 0).null().loc().cat(Category::Template),
+        ],
+    ),
+    table(
+        "EventMapping",
+        &[
+            fk("Dialog_", "Dialog", 72).key(),
+            id("Control_", 50).key().refs("Control", 2),
+            id("Event", 50).key(),
+            id("Attribute", 50),
+        ],
+    ),
+    table(
+        "Extension",
+        &[
+            s("Extension", 255).key().cat(Category::Text),
+            component("Component_").key(),
+            s("ProgId_", 255).null().refs("ProgId", 1).cat(Category::Text),
+            s("MIME_", 64).null().refs("MIME", 1).cat(Category::Text),
+            feature("Feature_"),
+        ],
+    ),
+    table(
+        "Feature",
+        &[
+            id("Feature", 38).key(),
+            feature("Feature_Parent").null(),
+            s("Title", 64).null().loc().cat(Category::Text),
+            s("Description", 255).null().loc().cat(Category::Text),
+            i2("Display").null().range(0, MAX_I2),
+            i2("Level").range(0, MAX_I2),
+            s("Directory_", 72)
+                .null()
+                .refs("Directory", 1)
+                .cat(Category::UpperCase),
+            i2("Attributes"),
+        ],
+    ),
+    table(
+        "FeatureComponents",
+        &[feature("Feature_").key(), component("Component_").key()],
+    ),
+    table(
+        "File",
+        &[
+            id("File", 72).key(),
+            component("Component_"),
+            s("FileName", 255).loc().cat(Category::Filename),
+            i4("FileSize").range(0, MAX_I4),
+            s("Version", 72).null().refs("File", 1).cat(Category::Version),
+            s("Language", 20).null().cat(Category::Language),
+            i2("Attributes").null().range(0, MAX_I2),
+            i4("Sequence").range(1, MAX_I4),
+        ],
+    ),
+    table(
+        "FileSFPCatalog",
+        &[
+            file("File_").key(),
+            s("SFPCatalog_", 255)
+                .key()
+                .refs("SFPCatalog", 1)
+                .cat(Category::Filename),
+        ],
+    ),
+    table(
+        "Font",
+        &[file("File_").key(), s("FontTitle", 128).null().cat(Category::Text)],
+    ),
+    table("Icon", &[id("Name", 72).key(), v("Data")]),
+    table(
+        "IniFile",
+        &[
+            id("IniFile", 72).key(),
+            s("FileName", 255).loc().cat(Category::Filename),
+            id("DirProperty", 72).null(),
+            s("Section", 96).loc().cat(Category::Formatted),
+            s("Key", 128).loc().cat(Category::Formatted),
+            s("Value", 255).loc().cat(Category::Formatted),
+            i2("Action").range(0, 3),
+            component("Component_"),
+        ],
+    ),
+    table(
+        "IniLocator",
+        &[
+            id("Signature_", 72).key(),
+            s("FileName", 255).cat(Category::Filename),
+            s("Section", 96).cat(Category::Text),
+            s("Key", 128).cat(Category::Text),
+            i2("Field").null().range(0, MAX_I2),
+            i2("Type").null().range(0, 2),
+        ],
+    ),
+    table("InstallExecuteSequence", SEQUENCE_COLUMNS),
+    table("InstallUISequence", SEQUENCE_COLUMNS),
+    table(
+        "IsolatedComponent",
+        &[
+            component("Component_Shared").key(),
+            component("Component_Application").key(),
+        ],
+    ),
+    table(
+        "LaunchCondition",
+        &[
+            s("Condition", 255).key().cat(Category::Condition),
+            s("Description", 255).loc().cat(Category::Formatted),
+        ],
+    ),
+    table(
+        "ListBox",
+        &[
+            id("Property", 72).key(),
+            i2("Order").key().range(1, MAX_I2),
+            s("Value", 64).cat(Category::Formatted),
+            s("Text", 64).null().loc().cat(Category::Text),
+        ],
+    ),
+    table(
+        "ListView",
+        &[
+            id("Property", 72).key(),
+            i2("Order").key().range(1, MAX_I2),
+            id("Value", 64),
+            s("Text", 64).null().loc().cat(Category::Text),
+            fk("Binary_", "Binary", 72).null(),
+        ],
+    ),
+    table(
+        "LockPermissions",
+        &[
+            id("LockObject", 72).key(),
+            id("Table", 32).key().set(&["Directory", "File", "Registry"]),
+            s("Domain", 255).key().null().cat(Category::Formatted),
+            s("User", 255).key().cat(Category::Formatted),
+            i4("Permission").null().range(-MAX_I4, MAX_I4),
+        ],
+    ),
+    table(
+        "MIME",
+        &[
+            s("ContentType", 64).key().cat(Category::Text),
+            s("Extension_", 255).refs("Extension", 1).cat(Category::Text),
+            guid("CLSID").null(),
+        ],
+    ),
+    table(
+        "Media",
+        &[
+            i2("DiskId").key().range(1, MAX_I2),
+            i4("LastSequence").range(0, MAX_I4),
+            s("DiskPrompt", 64).null().loc().cat(Category::Text),
+            s("Cabinet", 255).null().cat(Category::Cabinet),
+            s("VolumeLabel", 32).null().cat(Category::Text),
+            s("Source", 72).null().cat(Category::Property),
+        ],
+    ),
+    table(
+        "MoveFile",
+        &[
+            id("FileKey", 72).key(),
+            component("Component_"),
+            s("SourceName", 255).null().loc().cat(Category::Text),
+            s("DestName", 255).null().loc().cat(Category::Filename),
+            id("SourceFolder", 72).null(),
+            id("DestFolder", 72),
+            i2("Options").range(0, 1),
+        ],
+    ),
+    table(
+        "MsiAssembly",
+        &[
+            component("Component_").key(),
+            feature("Feature_"),
+            file("File_Manifest").null(),
+            file("File_Application").null(),
+            i2("Attributes").null().range(0, 1),
+        ],
+    ),
+    table(
+        "MsiAssemblyName",
+        &[
+            component("Component_").key(),
+            s("Name", 255).key().cat(Category::Text),
+            s("Value", 255).cat(Category::Text),
+        ],
+    ),
+    table(
+        "MsiDigitalCertificate",
+        &[id("DigitalCertificate", 72).key(), v("CertData")],
+    ),
+    table(
+        "MsiDigitalSignature",
+        &[
+            id("Table", 32).key().set(&["Media"]),
+            s("SignObject", 72).key().cat(Category::Text),
+            fk("DigitalCertificate_", "MsiDigitalCertificate", 72),
+            v("Hash").null(),
+        ],
+    ),
+    table(
+        "MsiEmbeddedChainer",
+        &[
+            id("MsiEmbeddedChainer", 72).key(),
+            s("Condition", 255).null().cat(Category::Condition),
+            s("CommandLine", 255).null().cat(Category::Formatted),
+            s("Source", 72).cat(Category::CustomSource),
+            i4("Type").null().range(2, 50),
+        ],
+    )
+    .since(SchemaVersion::V450),
+    table(
+        "MsiEmbeddedUI",
+        &[
+            id("MsiEmbeddedUI", 72).key(),
+            s("FileName", 72).cat(Category::Filename),
+            i2("Attributes").range(0, 3),
+            i4("MessageFilter").null().range(0, MAX_I4),
+            v("Data"),
+        ],
+    )
+    .since(SchemaVersion::V450),
+    table(
+        "MsiFileHash",
+        &[
+            file("File_").key(),
+            i2("Options").range(0, 0),
+            i4("HashPart1"),
+            i4("HashPart2"),
+            i4("HashPart3"),
+            i4("HashPart4"),
+        ],
+    ),
+    table(
+        "MsiLockPermissionsEx",
+        &[
+            id("MsiLockPermissionsEx", 72).key(),
+            id("LockObject", 72),
+            id("Table", 32).set(&[
+                "CreateFolder",
+                "File",
+                "Registry",
+                "ServiceInstall",
+            ]),
+            s("SDDLText", 0).loc().cat(Category::FormattedSddlText),
+            s("Condition", 255).null().cat(Category::Condition),
+        ],
+    )
+    .since(SchemaVersion::V500),
+    table(
+        "MsiPackageCertificate",
+        &[
+            id("PackageCertificate", 72).key(),
+            fk("DigitalCertificate_", "MsiDigitalCertificate", 72),
+        ],
+    )
+    .since(SchemaVersion::V300),
+    table(
+        "MsiPatchCertificate",
+        &[
+            id("PatchCertificate", 72).key(),
+            fk("DigitalCertificate_", "MsiDigitalCertificate", 72),
+        ],
+    )
+    .since(SchemaVersion::V300),
+    table("MsiPatchHeaders", &[id("StreamRef", 38).key(), v("Header")])
+        .since(SchemaVersion::V300),
+    table(
+        "MsiPatchMetadata",
+        &[
+            s("Company", 72).key().null().cat(Category::Text),
+            s("Property", 72).key().cat(Category::Text),
+            s("Value", 0).null().loc().cat(Category::Text),
+        ],
+    )
+    .since(SchemaVersion::V300),
+    table(
+        "MsiPatchOldAssemblyFile",
+        &[
+            file("File_").key(),
+            fk("Assembly_", "MsiPatchOldAssemblyName", 72).key().null(),
+        ],
+    )
+    .since(SchemaVersion::V300),
+    table(
+        "MsiPatchOldAssemblyName",
+        &[
+            id("Assembly", 72).key(),
+            s("Name", 255).key().cat(Category::Text),
+            s("Value", 255).null().cat(Category::Text),
+        ],
+    )
+    .since(SchemaVersion::V300),
+    table(
+        "MsiPatchSequence",
+        &[
+            id("PatchFamily", 72).key(),
+            guid("ProductCode").key().null(),
+            s("Sequence", 72).cat(Category::Version),
+            i4("Attributes").null().range(0, MAX_I4),
+        ],
+    )
+    .since(SchemaVersion::V300),
+    table("MsiSFCBypass", &[file("File_").key()]),
+    table(
+        "MsiServiceConfig",
+        &[
+            id("MsiServiceConfig", 72).key(),
+            s("Name", 255).cat(Category::Formatted),
+            i2("Event").range(0, 7),
+            i4("ConfigType").range(-MAX_I4, MAX_I4),
+            s("Argument", 0).null().cat(Category::Formatted),
+            component("Component_"),
+        ],
+    )
+    .since(SchemaVersion::V500),
+    table(
+        "MsiServiceConfigFailureActions",
+        &[
+            id("MsiServiceConfigFailureActions", 72).key(),
+            s("Name", 255).cat(Category::Formatted),
+            i2("Event").range(0, 7),
+            i4("ResetPeriod").null().range(0, MAX_I4),
+            s("RebootMessage", 255).null().loc().cat(Category::Formatted),
+            s("Command", 255).null().loc().cat(Category::Formatted),
+            s("Actions", 255).null().cat(Category::Formatted),
+            s("DelayActions", 255).null().cat(Category::Formatted),
+            component("Component_"),
+        ],
+    )
+    .since(SchemaVersion::V500),
+    table(
+        "MsiShortcutProperty",
+        &[
+            id("MsiShortcutProperty", 72).key(),
+            fk("Shortcut_", "Shortcut", 72),
+            s("PropertyKey", 0).cat(Category::Formatted),
+            s("PropVariantValue", 0).cat(Category::Formatted),
+        ],
+    )
+    .since(SchemaVersion::V500),
+    table(
+        "ODBCAttribute",
+        &[
+            fk("Driver_", "ODBCDriver", 72).key(),
+            s("Attribute", 40).key().cat(Category::Text),
+            s("Value", 255).null().cat(Category::Text),
+        ],
+    ),
+    table(
+        "ODBCDataSource",
+        &[
+            id("DataSource", 72).key(),
+            component("Component_"),
+            s("Description", 255).cat(Category::Text),
+            s("DriverDescription", 255).cat(Category::Text),
+            i2("Registration").range(0, 1),
+        ],
+    ),
+    table(
+        "ODBCDriver",
+        &[
+            id("Driver", 72).key(),
+            component("Component_"),
+            s("Description", 255).cat(Category::Text),
+            file("File_"),
+            file("File_Setup").null(),
+        ],
+    ),
+    table(
+        "ODBCSourceAttribute",
+        &[
+            fk("DataSource_", "ODBCDataSource", 72).key(),
+            s("Attribute", 32).key().cat(Category::Text),
+            s("Value", 255).null().cat(Category::Text),
+        ],
+    ),
+    table(
+        "ODBCTranslator",
+        &[
+            id("Translator", 72).key(),
+            component("Component_"),
+            s("Description", 255).cat(Category::Text),
+            file("File_"),
+            file("File_Setup").null(),
+        ],
+    ),
+    table(
+        "Patch",
+        &[
+            id("File_", 72).key(),
+            i4("Sequence").key().range(0, MAX_I4),
+            i4("PatchSize").range(0, MAX_I4),
+            i2("Attributes").range(0, MAX_I2),
+            v("Header").null(),
+            id("StreamRef_", 72).null(),
+        ],
+    ),
+    table(
+        "PatchPackage",
+        &[guid("PatchId").key(), i2("Media_").range(0, MAX_I2)],
+    ),
+    table(
+        "ProgId",
+        &[
+            s("ProgId", 255).key().cat(Category::Text),
+            s("ProgId_Parent", 255)
+                .null()
+                .refs("ProgId", 1)
+                .cat(Category::Text),
+            guid("Class_").null().refs("Class", 1),
+            s("Description", 255).null().loc().cat(Category::Text),
+            fk("Icon_", "Icon", 72).null(),
+            i2("IconIndex").null().range(-MAX_I2, MAX_I2),
+        ],
+    ),
+    table(
+        "Property",
+        &[id("Property", 72).key(), s("Value", 0).loc().cat(Category::Text)],
+    ),
+    table(
+        "PublishComponent",
+        &[
+            guid("ComponentId").key(),
+            s("Qualifier", 255).key().cat(Category::Text),
+            component("Component_").key(),
+            s("AppData", 255).null().loc().cat(Category::Text),
+            feature("Feature_"),
+        ],
+    ),
+    table(
+        "RadioButton",
+        &[
+            id("Property", 72).key(),
+            i2("Order").key().range(1, MAX_I2),
+            s("Value", 64).cat(Category::Formatted),
+            coord("X"),
+            coord("Y"),
+            coord("Width"),
+            coord("Height"),
+            s("Text", 64).null().loc().cat(Category::Text),
+            s("Help", 50).null().loc().cat(Category::Text),
+        ],
+    ),
+    table(
+        "RegLocator",
+        &[
+            id("Signature_", 72).key(),
+            i2("Root").range(0, 3),
+            s("Key", 255).cat(Category::RegPath),
+            s("Name", 255).null().cat(Category::Formatted),
+            i2("Type").null().range(0, 18),
+        ],
+    ),
+    table(
+        "Registry",
+        &[
+            id("Registry", 72).key(),
+            i2("Root").range(-1, 3),
+            s("Key", 255).loc().cat(Category::RegPath),
+            s("Name", 255).null().loc().cat(Category::Formatted),
+            s("Value", 0).null().loc().cat(Category::Formatted),
+            component("Component_"),
+        ],
+    ),
+    table(
+        "RemoveFile",
+        &[
+            id("FileKey", 72).key(),
+            component("Component_"),
+            s("FileName", 255).null().loc().cat(Category::WildCardFilename),
+            id("DirProperty", 72),
+            i2("InstallMode").range(1, 3),
+        ],
+    ),
+    table(
+        "RemoveIniFile",
+        &[
+            id("RemoveIniFile", 72).key(),
+            s("FileName", 255).loc().cat(Category::Filename),
+            id("DirProperty", 72).null(),
+            s("Section", 96).loc().cat(Category::Formatted),
+            s("Key", 128).loc().cat(Category::Formatted),
+            s("Value", 255).null().loc().cat(Category::Formatted),
+            i2("Action").range(2, 4),
+            component("Component_"),
+        ],
+    ),
+    table(
+        "RemoveRegistry",
+        &[
+            id("RemoveRegistry", 72).key(),
+            i2("Root").range(-1, 3),
+            s("Key", 255).loc().cat(Category::RegPath),
+            s("Name", 255).null().loc().cat(Category::Formatted),
+            component("Component_"),
+        ],
+    ),
+    table(
+        "ReserveCost",
+        &[
+            id("ReserveKey", 72).key(),
+            component("Component_"),
+            id("ReserveFolder", 72).null(),
+            i4("ReserveLocal").range(0, MAX_I4),
+            i4("ReserveSource").range(0, MAX_I4),
+        ],
+    ),
+    table(
+        "SFPCatalog",
+        &[
+            s("SFPCatalog", 255).key().cat(Category::Filename),
+            v("Catalog"),
+            s("Dependency", 0).null().cat(Category::Formatted),
+        ],
+    ),
+    table(
+        "SelfReg",
+        &[file("File_").key(), i2("Cost").null().range(0, MAX_I2)],
+    ),
+    table(
+        "ServiceControl",
+        &[
+            id("ServiceControl", 72).key(),
+            s("Name", 255).loc().cat(Category::Formatted),
+            i2("Event").range(0, 187),
+            s("Arguments", 255).null().loc().cat(Category::Formatted),
+            i2("Wait").null().range(0, 1),
+            component("Component_"),
+        ],
+    ),
+    table(
+        "ServiceInstall",
+        &[
+            id("ServiceInstall", 72).key(),
+            s("Name", 255).cat(Category::Formatted),
+            s("DisplayName", 255).null().loc().cat(Category::Formatted),
+            i4("ServiceType").range(-MAX_I4, MAX_I4),
+            i4("StartType").range(0, 4),
+            i4("ErrorControl").range(-MAX_I4, MAX_I4),
+            s("LoadOrderGroup", 255).null().cat(Category::Formatted),
+            s("Dependencies", 255).null().cat(Category::Formatted),
+            s("StartName", 255).null().cat(Category::Formatted),
+            s("Password", 255).null().cat(Category::Formatted),
+            s("Arguments", 255).null().cat(Category::Formatted),
+            component("Component_"),
+            s("Description", 255).null().loc().cat(Category::Text),
+        ],
+    ),
+    table(
+        "Shortcut",
+        &[
+            id("Shortcut", 72).key(),
+            fk("Directory_", "Directory", 72),
+            s("Name", 128).loc().cat(Category::Filename),
+            component("Component_"),
+            s("Target", 72).cat(Category::Shortcut),
+            s("Arguments", 255).null().cat(Category::Formatted),
+            s("Description", 255).null().loc().cat(Category::Text),
+            i2("Hotkey").null().range(0, MAX_I2),
+            fk("Icon_", "Icon", 72).null(),
+            i2("IconIndex").null().range(-MAX_I2, MAX_I2),
+            i2("ShowCmd").null().range(1, 7),
+            id("WkDir", 72).null(),
+            s("DisplayResourceDLL", 255)
+                .null()
+                .cat(Category::Formatted)
+                .since(SchemaVersion::V400),
+            i4("DisplayResourceId")
+                .null()
+                .range(0, MAX_I4)
+                .since(SchemaVersion::V400),
+            s("DescriptionResourceDLL", 255)
+                .null()
+                .cat(Category::Formatted)
+                .since(SchemaVersion::V400),
+            i4("DescriptionResourceId")
+                .null()
+                .range(0, MAX_I4)
+                .since(SchemaVersion::V400),
+        ],
+    ),
+    table(
+        "Signature",
+        &[
+            id("Signature", 72).key(),
+            s("FileName", 255).cat(Category::Filename),
+            s("MinVersion", 20).null().cat(Category::Version),
+            s("MaxVersion", 20).null().cat(Category::Version),
+            i4("MinSize").null().range(0, MAX_I4),
+            i4("MaxSize").null().range(0, MAX_I4),
+            i4("MinDate").null().range(0, MAX_I4),
+            i4("MaxDate").null().range(0, MAX_I4),
+            s("Languages", 255).null().cat(Category::Language),
+        ],
+    ),
+    table(
+        "TextStyle",
+        &[
+            id("TextStyle", 72).key(),
+            s("FaceName", 32).cat(Category::Text),
+            i2("Size").range(0, MAX_I2),
+            i4("Color").null().range(0, 0x00ff_ffff),
+            i2("StyleBits").null().range(0, 15),
+        ],
+    ),
+    table(
+        "TypeLib",
+        &[
+            guid("LibID").key(),
+            i2("Language").key().range(0, MAX_I2),
+            component("Component_").key(),
+            i4("Version").null().range(0, 0x00ff_ffff),
+            s("Description", 128).null().loc().cat(Category::Text),
+            fk("Directory_", "Directory", 72).null(),
+            feature("Feature_"),
+            i4("Cost").null().range(0, MAX_I4),
+        ],
+    ),
+    table(
+        "UIText",
+        &[
+            id("Key", 72).key(),
+            s("Text", 255).null().loc().cat(Category::Text),
+        ],
+    ),
+    table(
+        "Upgrade",
+        &[
+            guid("UpgradeCode").key(),
+            s("VersionMin", 20).key().null().cat(Category::Text),
+            s("VersionMax", 20).key().null().cat(Category::Text),
+            s("Language", 255).key().null().cat(Category::Language),
+            i4("Attributes").key().range(0, MAX_I4),
+            s("Remove", 255).null().cat(Category::Formatted),
+            s("ActionProperty", 72).cat(Category::UpperCase),
+        ],
+    ),
+    table(
+        "Verb",
+        &[
+            s("Extension_", 255)
+                .key()
+                .refs("Extension", 1)
+                .cat(Category::Text),
+            s("Verb", 32).key().cat(Category::Text),
+            i2("Sequence").null().range(0, MAX_I2),
+            s("Command", 255).null().loc().cat(Category::Formatted),
+            s("Argument", 255).null().loc().cat(Category::Formatted),
+        ],
+    ),
+];
+
+// ========================================================================= //
+
+#[cfg(test)]
+mod tests {
+    use super::{SchemaVersion, TABLES, find_table};
+
+    #[test]
+    fn tables_are_sorted() {
+        for pair in TABLES.windows(2) {
+            assert!(
+                pair[0].name < pair[1].name,
+                "{:?} should come after {:?}",
+                pair[0].name,
+                pair[1].name
+            );
+        }
+    }
+
+    #[test]
+    fn foreign_keys_refer_to_standard_tables() {
+        for table in TABLES {
+            for column in table.columns {
+                let Some((key_table, key_column)) = column.foreign_key else {
+                    continue;
+                };
+                let key_table = find_table(key_table).unwrap_or_else(|| {
+                    panic!(
+                        "{}.{} refers to unknown table",
+                        table.name, column.name
+                    )
+                });
+                assert!(key_table.since <= table.since.max(column.since));
+                assert!(key_column as usize <= key_table.columns.len());
+            }
+        }
+    }
+
+    #[test]
+    fn schema_version_display() {
+        assert_eq!(SchemaVersion::V200.to_string(), "2.0");
+        assert_eq!(SchemaVersion::V450.to_string(), "4.5");
+    }
+}
+
+// ========================================================================= //
@@ -0,0 +1,380 @@
+use crate::internal::codepage::CodePage;
+use crate::internal::column::{Column, ColumnType};
+use crate::internal::idt;
+use crate::internal::package::{Package, PackageType};
+use crate::internal::query::{Insert, Select};
+use crate::internal::summary::SummaryInfo;
+use crate::internal::table::Row;
+use crate::internal::value::Value;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, OpenFlags, params, params_from_iter};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Cursor, Read, Seek, Write};
+use std::path::Path;
+
+// ========================================================================= //
+
+const COLUMNS_TABLE_NAME: &str = "_Columns";
+const TABLES_TABLE_NAME: &str = "_Tables";
+const VALIDATION_TABLE_NAME: &str = "_Validation";
+
+// These tables hold everything about the package other than its database
+// tables.  MSI reserves names starting with an underscore, so these can't
+// clash with the names of the package's own tables.
+const SCHEMA_SQL: &str = "\
+    CREATE TABLE _Database (\
+        Name TEXT PRIMARY KEY NOT NULL, \
+        Value TEXT NOT NULL);\
+    CREATE TABLE _Columns (\
+        \"Table\" TEXT NOT NULL, \
+        Number INTEGER NOT NULL, \
+        Name TEXT NOT NULL, \
+        Type TEXT NOT NULL, \
+        PrimaryKey INTEGER NOT NULL, \
+        PRIMARY KEY (\"Table\", Number));\
+    CREATE TABLE _SummaryInformation (\
+        PropertyId INTEGER PRIMARY KEY NOT NULL, \
+        Value TEXT NOT NULL);\
+    CREATE TABLE _Streams (\
+        Name TEXT PRIMARY KEY NOT NULL, \
+        Data BLOB NOT NULL);\
+    CREATE TABLE _Storages (\
+        Name TEXT PRIMARY KEY NOT NULL, \
+        Data BLOB NOT NULL);";
+
+// ========================================================================= //
+
+fn sql_error(error: rusqlite::Error) -> io::Error {
+    io::Error::other(error)
+}
+
+/// Quotes a table or column name for use in an SQL statement.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn package_type_name(package_type: PackageType) -> &'static str {
+    match package_type {
+        PackageType::Installer => "Installer",
+        PackageType::Patch => "Patch",
+        PackageType::Transform => "Transform",
+    }
+}
+
+fn create_table_sql(table_name: &str, columns: &[Column]) -> String {
+    let mut definitions: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            let sql_type = match column.coltype() {
+                ColumnType::Int16 | ColumnType::Int32 => "INTEGER",
+                ColumnType::Str(_) => "TEXT",
+                ColumnType::Binary => "BLOB",
+            };
+            let not_null = if column.is_nullable() { "" } else { " NOT NULL" };
+            format!("{} {}{}", quote(column.name()), sql_type, not_null)
+        })
+        .collect();
+    let keys: Vec<String> = columns
+        .iter()
+        .filter(|column| column.is_primary_key())
+        .map(|column| quote(column.name()))
+        .collect();
+    if !keys.is_empty() {
+        definitions.push(format!("PRIMARY KEY ({})", keys.join(", ")));
+    }
+    format!("CREATE TABLE {} ({})", quote(table_name), definitions.join(", "))
+}
+
+// ========================================================================= //
+
+pub(crate) fn export<F: Read + Seek>(
+    package: &mut Package<F>,
+    path: &Path,
+) -> io::Result<()> {
+    let mut connection = Connection::open(path).map_err(sql_error)?;
+    let transaction = connection.transaction().map_err(sql_error)?;
+    transaction.execute_batch(SCHEMA_SQL).map_err(sql_error)?;
+
+    let package_type = package_type_name(package.package_type());
+    let codepage = package.database_codepage().id().to_string();
+    for (name, value) in
+        [("PackageType", package_type), ("CodePage", &codepage)]
+    {
+        transaction
+            .execute(
+                "INSERT INTO _Database VALUES (?1, ?2)",
+                params![name, value],
+            )
+            .map_err(sql_error)?;
+    }
+    for (property, value) in package.summary_info().idt_properties() {
+        transaction
+            .execute(
+                "INSERT INTO _SummaryInformation VALUES (?1, ?2)",
+                params![property, value],
+            )
+            .map_err(sql_error)?;
+    }
+
+    let tables: Vec<_> = package
+        .tables()
+        .filter(|table| {
+            table.name() != COLUMNS_TABLE_NAME
+                && table.name() != TABLES_TABLE_NAME
+        })
+        .cloned()
+        .collect();
+    let mut binary_streams = HashSet::<String>::new();
+    for table in tables {
+        let table_name = table.name();
+        let columns = table.columns();
+        transaction
+            .execute(&create_table_sql(table_name, columns), [])
+            .map_err(sql_error)?;
+        for (index, column) in columns.iter().enumerate() {
+            transaction
+                .execute(
+                    "INSERT INTO _Columns VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        table_name,
+                        index + 1,
+                        column.name(),
+                        idt::column_type_code(column),
+                        column.is_primary_key(),
+                    ],
+                )
+                .map_err(sql_error)?;
+        }
+        let placeholders: Vec<String> =
+            (1..=columns.len()).map(|index| format!("?{index}")).collect();
+        let insert_sql = format!(
+            "INSERT INTO {} VALUES ({})",
+            quote(table_name),
+            placeholders.join(", ")
+        );
+        let key_indices = table.primary_key_indices();
+        let rows: Vec<Row> =
+            package.select_rows(Select::table(table_name))?.collect();
+        for row in rows {
+            let mut values = Vec::<SqlValue>::with_capacity(row.len());
+            for index in 0..row.len() {
+                values.push(match &row[index] {
+                    Value::Null => SqlValue::Null,
+                    Value::Int(number) => {
+                        SqlValue::Integer(i64::from(*number))
+                    }
+                    Value::Str(string) => SqlValue::Text(string.clone()),
+                    Value::Binary => {
+                        let keys: Vec<&Value> = key_indices
+                            .iter()
+                            .map(|&index| &row[index])
+                            .collect();
+                        let stream_name =
+                            idt::binary_stream_name(table_name, &keys);
+                        let mut data = Vec::new();
+                        package
+                            .read_stream(&stream_name)?
+                            .read_to_end(&mut data)?;
+                        binary_streams.insert(stream_name);
+                        SqlValue::Blob(data)
+                    }
+                });
+            }
+            transaction
+                .execute(&insert_sql, params_from_iter(values))
+                .map_err(sql_error)?;
+        }
+    }
+
+    let stream_names: Vec<String> = package
+        .streams()
+        .filter(|name| !binary_streams.contains(name))
+        .collect();
+    for stream_name in stream_names {
+        let mut data = Vec::new();
+        package.read_stream(&stream_name)?.read_to_end(&mut data)?;
+        transaction
+            .execute(
+                "INSERT INTO _Streams VALUES (?1, ?2)",
+                params![stream_name, data],
+            )
+            .map_err(sql_error)?;
+    }
+    for storage_name in package.storage_names() {
+        let data = package
+            .export_storage(&storage_name, Cursor::new(Vec::new()))?
+            .into_inner();
+        transaction
+            .execute(
+                "INSERT INTO _Storages VALUES (?1, ?2)",
+                params![storage_name, data],
+            )
+            .map_err(sql_error)?;
+    }
+    transaction.commit().map_err(sql_error)
+}
+
+// ========================================================================= //
+
+pub(crate) fn import<F: Read + Write + Seek>(
+    path: &Path,
+    inner: F,
+) -> io::Result<Package<F>> {
+    let connection =
+        Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(sql_error)?;
+    let database: BTreeMap<String, String> =
+        select_pairs(&connection, "SELECT Name, Value FROM _Database")?;
+    let package_type = match database.get("PackageType").map(String::as_str) {
+        Some("Installer") => PackageType::Installer,
+        Some("Patch") => PackageType::Patch,
+        Some("Transform") => PackageType::Transform,
+        other => invalid_data!("Invalid SQLite package type {:?}", other),
+    };
+    let codepage = match database
+        .get("CodePage")
+        .and_then(|id| id.parse().ok())
+        .and_then(CodePage::from_id)
+    {
+        Some(codepage) => codepage,
+        None => invalid_data!(
+            "Invalid SQLite database code page {:?}",
+            database.get("CodePage")
+        ),
+    };
+    let mut package = Package::create(package_type, inner)?;
+    package.set_database_codepage(codepage);
+
+    let properties: BTreeMap<u32, String> = select_pairs(
+        &connection,
+        "SELECT PropertyId, Value FROM _SummaryInformation",
+    )?;
+    let mut summary_info = SummaryInfo::new();
+    for (property, value) in properties {
+        summary_info.set_idt_property(property, &value)?;
+    }
+    *package.summary_info_mut() = summary_info;
+
+    // Import _Validation last, so that its rows replace the ones generated
+    // when creating the other tables.
+    let mut tables = BTreeMap::<(bool, String), Vec<Column>>::new();
+    let mut statement = connection
+        .prepare(
+            "SELECT \"Table\", Name, Type, PrimaryKey FROM _Columns \
+             ORDER BY \"Table\", Number",
+        )
+        .map_err(sql_error)?;
+    let mut rows = statement.query([]).map_err(sql_error)?;
+    while let Some(row) = rows.next().map_err(sql_error)? {
+        let table_name: String = row.get(0).map_err(sql_error)?;
+        let name: String = row.get(1).map_err(sql_error)?;
+        let code: String = row.get(2).map_err(sql_error)?;
+        let is_primary_key: bool = row.get(3).map_err(sql_error)?;
+        let column = idt::parse_column(&name, &code, is_primary_key)?;
+        let key = (table_name == VALIDATION_TABLE_NAME, table_name);
+        tables.entry(key).or_default().push(column);
+    }
+    for ((_, table_name), columns) in tables {
+        import_table(&connection, &mut package, &table_name, columns)?;
+    }
+
+    let streams: BTreeMap<String, Vec<u8>> =
+        select_pairs(&connection, "SELECT Name, Data FROM _Streams")?;
+    for (stream_name, data) in streams {
+        package.write_stream(&stream_name)?.write_all(&data)?;
+    }
+    let storages: BTreeMap<String, Vec<u8>> =
+        select_pairs(&connection, "SELECT Name, Data FROM _Storages")?;
+    for (storage_name, data) in storages {
+        package.import_storage(&storage_name, Cursor::new(data))?;
+    }
+    package.flush()?;
+    Ok(package)
+}
+
+fn import_table<F: Read + Write + Seek>(
+    connection: &Connection,
+    package: &mut Package<F>,
+    table_name: &str,
+    columns: Vec<Column>,
+) -> io::Result<()> {
+    let names: Vec<String> =
+        columns.iter().map(|column| quote(column.name())).collect();
+    let sql =
+        format!("SELECT {} FROM {}", names.join(", "), quote(table_name));
+    let mut statement = connection.prepare(&sql).map_err(sql_error)?;
+    let mut sql_rows = statement.query([]).map_err(sql_error)?;
+    let mut rows = Vec::<Vec<Value>>::new();
+    let mut streams = Vec::<(String, Vec<u8>)>::new();
+    while let Some(sql_row) = sql_rows.next().map_err(sql_error)? {
+        let mut values = Vec::with_capacity(columns.len());
+        let mut blobs = Vec::<Vec<u8>>::new();
+        for (index, column) in columns.iter().enumerate() {
+            let value: SqlValue = sql_row.get(index).map_err(sql_error)?;
+            values.push(match value {
+                SqlValue::Null => Value::Null,
+                SqlValue::Integer(number) => match i32::try_from(number) {
+                    Ok(number) => Value::Int(number),
+                    Err(_) => invalid_data!(
+                        "SQLite table {:?} has out-of-range integer {} in \
+                         column {:?}",
+                        table_name,
+                        number,
+                        column.name()
+                    ),
+                },
+                SqlValue::Text(string) => Value::Str(string),
+                SqlValue::Blob(data) => {
+                    blobs.push(data);
+                    Value::Binary
+                }
+                SqlValue::Real(number) => invalid_data!(
+                    "SQLite table {:?} has non-integer number {} in column \
+                     {:?}",
+                    table_name,
+                    number,
+                    column.name()
+                ),
+            });
+        }
+        if !blobs.is_empty() {
+            let keys: Vec<&Value> = columns
+                .iter()
+                .zip(&values)
+                .filter(|(column, _)| column.is_primary_key())
+                .map(|(_, value)| value)
+                .collect();
+            let stream_name = idt::binary_stream_name(table_name, &keys);
+            for data in blobs {
+                streams.push((stream_name.clone(), data));
+            }
+        }
+        rows.push(values);
+    }
+    idt::replace_table(package, table_name, columns)?;
+    package.insert_rows(Insert::into(table_name).rows(rows))?;
+    for (stream_name, data) in streams {
+        package.write_stream(&stream_name)?.write_all(&data)?;
+    }
+    Ok(())
+}
+
+/// Runs a query that returns two columns, and collects its rows into a map.
+fn select_pairs<K, V>(
+    connection: &Connection,
+    sql: &str,
+) -> io::Result<BTreeMap<K, V>>
+where
+    K: rusqlite::types::FromSql + Ord,
+    V: rusqlite::types::FromSql,
+{
+    let mut statement = connection.prepare(sql).map_err(sql_error)?;
+    let pairs = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(sql_error)?
+        .collect::<Result<_, _>>()
+        .map_err(sql_error)?;
+    Ok(pairs)
+}
+
+// ========================================================================= //
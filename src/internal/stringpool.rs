@@ -1,5 +1,6 @@
 use crate::internal::codepage::CodePage;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io::{self, Read, Write};
 
 // ========================================================================= //
@@ -8,6 +9,10 @@ const LONG_STRING_REFS_BIT: u32 = 0x8000_0000;
 
 const MAX_STRING_REF: i32 = 0xff_ffff;
 
+/// The maximum number of entries in a string pool that uses short (two-byte)
+/// string refs.
+pub(crate) const MAX_SHORT_STRING_REFS: usize = u16::MAX as usize;
+
 // ========================================================================= //
 
 /// A reference to a string in the string pool.
@@ -110,29 +115,85 @@ impl StringPoolBuilder {
         self,
         mut reader: R,
     ) -> io::Result<StringPool> {
-        let mut strings = Vec::<(String, u16)>::new();
+        let mut string_pool = StringPool::new(self.codepage);
+        string_pool.long_string_refs = self.long_string_refs;
         for (length, refcount) in self.lengths_and_refcounts {
-            let mut buffer = vec![0u8; length as usize];
-            reader.read_exact(&mut buffer)?;
-            strings.push((self.codepage.decode(&buffer), refcount));
+            // Don't trust the length enough to preallocate a buffer for it;
+            // a corrupt pool could claim up to 4 GB for each string.
+            let mut buffer = Vec::new();
+            (&mut reader).take(u64::from(length)).read_to_end(&mut buffer)?;
+            if buffer.len() < length as usize {
+                invalid_data!(
+                    "String data is truncated (string #{} should have {} \
+                     bytes, but only {} remain)",
+                    string_pool.strings.len() + 1,
+                    length,
+                    buffer.len()
+                );
+            }
+            let string = self.codepage.decode(&buffer);
+            string_pool.push(string, refcount);
         }
-        Ok(StringPool {
-            codepage: self.codepage,
-            strings,
-            long_string_refs: self.long_string_refs,
-            is_modified: false,
-        })
+        string_pool.is_modified = false;
+        Ok(string_pool)
     }
 }
 
 // ========================================================================= //
 
+/// Controls how a package's string pool is laid out when the package is
+/// flushed.
+///
+/// By default, strings keep whatever positions they were given when they were
+/// first added, and entries freed by deleting strings are left empty until
+/// they are reused.  The other layouts rewrite the string pool (and every
+/// table that refers to it) on flush, which is slower, but produces output
+/// that depends only on the database's contents rather than on its history.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum StringPoolLayout {
+    /// Leave string positions alone (the default).
+    #[default]
+    Preserve,
+    /// Remove empty entries, keeping the remaining strings in their current
+    /// order.
+    Compact,
+    /// Remove empty entries, and sort the remaining strings.
+    Sorted,
+}
+
+// ========================================================================= //
+
 /// The string pool for an MSI package.
+#[derive(Clone)]
 pub struct StringPool {
     codepage: CodePage,
     strings: Vec<(String, u16)>,
+    /// Maps each string in the pool to the indices of the (non-empty) entries
+    /// holding it.  A string usually has only one entry, but can have more if
+    /// its refcount has overflowed, or if the pool was built that way by
+    /// another tool.
+    indices: HashMap<String, Vec<usize>>,
+    /// Indices of entries with a refcount of zero, which can be reused.
+    free_indices: BTreeSet<usize>,
     long_string_refs: bool,
     is_modified: bool,
+    /// One undo log for each transaction in progress (innermost last).
+    undo_logs: Vec<UndoLog>,
+}
+
+/// The state of a string pool from before a transaction began, used to roll
+/// the transaction back.  Only the entries that the transaction changes are
+/// recorded.
+#[derive(Clone)]
+struct UndoLog {
+    num_strings: usize,
+    codepage: CodePage,
+    long_string_refs: bool,
+    is_modified: bool,
+    // Maps the index of each entry changed during the transaction to its
+    // string and refcount from before the change.  Entries added during the
+    // transaction aren't recorded, since rolling back simply removes them.
+    entries: HashMap<usize, (String, u16)>,
 }
 
 impl StringPool {
@@ -141,11 +202,77 @@ impl StringPool {
         Self {
             codepage,
             strings: Vec::new(),
+            indices: HashMap::new(),
+            free_indices: BTreeSet::new(),
             long_string_refs: false,
             is_modified: true,
+            undo_logs: Vec::new(),
+        }
+    }
+
+    /// Appends a new entry to the end of the pool.
+    fn push(&mut self, string: String, refcount: u16) {
+        let index = self.strings.len();
+        if refcount == 0 {
+            self.free_indices.insert(index);
+        } else {
+            self.indices.entry(string.clone()).or_default().push(index);
+        }
+        self.strings.push((string, refcount));
+    }
+
+    /// If a transaction is in progress, records the current contents of the
+    /// given entry, unless they have already been recorded.  This must be
+    /// called before the entry is changed.
+    fn save(&mut self, index: usize) {
+        if let Some(undo_log) = self.undo_logs.last_mut() {
+            if index < undo_log.num_strings {
+                undo_log
+                    .entries
+                    .entry(index)
+                    .or_insert_with(|| self.strings[index].clone());
+            }
         }
     }
 
+    /// Removes the given entry from the index or free list (whichever it is
+    /// in), without changing the entry itself.
+    fn unindex_entry(&mut self, index: usize) {
+        let (ref string, refcount) = self.strings[index];
+        if refcount == 0 {
+            self.free_indices.remove(&index);
+        } else if let Some(indices) = self.indices.get_mut(string) {
+            indices.retain(|&other| other != index);
+            if indices.is_empty() {
+                self.indices.remove(string);
+            }
+        }
+    }
+
+    /// Adds the given entry to the index or free list, as appropriate for its
+    /// refcount.
+    fn index_entry(&mut self, index: usize) {
+        let (ref string, refcount) = self.strings[index];
+        if refcount == 0 {
+            self.free_indices.insert(index);
+        } else {
+            self.indices.entry(string.clone()).or_default().push(index);
+        }
+    }
+
+    /// Removes an entry that has just dropped to a refcount of zero from the
+    /// index, and adds it to the free list.
+    fn free_entry(&mut self, index: usize) {
+        let string = std::mem::take(&mut self.strings[index].0);
+        if let Some(indices) = self.indices.get_mut(&string) {
+            indices.retain(|&other| other != index);
+            if indices.is_empty() {
+                self.indices.remove(&string);
+            }
+        }
+        self.free_indices.insert(index);
+    }
+
     /// Gets the code page used for serializing the string data.
     pub fn codepage(&self) -> CodePage {
         self.codepage
@@ -159,7 +286,6 @@ impl StringPool {
 
     /// Returns the number of strings in the string pool (including empty
     /// entries).
-    #[allow(dead_code)]
     pub fn num_strings(&self) -> u32 {
         self.strings.len() as u32
     }
@@ -170,6 +296,15 @@ impl StringPool {
         self.long_string_refs
     }
 
+    /// Switches to serializing string references with three bytes instead of
+    /// two.  Any tables using this string pool must be rewritten to match.
+    pub(crate) fn set_long_string_refs(&mut self) {
+        if !self.long_string_refs {
+            self.is_modified = true;
+            self.long_string_refs = true;
+        }
+    }
+
     pub(crate) fn is_modified(&self) -> bool {
         self.is_modified
     }
@@ -178,7 +313,10 @@ impl StringPool {
         self.is_modified = false;
     }
 
-    /// Returns the string in the pool for the given reference.
+    /// Returns the string in the pool for the given reference, or an empty
+    /// string if the reference is out of range.  (Table rows are checked for
+    /// such references when they are read, so this only matters for rows
+    /// that haven't come from a table.)
     pub fn get(&self, string_ref: StringRef) -> &str {
         let index = string_ref.index();
         if index < self.strings.len() {
@@ -188,8 +326,18 @@ impl StringPool {
         }
     }
 
+    /// Returns true if the given reference points to an entry in the pool.
+    pub(crate) fn contains(&self, string_ref: StringRef) -> bool {
+        string_ref.index() < self.strings.len()
+    }
+
+    /// Returns an iterator over references to every entry in the pool
+    /// (including empty entries).
+    pub(crate) fn refs(&self) -> impl Iterator<Item = StringRef> {
+        (1..=self.strings.len() as i32).map(StringRef)
+    }
+
     /// Returns the pool's refcount for the given string reference.
-    #[allow(dead_code)]
     pub fn refcount(&self, string_ref: StringRef) -> u16 {
         let index = string_ref.index();
         if index < self.strings.len() { self.strings[index].1 } else { 0 }
@@ -197,58 +345,189 @@ impl StringPool {
 
     /// Inserts a string into the pool, or increments its refcount if it's
     /// already in the pool, and returns the index of the string in the pool.
-    pub fn incref(&mut self, string: String) -> StringRef {
-        self.is_modified = true;
-        // TODO: change the internal representation of StringPool to make this
-        // more efficient.
-        for (index, &mut (ref mut st, ref mut refcount)) in
-            self.strings.iter_mut().enumerate()
-        {
-            if *refcount == 0 {
-                debug_assert_eq!(st, "");
-                *st = string;
-                *refcount = 1;
-                return StringRef((index + 1) as i32);
+    /// Returns an error if there's no room left in the pool for the string.
+    pub fn incref(&mut self, string: String) -> io::Result<StringRef> {
+        let existing = self.indices.get(&string).and_then(|indices| {
+            indices
+                .iter()
+                .copied()
+                .find(|&index| self.strings[index].1 < u16::MAX)
+        });
+        if let Some(index) = existing {
+            self.save(index);
+            self.strings[index].1 += 1;
+            self.is_modified = true;
+            return Ok(StringRef((index + 1) as i32));
+        }
+        let index = if let Some(index) = self.free_indices.pop_first() {
+            self.save(index);
+            self.strings[index] = (string.clone(), 1);
+            index
+        } else {
+            if self.strings.len() >= MAX_SHORT_STRING_REFS
+                && !self.long_string_refs
+            {
+                // Callers are responsible for switching the database to long
+                // string refs (see `Package::reserve_strings`) before getting
+                // here.
+                invalid_input!("Too many strings for short string refs");
+            }
+            if self.strings.len() >= MAX_STRING_REF as usize {
+                invalid_input!("Too many distinct strings in string pool");
             }
-            if *st == string && *refcount < u16::MAX {
-                *refcount += 1;
-                return StringRef((index + 1) as i32);
+            self.strings.push((string.clone(), 1));
+            self.strings.len() - 1
+        };
+        self.is_modified = true;
+        self.indices.entry(string).or_default().push(index);
+        Ok(StringRef((index + 1) as i32))
+    }
+
+    /// Decrements the refcount of each of the given strings in the pool (once
+    /// for each time it appears in the list).  Returns an error without
+    /// changing anything if any reference doesn't point to an entry in the
+    /// pool, or if any refcount would drop below zero.
+    pub fn decref_all(&mut self, string_refs: &[StringRef]) -> io::Result<()> {
+        let mut counts = BTreeMap::<StringRef, u32>::new();
+        for &string_ref in string_refs {
+            if string_ref.index() >= self.strings.len() {
+                invalid_data!(
+                    "String reference #{} is invalid (string pool has only {} \
+                     entries)",
+                    string_ref.number(),
+                    self.strings.len()
+                );
             }
+            *counts.entry(string_ref).or_default() += 1;
         }
-        if self.strings.len() >= u16::MAX as usize && !self.long_string_refs {
-            // TODO: If this happens, we need to rewrite all database tables
-            // from short to long string refs.
-            panic!(
-                "Too many strings; rewriting to long string refs is not \
-                    yet supported"
-            );
+        for (&string_ref, &count) in &counts {
+            if u32::from(self.refcount(string_ref)) < count {
+                invalid_data!(
+                    "String #{} has more references than its refcount ({})",
+                    string_ref.number(),
+                    self.refcount(string_ref)
+                );
+            }
         }
-        if self.strings.len() >= MAX_STRING_REF as usize {
-            panic!("Too many distinct strings in string pool");
+        for (string_ref, count) in counts {
+            let index = string_ref.index();
+            self.save(index);
+            self.is_modified = true;
+            self.strings[index].1 -= count as u16;
+            if self.strings[index].1 == 0 {
+                self.free_entry(index);
+            }
         }
-        self.strings.push((string, 1));
-        StringRef(self.strings.len() as i32)
+        Ok(())
     }
 
-    /// Decrements the refcount of a string in the pool.
-    pub fn decref(&mut self, string_ref: StringRef) {
+    /// Overwrites the refcount of a string in the pool, clearing the string if
+    /// the new refcount is zero.  This is used to repair pools whose refcounts
+    /// don't match the references actually present in the database.
+    pub(crate) fn set_refcount(&mut self, string_ref: StringRef, count: u16) {
         let index = string_ref.index();
         if index >= self.strings.len() {
-            panic!(
-                "decref: string_ref {} invalid, pool has only {} entries",
-                string_ref.number(),
-                self.strings.len()
-            );
+            return;
+        }
+        self.save(index);
+        self.is_modified = true;
+        let refcount = self.strings[index].1;
+        self.strings[index].1 = count;
+        if refcount > 0 && count == 0 {
+            self.free_entry(index);
+        } else if refcount == 0 && count > 0 {
+            self.free_indices.remove(&index);
+            let string = self.strings[index].0.clone();
+            self.indices.entry(string).or_default().push(index);
+        }
+    }
+
+    /// Returns true if the pool has no empty entries, and (if `sorted` is true)
+    /// its strings are already in sorted order.
+    pub(crate) fn is_compact(&self, sorted: bool) -> bool {
+        self.free_indices.is_empty()
+            && (!sorted || self.strings.windows(2).all(|w| w[0].0 <= w[1].0))
+    }
+
+    /// Removes all empty entries from the pool, optionally sorting the
+    /// remaining strings, and returns a table mapping each old entry's index
+    /// to its new reference (or to `None` for removed entries).  Any tables
+    /// using this string pool must be rewritten to match.
+    pub(crate) fn compact(&mut self, sorted: bool) -> Vec<Option<StringRef>> {
+        for index in 0..self.strings.len() {
+            self.save(index);
         }
-        let (ref mut string, ref mut refcount) = self.strings[index];
-        if *refcount < 1 {
-            panic!("decref: string refcount is already zero");
+        let old_strings = std::mem::take(&mut self.strings);
+        let mut remap = vec![None; old_strings.len()];
+        let mut entries: Vec<(usize, (String, u16))> = old_strings
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (_, refcount))| *refcount > 0)
+            .collect();
+        if sorted {
+            entries.sort_by(|(_, (a, _)), (_, (b, _))| a.cmp(b));
         }
+        self.indices.clear();
+        self.free_indices.clear();
         self.is_modified = true;
-        *refcount -= 1;
-        if *refcount == 0 {
-            string.clear();
+        for (old_index, (string, refcount)) in entries {
+            remap[old_index] = Some(StringRef(self.strings.len() as i32 + 1));
+            self.push(string, refcount);
+        }
+        remap
+    }
+
+    /// Starts recording changes to the pool, so that they can be undone by
+    /// `rollback_transaction()`.  Transactions may be nested.
+    pub(crate) fn begin_transaction(&mut self) {
+        self.undo_logs.push(UndoLog {
+            num_strings: self.strings.len(),
+            codepage: self.codepage,
+            long_string_refs: self.long_string_refs,
+            is_modified: self.is_modified,
+            entries: HashMap::new(),
+        });
+    }
+
+    /// Keeps the changes made since the innermost transaction began.  If
+    /// there is an enclosing transaction, they can still be undone by rolling
+    /// that one back.
+    pub(crate) fn commit_transaction(&mut self) {
+        let undo_log = self.undo_logs.pop().unwrap();
+        if let Some(outer_log) = self.undo_logs.last_mut() {
+            // Records already in the enclosing log are older, and so take
+            // precedence.  Entries that didn't exist when the enclosing
+            // transaction began will be removed by rolling it back anyway.
+            for (index, original) in undo_log.entries {
+                if index < outer_log.num_strings {
+                    outer_log.entries.entry(index).or_insert(original);
+                }
+            }
+        }
+    }
+
+    /// Puts the pool back the way it was before the innermost transaction
+    /// began.
+    pub(crate) fn rollback_transaction(&mut self) {
+        let undo_log = self.undo_logs.pop().unwrap();
+        let changed: Vec<usize> = undo_log
+            .entries
+            .keys()
+            .copied()
+            .chain(undo_log.num_strings..self.strings.len())
+            .filter(|&index| index < self.strings.len())
+            .collect();
+        for index in changed {
+            self.unindex_entry(index);
         }
+        self.strings.resize_with(undo_log.num_strings, Default::default);
+        for (index, original) in undo_log.entries {
+            self.strings[index] = original;
+            self.index_entry(index);
+        }
+        self.codepage = undo_log.codepage;
+        self.long_string_refs = undo_log.long_string_refs;
+        self.is_modified = undo_log.is_modified;
     }
 
     /// Writes to the `_StringPool` table.
@@ -285,6 +564,7 @@ impl StringPool {
 mod tests {
     use super::{StringPool, StringPoolBuilder, StringRef};
     use crate::internal::codepage::CodePage;
+    use std::io::ErrorKind;
 
     #[test]
     fn read_string_ref() {
@@ -338,11 +618,20 @@ mod tests {
         let mut string_pool = StringPool::new(CodePage::default());
         assert!(!string_pool.long_string_refs());
         assert_eq!(string_pool.num_strings(), 0);
-        assert_eq!(string_pool.incref("Foo".to_string()), StringRef(1));
+        assert_eq!(
+            string_pool.incref("Foo".to_string()).unwrap(),
+            StringRef(1)
+        );
         assert_eq!(string_pool.num_strings(), 1);
-        assert_eq!(string_pool.incref("Quux".to_string()), StringRef(2));
+        assert_eq!(
+            string_pool.incref("Quux".to_string()).unwrap(),
+            StringRef(2)
+        );
         assert_eq!(string_pool.num_strings(), 2);
-        assert_eq!(string_pool.incref("Foo".to_string()), StringRef(1));
+        assert_eq!(
+            string_pool.incref("Foo".to_string()).unwrap(),
+            StringRef(1)
+        );
         assert_eq!(string_pool.num_strings(), 2);
         assert_eq!(string_pool.get(StringRef(1)), "Foo");
         assert_eq!(string_pool.refcount(StringRef(1)), 2);
@@ -368,9 +657,18 @@ mod tests {
     #[test]
     fn write_string_pool() {
         let mut string_pool = StringPool::new(CodePage::Windows1252);
-        assert_eq!(string_pool.incref("Foo".to_string()), StringRef(1));
-        assert_eq!(string_pool.incref("Quux".to_string()), StringRef(2));
-        assert_eq!(string_pool.incref("Foo".to_string()), StringRef(1));
+        assert_eq!(
+            string_pool.incref("Foo".to_string()).unwrap(),
+            StringRef(1)
+        );
+        assert_eq!(
+            string_pool.incref("Quux".to_string()).unwrap(),
+            StringRef(2)
+        );
+        assert_eq!(
+            string_pool.incref("Foo".to_string()).unwrap(),
+            StringRef(1)
+        );
         let mut pool_output = Vec::<u8>::new();
         string_pool.write_pool(&mut pool_output).expect("pool");
         assert_eq!(
@@ -393,6 +691,22 @@ mod tests {
         assert_eq!(string_pool.num_strings(), 2);
     }
 
+    #[test]
+    fn truncated_string_data() {
+        // The second string claims to be 4 GB long.
+        let pool: &[u8] = b"\xe9\xfd\x00\x00\x03\x00\x01\x00\
+                            \x00\x00\x01\x00\xff\xff\xff\xff";
+        let data: &[u8] = b"FooQuux";
+        let builder = StringPoolBuilder::read_from_pool(pool).expect("pool");
+        let error = builder.build_from_data(data).err().expect("error");
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "String data is truncated (string #2 should have 4294967295 \
+             bytes, but only 4 remain)"
+        );
+    }
+
     #[test]
     fn repeated_string() {
         let pool: &[u8] = b"\xe9\xfd\x00\x00\x03\x00\x02\x00\x03\x00\x07\x00";
@@ -446,7 +760,7 @@ mod tests {
 
         // Construct a string pool and add the long string
         let mut pool = StringPool::new(CodePage::Utf8);
-        pool.incref(rustmsi_70kb.clone());
+        pool.incref(rustmsi_70kb.clone()).unwrap();
 
         // Serialize the pool into binary format
         let mut pool_output = Vec::new();
@@ -476,9 +790,15 @@ mod tests {
         assert_eq!(string_pool.num_strings(), 1);
         assert_eq!(string_pool.get(StringRef(1)), "Foobar");
         assert_eq!(string_pool.refcount(StringRef(1)), 0xfffe);
-        assert_eq!(string_pool.incref("Foobar".to_string()), StringRef(1));
+        assert_eq!(
+            string_pool.incref("Foobar".to_string()).unwrap(),
+            StringRef(1)
+        );
         assert_eq!(string_pool.num_strings(), 1);
-        assert_eq!(string_pool.incref("Foobar".to_string()), StringRef(2));
+        assert_eq!(
+            string_pool.incref("Foobar".to_string()).unwrap(),
+            StringRef(2)
+        );
         assert_eq!(string_pool.num_strings(), 2);
         assert_eq!(string_pool.refcount(StringRef(1)), 0xffff);
         assert_eq!(string_pool.refcount(StringRef(2)), 1);
@@ -487,19 +807,166 @@ mod tests {
     #[test]
     fn reuse_entries() {
         let mut string_pool = StringPool::new(CodePage::default());
-        assert_eq!(string_pool.incref("Foo".to_string()), StringRef(1));
-        assert_eq!(string_pool.incref("Bar".to_string()), StringRef(2));
+        assert_eq!(
+            string_pool.incref("Foo".to_string()).unwrap(),
+            StringRef(1)
+        );
+        assert_eq!(
+            string_pool.incref("Bar".to_string()).unwrap(),
+            StringRef(2)
+        );
         assert_eq!(string_pool.num_strings(), 2);
-        string_pool.decref(StringRef(1));
+        string_pool.decref_all(&[StringRef(1)]).unwrap();
         assert_eq!(string_pool.refcount(StringRef(1)), 0);
         assert_eq!(string_pool.get(StringRef(1)), "");
         assert_eq!(string_pool.num_strings(), 2);
-        assert_eq!(string_pool.incref("Quux".to_string()), StringRef(1));
+        assert_eq!(
+            string_pool.incref("Quux".to_string()).unwrap(),
+            StringRef(1)
+        );
         assert_eq!(string_pool.num_strings(), 2);
         assert_eq!(string_pool.refcount(StringRef(1)), 1);
         assert_eq!(string_pool.get(StringRef(1)), "Quux");
     }
 
+    #[test]
+    fn roll_back_transaction() {
+        let mut string_pool = StringPool::new(CodePage::default());
+        for string in ["Foo", "Bar", "Baz"] {
+            string_pool.incref(string.to_string()).unwrap();
+        }
+        string_pool.decref_all(&[StringRef(2)]).unwrap();
+        string_pool.begin_transaction();
+        string_pool.decref_all(&[StringRef(1)]).unwrap();
+        string_pool.incref("Quux".to_string()).unwrap();
+        string_pool.begin_transaction();
+        string_pool.incref("Baz".to_string()).unwrap();
+        string_pool.incref("Blarg".to_string()).unwrap();
+        string_pool.incref("Zork".to_string()).unwrap();
+        string_pool.compact(true);
+        string_pool.commit_transaction();
+        string_pool.rollback_transaction();
+        assert_eq!(string_pool.num_strings(), 3);
+        assert_eq!(string_pool.get(StringRef(1)), "Foo");
+        assert_eq!(string_pool.refcount(StringRef(1)), 1);
+        assert_eq!(string_pool.refcount(StringRef(2)), 0);
+        assert_eq!(string_pool.get(StringRef(3)), "Baz");
+        assert_eq!(string_pool.refcount(StringRef(3)), 1);
+        // The index and free list must have been restored too.
+        assert_eq!(
+            string_pool.incref("Baz".to_string()).unwrap(),
+            StringRef(3)
+        );
+        assert_eq!(
+            string_pool.incref("Quux".to_string()).unwrap(),
+            StringRef(2)
+        );
+        assert_eq!(
+            string_pool.incref("Foo".to_string()).unwrap(),
+            StringRef(1)
+        );
+        assert_eq!(string_pool.num_strings(), 3);
+    }
+
+    #[test]
+    fn reuse_lowest_free_entry() {
+        let mut string_pool = StringPool::new(CodePage::default());
+        for string in ["Foo", "Bar", "Baz", "Quux"] {
+            string_pool.incref(string.to_string()).unwrap();
+        }
+        string_pool.decref_all(&[StringRef(3)]).unwrap();
+        string_pool.decref_all(&[StringRef(2)]).unwrap();
+        assert_eq!(
+            string_pool.incref("Baz".to_string()).unwrap(),
+            StringRef(2)
+        );
+        assert_eq!(
+            string_pool.incref("Bar".to_string()).unwrap(),
+            StringRef(3)
+        );
+        assert_eq!(
+            string_pool.incref("New".to_string()).unwrap(),
+            StringRef(5)
+        );
+        assert_eq!(string_pool.num_strings(), 5);
+    }
+
+    #[test]
+    fn prefer_existing_entry_over_free_entry() {
+        let mut string_pool = StringPool::new(CodePage::default());
+        assert_eq!(
+            string_pool.incref("Foo".to_string()).unwrap(),
+            StringRef(1)
+        );
+        assert_eq!(
+            string_pool.incref("Bar".to_string()).unwrap(),
+            StringRef(2)
+        );
+        string_pool.decref_all(&[StringRef(1)]).unwrap();
+        assert_eq!(
+            string_pool.incref("Bar".to_string()).unwrap(),
+            StringRef(2)
+        );
+        assert_eq!(string_pool.refcount(StringRef(2)), 2);
+        assert_eq!(string_pool.refcount(StringRef(1)), 0);
+    }
+
+    #[test]
+    fn build_with_free_entries() {
+        let pool: &[u8] = b"\xe9\xfd\x00\x00\x03\x00\x00\x00\x04\x00\x01\x00";
+        let data: &[u8] = b"FooQuux";
+        let builder = StringPoolBuilder::read_from_pool(pool).expect("pool");
+        let mut string_pool = builder.build_from_data(data).expect("data");
+        assert_eq!(
+            string_pool.incref("Quux".to_string()).unwrap(),
+            StringRef(2)
+        );
+        assert_eq!(
+            string_pool.incref("Foo".to_string()).unwrap(),
+            StringRef(1)
+        );
+        assert_eq!(string_pool.refcount(StringRef(1)), 1);
+        assert_eq!(string_pool.num_strings(), 2);
+    }
+
+    #[test]
+    fn compact_string_pool() {
+        let mut string_pool = StringPool::new(CodePage::default());
+        for string in ["Foo", "Bar", "Baz", "Quux"] {
+            string_pool.incref(string.to_string()).unwrap();
+        }
+        string_pool.decref_all(&[StringRef(2)]).unwrap();
+        assert!(!string_pool.is_compact(false));
+        let remap = string_pool.compact(false);
+        assert_eq!(
+            remap,
+            vec![
+                Some(StringRef(1)),
+                None,
+                Some(StringRef(2)),
+                Some(StringRef(3))
+            ]
+        );
+        assert!(string_pool.is_compact(false));
+        assert!(!string_pool.is_compact(true));
+        let remap = string_pool.compact(true);
+        assert_eq!(
+            remap,
+            vec![Some(StringRef(2)), Some(StringRef(1)), Some(StringRef(3))]
+        );
+        assert!(string_pool.is_compact(true));
+        assert_eq!(string_pool.get(StringRef(1)), "Baz");
+        assert_eq!(
+            string_pool.incref("Foo".to_string()).unwrap(),
+            StringRef(2)
+        );
+        assert_eq!(string_pool.refcount(StringRef(2)), 2);
+        assert_eq!(
+            string_pool.incref("Bar".to_string()).unwrap(),
+            StringRef(4)
+        );
+    }
+
     #[test]
     #[should_panic(expected = "Unknown codepage for string pool (123456)")]
     fn invalid_codepage() {
@@ -19,6 +19,7 @@ use uuid::Uuid;
 const FMTID: [u8; 16] =
     *b"\xe0\x85\x9f\xf2\xf9\x4f\x68\x10\xab\x91\x08\x00\x2b\x27\xb3\xd9";
 
+const PROPERTY_CODEPAGE: u32 = 1;
 const PROPERTY_TITLE: u32 = 2;
 const PROPERTY_SUBJECT: u32 = 3;
 const PROPERTY_AUTHOR: u32 = 4;
@@ -39,6 +40,7 @@ const PROPERTY_DOC_SECURITY: u32 = 19;
 // ========================================================================= //
 
 /// Summary information (e.g. title, author) about an MSI package.
+#[derive(Clone)]
 pub struct SummaryInfo {
     properties: PropertySet,
 }
@@ -268,6 +270,25 @@ impl SummaryInfo {
         self.properties.remove(PROPERTY_LAST_SAVE_TIME);
     }
 
+    /// Replaces each of the date/time properties that is set with the given
+    /// time.  This is used for making reproducible packages.
+    pub(crate) fn normalize_times(&mut self, timestamp: SystemTime) {
+        for property in [
+            PROPERTY_CREATION_TIME,
+            PROPERTY_LAST_PRINTED,
+            PROPERTY_LAST_SAVE_TIME,
+        ] {
+            if self.properties.get(property).is_some() {
+                self.properties.set(
+                    property,
+                    PropertyValue::FileTime(Timestamp::from_system_time(
+                        timestamp,
+                    )),
+                );
+            }
+        }
+    }
+
     /// Gets the list of languages from the "template" property, if one is set.
     /// This indicates the languages that this package supports.
     pub fn languages(&self) -> Vec<LanguageId> {
@@ -514,6 +535,240 @@ impl SummaryInfo {
     pub fn clear_last_saved_by(&mut self) {
         self.properties.remove(PROPERTY_LAST_SAVED_BY);
     }
+
+    /// Returns the ID and value of each property that is set, in order of ID,
+    /// with the values formatted as in a `_SummaryInformation` IDT file.
+    pub(crate) fn idt_properties(&self) -> Vec<(u32, String)> {
+        self.properties
+            .iter()
+            .filter_map(|(property, value)| {
+                let string = match value {
+                    // Code page IDs above 32767 are stored as negative I2s.
+                    _ if property == PROPERTY_CODEPAGE => {
+                        self.codepage().id().to_string()
+                    }
+                    PropertyValue::Empty | PropertyValue::Null => return None,
+                    PropertyValue::I1(number) => number.to_string(),
+                    PropertyValue::I2(number) => number.to_string(),
+                    PropertyValue::I4(number) => number.to_string(),
+                    PropertyValue::LpStr(string) => string.clone(),
+                    PropertyValue::FileTime(timestamp) => {
+                        timestamp.to_idt_string()
+                    }
+                };
+                Some((property, string))
+            })
+            .collect()
+    }
+
+    /// Sets a property from a value formatted as in a `_SummaryInformation`
+    /// IDT file.
+    pub(crate) fn set_idt_property(
+        &mut self,
+        property: u32,
+        value: &str,
+    ) -> io::Result<()> {
+        let value = match property {
+            PROPERTY_CODEPAGE => {
+                match value.parse().ok().and_then(CodePage::from_id) {
+                    Some(codepage) => {
+                        self.set_codepage(codepage);
+                        return Ok(());
+                    }
+                    None => invalid_data!(
+                        "Invalid summary info code page {:?}",
+                        value
+                    ),
+                }
+            }
+            PROPERTY_LAST_PRINTED
+            | PROPERTY_CREATION_TIME
+            | PROPERTY_LAST_SAVE_TIME => {
+                match Timestamp::parse_idt_string(value) {
+                    Some(timestamp) => PropertyValue::FileTime(timestamp),
+                    None => invalid_data!(
+                        "Invalid summary info timestamp {:?} for property {}",
+                        value,
+                        property
+                    ),
+                }
+            }
+            PROPERTY_PAGE_COUNT
+            | PROPERTY_WORD_COUNT
+            | PROPERTY_CHARACTER_COUNT
+            | PROPERTY_DOC_SECURITY => match value.parse() {
+                Ok(number) => PropertyValue::I4(number),
+                Err(_) => invalid_data!(
+                    "Invalid summary info integer {:?} for property {}",
+                    value,
+                    property
+                ),
+            },
+            _ => match value.parse() {
+                Ok(number) if !is_string_property(property) => {
+                    PropertyValue::I4(number)
+                }
+                _ => PropertyValue::LpStr(value.to_string()),
+            },
+        };
+        self.properties.set(property, value);
+        Ok(())
+    }
+}
+
+/// Returns true if the given summary info property holds a string.
+fn is_string_property(property: u32) -> bool {
+    matches!(
+        property,
+        PROPERTY_TITLE
+            | PROPERTY_SUBJECT
+            | PROPERTY_AUTHOR
+            | PROPERTY_KEYWORDS
+            | PROPERTY_COMMENTS
+            | PROPERTY_TEMPLATE
+            | PROPERTY_LAST_SAVED_BY
+            | PROPERTY_UUID
+            | PROPERTY_CREATING_APP
+    )
+}
+
+/// The serialized form of a `SummaryInfo`, with one field per property.
+#[cfg(feature = "serde")]
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct SummaryInfoData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subject: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    keywords: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comments: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arch: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    languages: Vec<LanguageId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_saved_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uuid: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_printed: Option<SystemTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    creation_time: Option<SystemTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_saved_time: Option<SystemTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page_count: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    word_count: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    character_count: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    creating_application: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    doc_security: Option<i32>,
+    codepage: CodePage,
+}
+
+/// Summary info serializes as a struct with one field for each property that
+/// is set (plus the code page, which is always present).
+#[cfg(feature = "serde")]
+impl serde::Serialize for SummaryInfo {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let data = SummaryInfoData {
+            title: self.title().map(str::to_string),
+            subject: self.subject().map(str::to_string),
+            author: self.author().map(str::to_string),
+            keywords: self.keywords(),
+            comments: self.comments().map(str::to_string),
+            arch: self.arch().map(str::to_string),
+            languages: self.languages(),
+            last_saved_by: self.last_saved_by().map(str::to_string),
+            uuid: self.uuid(),
+            last_printed: self.last_printed(),
+            creation_time: self.creation_time(),
+            last_saved_time: self.last_saved_time(),
+            page_count: self.page_count(),
+            word_count: self.word_count(),
+            character_count: self.character_count(),
+            creating_application: self
+                .creating_application()
+                .map(str::to_string),
+            doc_security: self.doc_security(),
+            codepage: self.codepage(),
+        };
+        data.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SummaryInfo {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let data = SummaryInfoData::deserialize(deserializer)?;
+        let mut summary = SummaryInfo::new();
+        summary.set_codepage(data.codepage);
+        if let Some(title) = data.title {
+            summary.set_title(title);
+        }
+        if let Some(subject) = data.subject {
+            summary.set_subject(subject);
+        }
+        if let Some(author) = data.author {
+            summary.set_author(author);
+        }
+        if !data.keywords.is_empty() {
+            summary.set_keywords(&data.keywords);
+        }
+        if let Some(comments) = data.comments {
+            summary.set_comments(comments);
+        }
+        if let Some(arch) = data.arch {
+            summary.set_arch(arch);
+        }
+        if !data.languages.is_empty() {
+            summary.set_languages(&data.languages);
+        }
+        if let Some(last_saved_by) = data.last_saved_by {
+            summary.set_last_saved_by(last_saved_by);
+        }
+        if let Some(uuid) = data.uuid {
+            summary.set_uuid(uuid);
+        }
+        if let Some(timestamp) = data.last_printed {
+            summary.set_last_printed(timestamp);
+        }
+        if let Some(timestamp) = data.creation_time {
+            summary.set_creation_time(timestamp);
+        }
+        if let Some(timestamp) = data.last_saved_time {
+            summary.set_last_save_time(timestamp);
+        }
+        if let Some(page_count) = data.page_count {
+            summary.set_page_count(page_count);
+        }
+        if let Some(word_count) = data.word_count {
+            summary.set_word_count(word_count);
+        }
+        if let Some(character_count) = data.character_count {
+            summary.set_character_count(character_count);
+        }
+        if let Some(app_name) = data.creating_application {
+            summary.set_creating_application(app_name);
+        }
+        if let Some(doc_security) = data.doc_security {
+            summary.set_doc_security(doc_security);
+        }
+        Ok(summary)
+    }
 }
 
 // ========================================================================= //
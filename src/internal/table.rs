@@ -2,7 +2,9 @@ use crate::internal::category::Category;
 use crate::internal::column::Column;
 use crate::internal::streamname;
 use crate::internal::stringpool::StringPool;
+use crate::internal::textrows;
 use crate::internal::value::{Value, ValueRef};
+use std::cmp::Ordering;
 use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::ops::Index;
 use std::rc::Rc;
@@ -82,6 +84,15 @@ impl Table {
             .collect()
     }
 
+    /// Returns the number of bytes that one row of this table takes up in the
+    /// table's stream.
+    pub(crate) fn row_size(&self) -> u64 {
+        self.columns
+            .iter()
+            .map(|col| col.coltype().width(self.long_string_refs))
+            .sum()
+    }
+
     pub(crate) fn index_for_column_name(
         &self,
         column_name: &str,
@@ -102,20 +113,19 @@ impl Table {
     ) -> io::Result<Vec<Vec<ValueRef>>> {
         let data_length = reader.seek(SeekFrom::End(0))?;
         reader.rewind()?;
-        let row_size = self
-            .columns
-            .iter()
-            .map(|col| col.coltype().width(self.long_string_refs))
-            .sum::<u64>();
+        let row_size = self.row_size();
         let num_columns = self.columns.len();
         let num_rows = data_length.checked_div(row_size).unwrap_or_default();
         // The number of rows cannot exceed 65536, according to this FAQ:
         // http://www.installsite.org/pages/en/msifaq/a/1043.htm
         if num_rows > 65536 {
-            invalid_data!(
-                "Number of rows is too large ({} > 65536)",
-                num_rows
-            );
+            msi_error!(Corrupt {
+                stream: self.name.clone(),
+                message: format!(
+                    "Number of rows is too large ({} > 65536)",
+                    num_rows
+                ),
+            });
         }
         let mut rows = vec![
             Vec::<ValueRef>::with_capacity(num_columns);
@@ -132,6 +142,56 @@ impl Table {
         Ok(rows)
     }
 
+    /// Returns an error if any of the given rows (as returned by
+    /// `read_rows()`) refers to a string that isn't in the string pool.
+    pub(crate) fn check_string_refs(
+        &self,
+        rows: &[Vec<ValueRef>],
+        string_pool: &StringPool,
+    ) -> io::Result<()> {
+        for (row_index, row) in rows.iter().enumerate() {
+            for (column, value_ref) in self.columns.iter().zip(row) {
+                if let ValueRef::Str(string_ref) = *value_ref {
+                    if !string_pool.contains(string_ref) {
+                        msi_error!(Corrupt {
+                            stream: self.name.clone(),
+                            message: format!(
+                                "Row {} of table {:?} refers to string #{} \
+                                 in column {:?}, but the string pool has \
+                                 only {} entries",
+                                row_index,
+                                self.name,
+                                string_ref.number(),
+                                column.name(),
+                                string_pool.num_strings()
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares two rows of this table according to the order in which they
+    /// are written out to the table's stream.
+    pub(crate) fn compare_rows(
+        &self,
+        row: &[ValueRef],
+        other: &[ValueRef],
+    ) -> Ordering {
+        for (idx, column) in self.columns.iter().enumerate() {
+            if let Some(Category::Binary) = column.category() {
+                continue;
+            }
+            match row[idx].cmp(&other[idx]) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            }
+        }
+        Ordering::Equal
+    }
+
     pub(crate) fn write_rows<W: Write>(
         &self,
         mut writer: W,
@@ -141,18 +201,7 @@ impl Table {
 
         // Rows must be sorted or else the MSI will be seen as invalid to
         // applications like `msiexec.exe`.
-        rows.sort_by(|row, other| {
-            for (idx, column) in self.columns.iter().enumerate() {
-                if let Some(Category::Binary) = column.category() {
-                    continue;
-                }
-                match row[idx].cmp(&other[idx]) {
-                    std::cmp::Ordering::Equal => continue,
-                    ordering => return ordering,
-                }
-            }
-            std::cmp::Ordering::Equal
-        });
+        rows.sort_by(|row, other| self.compare_rows(row, other));
         for (index, column) in self.columns.iter().enumerate() {
             let coltype = column.coltype();
             for row in &rows {
@@ -182,6 +231,12 @@ impl Row {
         Self { table, values }
     }
 
+    /// Returns the name of the table this row came from (which is empty for
+    /// the anonymous result of a join).
+    pub(crate) fn table_name(&self) -> &str {
+        self.table.name()
+    }
+
     /// Returns the number of values in the row.
     #[must_use]
     pub fn len(&self) -> usize {
@@ -205,10 +260,28 @@ impl Row {
     pub fn has_column(&self, column_name: &str) -> bool {
         self.table.has_column(column_name)
     }
+
+    /// Returns the value of the column with the given index, or `None` if
+    /// `index >= self.len()`.  Unlike indexing the row with `row[index]`, this
+    /// never panics.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&Value> {
+        self.values.get(index)
+    }
+
+    /// Returns the value of the column with the given name, or `None` if the
+    /// row has no such column.  Unlike indexing the row with
+    /// `row[column_name]`, this never panics.
+    #[must_use]
+    pub fn get_by_name(&self, column_name: &str) -> Option<&Value> {
+        self.table
+            .index_for_column_name(column_name)
+            .and_then(|index| self.values.get(index))
+    }
 }
 
 /// Gets the value of the column with the given index.  Panics if `index >=
-/// self.len()`.
+/// self.len()`; see `Row::get()` for a non-panicking alternative.
 impl Index<usize> for Row {
     type Output = Value;
 
@@ -232,7 +305,8 @@ impl Index<usize> for Row {
 }
 
 /// Gets the value of the column with the given name.  Panics if
-/// `!self.has_column(column_name)`.
+/// `!self.has_column(column_name)`; see `Row::get_by_name()` for a
+/// non-panicking alternative.
 impl Index<&str> for Row {
     type Output = Value;
 
@@ -255,6 +329,22 @@ impl Index<&str> for Row {
     }
 }
 
+/// A row serializes as a map from column names to values.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Row {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.values.len()))?;
+        for (column, value) in self.table.columns().iter().zip(&self.values) {
+            map.serialize_entry(column.name(), value)?;
+        }
+        map.end()
+    }
+}
+
 // ========================================================================= //
 
 /// An iterator over the rows in a database table.
@@ -280,6 +370,26 @@ impl<'a> Rows<'a> {
         self.table.columns()
     }
 
+    /// Writes the remaining rows to `writer` as CSV (RFC 4180, with CRLF
+    /// line endings).  The first record holds the column names and the
+    /// second their IDT type codes (e.g. `s72` or `I2`), so that the file can
+    /// be read back with `Package::import_csv()`.  Null values are written
+    /// as empty fields, and binary values as `BINARY_STREAM` (without their
+    /// data).
+    pub fn write_csv<W: Write>(self, writer: W) -> io::Result<()> {
+        textrows::write_csv(self, writer)
+    }
+
+    /// Writes the remaining rows to `writer` as JSON lines.  The first line
+    /// is an object listing the columns' names and IDT type codes, and each
+    /// later line is an object mapping column names to values, so that the
+    /// file can be read back with `Package::import_json_lines()`.  Null
+    /// values are written as `null`, and binary values as `[]` (without their
+    /// data).
+    pub fn write_json_lines<W: Write>(self, writer: W) -> io::Result<()> {
+        textrows::write_json_lines(self, writer)
+    }
+
     pub(crate) fn into_table_and_values(
         self,
     ) -> (Rc<Table>, Vec<Vec<ValueRef>>) {
@@ -312,6 +422,29 @@ impl<'a> Iterator for Rows<'a> {
 
 impl<'a> ExactSizeIterator for Rows<'a> {}
 
+/// Rows serialize as a sequence of maps from column names to values (see the
+/// `Serialize` impl for `Row`).  Only the rows that haven't yet been iterated
+/// over are included.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for Rows<'a> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+        let remaining = &self.rows[self.next_row_index..];
+        let mut seq = serializer.serialize_seq(Some(remaining.len()))?;
+        for value_refs in remaining {
+            let values: Vec<Value> = value_refs
+                .iter()
+                .map(|value_ref| value_ref.to_value(self.string_pool))
+                .collect();
+            seq.serialize_element(&Row::new(self.table.clone(), values))?;
+        }
+        seq.end()
+    }
+}
+
 // ========================================================================= //
 
 #[cfg(test)]
@@ -0,0 +1,798 @@
+use crate::internal::column::{Column, ColumnType};
+use crate::internal::idt::column_type_code;
+use crate::internal::package::Package;
+use crate::internal::query::Insert;
+use crate::internal::table::{Rows, Table};
+use crate::internal::value::Value;
+use std::error;
+use std::fmt;
+use std::io::{self, Read, Seek, Write};
+
+// ========================================================================= //
+
+/// The CSV field written for non-null binary values (whose data is not
+/// included).  This is the same as the `Display` form of `Value::Binary`.
+const BINARY_FIELD: &str = "BINARY_STREAM";
+
+// ========================================================================= //
+
+pub(crate) fn write_csv<W: Write>(
+    rows: Rows,
+    mut writer: W,
+) -> io::Result<()> {
+    let columns = rows.columns().to_vec();
+    let names: Vec<String> =
+        columns.iter().map(|column| column.name().to_string()).collect();
+    write_csv_record(&mut writer, &names)?;
+    let codes: Vec<String> = columns.iter().map(column_type_code).collect();
+    write_csv_record(&mut writer, &codes)?;
+    for row in rows {
+        let fields: Vec<String> = (0..row.len())
+            .map(|index| match &row[index] {
+                Value::Null => String::new(),
+                Value::Int(number) => number.to_string(),
+                Value::Str(string) => string.clone(),
+                Value::Binary => BINARY_FIELD.to_string(),
+            })
+            .collect();
+        write_csv_record(&mut writer, &fields)?;
+    }
+    Ok(())
+}
+
+fn write_csv_record<W: Write>(
+    writer: &mut W,
+    fields: &[String],
+) -> io::Result<()> {
+    let mut line = String::new();
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 {
+            line.push(',');
+        }
+        if field.contains([',', '"', '\r', '\n']) {
+            line.push('"');
+            line.push_str(&field.replace('"', "\"\""));
+            line.push('"');
+        } else {
+            line.push_str(field);
+        }
+    }
+    line.push_str("\r\n");
+    writer.write_all(line.as_bytes())
+}
+
+/// Splits CSV text into records, each paired with the (1-based) line number
+/// that it starts on.
+fn read_csv_records(text: &str) -> io::Result<Vec<(usize, Vec<String>)>> {
+    let mut records = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_number = 1;
+    while chars.peek().is_some() {
+        let start_line = line_number;
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        loop {
+            match chars.next() {
+                None => {
+                    fields.push(field);
+                    break;
+                }
+                Some(',') => fields.push(std::mem::take(&mut field)),
+                Some('\n') => {
+                    line_number += 1;
+                    fields.push(field);
+                    break;
+                }
+                Some('\r') if chars.peek() == Some(&'\n') => {}
+                Some('"') if field.is_empty() => loop {
+                    match chars.next() {
+                        None => invalid_data!(
+                            "CSV record on line {} has an unterminated quoted \
+                             field",
+                            start_line
+                        ),
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => {
+                            if !matches!(
+                                chars.peek(),
+                                None | Some(',' | '\r' | '\n')
+                            ) {
+                                invalid_data!(
+                                    "CSV record on line {} has text after a \
+                                     quoted field",
+                                    start_line
+                                );
+                            }
+                            break;
+                        }
+                        Some(chr) => {
+                            if chr == '\n' {
+                                line_number += 1;
+                            }
+                            field.push(chr);
+                        }
+                    }
+                },
+                Some(chr) => field.push(chr),
+            }
+        }
+        // Skip blank lines (e.g. a trailing newline at the end of the file).
+        if fields.len() > 1 || !fields[0].is_empty() {
+            records.push((start_line, fields));
+        }
+    }
+    Ok(records)
+}
+
+pub(crate) fn import_csv<F, R>(
+    package: &mut Package<F>,
+    table_name: &str,
+    mut reader: R,
+) -> io::Result<usize>
+where
+    F: Read + Write + Seek,
+    R: Read,
+{
+    let table = get_table(package, table_name)?;
+    let mut text = String::new();
+    if reader.read_to_string(&mut text).is_err() {
+        invalid_data!("CSV file is not valid UTF-8");
+    }
+    let mut records = read_csv_records(&text)?.into_iter();
+    let (names, codes) = match (records.next(), records.next()) {
+        (Some((_, names)), Some((_, codes))) => (names, codes),
+        _ => invalid_data!(
+            "CSV file must start with records of column names and types"
+        ),
+    };
+    if names.len() != codes.len() {
+        invalid_data!(
+            "CSV file has {} column names but {} column types",
+            names.len(),
+            codes.len()
+        );
+    }
+    let indices = match_columns(&table, &names, &codes)?;
+    let mut rows = Vec::<(usize, Vec<Value>)>::new();
+    for (line_number, fields) in records {
+        if fields.len() != indices.len() {
+            invalid_data!(
+                "Line {} of CSV file has {} fields, but its header has {} \
+                 columns",
+                line_number,
+                fields.len(),
+                indices.len()
+            );
+        }
+        let mut values = vec![Value::Null; table.columns().len()];
+        for (field, &index) in fields.into_iter().zip(&indices) {
+            let column = &table.columns()[index];
+            values[index] = parse_csv_field(column, field, line_number)?;
+        }
+        rows.push((line_number, values));
+    }
+    insert_rows(package, table_name, rows)
+}
+
+fn parse_csv_field(
+    column: &Column,
+    field: String,
+    line_number: usize,
+) -> io::Result<Value> {
+    if field.is_empty() {
+        return Ok(Value::Null);
+    }
+    match column.coltype() {
+        ColumnType::Int16 | ColumnType::Int32 => match field.parse() {
+            Ok(number) => Ok(Value::Int(number)),
+            Err(_) => invalid_data!(
+                "Line {} of CSV file has non-integer value {:?} in column \
+                 {:?}",
+                line_number,
+                field,
+                column.name()
+            ),
+        },
+        ColumnType::Str(_) => Ok(Value::Str(field)),
+        ColumnType::Binary if field == BINARY_FIELD => Ok(Value::Binary),
+        ColumnType::Binary => invalid_data!(
+            "Line {} of CSV file has {:?} in binary column {:?}, which can \
+             only hold {:?} or nothing",
+            line_number,
+            field,
+            column.name(),
+            BINARY_FIELD
+        ),
+    }
+}
+
+// ========================================================================= //
+
+pub(crate) fn write_json_lines<W: Write>(
+    rows: Rows,
+    mut writer: W,
+) -> io::Result<()> {
+    let columns = rows.columns().to_vec();
+    let metadata: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            format!(
+                "{{\"name\":{},\"type\":{}}}",
+                json_string(column.name()),
+                json_string(&column_type_code(column))
+            )
+        })
+        .collect();
+    writeln!(writer, "{{\"columns\":[{}]}}", metadata.join(","))?;
+    for row in rows {
+        let members: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                let value = match &row[index] {
+                    Value::Null => "null".to_string(),
+                    Value::Int(number) => number.to_string(),
+                    Value::Str(string) => json_string(string),
+                    Value::Binary => "[]".to_string(),
+                };
+                format!("{}:{}", json_string(column.name()), value)
+            })
+            .collect();
+        writeln!(writer, "{{{}}}", members.join(","))?;
+    }
+    Ok(())
+}
+
+fn json_string(string: &str) -> String {
+    let mut output = String::with_capacity(string.len() + 2);
+    output.push('"');
+    for chr in string.chars() {
+        match chr {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            _ if chr < ' ' => {
+                output.push_str(&format!("\\u{:04x}", chr as u32))
+            }
+            _ => output.push(chr),
+        }
+    }
+    output.push('"');
+    output
+}
+
+pub(crate) fn import_json_lines<F, R>(
+    package: &mut Package<F>,
+    table_name: &str,
+    mut reader: R,
+) -> io::Result<usize>
+where
+    F: Read + Write + Seek,
+    R: Read,
+{
+    let table = get_table(package, table_name)?;
+    let mut text = String::new();
+    if reader.read_to_string(&mut text).is_err() {
+        invalid_data!("JSON lines file is not valid UTF-8");
+    }
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+    let metadata = match lines.next() {
+        Some((line_number, line)) => parse_json_line(line, line_number)?,
+        None => invalid_data!("JSON lines file is missing its column list"),
+    };
+    let mut names = Vec::<String>::new();
+    let mut codes = Vec::<String>::new();
+    let columns = json_member(&metadata, "columns").map(Json::as_array);
+    for column in columns.unwrap_or(&[]) {
+        match (
+            json_member(column, "name").and_then(Json::as_str),
+            json_member(column, "type").and_then(Json::as_str),
+        ) {
+            (Some(name), Some(code)) => {
+                names.push(name.to_string());
+                codes.push(code.to_string());
+            }
+            _ => invalid_data!(
+                "JSON lines file has a malformed column list on its first line"
+            ),
+        }
+    }
+    if names.is_empty() {
+        invalid_data!(
+            "JSON lines file must start with a line listing its columns"
+        );
+    }
+    let indices = match_columns(&table, &names, &codes)?;
+    let mut rows = Vec::<(usize, Vec<Value>)>::new();
+    for (line_number, line) in lines {
+        let members = match parse_json_line(line, line_number)? {
+            Json::Object(members) => members,
+            _ => invalid_data!(
+                "Line {} of JSON lines file is not a JSON object",
+                line_number
+            ),
+        };
+        let mut values = vec![Value::Null; table.columns().len()];
+        for (name, json) in members {
+            let index = match names.iter().position(|other| *other == name) {
+                Some(position) => indices[position],
+                None => invalid_data!(
+                    "Line {} of JSON lines file has member {:?}, which is \
+                     not one of its columns",
+                    line_number,
+                    name
+                ),
+            };
+            let column = &table.columns()[index];
+            values[index] = parse_json_field(column, json, line_number)?;
+        }
+        rows.push((line_number, values));
+    }
+    insert_rows(package, table_name, rows)
+}
+
+fn parse_json_line(line: &str, line_number: usize) -> io::Result<Json> {
+    let mut parser = JsonParser::new(line);
+    match parser.parse_value().and_then(|json| parser.end(json)) {
+        Ok(json) => Ok(json),
+        Err(message) => invalid_data!(
+            "Line {} of JSON lines file is not valid JSON: {}",
+            line_number,
+            message
+        ),
+    }
+}
+
+fn json_member<'a>(json: &'a Json, name: &str) -> Option<&'a Json> {
+    match json {
+        Json::Object(members) => members
+            .iter()
+            .find(|(member_name, _)| member_name == name)
+            .map(|(_, value)| value),
+        _ => None,
+    }
+}
+
+fn parse_json_field(
+    column: &Column,
+    json: Json,
+    line_number: usize,
+) -> io::Result<Value> {
+    match (column.coltype(), json) {
+        (_, Json::Null) => Ok(Value::Null),
+        (ColumnType::Int16 | ColumnType::Int32, Json::Number(number)) => {
+            match number.parse() {
+                Ok(number) => Ok(Value::Int(number)),
+                Err(_) => invalid_data!(
+                    "Line {} of JSON lines file has non-integer value {} in \
+                     column {:?}",
+                    line_number,
+                    number,
+                    column.name()
+                ),
+            }
+        }
+        (ColumnType::Str(_), Json::String(string)) => Ok(Value::Str(string)),
+        (ColumnType::Binary, Json::Array(items)) if items.is_empty() => {
+            Ok(Value::Binary)
+        }
+        (coltype, json) => {
+            let expected = match coltype {
+                ColumnType::Int16 | ColumnType::Int32 => "a number",
+                ColumnType::Str(_) => "a string",
+                ColumnType::Binary => "[]",
+            };
+            invalid_data!(
+                "Line {} of JSON lines file has {} in column {:?}, which \
+                 should hold {} or null",
+                line_number,
+                json.description(),
+                column.name(),
+                expected
+            )
+        }
+    }
+}
+
+// ========================================================================= //
+
+/// A parsed JSON value.  Numbers are kept as text, so that they can be
+/// checked against the column type they're destined for.
+enum Json {
+    Null,
+    Bool,
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(items) => items,
+            _ => &[],
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Bool => "a boolean",
+            Json::Number(_) => "a number",
+            Json::String(_) => "a string",
+            Json::Array(_) => "an array",
+            Json::Object(_) => "an object",
+        }
+    }
+}
+
+/// The maximum depth of nested arrays and objects that `JsonParser` accepts,
+/// so that a malicious line can't overflow the stack.  Valid rows only ever
+/// nest one level deep.
+const MAX_JSON_DEPTH: usize = 128;
+
+struct JsonParser<'a> {
+    text: &'a str,
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(text: &'a str) -> JsonParser<'a> {
+        JsonParser { text, position: 0, depth: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.rest().chars().next()
+    }
+
+    fn expect(&mut self, chr: char) -> Result<(), String> {
+        if self.peek() == Some(chr) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(format!("expected {:?} at column {}", chr, self.position + 1))
+        }
+    }
+
+    fn end(&mut self, json: Json) -> Result<Json, String> {
+        match self.peek() {
+            None => Ok(json),
+            Some(_) => {
+                Err(format!("unexpected text at column {}", self.position + 1))
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        if self.depth >= MAX_JSON_DEPTH {
+            return Err(format!(
+                "values are nested too deeply at column {}",
+                self.position + 1
+            ));
+        }
+        self.depth += 1;
+        let result = self.parse_nested_value();
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_nested_value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some('{') => {
+                self.position += 1;
+                let mut members = Vec::new();
+                if self.peek() == Some('}') {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    if self.peek() != Some('"') {
+                        return Err(format!(
+                            "expected a member name at column {}",
+                            self.position + 1
+                        ));
+                    }
+                    let name = self.parse_string()?;
+                    self.expect(':')?;
+                    members.push((name, self.parse_value()?));
+                    match self.peek() {
+                        Some(',') => self.position += 1,
+                        _ => {
+                            self.expect('}')?;
+                            return Ok(Json::Object(members));
+                        }
+                    }
+                }
+            }
+            Some('[') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                if self.peek() == Some(']') {
+                    self.position += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.parse_value()?);
+                    match self.peek() {
+                        Some(',') => self.position += 1,
+                        _ => {
+                            self.expect(']')?;
+                            return Ok(Json::Array(items));
+                        }
+                    }
+                }
+            }
+            Some('"') => Ok(Json::String(self.parse_string()?)),
+            Some('-' | '0'..='9') => {
+                let rest = self.rest();
+                let length = rest
+                    .find(|chr: char| {
+                        !matches!(chr, '-' | '+' | '.' | 'e' | 'E' | '0'..='9')
+                    })
+                    .unwrap_or(rest.len());
+                self.position += length;
+                Ok(Json::Number(rest[..length].to_string()))
+            }
+            _ => {
+                for (word, json) in [
+                    ("null", Json::Null),
+                    ("true", Json::Bool),
+                    ("false", Json::Bool),
+                ] {
+                    if self.rest().starts_with(word) {
+                        self.position += word.len();
+                        return Ok(json);
+                    }
+                }
+                Err(format!(
+                    "expected a value at column {}",
+                    self.position + 1
+                ))
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((offset, chr)) = chars.next() {
+            match chr {
+                '"' => {
+                    self.position += offset + 1;
+                    return Ok(string);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, chr)| chr) {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let mut code = hex_code(&mut chars)?;
+                            if (0xd800..0xdc00).contains(&code) {
+                                // A surrogate pair.
+                                if chars.next().map(|(_, chr)| chr)
+                                    != Some('\\')
+                                    || chars.next().map(|(_, chr)| chr)
+                                        != Some('u')
+                                {
+                                    return Err("unpaired surrogate".into());
+                                }
+                                let low = hex_code(&mut chars)?;
+                                code = 0x10000
+                                    + ((code - 0xd800) << 10)
+                                    + (low.wrapping_sub(0xdc00) & 0x3ff);
+                            }
+                            match char::from_u32(code) {
+                                Some(chr) => chr,
+                                None => {
+                                    return Err(format!(
+                                        "invalid escape \\u{code:04x}"
+                                    ));
+                                }
+                            }
+                        }
+                        _ => return Err("invalid escape in string".into()),
+                    };
+                    string.push(escaped);
+                }
+                _ => string.push(chr),
+            }
+        }
+        Err("unterminated string".into())
+    }
+}
+
+fn hex_code(chars: &mut std::str::CharIndices) -> Result<u32, String> {
+    let digits: String = chars.take(4).map(|(_, chr)| chr).collect();
+    match u32::from_str_radix(&digits, 16) {
+        Ok(code) if digits.len() == 4 => Ok(code),
+        _ => Err(format!("invalid escape \\u{digits}")),
+    }
+}
+
+// ========================================================================= //
+
+fn get_table<F>(package: &Package<F>, table_name: &str) -> io::Result<Table> {
+    match package.get_table(table_name) {
+        Some(table) => Ok(table.clone()),
+        None => msi_error!(TableNotFound { table: table_name.to_string() }),
+    }
+}
+
+/// Checks a file's column names and IDT type codes against the table, and
+/// returns the index within the table of each of the file's columns.
+fn match_columns(
+    table: &Table,
+    names: &[String],
+    codes: &[String],
+) -> io::Result<Vec<usize>> {
+    let mut indices = Vec::with_capacity(names.len());
+    for (name, code) in names.iter().zip(codes) {
+        let index = match table
+            .columns()
+            .iter()
+            .position(|column| column.name() == name)
+        {
+            Some(index) => index,
+            None => msi_error!(ColumnNotFound {
+                table: table.name().to_string(),
+                column: name.clone(),
+            }),
+        };
+        if indices.contains(&index) {
+            invalid_data!("File has more than one {:?} column", name);
+        }
+        let expected = column_type_code(&table.columns()[index]);
+        if *code != expected {
+            invalid_data!(
+                "Column {:?} of table {:?} has type {}, but the file says {}",
+                name,
+                table.name(),
+                expected,
+                code
+            );
+        }
+        indices.push(index);
+    }
+    Ok(indices)
+}
+
+/// Inserts each row (paired with the line number it came from) into the
+/// table, rolling back all of them if any row is invalid.  Returns the number
+/// of rows inserted.
+fn insert_rows<F: Read + Write + Seek>(
+    package: &mut Package<F>,
+    table_name: &str,
+    rows: Vec<(usize, Vec<Value>)>,
+) -> io::Result<usize> {
+    let num_rows = rows.len();
+    package.transaction(|package| {
+        for (line_number, values) in rows {
+            let query = Insert::into(table_name).row(values);
+            if let Err(error) = package.insert_rows(query) {
+                let kind = error.kind();
+                return Err(io::Error::new(
+                    kind,
+                    LineError { line_number, error },
+                ));
+            }
+        }
+        Ok(())
+    })?;
+    Ok(num_rows)
+}
+
+/// An error from inserting the row from a particular line of a text file.
+/// It keeps the original error as its source, so that `Error::from_io()` can
+/// still find the specific cause.
+#[derive(Debug)]
+struct LineError {
+    line_number: usize,
+    error: io::Error,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "Line {}: {}", self.line_number, self.error)
+    }
+}
+
+impl error::Error for LineError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+// ========================================================================= //
+
+#[cfg(test)]
+mod tests {
+    use super::{Json, JsonParser, json_string, read_csv_records};
+
+    #[test]
+    fn csv_records() {
+        let text = "a,b\r\n\"x,y\",\"say \"\"hi\"\"\"\n\"two\nlines\",\n\n";
+        let records = read_csv_records(text).unwrap();
+        assert_eq!(
+            records,
+            vec![
+                (1, vec!["a".to_string(), "b".to_string()]),
+                (2, vec!["x,y".to_string(), "say \"hi\"".to_string()]),
+                (3, vec!["two\nlines".to_string(), String::new()]),
+            ]
+        );
+        assert!(read_csv_records("\"open").is_err());
+        assert!(read_csv_records("\"a\"b").is_err());
+    }
+
+    #[test]
+    fn json_strings() {
+        let string = "quote \" slash \\ tab \t nul \0 caf\u{e9} \u{1f600}";
+        let json = json_string(string);
+        assert_eq!(
+            json,
+            "\"quote \\\" slash \\\\ tab \\t nul \\u0000 caf\u{e9} \
+             \u{1f600}\""
+        );
+        let mut parser = JsonParser::new(&json);
+        match parser.parse_value() {
+            Ok(Json::String(parsed)) => assert_eq!(parsed, string),
+            _ => panic!("failed to parse {json}"),
+        }
+        let mut parser = JsonParser::new("\"\\ud83d\\ude00\\u00e9\"");
+        match parser.parse_value() {
+            Ok(Json::String(parsed)) => assert_eq!(parsed, "\u{1f600}\u{e9}"),
+            _ => panic!("failed to parse surrogate pair"),
+        }
+    }
+
+    #[test]
+    fn json_nesting_depth() {
+        let nested = format!("{}1{}", "[".repeat(100), "]".repeat(100));
+        assert!(JsonParser::new(&nested).parse_value().is_ok());
+        let too_deep = "[".repeat(100_000);
+        match JsonParser::new(&too_deep).parse_value() {
+            Err(message) => assert_eq!(
+                message,
+                "values are nested too deeply at column 129"
+            ),
+            Ok(_) => panic!("parsed {too_deep}"),
+        }
+    }
+}
+
+// ========================================================================= //
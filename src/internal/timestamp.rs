@@ -27,6 +27,58 @@ impl Timestamp {
     pub fn write_to<W: Write>(self, writer: &mut W) -> io::Result<()> {
         writer.write_u64::<LittleEndian>(self.0)
     }
+
+    /// Formats this timestamp as a UTC date and time in the
+    /// `yyyy/mm/dd hh:mm:ss` format used by IDT files, dropping any fraction
+    /// of a second.
+    pub fn to_idt_string(self) -> String {
+        let secs = self.0 / TICKS_PER_SEC;
+        let days = (secs / SECS_PER_DAY) as i64 - DAYS_FROM_1601_TO_1970;
+        let secs_of_day = secs % SECS_PER_DAY;
+        let (year, month, day) = civil_from_days(days);
+        format!(
+            "{:04}/{:02}/{:02} {:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            secs_of_day / 3600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60
+        )
+    }
+
+    /// Parses a UTC date and time in the `yyyy/mm/dd hh:mm:ss` format used by
+    /// IDT files.  Returns `None` if the string isn't in that format, or names
+    /// a time that can't be represented.
+    pub fn parse_idt_string(string: &str) -> Option<Self> {
+        let (date, time) = string.trim().split_once(' ')?;
+        let date: Vec<i64> =
+            date.split('/').map(str::parse).collect::<Result<_, _>>().ok()?;
+        let time: Vec<u64> =
+            time.split(':').map(str::parse).collect::<Result<_, _>>().ok()?;
+        let (&[year, month, day], &[hour, minute, second]) =
+            (date.as_slice(), time.as_slice())
+        else {
+            return None;
+        };
+        if !(1..=12).contains(&month)
+            || !(1..=31).contains(&day)
+            || hour >= 24
+            || minute >= 60
+            || second >= 60
+        {
+            return None;
+        }
+        let days = days_from_civil(year, month, day);
+        if civil_from_days(days) != (year, month, day) {
+            return None; // e.g. February 30th
+        }
+        let days = u64::try_from(days + DAYS_FROM_1601_TO_1970).ok()?;
+        let secs = days
+            .checked_mul(SECS_PER_DAY)?
+            .checked_add(hour * 3600 + minute * 60 + second)?;
+        Some(Self(secs.checked_mul(TICKS_PER_SEC)?))
+    }
 }
 
 //===========================================================================//
@@ -34,6 +86,44 @@ impl Timestamp {
 /// The Windows timestamp value for the Unix epoch (Jan 1, 1970 UTC).
 const UNIX_EPOCH_TIMESTAMP: u64 = 116444736000000000;
 
+const TICKS_PER_SEC: u64 = 10_000_000;
+const SECS_PER_DAY: u64 = 86_400;
+
+/// The number of days between January 1, 1601 and January 1, 1970.
+const DAYS_FROM_1601_TO_1970: i64 = 134_774;
+
+/// Converts a number of days since the Unix epoch to a (year, month, day)
+/// date in the proleptic Gregorian calendar.  This uses the algorithm from
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month =
+        if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// The inverse of `civil_from_days`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
 /// Converts a local `SystemTime` to a Windows timestamp value.
 fn timestamp_from_system_time(system_time: SystemTime) -> u64 {
     match system_time.duration_since(UNIX_EPOCH) {
@@ -90,7 +180,7 @@ fn timestamp_delta_to_duration(delta: u64) -> Duration {
 #[cfg(test)]
 mod tests {
     use super::{
-        UNIX_EPOCH_TIMESTAMP, duration_to_timestamp_delta,
+        Timestamp, UNIX_EPOCH_TIMESTAMP, duration_to_timestamp_delta,
         system_time_from_timestamp, timestamp_delta_to_duration,
         timestamp_from_system_time,
     };
@@ -163,6 +253,32 @@ mod tests {
         let max_time = system_time_from_timestamp(u64::MAX);
         assert!(min_time <= max_time);
     }
+
+    #[test]
+    fn idt_strings() {
+        let timestamp = Timestamp(131343363960000000);
+        assert_eq!(timestamp.to_idt_string(), "2017/03/18 18:46:36");
+        assert_eq!(
+            Timestamp::parse_idt_string("2017/03/18 18:46:36"),
+            Some(timestamp)
+        );
+        assert_eq!(Timestamp(0).to_idt_string(), "1601/01/01 00:00:00");
+        assert_eq!(
+            Timestamp::parse_idt_string("1601/01/01 00:00:00"),
+            Some(Timestamp(0))
+        );
+        let timestamp = Timestamp(116302906200000000);
+        assert_eq!(timestamp.to_idt_string(), "1969/07/20 20:17:00");
+        assert_eq!(
+            Timestamp::parse_idt_string("2000/02/29 12:00:00")
+                .map(Timestamp::to_idt_string),
+            Some("2000/02/29 12:00:00".to_string())
+        );
+        assert_eq!(Timestamp::parse_idt_string("2001/02/29 12:00:00"), None);
+        assert_eq!(Timestamp::parse_idt_string("1600/12/31 23:59:59"), None);
+        assert_eq!(Timestamp::parse_idt_string("2017/03/18"), None);
+        assert_eq!(Timestamp::parse_idt_string("2017/03/18 24:00:00"), None);
+    }
 }
 
 //===========================================================================//
@@ -0,0 +1,1570 @@
+use crate::internal::category::Category;
+use crate::internal::column::ColumnType;
+use crate::internal::expr::Expr;
+use crate::internal::package::Package;
+use crate::internal::query::Select;
+use crate::internal::table::Table;
+use crate::internal::value::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Read, Seek};
+use std::str;
+
+// ========================================================================= //
+
+const COMPONENT_TABLE_NAME: &str = "Component";
+const CREATE_FOLDER_TABLE_NAME: &str = "CreateFolder";
+const CUSTOM_ACTION_TABLE_NAME: &str = "CustomAction";
+const DIALOG_TABLE_NAME: &str = "Dialog";
+const DIRECTORY_TABLE_NAME: &str = "Directory";
+const FILE_TABLE_NAME: &str = "File";
+const ODBC_DATA_SOURCE_TABLE_NAME: &str = "ODBCDataSource";
+const PROPERTY_TABLE_NAME: &str = "Property";
+const REGISTRY_TABLE_NAME: &str = "Registry";
+const ICE_SEQUENCE_TABLE_NAME: &str = "_ICESequence";
+const VALIDATION_TABLE_NAME: &str = "_Validation";
+
+// These system tables describe the database schema itself, and are not
+// expected to have entries in the _Validation table:
+const SCHEMA_TABLE_NAMES: &[&str] = &["_Columns", "_Tables"];
+
+// Bits for the Component table's Attributes column:
+const COMPONENT_REGISTRY_KEY_PATH_BIT: i32 = 0x4;
+const COMPONENT_ODBC_DATA_SOURCE_BIT: i32 = 0x20;
+
+// Mask for the base type in the CustomAction table's Type column:
+const CUSTOM_ACTION_BASE_TYPE_MASK: i32 = 0x7;
+
+const UI_SEQUENCE_TABLE_NAMES: &[&str] =
+    &["AdminUISequence", "InstallUISequence"];
+const EXECUTE_SEQUENCE_TABLE_NAMES: &[&str] =
+    &["AdminExecuteSequence", "AdvtExecuteSequence", "InstallExecuteSequence"];
+
+// Standard actions that must only appear between InstallInitialize and
+// InstallFinalize in an execute sequence table.
+const SCRIPT_ACTIONS: &[&str] = &[
+    "AllocateRegistrySpace",
+    "BindImage",
+    "CreateFolders",
+    "CreateShortcuts",
+    "DeleteServices",
+    "DuplicateFiles",
+    "InstallFiles",
+    "InstallODBC",
+    "InstallServices",
+    "MoveFiles",
+    "ProcessComponents",
+    "PublishComponents",
+    "PublishFeatures",
+    "PublishProduct",
+    "RegisterClassInfo",
+    "RegisterExtensionInfo",
+    "RegisterMIMEInfo",
+    "RegisterProduct",
+    "RegisterProgIdInfo",
+    "RegisterTypeLibraries",
+    "RegisterUser",
+    "RemoveDuplicateFiles",
+    "RemoveFiles",
+    "RemoveFolders",
+    "RemoveIniValues",
+    "RemoveODBC",
+    "RemoveRegistryValues",
+    "RemoveShortcuts",
+    "SelfRegModules",
+    "SelfUnregModules",
+    "StartServices",
+    "StopServices",
+    "UnpublishComponents",
+    "UnpublishFeatures",
+    "UnregisterClassInfo",
+    "UnregisterExtensionInfo",
+    "UnregisterMIMEInfo",
+    "UnregisterProgIdInfo",
+    "UnregisterTypeLibraries",
+    "WriteEnvironmentStrings",
+    "WriteIniValues",
+    "WriteRegistryValues",
+];
+
+// Pairs of standard actions that, when both present in a sequence table, must
+// appear in this relative order.
+const ORDERED_ACTIONS: &[(&str, &str)] = &[
+    ("CostInitialize", "FileCost"),
+    ("FileCost", "CostFinalize"),
+    ("CostInitialize", "CostFinalize"),
+    ("CostFinalize", "InstallValidate"),
+    ("InstallValidate", "InstallInitialize"),
+    ("InstallInitialize", "InstallFinalize"),
+    ("RemoveFiles", "InstallFiles"),
+    ("InstallFiles", "DuplicateFiles"),
+    ("RemoveRegistryValues", "WriteRegistryValues"),
+    ("RemoveShortcuts", "CreateShortcuts"),
+    ("StopServices", "DeleteServices"),
+    ("InstallServices", "StartServices"),
+    ("UnpublishFeatures", "PublishFeatures"),
+    ("RegisterProduct", "PublishProduct"),
+];
+
+const STANDARD_ACTIONS: &[&str] = &[
+    "ADMIN",
+    "ADVERTISE",
+    "AllocateRegistrySpace",
+    "AppSearch",
+    "BindImage",
+    "CCPSearch",
+    "CostFinalize",
+    "CostInitialize",
+    "CreateFolders",
+    "CreateShortcuts",
+    "DeleteServices",
+    "DisableRollback",
+    "DuplicateFiles",
+    "ExecuteAction",
+    "FileCost",
+    "FindRelatedProducts",
+    "ForceReboot",
+    "INSTALL",
+    "InstallAdminPackage",
+    "InstallExecute",
+    "InstallExecuteAgain",
+    "InstallFiles",
+    "InstallFinalize",
+    "InstallInitialize",
+    "InstallODBC",
+    "InstallServices",
+    "InstallSFPCatalogFile",
+    "InstallValidate",
+    "IsolateComponents",
+    "LaunchConditions",
+    "MigrateFeatureStates",
+    "MoveFiles",
+    "MsiConfigureServices",
+    "MsiPublishAssemblies",
+    "MsiUnpublishAssemblies",
+    "PatchFiles",
+    "ProcessComponents",
+    "PublishComponents",
+    "PublishFeatures",
+    "PublishProduct",
+    "RegisterClassInfo",
+    "RegisterComPlus",
+    "RegisterExtensionInfo",
+    "RegisterFonts",
+    "RegisterMIMEInfo",
+    "RegisterProduct",
+    "RegisterProgIdInfo",
+    "RegisterTypeLibraries",
+    "RegisterUser",
+    "RemoveDuplicateFiles",
+    "RemoveEnvironmentStrings",
+    "RemoveExistingProducts",
+    "RemoveFiles",
+    "RemoveFolders",
+    "RemoveIniValues",
+    "RemoveODBC",
+    "RemoveRegistryValues",
+    "RemoveShortcuts",
+    "ResolveSource",
+    "RMCCPSearch",
+    "ScheduleReboot",
+    "SelfRegModules",
+    "SelfUnregModules",
+    "SEQUENCE",
+    "SetODBCFolders",
+    "StartServices",
+    "StopServices",
+    "UnpublishComponents",
+    "UnpublishFeatures",
+    "UnregisterClassInfo",
+    "UnregisterComPlus",
+    "UnregisterExtensionInfo",
+    "UnregisterFonts",
+    "UnregisterMIMEInfo",
+    "UnregisterProgIdInfo",
+    "UnregisterTypeLibraries",
+    "ValidateProductID",
+    "WriteEnvironmentStrings",
+    "WriteIniValues",
+    "WriteRegistryValues",
+];
+
+// ========================================================================= //
+
+/// Indicates how serious a validation diagnostic is.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    /// A problem that will likely cause the package to fail to install
+    /// correctly.
+    Error,
+    /// A problem that may cause unexpected behavior at install time.
+    Warning,
+    /// Informational output that does not indicate a problem.
+    Info,
+}
+
+impl Severity {
+    /// Returns the lowercase name of this severity level.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.as_str().fmt(formatter)
+    }
+}
+
+// ========================================================================= //
+
+/// An Internal Consistency Evaluator (ICE) rule that can be run over a
+/// package.
+///
+/// These are reimplementations of a subset of the rules that ship with the
+/// Windows Installer SDK (in `darice.cub`), along with a few additional
+/// checks that have no direct ICE equivalent.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Rule {
+    /// Checks that component key paths refer to a file, registry entry, or
+    /// ODBC data source belonging to that same component.
+    Ice02,
+    /// Checks every value in the database against the data types, nullability,
+    /// ranges, categories, sets, and foreign keys given in the `_Validation`
+    /// table.
+    Ice03,
+    /// Checks that every column in the database has an entry in the
+    /// `_Validation` table.
+    Ice06,
+    /// Checks that no two components share the same component GUID.
+    Ice08,
+    /// Checks that the actions in the sequence tables are known and are
+    /// ordered correctly.
+    Ice27,
+    /// Checks that every reference to a directory names an existing entry in
+    /// the `Directory` table, and that the directory tree contains no cycles.
+    DirectoryTree,
+}
+
+impl Rule {
+    /// Returns a list of all rules, in the order that they are run by
+    /// [`Package::validate`](struct.Package.html#method.validate).
+    #[must_use]
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::Ice02,
+            Self::Ice03,
+            Self::Ice06,
+            Self::Ice08,
+            Self::Ice27,
+            Self::DirectoryTree,
+        ]
+    }
+
+    /// Returns the name of this rule (e.g. `"ICE03"`).
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ice02 => "ICE02",
+            Self::Ice03 => "ICE03",
+            Self::Ice06 => "ICE06",
+            Self::Ice08 => "ICE08",
+            Self::Ice27 => "ICE27",
+            Self::DirectoryTree => "DirectoryTree",
+        }
+    }
+
+    fn run(self, database: &Database, output: &mut Vec<Diagnostic>) {
+        match self {
+            Self::Ice02 => check_key_paths(database, output),
+            Self::Ice03 => check_validation_data(database, output),
+            Self::Ice06 => check_missing_validation(database, output),
+            Self::Ice08 => check_duplicate_component_guids(database, output),
+            Self::Ice27 => check_sequences(database, output),
+            Self::DirectoryTree => check_directory_tree(database, output),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.as_str().fmt(formatter)
+    }
+}
+
+impl str::FromStr for Rule {
+    type Err = io::Error;
+
+    fn from_str(string: &str) -> io::Result<Self> {
+        for rule in Self::all() {
+            if rule.as_str().eq_ignore_ascii_case(string) {
+                return Ok(rule);
+            }
+        }
+        invalid_input!("Unknown validation rule: {:?}", string)
+    }
+}
+
+// ========================================================================= //
+
+/// A single problem (or informational note) reported by validating a
+/// package.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    rule: Rule,
+    severity: Severity,
+    message: String,
+    table: Option<String>,
+    column: Option<String>,
+    primary_key: Vec<Value>,
+}
+
+impl Diagnostic {
+    fn new<S: Into<String>>(
+        rule: Rule,
+        severity: Severity,
+        message: S,
+    ) -> Self {
+        Self {
+            rule,
+            severity,
+            message: message.into(),
+            table: None,
+            column: None,
+            primary_key: Vec::new(),
+        }
+    }
+
+    fn in_table(mut self, table: &str) -> Self {
+        self.table = Some(table.to_string());
+        self
+    }
+
+    fn in_column(mut self, column: &str) -> Self {
+        self.column = Some(column.to_string());
+        self
+    }
+
+    fn in_row(mut self, table: &Table, values: &[Value]) -> Self {
+        self.primary_key = table
+            .primary_key_indices()
+            .into_iter()
+            .map(|index| values[index].clone())
+            .collect();
+        self
+    }
+
+    /// Returns the rule that produced this diagnostic.
+    #[must_use]
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Returns how serious this diagnostic is.
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Returns a human-readable description of the problem.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the name of the table the problem was found in, if any.
+    #[must_use]
+    pub fn table(&self) -> Option<&str> {
+        self.table.as_deref()
+    }
+
+    /// Returns the name of the column the problem was found in, if any.
+    #[must_use]
+    pub fn column(&self) -> Option<&str> {
+        self.column.as_deref()
+    }
+
+    /// Returns the primary key values of the row the problem was found in.
+    /// This will be empty if the problem isn't specific to a single row.
+    #[must_use]
+    pub fn primary_key(&self) -> &[Value] {
+        &self.primary_key
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{} {}", self.rule, self.severity)?;
+        if let Some(ref table) = self.table {
+            write!(formatter, " in {table}")?;
+            if let Some(ref column) = self.column {
+                write!(formatter, ".{column}")?;
+            }
+            if !self.primary_key.is_empty() {
+                let keys: Vec<String> =
+                    self.primary_key.iter().map(Value::to_string).collect();
+                write!(formatter, " [{}]", keys.join(", "))?;
+            }
+        }
+        write!(formatter, ": {}", self.message)
+    }
+}
+
+// ========================================================================= //
+
+/// A validation rule from a .cub file that could not be run.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SkippedRule {
+    name: String,
+    reason: String,
+}
+
+impl SkippedRule {
+    /// Returns the name of the rule's action in the `_ICESequence` table (e.g.
+    /// `"ICE33"`).
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns a human-readable explanation of why the rule was skipped.
+    #[must_use]
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl fmt::Display for SkippedRule {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{} skipped: {}", self.name, self.reason)
+    }
+}
+
+/// The results of running the validation rules from a .cub file over a
+/// package.
+#[derive(Clone, Debug)]
+pub struct CubReport {
+    diagnostics: Vec<Diagnostic>,
+    executed: Vec<Rule>,
+    skipped: Vec<SkippedRule>,
+}
+
+impl CubReport {
+    /// Returns the problems found by the rules that were run.
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the rules that were run, in the order they were run.
+    #[must_use]
+    pub fn executed(&self) -> &[Rule] {
+        &self.executed
+    }
+
+    /// Returns the rules from the .cub file that could not be run.
+    #[must_use]
+    pub fn skipped(&self) -> &[SkippedRule] {
+        &self.skipped
+    }
+}
+
+// ========================================================================= //
+
+/// One row of the `_Validation` table.
+#[derive(Clone)]
+pub(crate) struct ValidationEntry {
+    nullable: bool,
+    min_value: Option<i32>,
+    max_value: Option<i32>,
+    key_table: Option<String>,
+    key_column: Option<i32>,
+    category: Option<String>,
+    set: Option<String>,
+}
+
+impl ValidationEntry {
+    fn from_values(table: &Table, values: &[Value]) -> Self {
+        Self {
+            nullable: column_value(table, values, "Nullable").as_str()
+                == Some("Y"),
+            min_value: column_value(table, values, "MinValue").as_int(),
+            max_value: column_value(table, values, "MaxValue").as_int(),
+            key_table: column_value(table, values, "KeyTable")
+                .as_str()
+                .map(str::to_string),
+            key_column: column_value(table, values, "KeyColumn").as_int(),
+            category: column_value(table, values, "Category")
+                .as_str()
+                .map(str::to_string),
+            set: column_value(table, values, "Set")
+                .as_str()
+                .map(str::to_string),
+        }
+    }
+}
+
+/// Returns the value in the named column of a row, or null if the table has
+/// no such column (which can happen for a nonstandard `_Validation` table in
+/// a package opened leniently).
+fn column_value<'a>(
+    table: &Table,
+    values: &'a [Value],
+    column_name: &str,
+) -> &'a Value {
+    table
+        .index_for_column_name(column_name)
+        .and_then(|index| values.get(index))
+        .unwrap_or(&Value::Null)
+}
+
+/// An in-memory snapshot of a package's tables, used for running validation
+/// rules.
+pub(crate) struct Database {
+    tables: BTreeMap<String, (Table, Vec<Vec<Value>>)>,
+    validation: HashMap<(String, String), ValidationEntry>,
+    has_validation_table: bool,
+    // Tables that were merged in from a .cub file, rather than coming from the
+    // package being validated; we don't report problems in these tables.
+    merged_tables: HashSet<String>,
+}
+
+impl Database {
+    pub(crate) fn load<F: Read + Seek>(
+        package: &mut Package<F>,
+    ) -> io::Result<Self> {
+        let tables: Vec<Table> = package
+            .tables()
+            .filter(|table| !SCHEMA_TABLE_NAMES.contains(&table.name()))
+            .cloned()
+            .collect();
+        let mut loaded = BTreeMap::new();
+        for table in tables {
+            let rows: Vec<Vec<Value>> = package
+                .select_rows(Select::table(table.name()))?
+                .map(|row| (0..row.len()).map(|i| row[i].clone()).collect())
+                .collect();
+            loaded.insert(table.name().to_string(), (table, rows));
+        }
+        let mut validation = HashMap::new();
+        let has_validation_table = loaded.contains_key(VALIDATION_TABLE_NAME);
+        if let Some((table, rows)) = loaded.get(VALIDATION_TABLE_NAME) {
+            for values in rows {
+                if let (Some(table_name), Some(column_name)) = (
+                    column_value(table, values, "Table").as_str(),
+                    column_value(table, values, "Column").as_str(),
+                ) {
+                    let key =
+                        (table_name.to_string(), column_name.to_string());
+                    validation.insert(
+                        key,
+                        ValidationEntry::from_values(table, values),
+                    );
+                }
+            }
+        }
+        Ok(Self {
+            tables: loaded,
+            validation,
+            has_validation_table,
+            merged_tables: HashSet::new(),
+        })
+    }
+
+    /// Merges another database (typically loaded from a .cub file) into this
+    /// one.  Tables and `_Validation` entries that already exist in this
+    /// database take precedence over those in the other database.
+    fn merge(&mut self, other: Self) {
+        for (name, entry) in other.tables {
+            if !self.tables.contains_key(&name) {
+                self.merged_tables.insert(name.clone());
+                self.tables.insert(name, entry);
+            }
+        }
+        for (key, entry) in other.validation {
+            self.validation.entry(key).or_insert(entry);
+        }
+        self.has_validation_table |= other.has_validation_table;
+    }
+
+    /// Returns the names and values from the `Property` table.
+    fn properties(&self) -> HashMap<String, String> {
+        let names = self.column_values(PROPERTY_TABLE_NAME, "Property");
+        let values = self.column_values(PROPERTY_TABLE_NAME, "Value");
+        names
+            .into_iter()
+            .zip(values)
+            .filter_map(|(name, value)| match (name, value) {
+                (Value::Str(name), Value::Str(value)) => Some((name, value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn table(&self, name: &str) -> Option<&(Table, Vec<Vec<Value>>)> {
+        self.tables.get(name)
+    }
+
+    fn column_values(
+        &self,
+        table_name: &str,
+        column_name: &str,
+    ) -> Vec<Value> {
+        match self.tables.get(table_name) {
+            Some((table, rows)) => match table
+                .index_for_column_name(column_name)
+            {
+                Some(index) => {
+                    rows.iter().map(|values| values[index].clone()).collect()
+                }
+                None => Vec::new(),
+            },
+            None => Vec::new(),
+        }
+    }
+
+    fn key_set(
+        &self,
+        table_name: &str,
+        column_index: usize,
+    ) -> HashSet<Value> {
+        match self.tables.get(table_name) {
+            Some((_, rows)) => rows
+                .iter()
+                .filter_map(|values| values.get(column_index).cloned())
+                .collect(),
+            None => HashSet::new(),
+        }
+    }
+}
+
+/// Runs the given validation rules over the database.
+pub(crate) fn run_rules(
+    database: &Database,
+    rules: &[Rule],
+) -> Vec<Diagnostic> {
+    let mut output = Vec::new();
+    for &rule in rules {
+        rule.run(database, &mut output);
+    }
+    output.retain(|diagnostic| match diagnostic.table {
+        Some(ref table) => !database.merged_tables.contains(table),
+        None => true,
+    });
+    output
+}
+
+/// Merges the tables from a .cub file into the database, then goes through the
+/// actions listed in the .cub file's `_ICESequence` table (in sequence
+/// order), evaluating each one's condition against the merged `Property`
+/// table.  Actions whose condition holds and that name a built-in `Rule` are
+/// run; all others (including any that would need a custom action from the
+/// .cub file to be executed) are reported as skipped.
+pub(crate) fn run_rules_from_cub(
+    mut database: Database,
+    cub: Database,
+) -> CubReport {
+    let mut actions: Vec<(i32, String, Option<String>)> = Vec::new();
+    if let Some((table, rows)) = cub.table(ICE_SEQUENCE_TABLE_NAME) {
+        let action_index = table.index_for_column_name("Action");
+        let condition_index = table.index_for_column_name("Condition");
+        let sequence_index = table.index_for_column_name("Sequence");
+        for values in rows {
+            let action = match action_index.and_then(|i| values[i].as_str()) {
+                Some(action) => action.to_string(),
+                None => continue,
+            };
+            let condition = condition_index
+                .and_then(|i| values[i].as_str())
+                .filter(|condition| !condition.trim().is_empty())
+                .map(str::to_string);
+            let sequence =
+                sequence_index.and_then(|i| values[i].as_int()).unwrap_or(0);
+            actions.push((sequence, action, condition));
+        }
+    }
+    actions.sort();
+    let custom_action_types: HashMap<String, i32> =
+        match cub.table(CUSTOM_ACTION_TABLE_NAME) {
+            Some((table, rows)) => {
+                match (
+                    table.index_for_column_name("Action"),
+                    table.index_for_column_name("Type"),
+                ) {
+                    (Some(a), Some(t)) => rows
+                        .iter()
+                        .filter_map(|values| {
+                            Some((
+                                values[a].as_str()?.to_string(),
+                                values[t].as_int()?,
+                            ))
+                        })
+                        .collect(),
+                    _ => HashMap::new(),
+                }
+            }
+            None => HashMap::new(),
+        };
+    database.merge(cub);
+    let properties = database.properties();
+    let property_value = |name: &str| match properties.get(name) {
+        Some(value) => match value.parse::<i32>() {
+            Ok(number) => Value::Int(number),
+            Err(_) => Value::Str(value.clone()),
+        },
+        None => Value::Str(String::new()),
+    };
+    let mut report = CubReport {
+        diagnostics: Vec::new(),
+        executed: Vec::new(),
+        skipped: Vec::new(),
+    };
+    for (_, action, condition) in actions {
+        if let Some(condition) = condition {
+            let reason = match parse_condition(&condition) {
+                Ok(expr) => {
+                    if expr.eval_with(property_value).to_bool() {
+                        None
+                    } else {
+                        Some(format!("condition {condition:?} is false"))
+                    }
+                }
+                Err(error) => Some(format!(
+                    "condition {condition:?} can't be evaluated: {error}"
+                )),
+            };
+            if let Some(reason) = reason {
+                report.skipped.push(SkippedRule { name: action, reason });
+                continue;
+            }
+        }
+        let reason = if let Ok(rule) = action.parse::<Rule>() {
+            if !report.executed.contains(&rule) {
+                report.executed.push(rule);
+                report.diagnostics.extend(run_rules(&database, &[rule]));
+            }
+            continue;
+        } else {
+            match custom_action_types
+                .get(&action)
+                .map(|&ty| ty & CUSTOM_ACTION_BASE_TYPE_MASK)
+            {
+                Some(1) => "requires running a DLL custom action",
+                Some(2) => "requires running an EXE custom action",
+                Some(5) => "requires running a JScript custom action",
+                Some(6) => "requires running a VBScript custom action",
+                _ => "no built-in implementation of this rule",
+            }
+            .to_string()
+        };
+        report.skipped.push(SkippedRule { name: action, reason });
+    }
+    report
+}
+
+// ========================================================================= //
+
+/// A token of a conditional expression.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Identifier(String),
+    Integer(i32),
+    Str(String),
+    Operator(&'static str),
+    LeftParen,
+    RightParen,
+}
+
+/// Operators, longest first so that e.g. `<=` isn't lexed as `<` then `=`.
+const CONDITION_OPERATORS: &[&str] = &[
+    "~><", "~<<", "~>>", "~<>", "~<=", "~>=", "~=", "~<", "~>", "><", "<<",
+    ">>", "<>", "<=", ">=", "=", "<", ">", "-",
+];
+
+fn lex_condition(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(chr) = rest.chars().next() {
+        if chr.is_whitespace() {
+            rest = &rest[chr.len_utf8()..];
+        } else if chr == '(' || chr == ')' {
+            tokens.push(if chr == '(' {
+                Token::LeftParen
+            } else {
+                Token::RightParen
+            });
+            rest = &rest[1..];
+        } else if chr == '"' {
+            let end = match rest[1..].find('"') {
+                Some(end) => end + 1,
+                None => return Err("unterminated string".to_string()),
+            };
+            tokens.push(Token::Str(rest[1..end].to_string()));
+            rest = &rest[end + 1..];
+        } else if chr.is_ascii_digit() {
+            let end = rest
+                .find(|chr: char| !chr.is_ascii_digit())
+                .unwrap_or(rest.len());
+            match rest[..end].parse::<i32>() {
+                Ok(number) => tokens.push(Token::Integer(number)),
+                Err(_) => {
+                    return Err(format!(
+                        "integer {} is too large",
+                        &rest[..end]
+                    ));
+                }
+            }
+            rest = &rest[end..];
+        } else if chr.is_ascii_alphabetic() || chr == '_' {
+            let end = rest
+                .find(|chr: char| {
+                    !(chr.is_ascii_alphanumeric() || chr == '_' || chr == '.')
+                })
+                .unwrap_or(rest.len());
+            tokens.push(Token::Identifier(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if "%$?&!".contains(chr) {
+            return Err(format!(
+                "{chr:?} references (to environment variables or component \
+                 or feature states) are not supported"
+            ));
+        } else {
+            match CONDITION_OPERATORS.iter().find(|&&op| rest.starts_with(op))
+            {
+                Some(&op) => {
+                    tokens.push(Token::Operator(op));
+                    rest = &rest[op.len()..];
+                }
+                None => return Err(format!("unexpected character {chr:?}")),
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses a Windows Installer conditional expression (as found in the
+/// `Condition` column of `_ICESequence`) into an `Expr`, in which each
+/// property is referred to as a column with the property's name.  Returns an
+/// error message for syntax that can't be evaluated outside of an
+/// installation, such as component and feature states.
+fn parse_condition(text: &str) -> Result<Expr, String> {
+    let tokens = lex_condition(text)?;
+    let mut parser = ConditionParser { tokens: &tokens, position: 0 };
+    let expr = parser.parse_imp()?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(expr),
+        Some(token) => Err(format!("unexpected {token:?}")),
+    }
+}
+
+struct ConditionParser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl ConditionParser<'_> {
+    fn next_is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.position),
+                 Some(Token::Identifier(name))
+                     if name.eq_ignore_ascii_case(keyword))
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    // Logical operators, from lowest to highest precedence: IMP, EQV, XOR,
+    // OR, AND, NOT.
+
+    fn parse_imp(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_eqv()?;
+        while self.next_is_keyword("IMP") {
+            self.position += 1;
+            expr = expr.not().or(self.parse_eqv()?);
+        }
+        Ok(expr)
+    }
+
+    fn parse_eqv(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_xor()?;
+        while self.next_is_keyword("EQV") {
+            self.position += 1;
+            let rhs = self.parse_xor()?;
+            expr =
+                Expr::boolean(true).and(expr).eq(Expr::boolean(true).and(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn parse_xor(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_or()?;
+        while self.next_is_keyword("XOR") {
+            self.position += 1;
+            let rhs = self.parse_or()?;
+            expr =
+                Expr::boolean(true).and(expr).ne(Expr::boolean(true).and(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_and()?;
+        while self.next_is_keyword("OR") {
+            self.position += 1;
+            expr = expr.or(self.parse_and()?);
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_not()?;
+        while self.next_is_keyword("AND") {
+            self.position += 1;
+            expr = expr.and(self.parse_not()?);
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.next_is_keyword("NOT") {
+            self.position += 1;
+            return Ok(self.parse_not()?.not());
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        let (lhs, is_property) = self.parse_term()?;
+        let op = match self.tokens.get(self.position) {
+            Some(&Token::Operator(op)) if op != "-" => op,
+            // On its own, a property is true if it has any value at all
+            // (even zero).
+            _ if is_property => return Ok(lhs.ne(Expr::string(""))),
+            _ => return Ok(lhs),
+        };
+        self.position += 1;
+        let (rhs, _) = self.parse_term()?;
+        Ok(match op {
+            "=" => lhs.eq(rhs),
+            "<>" => lhs.ne(rhs),
+            "<" => lhs.lt(rhs),
+            "<=" => lhs.le(rhs),
+            ">" => lhs.gt(rhs),
+            ">=" => lhs.ge(rhs),
+            _ => return Err(format!("operator {op:?} is not supported")),
+        })
+    }
+
+    /// Parses a value, and returns whether it is a property reference.
+    fn parse_term(&mut self) -> Result<(Expr, bool), String> {
+        match self.next() {
+            Some(Token::LeftParen) => {
+                let expr = self.parse_imp()?;
+                match self.next() {
+                    Some(Token::RightParen) => Ok((expr, false)),
+                    _ => Err("missing closing parenthesis".to_string()),
+                }
+            }
+            Some(Token::Operator("-")) => match self.next() {
+                Some(&Token::Integer(number)) => {
+                    Ok((Expr::integer(-number), false))
+                }
+                _ => Err("expected an integer after \"-\"".to_string()),
+            },
+            Some(&Token::Integer(number)) => {
+                Ok((Expr::integer(number), false))
+            }
+            Some(Token::Str(string)) => {
+                Ok((Expr::string(string.as_str()), false))
+            }
+            Some(Token::Identifier(name)) => {
+                Ok((Expr::col(name.as_str()), true))
+            }
+            Some(token) => Err(format!("unexpected {token:?}")),
+            None => Err("unexpected end of condition".to_string()),
+        }
+    }
+}
+
+// ========================================================================= //
+
+fn check_key_paths(database: &Database, output: &mut Vec<Diagnostic>) {
+    let (table, rows) = match database.table(COMPONENT_TABLE_NAME) {
+        Some(entry) => entry,
+        None => return,
+    };
+    let (component_index, attributes_index, key_path_index) = match (
+        table.index_for_column_name("Component"),
+        table.index_for_column_name("Attributes"),
+        table.index_for_column_name("KeyPath"),
+    ) {
+        (Some(c), Some(a), Some(k)) => (c, a, k),
+        _ => return,
+    };
+    for values in rows {
+        let key_path = match values[key_path_index].as_str() {
+            Some(key_path) => key_path,
+            None => continue,
+        };
+        let component = &values[component_index];
+        let attributes = values[attributes_index].as_int().unwrap_or(0);
+        let (key_table_name, key_column) =
+            if (attributes & COMPONENT_REGISTRY_KEY_PATH_BIT) != 0 {
+                (REGISTRY_TABLE_NAME, "Registry")
+            } else if (attributes & COMPONENT_ODBC_DATA_SOURCE_BIT) != 0 {
+                (ODBC_DATA_SOURCE_TABLE_NAME, "DataSource")
+            } else {
+                (FILE_TABLE_NAME, "File")
+            };
+        let owner = database.table(key_table_name).and_then(|(t, rows)| {
+            let key_index = t.index_for_column_name(key_column)?;
+            let owner_index = t.index_for_column_name("Component_")?;
+            rows.iter()
+                .find(|row| row[key_index].as_str() == Some(key_path))
+                .map(|row| row[owner_index].clone())
+        });
+        let diagnostic = match owner {
+            None => Diagnostic::new(
+                Rule::Ice02,
+                Severity::Error,
+                format!(
+                    "KeyPath refers to {key_table_name} entry {key_path:?}, \
+                     which does not exist"
+                ),
+            ),
+            Some(ref owner) if owner != component => Diagnostic::new(
+                Rule::Ice02,
+                Severity::Error,
+                format!(
+                    "KeyPath refers to {key_table_name} entry {key_path:?}, \
+                     which belongs to component {owner}"
+                ),
+            ),
+            Some(_) => continue,
+        };
+        output.push(
+            diagnostic
+                .in_table(COMPONENT_TABLE_NAME)
+                .in_column("KeyPath")
+                .in_row(table, values),
+        );
+    }
+}
+
+fn check_validation_data(database: &Database, output: &mut Vec<Diagnostic>) {
+    let mut key_sets = HashMap::<(String, usize), HashSet<Value>>::new();
+    for (table_name, (table, rows)) in &database.tables {
+        for (index, column) in table.columns().iter().enumerate() {
+            let key = (table_name.clone(), column.name().to_string());
+            let entry = match database.validation.get(&key) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let category = match entry.category {
+                Some(ref name) => match name.parse::<Category>() {
+                    Ok(category) => Some(category),
+                    Err(_) => {
+                        output.push(
+                            Diagnostic::new(
+                                Rule::Ice03,
+                                Severity::Warning,
+                                format!("Unknown category {name:?}"),
+                            )
+                            .in_table(table_name)
+                            .in_column(column.name()),
+                        );
+                        None
+                    }
+                },
+                None => None,
+            };
+            let set: Option<Vec<&str>> =
+                entry.set.as_ref().map(|set| set.split(';').collect());
+            let key_tables: Vec<&str> = match entry.key_table {
+                Some(ref key_table) => key_table.split(';').collect(),
+                None => Vec::new(),
+            };
+            for values in rows {
+                let value = &values[index];
+                let problem = match *value {
+                    Value::Null => {
+                        if entry.nullable {
+                            None
+                        } else {
+                            Some("Not a nullable column".to_string())
+                        }
+                    }
+                    Value::Binary => None,
+                    Value::Int(number) => {
+                        if entry.min_value.is_some_and(|min| number < min)
+                            || entry.max_value.is_some_and(|max| number > max)
+                        {
+                            Some(format!("Value {number} is out of range"))
+                        } else {
+                            None
+                        }
+                    }
+                    Value::Str(ref string) => {
+                        if let ColumnType::Str(max_len) = column.coltype() {
+                            if max_len > 0 && string.chars().count() > max_len
+                            {
+                                Some(format!(
+                                    "String {string:?} overflows column \
+                                     (max length {max_len})"
+                                ))
+                            } else if category.is_some_and(|category| {
+                                !category.validate(string)
+                            }) {
+                                Some(format!(
+                                    "Value {:?} is not a valid {}",
+                                    string,
+                                    category.unwrap()
+                                ))
+                            } else if set.as_ref().is_some_and(|set| {
+                                !set.contains(&string.as_str())
+                            }) {
+                                Some(format!(
+                                    "Value {string:?} is not a member of the \
+                                     column's set"
+                                ))
+                            } else {
+                                None
+                            }
+                        } else {
+                            None
+                        }
+                    }
+                };
+                let problem = problem.or_else(|| {
+                    if value.is_null() || key_tables.is_empty() {
+                        return None;
+                    }
+                    let key_column = entry.key_column.unwrap_or(1);
+                    if key_column < 1 {
+                        return None;
+                    }
+                    let key_index = (key_column - 1) as usize;
+                    let mut any_table = false;
+                    for key_table in &key_tables {
+                        if database.table(key_table).is_none() {
+                            continue;
+                        }
+                        any_table = true;
+                        let set = key_sets
+                            .entry((key_table.to_string(), key_index))
+                            .or_insert_with(|| {
+                                database.key_set(key_table, key_index)
+                            });
+                        if set.contains(value) {
+                            return None;
+                        }
+                    }
+                    if any_table {
+                        Some(format!(
+                            "Value {value} is not a valid foreign key into {}",
+                            key_tables.join(";")
+                        ))
+                    } else {
+                        Some(format!(
+                            "Value {value} refers to table {}, which does not \
+                             exist",
+                            key_tables.join(";")
+                        ))
+                    }
+                });
+                if let Some(message) = problem {
+                    output.push(
+                        Diagnostic::new(Rule::Ice03, Severity::Error, message)
+                            .in_table(table_name)
+                            .in_column(column.name())
+                            .in_row(table, values),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn check_missing_validation(
+    database: &Database,
+    output: &mut Vec<Diagnostic>,
+) {
+    if !database.has_validation_table {
+        output.push(Diagnostic::new(
+            Rule::Ice06,
+            Severity::Error,
+            "Database has no _Validation table",
+        ));
+        return;
+    }
+    for (table_name, (table, _)) in &database.tables {
+        for column in table.columns() {
+            let key = (table_name.clone(), column.name().to_string());
+            if !database.validation.contains_key(&key) {
+                output.push(
+                    Diagnostic::new(
+                        Rule::Ice06,
+                        Severity::Error,
+                        "Column is missing from the _Validation table",
+                    )
+                    .in_table(table_name)
+                    .in_column(column.name()),
+                );
+            }
+        }
+    }
+}
+
+fn check_duplicate_component_guids(
+    database: &Database,
+    output: &mut Vec<Diagnostic>,
+) {
+    let (table, rows) = match database.table(COMPONENT_TABLE_NAME) {
+        Some(entry) => entry,
+        None => return,
+    };
+    let (component_index, guid_index) = match (
+        table.index_for_column_name("Component"),
+        table.index_for_column_name("ComponentId"),
+    ) {
+        (Some(c), Some(g)) => (c, g),
+        _ => return,
+    };
+    let mut seen = HashMap::<String, &Value>::new();
+    for values in rows {
+        let guid = match values[guid_index].as_str() {
+            Some(guid) => guid.to_ascii_uppercase(),
+            None => continue,
+        };
+        if let Some(other) = seen.get(&guid) {
+            output.push(
+                Diagnostic::new(
+                    Rule::Ice08,
+                    Severity::Error,
+                    format!(
+                        "Component GUID {guid} is also used by component \
+                         {other}"
+                    ),
+                )
+                .in_table(COMPONENT_TABLE_NAME)
+                .in_column("ComponentId")
+                .in_row(table, values),
+            );
+        } else {
+            seen.insert(guid, &values[component_index]);
+        }
+    }
+}
+
+fn check_sequences(database: &Database, output: &mut Vec<Diagnostic>) {
+    let custom_actions: HashSet<Value> = database
+        .column_values(CUSTOM_ACTION_TABLE_NAME, "Action")
+        .into_iter()
+        .collect();
+    let dialogs: HashSet<Value> = database
+        .column_values(DIALOG_TABLE_NAME, "Dialog")
+        .into_iter()
+        .collect();
+    let table_names = UI_SEQUENCE_TABLE_NAMES
+        .iter()
+        .map(|&name| (name, false))
+        .chain(EXECUTE_SEQUENCE_TABLE_NAMES.iter().map(|&name| (name, true)));
+    for (table_name, is_execute) in table_names {
+        let (table, rows) = match database.table(table_name) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let (action_index, sequence_index) = match (
+            table.index_for_column_name("Action"),
+            table.index_for_column_name("Sequence"),
+        ) {
+            (Some(a), Some(s)) => (a, s),
+            _ => continue,
+        };
+        let mut sequences = HashMap::<&str, (i32, &Vec<Value>)>::new();
+        let mut by_number = BTreeMap::<i32, &str>::new();
+        for values in rows {
+            let action = match values[action_index].as_str() {
+                Some(action) => action,
+                None => continue,
+            };
+            let known = STANDARD_ACTIONS.contains(&action)
+                || custom_actions.contains(&values[action_index])
+                || (!is_execute && dialogs.contains(&values[action_index]));
+            if !known {
+                output.push(
+                    Diagnostic::new(
+                        Rule::Ice27,
+                        Severity::Error,
+                        format!(
+                            "Unknown action {action:?}; it is not a standard \
+                             action, custom action{}",
+                            if is_execute { "" } else { ", or dialog" }
+                        ),
+                    )
+                    .in_table(table_name)
+                    .in_column("Action")
+                    .in_row(table, values),
+                );
+            }
+            if let Some(number) = values[sequence_index].as_int() {
+                if number > 0 {
+                    if let Some(other) = by_number.insert(number, action) {
+                        output.push(
+                            Diagnostic::new(
+                                Rule::Ice27,
+                                Severity::Warning,
+                                format!(
+                                    "Sequence number {number} is shared with \
+                                     action {other:?}"
+                                ),
+                            )
+                            .in_table(table_name)
+                            .in_column("Sequence")
+                            .in_row(table, values),
+                        );
+                    }
+                    sequences.insert(action, (number, values));
+                }
+            }
+        }
+        for &(before, after) in ORDERED_ACTIONS {
+            if let (Some(&(first, _)), Some(&(second, values))) =
+                (sequences.get(before), sequences.get(after))
+            {
+                if first >= second {
+                    output.push(
+                        Diagnostic::new(
+                            Rule::Ice27,
+                            Severity::Error,
+                            format!(
+                                "Action {after:?} (sequence {second}) must \
+                                 come after {before:?} (sequence {first})"
+                            ),
+                        )
+                        .in_table(table_name)
+                        .in_column("Sequence")
+                        .in_row(table, values),
+                    );
+                }
+            }
+        }
+        if is_execute {
+            let start = sequences.get("InstallInitialize").map(|&(n, _)| n);
+            let end = sequences.get("InstallFinalize").map(|&(n, _)| n);
+            for &action in SCRIPT_ACTIONS {
+                let (number, values) = match sequences.get(action) {
+                    Some(&entry) => entry,
+                    None => continue,
+                };
+                let in_script = start.is_some_and(|start| number > start)
+                    && end.is_some_and(|end| number < end);
+                if !in_script {
+                    output.push(
+                        Diagnostic::new(
+                            Rule::Ice27,
+                            Severity::Error,
+                            format!(
+                                "Action {action:?} must be sequenced between \
+                                 InstallInitialize and InstallFinalize"
+                            ),
+                        )
+                        .in_table(table_name)
+                        .in_column("Sequence")
+                        .in_row(table, values),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn check_directory_tree(database: &Database, output: &mut Vec<Diagnostic>) {
+    let directories = match database.table(DIRECTORY_TABLE_NAME) {
+        Some((table, rows)) => {
+            let (dir_index, parent_index) = match (
+                table.index_for_column_name("Directory"),
+                table.index_for_column_name("Directory_Parent"),
+            ) {
+                (Some(d), Some(p)) => (d, p),
+                _ => return,
+            };
+            let mut parents = HashMap::<&str, Option<&str>>::new();
+            for values in rows {
+                if let Some(dir) = values[dir_index].as_str() {
+                    let parent = values[parent_index]
+                        .as_str()
+                        .filter(|&parent| parent != dir);
+                    parents.insert(dir, parent);
+                }
+            }
+            for values in rows {
+                let dir = match values[dir_index].as_str() {
+                    Some(dir) => dir,
+                    None => continue,
+                };
+                if let Some(parent) = parents[dir] {
+                    if !parents.contains_key(parent) {
+                        output.push(
+                            Diagnostic::new(
+                                Rule::DirectoryTree,
+                                Severity::Error,
+                                format!(
+                                    "Parent directory {parent:?} does not \
+                                     exist"
+                                ),
+                            )
+                            .in_table(DIRECTORY_TABLE_NAME)
+                            .in_column("Directory_Parent")
+                            .in_row(table, values),
+                        );
+                        continue;
+                    }
+                }
+                // Walk up the parent chain.  If it gets back to this
+                // directory, the directory is on a cycle; if it takes more
+                // steps than there are directories without doing so, the
+                // chain leads into a cycle further up.
+                let mut current = dir;
+                let mut steps = 0;
+                while let Some(Some(parent)) = parents.get(current) {
+                    current = parent;
+                    steps += 1;
+                    let message = if current == dir {
+                        "Directory is part of a cycle"
+                    } else if steps > parents.len() {
+                        "Directory's parent chain leads into a cycle"
+                    } else {
+                        continue;
+                    };
+                    output.push(
+                        Diagnostic::new(
+                            Rule::DirectoryTree,
+                            Severity::Error,
+                            message,
+                        )
+                        .in_table(DIRECTORY_TABLE_NAME)
+                        .in_column("Directory_Parent")
+                        .in_row(table, values),
+                    );
+                    break;
+                }
+            }
+            parents.keys().map(|&dir| dir.to_string()).collect()
+        }
+        None => HashSet::<String>::new(),
+    };
+    for (table_name, column_name) in [
+        (COMPONENT_TABLE_NAME, "Directory_"),
+        (CREATE_FOLDER_TABLE_NAME, "Directory_"),
+    ] {
+        let (table, rows) = match database.table(table_name) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let index = match table.index_for_column_name(column_name) {
+            Some(index) => index,
+            None => continue,
+        };
+        for values in rows {
+            if let Some(dir) = values[index].as_str() {
+                if !directories.contains(dir) {
+                    output.push(
+                        Diagnostic::new(
+                            Rule::DirectoryTree,
+                            Severity::Error,
+                            format!("Directory {dir:?} does not exist"),
+                        )
+                        .in_table(table_name)
+                        .in_column(column_name)
+                        .in_row(table, values),
+                    );
+                }
+            }
+        }
+    }
+}
+
+// ========================================================================= //
+
+#[cfg(test)]
+mod tests {
+    use super::{Diagnostic, Rule, Severity, parse_condition};
+    use crate::internal::value::Value;
+
+    #[test]
+    fn rule_name_round_trip() {
+        for rule in Rule::all() {
+            assert_eq!(rule.to_string().parse::<Rule>().unwrap(), rule);
+        }
+        assert_eq!("ice03".parse::<Rule>().unwrap(), Rule::Ice03);
+        assert!("ICE99".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn evaluate_conditions() {
+        let eval = |condition: &str| {
+            parse_condition(condition).map(|expr| {
+                expr.eval_with(|name| match name {
+                    "Level" => Value::Int(3),
+                    "Zero" => Value::Int(0),
+                    "Name" => Value::from("Widget"),
+                    _ => Value::from(""),
+                })
+                .to_bool()
+            })
+        };
+        assert_eq!(eval("Level"), Ok(true));
+        assert_eq!(eval("Zero"), Ok(true));
+        assert_eq!(eval("Missing"), Ok(false));
+        assert_eq!(eval("not Missing"), Ok(true));
+        assert_eq!(eval("Level >= 3 AND Name = \"Widget\""), Ok(true));
+        assert_eq!(eval("Level < -1 OR Missing"), Ok(false));
+        assert_eq!(eval("NOT (Level <> 3)"), Ok(true));
+        assert_eq!(eval("Level XOR Name"), Ok(false));
+        assert_eq!(eval("Missing EQV Zero = 1"), Ok(true));
+        assert_eq!(eval("Level IMP Missing"), Ok(false));
+        assert_eq!(
+            eval("$Comp = 3"),
+            Err("'$' references (to environment variables or component or \
+                 feature states) are not supported"
+                .to_string())
+        );
+        assert_eq!(
+            eval("Name >< \"idg\""),
+            Err("operator \"><\" is not supported".to_string())
+        );
+        assert_eq!(
+            eval("(Level = 3"),
+            Err("missing closing parenthesis".to_string())
+        );
+        assert!(eval("Level Name").is_err());
+    }
+
+    #[test]
+    fn display_diagnostic() {
+        let diagnostic =
+            Diagnostic::new(Rule::Ice03, Severity::Error, "Bad value")
+                .in_table("File")
+                .in_column("FileSize");
+        assert_eq!(
+            diagnostic.to_string(),
+            "ICE03 error in File.FileSize: Bad value"
+        );
+        let mut diagnostic = diagnostic;
+        diagnostic.primary_key = vec![Value::from("foo.txt")];
+        assert_eq!(
+            diagnostic.to_string(),
+            "ICE03 error in File.FileSize [\"foo.txt\"]: Bad value"
+        );
+    }
+}
+
+// ========================================================================= //
@@ -2,6 +2,7 @@ use crate::internal::language::LanguageId;
 use crate::internal::stringpool::{StringPool, StringRef};
 use std::convert::From;
 use std::fmt;
+use std::io;
 use uuid::Uuid;
 
 // ========================================================================= //
@@ -149,6 +150,115 @@ impl From<Uuid> for Value {
     }
 }
 
+/// Null values serialize as none (e.g. `null` in JSON), integers as `i32`, and
+/// strings as strings.  Binary values serialize as an empty byte array (e.g.
+/// `[]` in JSON), since the stream's data isn't stored in the row itself.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Value {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match *self {
+            Self::Null => serializer.serialize_none(),
+            Self::Int(number) => serializer.serialize_i32(number),
+            Self::Str(ref string) => serializer.serialize_str(string),
+            Self::Binary => serializer.serialize_bytes(&[]),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Value {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+struct ValueVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("null, a 32-bit integer, a string, or []")
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, number: i64) -> Result<Value, E> {
+        match i32::try_from(number) {
+            Ok(number) => Ok(Value::Int(number)),
+            Err(_) => Err(E::invalid_value(
+                serde::de::Unexpected::Signed(number),
+                &self,
+            )),
+        }
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, number: u64) -> Result<Value, E> {
+        match i32::try_from(number) {
+            Ok(number) => Ok(Value::Int(number)),
+            Err(_) => Err(E::invalid_value(
+                serde::de::Unexpected::Unsigned(number),
+                &self,
+            )),
+        }
+    }
+
+    fn visit_str<E: serde::de::Error>(self, string: &str) -> Result<Value, E> {
+        Ok(Value::Str(string.to_string()))
+    }
+
+    fn visit_string<E: serde::de::Error>(
+        self,
+        string: String,
+    ) -> Result<Value, E> {
+        Ok(Value::Str(string))
+    }
+
+    fn visit_bytes<E: serde::de::Error>(
+        self,
+        bytes: &[u8],
+    ) -> Result<Value, E> {
+        if bytes.is_empty() {
+            Ok(Value::Binary)
+        } else {
+            Err(E::invalid_value(serde::de::Unexpected::Bytes(bytes), &self))
+        }
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> Result<Value, A::Error> {
+        match seq.next_element::<serde::de::IgnoredAny>()? {
+            None => Ok(Value::Binary),
+            Some(_) => Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Seq,
+                &self,
+            )),
+        }
+    }
+}
+
 // ========================================================================= //
 
 /// An indirect value from one cell in a database table row.
@@ -167,21 +277,62 @@ pub enum ValueRef {
 impl ValueRef {
     /// Interns the given value into the string pool (if it is a string), and
     /// returns a corresponding `ValueRef`.
-    pub fn create(value: Value, string_pool: &mut StringPool) -> Self {
-        match value {
+    pub fn create(
+        value: Value,
+        string_pool: &mut StringPool,
+    ) -> io::Result<Self> {
+        Ok(match value {
             Value::Null => Self::Null,
             Value::Int(number) => Self::Int(number),
-            Value::Str(string) => Self::Str(string_pool.incref(string)),
+            Value::Str(string) => Self::Str(string_pool.incref(string)?),
             Value::Binary => Self::Binary,
-        }
+        })
     }
 
-    /// Removes the reference from the string pool (if is a string reference).
-    pub fn remove(self, string_pool: &mut StringPool) {
-        match self {
-            Self::Null | Self::Int(_) | Self::Binary => {}
-            Self::Str(string_ref) => string_pool.decref(string_ref),
+    /// Interns each of the given values into the string pool, as with
+    /// `create()`.  If any of them fails, the values interned so far are
+    /// removed again before returning the error, leaving the string pool
+    /// unchanged.
+    pub(crate) fn create_all<I>(
+        values: I,
+        string_pool: &mut StringPool,
+    ) -> io::Result<Vec<ValueRef>>
+    where
+        I: IntoIterator<Item = Value>,
+    {
+        let mut value_refs = Vec::new();
+        for value in values {
+            match ValueRef::create(value, string_pool) {
+                Ok(value_ref) => value_refs.push(value_ref),
+                Err(error) => {
+                    // These references were only just created, so they can't
+                    // be invalid.
+                    ValueRef::remove_all(&value_refs, string_pool).unwrap();
+                    return Err(error);
+                }
+            }
         }
+        Ok(value_refs)
+    }
+
+    /// Removes the given references from the string pool (for those that are
+    /// string references).  Returns an error without changing the string pool
+    /// if any of the references are invalid.
+    pub fn remove_all<'a, I>(
+        value_refs: I,
+        string_pool: &mut StringPool,
+    ) -> io::Result<()>
+    where
+        I: IntoIterator<Item = &'a ValueRef>,
+    {
+        let string_refs: Vec<StringRef> = value_refs
+            .into_iter()
+            .filter_map(|value_ref| match *value_ref {
+                Self::Str(string_ref) => Some(string_ref),
+                Self::Null | Self::Int(_) | Self::Binary => None,
+            })
+            .collect();
+        string_pool.decref_all(&string_refs)
     }
 
     /// Dereferences the `ValueRef` into a `Value`.
@@ -204,7 +355,7 @@ mod tests {
     use super::{Value, ValueRef};
     use crate::internal::codepage::CodePage;
     use crate::internal::language::LanguageId;
-    use crate::internal::stringpool::StringPool;
+    use crate::internal::stringpool::{MAX_SHORT_STRING_REFS, StringPool};
     use uuid::Uuid;
 
     #[test]
@@ -262,17 +413,37 @@ mod tests {
         let mut string_pool = StringPool::new(CodePage::default());
 
         let value = Value::Null;
-        let value_ref = ValueRef::create(value.clone(), &mut string_pool);
+        let value_ref =
+            ValueRef::create(value.clone(), &mut string_pool).unwrap();
         assert_eq!(value_ref.to_value(&string_pool), value);
 
         let value = Value::Int(1234567);
-        let value_ref = ValueRef::create(value.clone(), &mut string_pool);
+        let value_ref =
+            ValueRef::create(value.clone(), &mut string_pool).unwrap();
         assert_eq!(value_ref.to_value(&string_pool), value);
 
         let value = Value::Str("Hello, world!".to_string());
-        let value_ref = ValueRef::create(value.clone(), &mut string_pool);
+        let value_ref =
+            ValueRef::create(value.clone(), &mut string_pool).unwrap();
         assert_eq!(value_ref.to_value(&string_pool), value);
     }
+
+    #[test]
+    fn create_all_value_refs_fails_cleanly() {
+        let mut string_pool = StringPool::new(CodePage::default());
+        let first = string_pool.incref("s0".to_string()).unwrap();
+        for index in 1..MAX_SHORT_STRING_REFS {
+            string_pool.incref(format!("s{}", index)).unwrap();
+        }
+        let values = vec![
+            Value::Int(1),
+            Value::from("s0"),
+            Value::from("one too many"),
+        ];
+        assert!(ValueRef::create_all(values, &mut string_pool).is_err());
+        assert_eq!(string_pool.refcount(first), 1);
+        assert_eq!(string_pool.num_strings(), MAX_SHORT_STRING_REFS as u32);
+    }
 }
 
 // ========================================================================= //
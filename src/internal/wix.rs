@@ -0,0 +1,975 @@
+use crate::internal::idt::{binary_stream_name, check_file_name};
+use crate::internal::package::Package;
+use crate::internal::query::Select;
+use crate::internal::rows::{
+    ComponentAttributes, ComponentRow, CustomActionRow, CustomActionType,
+    DirectoryRow, FeatureAttributes, FeatureComponentsRow, FeatureRow,
+    FileAttributes, FileRow, MediaRow, PropertyRow, RegistryRow, ShortcutRow,
+    TypedRow,
+};
+use crate::internal::value::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read, Seek};
+use std::path::{Path, PathBuf};
+
+// ========================================================================= //
+
+const WIX_NAMESPACE: &str = "http://wixtoolset.org/schemas/v4/wxs";
+
+/// The name of the WiX source file written by `decompile()`.
+const WXS_FILE_NAME: &str = "Package.wxs";
+
+/// The subdirectory that `File` elements' sources refer to.  MSI cabinets
+/// name each file after its `File` table key, so extracting the package's
+/// cabinets into this directory supplies every file.
+const FILES_DIR_NAME: &str = "File";
+
+const TARGET_DIR: &str = "TARGETDIR";
+
+/// Tables whose rows are named binary streams that become elements of the
+/// same name, with the stream data extracted to a subdirectory.
+const BINARY_TABLE_NAMES: &[&str] = &["Binary", "Icon"];
+
+const SEQUENCE_TABLE_NAMES: &[&str] = &[
+    "InstallUISequence",
+    "InstallExecuteSequence",
+    "AdminUISequence",
+    "AdminExecuteSequence",
+    "AdvtExecuteSequence",
+];
+
+/// Properties that become attributes of the `Package` element, rather than
+/// `Property` elements.
+const PACKAGE_PROPERTIES: &[(&str, &str)] = &[
+    ("ProductName", "Name"),
+    ("Manufacturer", "Manufacturer"),
+    ("ProductVersion", "Version"),
+    ("ProductLanguage", "Language"),
+    ("ProductCode", "ProductCode"),
+    ("UpgradeCode", "UpgradeCode"),
+];
+
+/// Directories that WiX v4 declares with `StandardDirectory` elements.
+const STANDARD_DIRECTORIES: &[&str] = &[
+    "AdminToolsFolder",
+    "AppDataFolder",
+    "CommonAppDataFolder",
+    "CommonFiles64Folder",
+    "CommonFilesFolder",
+    "DesktopFolder",
+    "FavoritesFolder",
+    "FontsFolder",
+    "LocalAppDataFolder",
+    "MyPicturesFolder",
+    "NetHoodFolder",
+    "PersonalFolder",
+    "PrintHoodFolder",
+    "ProgramFiles64Folder",
+    "ProgramFilesFolder",
+    "ProgramMenuFolder",
+    "RecentFolder",
+    "SendToFolder",
+    "StartMenuFolder",
+    "StartupFolder",
+    "System16Folder",
+    "System64Folder",
+    "SystemFolder",
+    "TempFolder",
+    "TemplateFolder",
+    "WindowsFolder",
+    "WindowsVolume",
+];
+
+// ========================================================================= //
+
+enum Node {
+    Element(Element),
+    Comment(String),
+}
+
+struct Element {
+    name: String,
+    attributes: Vec<(&'static str, String)>,
+    children: Vec<Node>,
+    text: Option<String>,
+}
+
+impl Element {
+    fn new(name: &str) -> Element {
+        Element {
+            name: name.to_string(),
+            attributes: Vec::new(),
+            children: Vec::new(),
+            text: None,
+        }
+    }
+
+    fn attr<S: ToString>(mut self, name: &'static str, value: S) -> Element {
+        self.attributes.push((name, value.to_string()));
+        self
+    }
+
+    fn opt_attr<S: ToString>(
+        self,
+        name: &'static str,
+        value: Option<S>,
+    ) -> Element {
+        match value {
+            Some(value) => self.attr(name, value),
+            None => self,
+        }
+    }
+
+    fn flag(
+        self,
+        name: &'static str,
+        value: &str,
+        condition: bool,
+    ) -> Element {
+        if condition { self.attr(name, value) } else { self }
+    }
+
+    fn push(&mut self, child: Element) {
+        self.children.push(Node::Element(child));
+    }
+
+    fn write(&self, output: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        output.push_str(&indent);
+        output.push('<');
+        output.push_str(&self.name);
+        for (name, value) in &self.attributes {
+            output.push_str(&format!(" {}=\"{}\"", name, escape(value, true)));
+        }
+        if let Some(ref text) = self.text {
+            output.push_str(&format!(
+                ">{}</{}>\n",
+                escape(text, false),
+                self.name
+            ));
+        } else if self.children.is_empty() {
+            output.push_str(" />\n");
+        } else {
+            output.push_str(">\n");
+            for child in &self.children {
+                match child {
+                    Node::Element(element) => element.write(output, depth + 1),
+                    Node::Comment(comment) => output.push_str(&format!(
+                        "{}  <!-- {} -->\n",
+                        indent,
+                        escape_comment(comment)
+                    )),
+                }
+            }
+            output.push_str(&format!("{}</{}>\n", indent, self.name));
+        }
+    }
+}
+
+/// Breaks up every `--` in the given text, which isn't allowed within an XML
+/// comment.  (A single pass isn't enough, since `---` would become `- --`.)
+fn escape_comment(comment: &str) -> String {
+    let mut text = comment.to_string();
+    while text.contains("--") {
+        text = text.replace("--", "- -");
+    }
+    text
+}
+
+/// Returns true if the given string can be used as an XML element name.
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|chr| chr.is_alphabetic() || chr == '_')
+        && chars
+            .all(|chr| chr.is_alphanumeric() || matches!(chr, '_' | '-' | '.'))
+}
+
+fn escape(string: &str, in_attribute: bool) -> String {
+    let mut output = String::with_capacity(string.len());
+    for chr in string.chars() {
+        match chr {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' if in_attribute => output.push_str("&quot;"),
+            '\t' | '\n' | '\r' if in_attribute => {
+                output.push_str(&format!("&#{};", chr as u32))
+            }
+            _ => output.push(chr),
+        }
+    }
+    output
+}
+
+// ========================================================================= //
+
+pub(crate) fn decompile<F: Read + Seek>(
+    package: &mut Package<F>,
+    dir: &Path,
+) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let mut properties = typed_rows::<F, PropertyRow>(package)?;
+    let mut root = Element::new("Package");
+    for &(property, attribute) in PACKAGE_PROPERTIES {
+        if let Some(index) =
+            properties.iter().position(|row| row.property == property)
+        {
+            root = root.attr(attribute, properties.remove(index).value);
+        }
+    }
+    root = root.attr("Codepage", package.database_codepage().id());
+    let summary_info = package.summary_info();
+    root = root.opt_attr("InstallerVersion", summary_info.page_count()).attr(
+        "Compressed",
+        if summary_info.word_count().unwrap_or(0) & 0x2 != 0 {
+            "yes"
+        } else {
+            "no"
+        },
+    );
+    let scope =
+        match properties.iter().position(|row| row.property == "ALLUSERS") {
+            Some(index) => match properties[index].value.as_str() {
+                "1" => {
+                    properties.remove(index);
+                    Some("perMachine")
+                }
+                "2" => {
+                    properties.remove(index);
+                    Some("perUserOrMachine")
+                }
+                _ => None,
+            },
+            None => Some("perUser"),
+        };
+    root = root.opt_attr("Scope", scope);
+
+    let keywords = summary_info.keywords().join(", ");
+    let summary_element = Element::new("SummaryInformation")
+        .opt_attr("Description", summary_info.subject())
+        .opt_attr("Manufacturer", summary_info.author())
+        .flag("Keywords", &keywords, !keywords.is_empty())
+        .opt_attr("Comments", summary_info.comments());
+    if !summary_element.attributes.is_empty() {
+        root.push(summary_element);
+    }
+
+    for media in typed_rows::<F, MediaRow>(package)? {
+        let mut element = Element::new("Media")
+            .attr("Id", media.disk_id)
+            .opt_attr("DiskPrompt", media.disk_prompt)
+            .opt_attr("VolumeLabel", media.volume_label)
+            .opt_attr("Source", media.source);
+        if let Some(cabinet) = media.cabinet {
+            match cabinet.strip_prefix('#') {
+                Some(stream_name) => {
+                    element = element
+                        .attr("Cabinet", stream_name)
+                        .attr("EmbedCab", "yes");
+                    if package.has_stream(stream_name) {
+                        extract_stream(
+                            package,
+                            stream_name,
+                            dir,
+                            &[stream_name],
+                        )?;
+                    }
+                }
+                None => element = element.attr("Cabinet", cabinet),
+            }
+        }
+        root.push(element);
+    }
+
+    for property in properties {
+        root.push(
+            Element::new("Property")
+                .attr("Id", property.property)
+                .attr("Value", property.value),
+        );
+    }
+
+    for &table_name in BINARY_TABLE_NAMES {
+        if !package.has_table(table_name) {
+            continue;
+        }
+        let names: Vec<String> = package
+            .select_rows(Select::table(table_name))?
+            .filter_map(|row| match row.get_by_name("Name") {
+                Some(Value::Str(name)) => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        for name in names {
+            let stream_name =
+                binary_stream_name(table_name, &[&Value::from(name.as_str())]);
+            let source = format!("{table_name}\\{name}");
+            if package.has_stream(&stream_name) {
+                extract_stream(
+                    package,
+                    &stream_name,
+                    dir,
+                    &[table_name, &name],
+                )?;
+            }
+            root.push(
+                Element::new(table_name)
+                    .attr("Id", name)
+                    .attr("SourceFile", source),
+            );
+        }
+    }
+
+    decompile_directories(package, &mut root)?;
+    decompile_features(package, &mut root)?;
+    let custom_actions = decompile_custom_actions(package, &mut root)?;
+    decompile_sequences(package, &mut root, &custom_actions)?;
+
+    let mut wix = Element::new("Wix").attr("xmlns", WIX_NAMESPACE);
+    wix.push(root);
+    let mut output =
+        String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    wix.write(&mut output, 0);
+    let path = dir.join(WXS_FILE_NAME);
+    fs::write(&path, output)?;
+    Ok(path)
+}
+
+/// Reads every row of a typed table, or no rows if the package doesn't have
+/// the table.
+fn typed_rows<F: Read + Seek, T: TypedRow>(
+    package: &mut Package<F>,
+) -> io::Result<Vec<T>> {
+    if package.has_table(T::TABLE_NAME) {
+        package.typed_rows()
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// Copies a stream to a file within `dir`, at the path made up of the given
+/// names (each of which is checked, since they come from the package).
+fn extract_stream<F: Read + Seek>(
+    package: &mut Package<F>,
+    stream_name: &str,
+    dir: &Path,
+    names: &[&str],
+) -> io::Result<()> {
+    let mut path = dir.to_path_buf();
+    for name in names {
+        check_file_name(name)?;
+        path.push(name);
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut stream = package.read_stream(stream_name)?;
+    let mut file = fs::File::create(path)?;
+    io::copy(&mut stream, &mut file)?;
+    Ok(())
+}
+
+/// Returns the long form of the target name in a `Directory` table
+/// `DefaultDir` value or `File` table `FileName` value (which have the form
+/// `[short|]long[:source]`).
+fn long_name(name: &str) -> &str {
+    let target = name.split(':').next().unwrap_or(name);
+    target.rsplit('|').next().unwrap_or(target)
+}
+
+// ========================================================================= //
+
+fn decompile_directories<F: Read + Seek>(
+    package: &mut Package<F>,
+    root: &mut Element,
+) -> io::Result<()> {
+    let directories = typed_rows::<F, DirectoryRow>(package)?;
+    let mut components = HashMap::<String, Vec<Element>>::new();
+    {
+        let files = typed_rows::<F, FileRow>(package)?;
+        let registry_entries = typed_rows::<F, RegistryRow>(package)?;
+        let shortcuts = typed_rows::<F, ShortcutRow>(package)?;
+        for component in typed_rows::<F, ComponentRow>(package)? {
+            let directory = component.directory.clone();
+            let element = component_element(
+                component,
+                &files,
+                &registry_entries,
+                &shortcuts,
+            );
+            components.entry(directory).or_default().push(element);
+        }
+    }
+
+    let mut children = HashMap::<&str, Vec<&DirectoryRow>>::new();
+    let mut roots = Vec::<&DirectoryRow>::new();
+    for directory in &directories {
+        match directory.parent.as_deref() {
+            Some(parent) if parent != directory.directory => {
+                children.entry(parent).or_default().push(directory)
+            }
+            _ => roots.push(directory),
+        }
+    }
+    for directory in roots {
+        if directory.directory == TARGET_DIR {
+            // WiX v4 declares TARGETDIR implicitly; its subdirectories and
+            // components go directly in the Package element.
+            for child in children.get(TARGET_DIR).into_iter().flatten() {
+                root.push(directory_element(
+                    child,
+                    &children,
+                    &mut components,
+                ));
+            }
+        } else {
+            root.push(directory_element(
+                directory,
+                &children,
+                &mut components,
+            ));
+        }
+    }
+    // Components of TARGETDIR, or of directories that don't exist.
+    let mut remaining: Vec<(String, Vec<Element>)> =
+        components.into_iter().collect();
+    remaining.sort_by(|a, b| a.0.cmp(&b.0));
+    for (directory, elements) in remaining {
+        for element in elements {
+            root.push(element.attr("Directory", &directory));
+        }
+    }
+    Ok(())
+}
+
+fn directory_element(
+    directory: &DirectoryRow,
+    children: &HashMap<&str, Vec<&DirectoryRow>>,
+    components: &mut HashMap<String, Vec<Element>>,
+) -> Element {
+    let id = directory.directory.as_str();
+    let mut element = if STANDARD_DIRECTORIES.contains(&id) {
+        Element::new("StandardDirectory").attr("Id", id)
+    } else {
+        Element::new("Directory")
+            .attr("Id", id)
+            .attr("Name", long_name(&directory.default_dir))
+    };
+    for component in components.remove(id).into_iter().flatten() {
+        element.push(component);
+    }
+    for child in children.get(id).into_iter().flatten() {
+        element.push(directory_element(child, children, components));
+    }
+    element
+}
+
+fn component_element(
+    component: ComponentRow,
+    files: &[FileRow],
+    registry_entries: &[RegistryRow],
+    shortcuts: &[ShortcutRow],
+) -> Element {
+    let attributes = component.attributes;
+    let key_path = component.key_path.as_deref();
+    let is_registry_key_path =
+        attributes.contains(ComponentAttributes::REGISTRY_KEY_PATH);
+    let mut element = Element::new("Component")
+        .attr("Id", &component.component)
+        .attr("Guid", component.component_id.as_deref().unwrap_or(""))
+        .opt_attr("Condition", component.condition.as_deref())
+        .flag("KeyPath", "yes", key_path.is_none())
+        .flag(
+            "Location",
+            "source",
+            attributes.contains(ComponentAttributes::SOURCE_ONLY),
+        )
+        .flag(
+            "Location",
+            "either",
+            attributes.contains(ComponentAttributes::OPTIONAL),
+        )
+        .flag(
+            "SharedDllRefCount",
+            "yes",
+            attributes.contains(ComponentAttributes::SHARED_DLL_REF_COUNT),
+        )
+        .flag(
+            "Permanent",
+            "yes",
+            attributes.contains(ComponentAttributes::PERMANENT),
+        )
+        .flag(
+            "Transitive",
+            "yes",
+            attributes.contains(ComponentAttributes::TRANSITIVE),
+        )
+        .flag(
+            "NeverOverwrite",
+            "yes",
+            attributes.contains(ComponentAttributes::NEVER_OVERWRITE),
+        )
+        .flag(
+            "Bitness",
+            "always64",
+            attributes.contains(ComponentAttributes::WIN64),
+        )
+        .flag(
+            "DisableRegistryReflection",
+            "yes",
+            attributes
+                .contains(ComponentAttributes::DISABLE_REGISTRY_REFLECTION),
+        )
+        .flag(
+            "UninstallWhenSuperseded",
+            "yes",
+            attributes
+                .contains(ComponentAttributes::UNINSTALL_ON_SUPERSEDENCE),
+        )
+        .flag(
+            "Shared",
+            "yes",
+            attributes.contains(ComponentAttributes::SHARED),
+        );
+
+    for file in
+        files.iter().filter(|file| file.component == component.component)
+    {
+        let attributes = file.attributes;
+        element.push(
+            Element::new("File")
+                .attr("Id", &file.file)
+                .attr("Name", long_name(&file.file_name))
+                .attr("Source", format!("{}\\{}", FILES_DIR_NAME, file.file))
+                .flag(
+                    "KeyPath",
+                    "yes",
+                    !is_registry_key_path && key_path == Some(&file.file),
+                )
+                .flag(
+                    "ReadOnly",
+                    "yes",
+                    attributes.contains(FileAttributes::READ_ONLY),
+                )
+                .flag(
+                    "Hidden",
+                    "yes",
+                    attributes.contains(FileAttributes::HIDDEN),
+                )
+                .flag(
+                    "System",
+                    "yes",
+                    attributes.contains(FileAttributes::SYSTEM),
+                )
+                .flag(
+                    "Vital",
+                    "no",
+                    !attributes.contains(FileAttributes::VITAL),
+                )
+                .flag(
+                    "Checksum",
+                    "yes",
+                    attributes.contains(FileAttributes::CHECKSUM),
+                )
+                .flag(
+                    "Compressed",
+                    "yes",
+                    attributes.contains(FileAttributes::COMPRESSED),
+                )
+                .flag(
+                    "Compressed",
+                    "no",
+                    attributes.contains(FileAttributes::NONCOMPRESSED),
+                ),
+        );
+    }
+
+    for entry in registry_entries
+        .iter()
+        .filter(|entry| entry.component == component.component)
+    {
+        let is_key_path =
+            is_registry_key_path && key_path == Some(&entry.registry);
+        element.push(registry_element(entry, is_key_path));
+    }
+
+    for shortcut in shortcuts
+        .iter()
+        .filter(|shortcut| shortcut.component == component.component)
+    {
+        element.push(shortcut_element(shortcut));
+    }
+    element
+}
+
+fn registry_element(entry: &RegistryRow, is_key_path: bool) -> Element {
+    let root = match entry.root {
+        -1 => "HKMU",
+        0 => "HKCR",
+        1 => "HKCU",
+        2 => "HKLM",
+        3 => "HKU",
+        _ => "",
+    };
+    let name = entry.name.as_deref();
+    if entry.value.is_none() && matches!(name, Some("+" | "-" | "*")) {
+        return Element::new("RegistryKey")
+            .attr("Id", &entry.registry)
+            .attr("Root", root)
+            .attr("Key", &entry.key)
+            .flag("ForceCreateOnInstall", "yes", name != Some("-"))
+            .flag("ForceDeleteOnUninstall", "yes", name != Some("+"));
+    }
+    let value = entry.value.as_deref().unwrap_or("");
+    let (value_type, value) = if let Some(rest) = value.strip_prefix("#x") {
+        ("binary", rest.to_string())
+    } else if let Some(rest) = value.strip_prefix("#%") {
+        ("expandable", rest.to_string())
+    } else if let Some(rest) = value.strip_prefix("##") {
+        ("string", format!("#{rest}"))
+    } else if let Some(rest) = value.strip_prefix('#') {
+        ("integer", rest.to_string())
+    } else if value.contains("[~]") {
+        ("multiString", value.to_string())
+    } else {
+        ("string", value.to_string())
+    };
+    let mut element = Element::new("RegistryValue")
+        .attr("Id", &entry.registry)
+        .attr("Root", root)
+        .attr("Key", &entry.key)
+        .opt_attr("Name", name)
+        .attr("Type", value_type)
+        .flag("KeyPath", "yes", is_key_path);
+    if value_type == "multiString" {
+        for item in value.split("[~]").filter(|item| !item.is_empty()) {
+            element.push(Element::new("MultiStringValue").attr("Value", item));
+        }
+    } else {
+        element = element.attr("Value", value);
+    }
+    element
+}
+
+fn shortcut_element(shortcut: &ShortcutRow) -> Element {
+    // A target that isn't a formatted path names a feature, which makes the
+    // shortcut advertised.
+    let is_advertised = !shortcut.target.starts_with('[');
+    let show = match shortcut.show_cmd {
+        Some(3) => Some("maximized"),
+        Some(7) => Some("minimized"),
+        Some(1) => Some("normal"),
+        _ => None,
+    };
+    Element::new("Shortcut")
+        .attr("Id", &shortcut.shortcut)
+        .attr("Directory", &shortcut.directory)
+        .attr("Name", long_name(&shortcut.name))
+        .flag("Target", &shortcut.target, !is_advertised)
+        .flag("Advertise", "yes", is_advertised)
+        .opt_attr("Arguments", shortcut.arguments.as_deref())
+        .opt_attr("Description", shortcut.description.as_deref())
+        .opt_attr("Hotkey", shortcut.hotkey)
+        .opt_attr("Icon", shortcut.icon.as_deref())
+        .opt_attr("IconIndex", shortcut.icon_index)
+        .opt_attr("Show", show)
+        .opt_attr("WorkingDirectory", shortcut.working_dir.as_deref())
+        .opt_attr(
+            "DisplayResourceDll",
+            shortcut.display_resource_dll.as_deref(),
+        )
+        .opt_attr("DisplayResourceId", shortcut.display_resource_id)
+        .opt_attr(
+            "DescriptionResourceDll",
+            shortcut.description_resource_dll.as_deref(),
+        )
+        .opt_attr("DescriptionResourceId", shortcut.description_resource_id)
+}
+
+// ========================================================================= //
+
+fn decompile_features<F: Read + Seek>(
+    package: &mut Package<F>,
+    root: &mut Element,
+) -> io::Result<()> {
+    let features = typed_rows::<F, FeatureRow>(package)?;
+    let feature_components = typed_rows::<F, FeatureComponentsRow>(package)?;
+    let ids: HashSet<&str> =
+        features.iter().map(|row| row.feature.as_str()).collect();
+    for feature in features.iter().filter(|feature| match &feature.parent {
+        Some(parent) => !ids.contains(parent.as_str()),
+        None => true,
+    }) {
+        root.push(feature_element(feature, &features, &feature_components));
+    }
+    Ok(())
+}
+
+fn feature_element(
+    feature: &FeatureRow,
+    features: &[FeatureRow],
+    feature_components: &[FeatureComponentsRow],
+) -> Element {
+    let attributes = feature.attributes;
+    let display = match feature.display {
+        Some(0) => Some("hidden".to_string()),
+        Some(display) => Some(display.to_string()),
+        None => None,
+    };
+    let mut element = Element::new("Feature")
+        .attr("Id", &feature.feature)
+        .opt_attr("Title", feature.title.as_deref())
+        .opt_attr("Description", feature.description.as_deref())
+        .opt_attr("Display", display)
+        .attr("Level", feature.level)
+        .opt_attr("ConfigurableDirectory", feature.directory.as_deref())
+        .flag(
+            "InstallDefault",
+            "source",
+            attributes.contains(FeatureAttributes::FAVOR_SOURCE),
+        )
+        .flag(
+            "InstallDefault",
+            "followParent",
+            attributes.contains(FeatureAttributes::FOLLOW_PARENT),
+        )
+        .flag(
+            "TypicalDefault",
+            "advertise",
+            attributes.contains(FeatureAttributes::FAVOR_ADVERTISE),
+        )
+        .flag(
+            "AllowAdvertise",
+            "no",
+            attributes.contains(FeatureAttributes::DISALLOW_ADVERTISE),
+        )
+        .flag(
+            "AllowAbsent",
+            "no",
+            attributes.contains(FeatureAttributes::UI_DISALLOW_ABSENT),
+        );
+    for row in
+        feature_components.iter().filter(|row| row.feature == feature.feature)
+    {
+        element.push(Element::new("ComponentRef").attr("Id", &row.component));
+    }
+    for child in features.iter().filter(|child| {
+        child.parent.as_deref() == Some(feature.feature.as_str())
+            && child.feature != feature.feature
+    }) {
+        element.push(feature_element(child, features, feature_components));
+    }
+    element
+}
+
+// ========================================================================= //
+
+/// Adds an element for each custom action, and returns the actions' names.
+fn decompile_custom_actions<F: Read + Seek>(
+    package: &mut Package<F>,
+    root: &mut Element,
+) -> io::Result<HashSet<String>> {
+    let mut names = HashSet::new();
+    for action in typed_rows::<F, CustomActionRow>(package)? {
+        names.insert(action.action.clone());
+        match custom_action_element(&action) {
+            Some(element) => root.push(element),
+            None => root.children.push(Node::Comment(format!(
+                "Custom action {:?} has type {}, which has no WiX equivalent",
+                action.action,
+                action.action_type.bits()
+            ))),
+        }
+    }
+    Ok(names)
+}
+
+fn custom_action_element(action: &CustomActionRow) -> Option<Element> {
+    let action_type = action.action_type;
+    let source = action.source.as_deref();
+    let target = action.target.as_deref();
+    let mut element = Element::new("CustomAction").attr("Id", &action.action);
+    let mut script = None;
+    element = match (action_type.base_type(), action_type.source_type()) {
+        // DLLs and executables stored in the Binary table, installed files,
+        // directories, and properties.
+        (1, 0x00) => {
+            element.opt_attr("BinaryRef", source).opt_attr("DllEntry", target)
+        }
+        (1, 0x10) => {
+            element.opt_attr("FileRef", source).opt_attr("DllEntry", target)
+        }
+        (2, 0x00) => element
+            .opt_attr("BinaryRef", source)
+            .opt_attr("ExeCommand", target),
+        (2, 0x10) => {
+            element.opt_attr("FileRef", source).opt_attr("ExeCommand", target)
+        }
+        (2, 0x20) => element
+            .opt_attr("Directory", source)
+            .opt_attr("ExeCommand", target),
+        (2, 0x30) => {
+            element.opt_attr("Property", source).opt_attr("ExeCommand", target)
+        }
+        // Scripts stored in the Binary table, installed files, or
+        // properties, or given inline.
+        (5 | 6, 0x00) => element
+            .opt_attr("BinaryRef", source)
+            .opt_attr(script_call(action_type), target),
+        (5 | 6, 0x10) => element
+            .opt_attr("FileRef", source)
+            .opt_attr(script_call(action_type), target),
+        (5 | 6, 0x20) => {
+            script = target;
+            element.attr(
+                "Script",
+                if action_type.base_type() == 5 {
+                    "jscript"
+                } else {
+                    "vbscript"
+                },
+            )
+        }
+        (5 | 6, 0x30) => element
+            .opt_attr("Property", source)
+            .opt_attr(script_call(action_type), target),
+        // Errors, and setting directories and properties.
+        (3, 0x10) => element.opt_attr("Error", target),
+        (3, 0x20) => {
+            element.opt_attr("Directory", source).opt_attr("Value", target)
+        }
+        (3, 0x30) => {
+            element.opt_attr("Property", source).opt_attr("Value", target)
+        }
+        _ => return None,
+    };
+    let is_deferred = action_type.contains(CustomActionType::IN_SCRIPT);
+    let execute = if is_deferred {
+        if action_type.contains(CustomActionType::ROLLBACK) {
+            Some("rollback")
+        } else if action_type.contains(CustomActionType::COMMIT) {
+            Some("commit")
+        } else {
+            Some("deferred")
+        }
+    } else if action_type.contains(CustomActionType::CLIENT_REPEAT) {
+        Some("secondSequence")
+    } else if action_type.contains(CustomActionType::FIRST_SEQUENCE) {
+        Some("firstSequence")
+    } else if action_type.contains(CustomActionType::ONCE_PER_PROCESS) {
+        Some("oncePerProcess")
+    } else {
+        None
+    };
+    let is_async = action_type.contains(CustomActionType::ASYNC);
+    let is_continue = action_type.contains(CustomActionType::CONTINUE);
+    let return_type = match (is_async, is_continue) {
+        (true, true) => Some("asyncNoWait"),
+        (true, false) => Some("asyncWait"),
+        (false, true) => Some("ignore"),
+        (false, false) => None,
+    };
+    element = element
+        .opt_attr("Execute", execute)
+        .opt_attr("Return", return_type)
+        .flag(
+            "Impersonate",
+            "no",
+            is_deferred
+                && action_type.contains(CustomActionType::NO_IMPERSONATE),
+        )
+        .flag(
+            "Bitness",
+            "always64",
+            action_type.contains(CustomActionType::WIN64),
+        )
+        .flag(
+            "HideTarget",
+            "yes",
+            action_type.contains(CustomActionType::HIDE_TARGET),
+        )
+        .flag(
+            "TerminalServerAware",
+            "yes",
+            action_type.contains(CustomActionType::TS_AWARE),
+        );
+    element.text = script.map(str::to_string);
+    Some(element)
+}
+
+fn script_call(action_type: CustomActionType) -> &'static str {
+    if action_type.base_type() == 5 { "JScriptCall" } else { "VBScriptCall" }
+}
+
+// ========================================================================= //
+
+fn decompile_sequences<F: Read + Seek>(
+    package: &mut Package<F>,
+    root: &mut Element,
+    custom_actions: &HashSet<String>,
+) -> io::Result<()> {
+    let dialogs: HashSet<String> = if package.has_table("Dialog") {
+        package
+            .select_rows(Select::table("Dialog"))?
+            .filter_map(|row| match row.get_by_name("Dialog") {
+                Some(Value::Str(name)) => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    } else {
+        HashSet::new()
+    };
+    for &table_name in SEQUENCE_TABLE_NAMES {
+        if !package.has_table(table_name) {
+            continue;
+        }
+        let mut actions: Vec<(Option<i32>, String, Option<String>)> = package
+            .select_rows(Select::table(table_name))?
+            .filter_map(|row| {
+                let action = match row.get_by_name("Action") {
+                    Some(Value::Str(action)) => action.clone(),
+                    _ => return None,
+                };
+                let condition = match row.get_by_name("Condition") {
+                    Some(Value::Str(condition)) => Some(condition.clone()),
+                    _ => None,
+                };
+                let sequence = match row.get_by_name("Sequence") {
+                    Some(&Value::Int(sequence)) => Some(sequence),
+                    _ => None,
+                };
+                Some((sequence, action, condition))
+            })
+            .collect();
+        if actions.is_empty() {
+            continue;
+        }
+        actions.sort();
+        let mut sequence_element = Element::new(table_name);
+        for (sequence, action, condition) in actions {
+            let element = if custom_actions.contains(&action) {
+                Element::new("Custom").attr("Action", action)
+            } else if dialogs.contains(&action) {
+                Element::new("Show").attr("Dialog", action)
+            } else if is_xml_name(&action) {
+                Element::new(&action)
+            } else {
+                sequence_element.children.push(Node::Comment(format!(
+                    "Action {:?} is not a valid element name",
+                    action
+                )));
+                continue;
+            };
+            sequence_element.push(
+                element
+                    .opt_attr("Sequence", sequence)
+                    .opt_attr("Condition", condition),
+            );
+        }
+        root.push(sequence_element);
+    }
+    Ok(())
+}
+
+// ========================================================================= //
@@ -18,19 +18,77 @@ mod internal;
 
 pub use crate::internal::category::Category;
 pub use crate::internal::codepage::CodePage;
-pub use crate::internal::column::{Column, ColumnBuilder, ColumnType};
+pub use crate::internal::column::{
+    Column, ColumnBuilder, ColumnType, ColumnValidation,
+};
+pub use crate::internal::error::Error;
 pub use crate::internal::expr::Expr;
+pub use crate::internal::integrity::IntegrityProblem;
 pub use crate::internal::language::LanguageId;
-pub use crate::internal::package::{Package, PackageType, Tables};
+pub use crate::internal::package::{
+    OpenOptions, Package, PackageType, SaveOptions, Tables,
+};
 pub use crate::internal::query::{Delete, Insert, Select, Update};
 pub use crate::internal::stream::{StreamReader, StreamWriter, Streams};
+pub use crate::internal::stringpool::StringPoolLayout;
 pub use crate::internal::summary::SummaryInfo;
 pub use crate::internal::table::{Row, Rows, Table};
+pub use crate::internal::validate::{
+    CubReport, Diagnostic, Rule, Severity, SkippedRule,
+};
 pub use crate::internal::value::Value;
 use std::fs;
 use std::io;
 use std::path::Path;
 
+/// Strongly typed rows for some of the most common standard tables.
+///
+/// Each type in this module corresponds to one standard table (e.g.
+/// `FileRow` to the `File` table), with a field per column and flag types for
+/// the tables' attribute bitfields.  See `Package::typed_rows()` and
+/// `Package::insert_typed_rows()`.
+///
+/// Row types for custom tables can be written by hand (by implementing
+/// `TypedRow` and `MsiTable`), or, with the `derive` feature enabled, with
+/// `#[derive(MsiTable)]`.
+pub mod rows {
+    pub use crate::internal::rows::{
+        ComponentAttributes, ComponentRow, CustomActionRow, CustomActionType,
+        DirectoryRow, FeatureAttributes, FeatureComponentsRow, FeatureRow,
+        FieldValue, FileAttributes, FileRow, MediaRow, MsiTable, PropertyRow,
+        RegistryRow, ShortcutRow, TypedRow,
+    };
+    #[cfg(feature = "derive")]
+    pub use msi_derive::MsiTable;
+}
+
+/// Building complete installer packages from a declarative manifest.
+///
+/// A `Manifest` describes a product's metadata, directories, components,
+/// files, and features, and can be read from TOML or JSON.  Building it
+/// creates a package with all of the necessary tables, standard action
+/// sequences, summary information, and an embedded cabinet holding the
+/// files.  Requires the `builder` feature.
+#[cfg(feature = "builder")]
+pub mod builder {
+    pub use crate::internal::builder::{
+        Arch, Component, Directory, Feature, File, Manifest, Product, Scope,
+    };
+}
+
+/// Definitions of the standard Windows Installer database tables.
+///
+/// The Windows Installer SDK documents about a hundred standard tables (such
+/// as `File`, `Component`, and `Directory`) whose columns and `_Validation`
+/// metadata are fixed; this module describes each of them, so that they can
+/// be created without having to spell out their columns by hand.  See also
+/// `Package::create_standard_table()`.
+pub mod schema {
+    pub use crate::internal::schema::{
+        SchemaVersion, is_standard_table, table_columns, table_names,
+    };
+}
+
 // ========================================================================= //
 
 /// Opens an existing MSI file at the given path in read-only mode.
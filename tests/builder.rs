@@ -0,0 +1,335 @@
+#[macro_use]
+mod testutil;
+
+use msi::builder::Manifest;
+use msi::rows::{
+    ComponentAttributes, ComponentRow, DirectoryRow, FeatureAttributes,
+    FeatureComponentsRow, FeatureRow, FileRow, MediaRow, PropertyRow,
+};
+use msi::{Package, Select};
+use std::fs;
+use std::io::{Cursor, ErrorKind, Read};
+use std::path::Path;
+
+// ========================================================================= //
+
+const MANIFEST: &str = r#"
+[product]
+name = "Widget"
+manufacturer = "Widgets Inc."
+version = "1.2.3"
+upgrade_code = "0a6d4c1e-55b2-4e1f-8e2b-6f3d2c1b0a02"
+product_code = "5f0b6b6e-9d1c-4c3e-9a43-2f5b8d7a6c01"
+package_code = "c2e4a7d0-3b1f-4e6a-8d2c-9f0e1a2b3c4d"
+arch = "x64"
+description = "Widget 1.2.3"
+
+[[directories]]
+id = "INSTALLDIR"
+parent = "ProgramFiles64Folder"
+name = "Widget Tools"
+
+[[directories]]
+id = "DocsDir"
+parent = "INSTALLDIR"
+name = "docs"
+
+[[components]]
+id = "MainExe"
+directory = "INSTALLDIR"
+files = [
+    { source = "bin/widget.exe" },
+    { source = "bin/Widget Helper.exe", id = "Helper" },
+]
+
+[[components]]
+id = "Docs"
+directory = "DocsDir"
+guid = "7d3f2e1c-4b5a-4968-8776-655443322110"
+files = [{ source = "README.txt", name = "Read Me.txt" }]
+
+[[features]]
+id = "Complete"
+title = "Widget"
+allow_absent = false
+components = ["MainExe"]
+
+[[features.features]]
+id = "Documentation"
+title = "Documentation"
+level = 2
+components = ["Docs"]
+
+[properties]
+ARPCONTACT = "support@example.com"
+"#;
+
+/// Returns a temporary directory holding the source files that `MANIFEST`
+/// refers to.
+fn source_dir(test_name: &str) -> testutil::TempDir {
+    let dir = testutil::temp_dir(test_name);
+    fs::create_dir_all(dir.join("bin")).unwrap();
+    fs::write(dir.join("bin/widget.exe"), b"MZ widget").unwrap();
+    fs::write(dir.join("bin/Widget Helper.exe"), b"MZ helper").unwrap();
+    fs::write(dir.join("README.txt"), b"Read me!\n").unwrap();
+    dir
+}
+
+fn build(manifest: &Manifest, source_dir: &Path) -> Package<Cursor<Vec<u8>>> {
+    let package = manifest.build(source_dir, Cursor::new(Vec::new())).unwrap();
+    let cursor = package.into_inner().unwrap();
+    Package::open(cursor).unwrap()
+}
+
+// ========================================================================= //
+
+#[test]
+fn build_from_toml() {
+    let dir = source_dir("build_from_toml");
+    let manifest = Manifest::from_toml(MANIFEST).unwrap();
+    let mut package = build(&manifest, &dir);
+
+    let summary_info = package.summary_info();
+    assert_eq!(summary_info.subject(), Some("Widget"));
+    assert_eq!(summary_info.author(), Some("Widgets Inc."));
+    assert_eq!(summary_info.arch(), Some("x64"));
+    assert_eq!(summary_info.page_count(), Some(500));
+    assert_eq!(summary_info.word_count(), Some(2));
+    assert_eq!(
+        summary_info.uuid().unwrap().hyphenated().to_string(),
+        "c2e4a7d0-3b1f-4e6a-8d2c-9f0e1a2b3c4d"
+    );
+
+    let properties = package.typed_rows::<PropertyRow>().unwrap();
+    let property = |name: &str| {
+        properties
+            .iter()
+            .find(|row| row.property == name)
+            .map(|row| row.value.as_str())
+    };
+    assert_eq!(property("ProductName"), Some("Widget"));
+    assert_eq!(property("ProductVersion"), Some("1.2.3"));
+    assert_eq!(
+        property("ProductCode"),
+        Some("{5F0B6B6E-9D1C-4C3E-9A43-2F5B8D7A6C01}")
+    );
+    assert_eq!(property("ALLUSERS"), Some("1"));
+    assert_eq!(property("ARPCONTACT"), Some("support@example.com"));
+
+    let mut directories = package.typed_rows::<DirectoryRow>().unwrap();
+    directories.sort_by(|a, b| a.directory.cmp(&b.directory));
+    let directory =
+        |id: &str, parent: Option<&str>, default_dir: &str| DirectoryRow {
+            directory: id.to_string(),
+            parent: parent.map(str::to_string),
+            default_dir: default_dir.to_string(),
+        };
+    assert_eq!(
+        directories,
+        vec![
+            directory("DocsDir", Some("INSTALLDIR"), "docs"),
+            directory(
+                "INSTALLDIR",
+                Some("ProgramFiles64Folder"),
+                "WIDGET~1|Widget Tools"
+            ),
+            directory("ProgramFiles64Folder", Some("TARGETDIR"), "."),
+            directory("TARGETDIR", None, "SourceDir"),
+        ]
+    );
+
+    let mut components = package.typed_rows::<ComponentRow>().unwrap();
+    components.sort_by(|a, b| a.component.cmp(&b.component));
+    assert_eq!(components.len(), 2);
+    assert_eq!(
+        components[0].component_id.as_deref(),
+        Some("{7D3F2E1C-4B5A-4968-8776-655443322110}")
+    );
+    assert_eq!(components[0].key_path.as_deref(), Some("Read_Me.txt"));
+    assert_eq!(components[1].attributes, ComponentAttributes::WIN64);
+    assert_eq!(components[1].key_path.as_deref(), Some("widget.exe"));
+    // Component GUIDs that aren't given are derived from the upgrade code,
+    // so they are the same for every build.
+    let rebuilt = build(&manifest, &dir)
+        .typed_rows::<ComponentRow>()
+        .unwrap()
+        .into_iter()
+        .find(|row| row.component == "MainExe")
+        .unwrap();
+    assert_eq!(rebuilt.component_id, components[1].component_id);
+
+    let mut files = package.typed_rows::<FileRow>().unwrap();
+    files.sort_by_key(|row| row.sequence);
+    let files: Vec<_> = files
+        .iter()
+        .map(|row| {
+            (
+                row.file.as_str(),
+                row.file_name.as_str(),
+                row.file_size,
+                row.sequence,
+            )
+        })
+        .collect();
+    assert_eq!(
+        files,
+        vec![
+            ("widget.exe", "widget.exe", 9, 1),
+            ("Helper", "WIDGET~1.EXE|Widget Helper.exe", 9, 2),
+            ("Read_Me.txt", "README~1.TXT|Read Me.txt", 9, 3),
+        ]
+    );
+
+    let features = package.typed_rows::<FeatureRow>().unwrap();
+    assert_eq!(features.len(), 2);
+    assert_eq!(features[0].feature, "Complete");
+    assert_eq!(features[0].attributes, FeatureAttributes::UI_DISALLOW_ABSENT);
+    assert_eq!(features[1].feature, "Documentation");
+    assert_eq!(features[1].parent.as_deref(), Some("Complete"));
+    assert_eq!(features[1].level, 2);
+    assert_eq!(package.typed_rows::<FeatureComponentsRow>().unwrap().len(), 2);
+
+    let media = package.typed_rows::<MediaRow>().unwrap();
+    assert_eq!(media.len(), 1);
+    assert_eq!(media[0].last_sequence, 3);
+    assert_eq!(media[0].cabinet.as_deref(), Some("#product.cab"));
+    let mut cabinet = Vec::new();
+    package
+        .read_stream("product.cab")
+        .unwrap()
+        .read_to_end(&mut cabinet)
+        .unwrap();
+    assert_eq!(&cabinet[..4], b"MSCF");
+    assert!(cabinet.ends_with(b"MZ widgetMZ helperRead me!\n"));
+
+    let actions: Vec<String> = package
+        .select_rows(Select::table("InstallExecuteSequence"))
+        .unwrap()
+        .map(|row| row["Action"].as_str().unwrap().to_string())
+        .collect();
+    assert!(actions.iter().any(|action| action == "InstallFiles"));
+    assert!(package.has_table("AdvtExecuteSequence"));
+
+    assert_eq!(package.check_integrity().unwrap(), vec![]);
+    assert_eq!(package.validate().unwrap(), vec![]);
+}
+
+#[test]
+fn build_from_json() {
+    let dir = source_dir("build_from_json");
+    let json = r#"{
+        "product": {
+            "name": "Widget",
+            "manufacturer": "Widgets Inc.",
+            "version": "0.1.0",
+            "upgrade_code": "0a6d4c1e-55b2-4e1f-8e2b-6f3d2c1b0a02",
+            "scope": "per-user"
+        },
+        "components": [{
+            "id": "Readme",
+            "directory": "LocalAppDataFolder",
+            "files": [{"source": "README.txt"}]
+        }],
+        "features": [{"id": "Main", "components": ["Readme"]}]
+    }"#;
+    let manifest_path = dir.join("widget.json");
+    fs::write(&manifest_path, json).unwrap();
+    let manifest = Manifest::from_file(&manifest_path).unwrap();
+    let mut package = build(&manifest, &dir);
+    let summary_info = package.summary_info();
+    assert_eq!(summary_info.arch(), Some("Intel"));
+    // Per-user packages need Windows Installer 5.0, and don't need elevated
+    // privileges.
+    assert_eq!(summary_info.page_count(), Some(500));
+    assert_eq!(summary_info.word_count(), Some(10));
+    assert!(summary_info.uuid().is_some());
+    let properties = package.typed_rows::<PropertyRow>().unwrap();
+    assert!(properties.iter().any(|row| row.property == "MSIINSTALLPERUSER"
+        && row.value == "1"));
+    let components = package.typed_rows::<ComponentRow>().unwrap();
+    assert_eq!(components[0].directory, "LocalAppDataFolder");
+    assert_eq!(components[0].attributes, ComponentAttributes::empty());
+    assert_eq!(package.check_integrity().unwrap(), vec![]);
+    assert_eq!(package.validate().unwrap(), vec![]);
+}
+
+#[test]
+fn invalid_manifests() {
+    let dir = source_dir("invalid_manifests");
+    let build_error = |text: &str| {
+        Manifest::from_toml(text)
+            .and_then(|manifest| manifest.build(&dir, Cursor::new(Vec::new())))
+            .map(|_| ())
+    };
+    let product = "[product]\n\
+                   name = \"Widget\"\n\
+                   manufacturer = \"Widgets Inc.\"\n\
+                   upgrade_code = \"0a6d4c1e-55b2-4e1f-8e2b-6f3d2c1b0a02\"\n";
+    let component = "[[components]]\n\
+                     id = \"Main\"\n\
+                     directory = \"ProgramFilesFolder\"\n\
+                     files = [{ source = \"README.txt\" }]\n";
+
+    assert_error!(
+        build_error(&format!("{product}version = \"1.2\"\n")),
+        ErrorKind::InvalidData,
+        "Manifest has invalid product version \"1.2\" (expected \
+         major.minor.build, at most 255.255.65535)"
+    );
+    let product = format!("{product}version = \"1.0.0\"\n");
+    assert_error!(
+        build_error(&format!("{product}{component}")),
+        ErrorKind::InvalidData,
+        "Component \"Main\" doesn't belong to any feature"
+    );
+    assert_error!(
+        build_error(&format!(
+            "{product}{component}\
+             [[features]]\nid = \"F\"\ncomponents = [\"Other\"]\n"
+        )),
+        ErrorKind::InvalidData,
+        "Feature \"F\" refers to component \"Other\", which isn't in the \
+         manifest"
+    );
+    assert_error!(
+        build_error(&format!(
+            "{product}[[directories]]\nid = \"A\"\nparent = \"B\"\n\
+             name = \"a\"\n\
+             [[directories]]\nid = \"B\"\nparent = \"A\"\nname = \"b\"\n"
+        )),
+        ErrorKind::InvalidData,
+        "Directory \"A\" is its own ancestor"
+    );
+    assert_error!(
+        build_error(&format!(
+            "{product}[[directories]]\nid = \"A\"\nparent = \"Nowhere\"\n\
+             name = \"a\"\n"
+        )),
+        ErrorKind::InvalidData,
+        "Directory \"A\" has parent \"Nowhere\", which is neither a \
+         directory in the manifest nor a standard directory"
+    );
+    assert_error!(
+        build_error(&format!("{product}[properties]\nProductName = \"X\"\n")),
+        ErrorKind::InvalidData,
+        "Manifest may not set the \"ProductName\" property, which comes from \
+         the product metadata"
+    );
+    let missing = build_error(&format!(
+        "{product}[[components]]\nid = \"Main\"\n\
+         directory = \"ProgramFilesFolder\"\n\
+         files = [{{ source = \"missing.txt\" }}]\n"
+    ))
+    .unwrap_err();
+    assert_eq!(missing.kind(), ErrorKind::NotFound);
+    assert!(missing.to_string().starts_with("Can't read source"), "{missing}");
+    let unknown =
+        build_error(&format!("{product}colour = \"blue\"\n")).unwrap_err();
+    assert_eq!(unknown.kind(), ErrorKind::InvalidData);
+    assert!(
+        unknown.to_string().starts_with("Invalid TOML manifest: "),
+        "{unknown}"
+    );
+}
+
+// ========================================================================= //
@@ -0,0 +1,166 @@
+#[macro_use]
+mod testutil;
+
+use msi::{Column, Error, Expr, Insert, Package, Select, Update, Value};
+use std::io::{self, Cursor, ErrorKind};
+
+// ========================================================================= //
+
+fn create_foobar_package() -> Package<Cursor<Vec<u8>>> {
+    let mut package = testutil::create_package();
+    let columns = vec![
+        Column::build("Key").primary_key().int16(),
+        Column::build("Name").string(8),
+    ];
+    package.create_table("Foobar", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Foobar")
+                .row(vec![Value::Int(1), Value::from("One")]),
+        )
+        .unwrap();
+    package
+}
+
+fn msi_error<T>(result: io::Result<T>) -> (ErrorKind, Error) {
+    match result {
+        Ok(_) => panic!("Expected an error, but result was Ok"),
+        Err(error) => match Error::from_io(&error) {
+            Some(inner) => {
+                assert_eq!(error.kind(), inner.kind());
+                assert_eq!(error.to_string(), inner.to_string());
+                (error.kind(), inner.clone())
+            }
+            None => panic!("Expected an msi::Error, but got {:?}", error),
+        },
+    }
+}
+
+// ========================================================================= //
+
+#[test]
+fn table_errors() {
+    let mut package = create_foobar_package();
+    assert_eq!(
+        msi_error(package.select_rows(Select::table("Quux"))),
+        (ErrorKind::NotFound, Error::TableNotFound { table: "Quux".into() })
+    );
+    assert_eq!(
+        msi_error(package.create_table(
+            "Foobar",
+            vec![Column::build("Key").primary_key().int16()]
+        )),
+        (
+            ErrorKind::AlreadyExists,
+            Error::TableAlreadyExists { table: "Foobar".into() }
+        )
+    );
+    let query = Select::table("Foobar").columns(&["Key", "Bogus"]);
+    assert_eq!(
+        msi_error(package.select_rows(query)),
+        (
+            ErrorKind::InvalidInput,
+            Error::ColumnNotFound {
+                table: "Foobar".into(),
+                column: "Bogus".into()
+            }
+        )
+    );
+    assert_eq!(
+        msi_error(package.read_stream("Blob")),
+        (ErrorKind::NotFound, Error::StreamNotFound { stream: "Blob".into() })
+    );
+}
+
+#[test]
+fn insert_errors() {
+    let mut package = create_foobar_package();
+    let query = Insert::into("Foobar")
+        .row(vec![Value::Int(2), Value::from("Two")])
+        .row(vec![Value::Int(3)]);
+    assert_eq!(
+        msi_error(package.insert_rows(query)),
+        (
+            ErrorKind::InvalidInput,
+            Error::WrongNumberOfValues {
+                table: "Foobar".into(),
+                row: 1,
+                expected: 2,
+                actual: 1,
+            }
+        )
+    );
+    let query = Insert::into("Foobar")
+        .row(vec![Value::Int(2), Value::from("Two")])
+        .row(vec![Value::Int(3), Value::from("Much too long")]);
+    assert_eq!(
+        msi_error(package.insert_rows(query)),
+        (
+            ErrorKind::InvalidInput,
+            Error::InvalidValue {
+                table: "Foobar".into(),
+                column: "Name".into(),
+                value: Value::from("Much too long"),
+                row: Some(1),
+            }
+        )
+    );
+    let query = Insert::into("Foobar")
+        .row(vec![Value::Int(2), Value::from("Two")])
+        .row(vec![Value::Int(1), Value::from("Uno")]);
+    assert_eq!(
+        msi_error(package.insert_rows(query)),
+        (
+            ErrorKind::AlreadyExists,
+            Error::DuplicateKey {
+                table: "Foobar".into(),
+                key: vec![Value::Int(1)],
+                row: 1,
+            }
+        )
+    );
+    let query = Insert::into("Foobar")
+        .row(vec![Value::Int(2), Value::from("Two")])
+        .row(vec![Value::Int(2), Value::from("Dos")]);
+    assert_eq!(
+        msi_error(package.insert_rows(query)),
+        (
+            ErrorKind::InvalidInput,
+            Error::RepeatedKey {
+                table: "Foobar".into(),
+                key: vec![Value::Int(2)],
+                row: 1,
+            }
+        )
+    );
+}
+
+#[test]
+fn update_errors() {
+    let mut package = create_foobar_package();
+    let query = Update::table("Foobar")
+        .set("Name", Value::Null)
+        .with(Expr::col("Key").eq(Expr::integer(1)));
+    assert_eq!(
+        msi_error(package.update_rows(query)),
+        (
+            ErrorKind::InvalidInput,
+            Error::InvalidValue {
+                table: "Foobar".into(),
+                column: "Name".into(),
+                value: Value::Null,
+                row: None,
+            }
+        )
+    );
+}
+
+#[test]
+fn other_errors_are_plain_io_errors() {
+    let mut package = create_foobar_package();
+    let error = package.drop_table("_Columns").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert!(Error::from_io(&error).is_none());
+}
+
+// ========================================================================= //
@@ -0,0 +1,298 @@
+#[macro_use]
+mod testutil;
+
+use msi::{
+    CodePage, Column, ColumnType, Expr, Insert, Package, Select, Value,
+};
+use std::fs;
+use std::io::{ErrorKind, Read, Write};
+
+// ========================================================================= //
+
+// ========================================================================= //
+
+#[test]
+fn export_idt() {
+    let mut package = testutil::create_notes_package();
+    testutil::insert_notes(&mut package);
+    let mut output = Vec::new();
+    package.export_idt("Notes", &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "Note\tPriority\tText\r\n\
+         s72\tI2\tL0\r\n\
+         Notes\tNote\r\n\
+         First\t-1\tSay \"hi\",\x11\x19then\x10leave\r\n\
+         Second\t\t\r\n"
+    );
+
+    assert_error!(
+        package.export_idt("Nonexistent", Vec::new()),
+        ErrorKind::NotFound,
+        "Table \"Nonexistent\" does not exist"
+    );
+}
+
+#[test]
+fn import_idt_round_trip() {
+    let mut package = testutil::create_notes_package();
+    testutil::insert_notes(&mut package);
+    let mut output = Vec::new();
+    package.export_idt("Notes", &mut output).unwrap();
+
+    let mut package2 = testutil::create_package();
+    let table_name = package2.import_idt(output.as_slice()).unwrap();
+    assert_eq!(table_name, "Notes");
+    let table = package2.get_table("Notes").unwrap();
+    let columns = table.columns();
+    assert!(columns[0].is_primary_key());
+    assert_eq!(columns[0].coltype(), ColumnType::Str(72));
+    assert_eq!(columns[1].coltype(), ColumnType::Int16);
+    assert!(columns[1].is_nullable());
+    assert!(columns[2].is_localizable());
+    assert_eq!(
+        testutil::select_values(&mut package2, "Notes"),
+        testutil::select_values(&mut package, "Notes")
+    );
+}
+
+#[test]
+fn import_idt_replaces_existing_table() {
+    let mut package = testutil::create_notes_package();
+    testutil::insert_notes(&mut package);
+
+    // Same columns: the rows are replaced.
+    let idt = "Note\tPriority\tText\r\ns72\tI2\tL0\r\nNotes\tNote\r\n\
+               Third\t3\t\r\n";
+    package.import_idt(idt.as_bytes()).unwrap();
+    assert_eq!(
+        testutil::select_values(&mut package, "Notes"),
+        vec![vec![Value::from("Third"), Value::Int(3), Value::Null]]
+    );
+
+    // Different columns: the table is recreated.
+    let idt = "Note\tDone\ns72\ti2\nNotes\tNote\nFourth\t1\n";
+    package.import_idt(idt.as_bytes()).unwrap();
+    assert_eq!(package.get_table("Notes").unwrap().columns().len(), 2);
+    assert_eq!(
+        testutil::select_values(&mut package, "Notes"),
+        vec![vec![Value::from("Fourth"), Value::Int(1)]]
+    );
+}
+
+#[test]
+fn import_idt_errors() {
+    let mut package = testutil::create_notes_package();
+    testutil::insert_notes(&mut package);
+    assert_error!(
+        package.import_idt("Note\ns72\n".as_bytes()),
+        ErrorKind::InvalidData,
+        "IDT file must start with three header lines"
+    );
+    assert_error!(
+        package.import_idt("Note\tPriority\ns72\nNotes\tNote\n".as_bytes()),
+        ErrorKind::InvalidData,
+        "IDT file for table \"Notes\" has 2 column names but 1 column types"
+    );
+    assert_error!(
+        package.import_idt("Note\ns72\nNotes\tKey\n".as_bytes()),
+        ErrorKind::InvalidData,
+        "IDT file for table \"Notes\" has primary key \"Key\", which is not \
+         one of its columns"
+    );
+    assert_error!(
+        package.import_idt("Note\tCount\ns72\tx4\nNotes\tNote\n".as_bytes()),
+        ErrorKind::InvalidData,
+        "Invalid IDT column type \"x4\" for column \"Count\""
+    );
+    assert_error!(
+        package.import_idt(
+            "Note\tCount\ns72\ti4\nNotes\tNote\nA\t1\nB\t2\t3\n".as_bytes()
+        ),
+        ErrorKind::InvalidData,
+        "Line 5 of IDT file for table \"Notes\" has 3 fields, but the table \
+         has 2 columns"
+    );
+    assert_error!(
+        package.import_idt(
+            "Note\tCount\ns72\ti4\nNotes\tNote\nA\tmany\n".as_bytes()
+        ),
+        ErrorKind::InvalidData,
+        "Line 4 of IDT file for table \"Notes\" has non-integer value \
+         \"many\" in column \"Count\""
+    );
+    // Failed imports leave the existing table alone.
+    assert_eq!(package.get_table("Notes").unwrap().columns().len(), 3);
+    assert_eq!(testutil::select_values(&mut package, "Notes").len(), 2);
+}
+
+#[test]
+fn force_codepage() {
+    let mut package = testutil::create_package();
+    package.set_database_codepage(CodePage::Windows1252);
+    let mut output = Vec::new();
+    package.export_idt("_ForceCodepage", &mut output).unwrap();
+    assert_eq!(output, b"\r\n\r\n1252\t_ForceCodepage\r\n");
+
+    let mut package2 = testutil::create_package();
+    let table_name = package2.import_idt(output.as_slice()).unwrap();
+    assert_eq!(table_name, "_ForceCodepage");
+    assert_eq!(package2.database_codepage(), CodePage::Windows1252);
+}
+
+#[test]
+fn non_ascii_values_record_codepage() {
+    let mut package = testutil::create_package();
+    package.set_database_codepage(CodePage::Windows1252);
+    let columns = vec![
+        Column::build("Key").primary_key().id_string(8),
+        Column::build("Word").string(16),
+    ];
+    package.create_table("Words", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Words")
+                .row(vec![Value::from("A"), Value::from("caf\u{e9}")]),
+        )
+        .unwrap();
+    let mut output = Vec::new();
+    package.export_idt("Words", &mut output).unwrap();
+    assert_eq!(
+        output,
+        b"Key\tWord\r\ns8\ts16\r\n1252\tWords\tKey\r\nA\tcaf\xe9\r\n"
+    );
+
+    let mut package2 = testutil::create_package();
+    package2.import_idt(output.as_slice()).unwrap();
+    assert_eq!(
+        testutil::select_values(&mut package2, "Words"),
+        vec![vec![Value::from("A"), Value::from("caf\u{e9}")]]
+    );
+}
+
+#[test]
+fn binary_side_car_files() {
+    let dir = testutil::temp_dir("binary_side_car_files");
+    let mut package = testutil::create_package();
+    let columns = vec![
+        Column::build("Name").primary_key().id_string(72),
+        Column::build("Data").binary(),
+    ];
+    package.create_table("Binary", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Binary")
+                .row(vec![Value::from("Icon"), Value::Binary]),
+        )
+        .unwrap();
+    package.write_stream("Binary.Icon").unwrap().write_all(b"xyzzy").unwrap();
+
+    let path = package.export_idt_to_dir("Binary", &dir).unwrap();
+    assert_eq!(path, dir.join("Binary.idt"));
+    assert_eq!(
+        fs::read(&path).unwrap(),
+        b"Name\tData\r\ns72\tv0\r\nBinary\tName\r\nIcon\tIcon.ibd\r\n"
+    );
+    assert_eq!(
+        fs::read(dir.join("Binary").join("Icon.ibd")).unwrap(),
+        b"xyzzy"
+    );
+
+    let mut package2 = testutil::create_package();
+    assert_error!(
+        package2.import_idt(fs::read(&path).unwrap().as_slice()),
+        ErrorKind::InvalidInput,
+        "IDT file for table \"Binary\" refers to binary data files, which can \
+         only be imported from a file on disk"
+    );
+    package2.import_idt_file(&path).unwrap();
+    assert_eq!(
+        testutil::select_values(&mut package2, "Binary"),
+        vec![vec![Value::from("Icon"), Value::Binary]]
+    );
+    let mut data = Vec::new();
+    package2
+        .read_stream("Binary.Icon")
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, b"xyzzy");
+}
+
+#[test]
+fn side_car_file_names_must_stay_in_dir() {
+    let dir = testutil::temp_dir("side_car_file_names_must_stay_in_dir");
+    let out_dir = dir.join("out");
+    let mut package = testutil::create_package();
+    let columns = vec![
+        Column::build("Name").primary_key().string(72),
+        Column::build("Data").binary(),
+    ];
+    package.create_table("Binary", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Binary")
+                .row(vec![Value::from("../../escaped"), Value::Binary]),
+        )
+        .unwrap();
+    assert_error!(
+        package.export_idt_to_dir("Binary", &out_dir),
+        ErrorKind::InvalidData,
+        "\"../../escaped.ibd\" is not a valid file name"
+    );
+    assert!(!out_dir.exists());
+    assert!(!dir.join("escaped.ibd").exists());
+
+    fs::create_dir_all(dir.join("Binary")).unwrap();
+    fs::write(dir.join("secret.ibd"), b"secret").unwrap();
+    for file_name in ["../secret.ibd", "..\\secret.ibd", "C:secret.ibd"] {
+        let path = dir.join("Binary.idt");
+        let idt = format!(
+            "Name\tData\r\ns72\tv0\r\nBinary\tName\r\nKey\t{file_name}\r\n"
+        );
+        fs::write(&path, idt).unwrap();
+        let mut package = testutil::create_package();
+        assert_error!(
+            package.import_idt_file(&path),
+            ErrorKind::InvalidData,
+            format!("{:?} is not a valid file name", file_name)
+        );
+        assert!(!package.has_table("Binary"));
+    }
+}
+
+#[test]
+fn imported_validation_rows_are_kept() {
+    let idt = "Table\tColumn\tNullable\tMinValue\tMaxValue\tKeyTable\t\
+               KeyColumn\tCategory\tSet\tDescription\r\n\
+               s32\ts32\ts4\tI4\tI4\tS255\tI2\tS32\tS255\tS255\r\n\
+               _Validation\tTable\tColumn\r\n\
+               Notes\tNote\tN\t\t\t\t\tIdentifier\t\tThe note's key\r\n\
+               Notes\tPriority\tY\t0\t10\t\t\t\t\t\r\n";
+    let mut package = testutil::create_package();
+    package.import_idt(idt.as_bytes()).unwrap();
+    let idt = "Note\tPriority\r\ns72\tI2\r\nNotes\tNote\r\nA\t5\r\n";
+    package.import_idt(idt.as_bytes()).unwrap();
+
+    let rows: Vec<_> = package
+        .select_rows(
+            Select::table("_Validation")
+                .with(Expr::col("Table").eq(Expr::string("Notes"))),
+        )
+        .unwrap()
+        .collect();
+    assert_eq!(rows.len(), 2);
+    let note = rows.iter().find(|row| row["Column"] == Value::from("Note"));
+    assert_eq!(note.unwrap()["Description"], Value::from("The note's key"));
+    let table = package.get_table("Notes").unwrap();
+    assert_eq!(table.columns()[1].value_range(), None);
+
+    // The imported validation is used once the package is reopened.
+    let cursor = package.into_inner().unwrap();
+    let package = Package::open(cursor).unwrap();
+    let table = package.get_table("Notes").unwrap();
+    assert_eq!(table.columns()[0].description(), Some("The note's key"));
+    assert_eq!(table.columns()[1].value_range(), Some((0, 10)));
+}
+
+// ========================================================================= //
@@ -0,0 +1,61 @@
+#[macro_use]
+mod testutil;
+
+use msi::{Insert, IntegrityProblem, Package, Value};
+use std::io::Write;
+
+// ========================================================================= //
+
+#[test]
+fn new_package_has_no_problems() {
+    let mut package = testutil::create_numbers_package();
+    assert_eq!(package.check_integrity().unwrap(), vec![]);
+    package.drop_table("Numbers").unwrap();
+    assert_eq!(package.check_integrity().unwrap(), vec![]);
+
+    let cursor = package.into_inner().unwrap();
+    let mut package = Package::open(cursor).unwrap();
+    assert_eq!(package.check_integrity().unwrap(), vec![]);
+}
+
+#[test]
+fn remove_orphaned_table_stream() {
+    let mut package = testutil::create_numbers_package();
+    package
+        .insert_rows(
+            Insert::into("Numbers")
+                .row(vec![Value::Int(1), Value::from("One")])
+                .row(vec![Value::Int(2), Value::from("Two")])
+                .row(vec![Value::Int(3), Value::from("One")]),
+        )
+        .unwrap();
+    let cursor = package.into_inner().unwrap();
+    let mut comp = cfb::CompoundFile::open(cursor).unwrap();
+    // A leading U+4840 character marks a stream as holding table data.
+    comp.create_stream("\u{4840}Junk").unwrap().write_all(b"junk").unwrap();
+    let cursor = comp.into_inner();
+
+    let mut package = Package::open(cursor).unwrap();
+    let problems = package.check_integrity().unwrap();
+    assert_eq!(
+        problems,
+        vec![IntegrityProblem::OrphanedStream { name: "Junk".to_string() }]
+    );
+    assert_eq!(
+        problems[0].to_string(),
+        "Table stream \"Junk\" doesn't belong to any table"
+    );
+    assert_eq!(package.repair().unwrap(), problems);
+
+    let cursor = package.into_inner().unwrap();
+    let comp = cfb::CompoundFile::open(cursor).unwrap();
+    assert!(!comp.exists("\u{4840}Junk"));
+    let mut package = Package::open(comp.into_inner()).unwrap();
+    assert_eq!(package.check_integrity().unwrap(), vec![]);
+    assert_eq!(
+        package.select_rows(msi::Select::table("Numbers")).unwrap().len(),
+        3
+    );
+}
+
+// ========================================================================= //
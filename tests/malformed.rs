@@ -0,0 +1,162 @@
+#[macro_use]
+mod testutil;
+
+use msi::{Column, Expr, Insert, OpenOptions, Package, Select};
+use msi::{Delete, Update, Value};
+use std::io::{Cursor, ErrorKind};
+
+// ========================================================================= //
+
+const FIRST_KEY: i32 = 0x12345;
+
+/// Creates a package with a "Words" table containing the given strings, keyed
+/// by consecutive integers starting at `FIRST_KEY`.
+fn create_words_package(words: &[&str]) -> Cursor<Vec<u8>> {
+    let mut package = testutil::create_package();
+    let columns = vec![
+        Column::build("Key").primary_key().int32(),
+        Column::build("Word").string(16),
+    ];
+    package.create_table("Words", columns).unwrap();
+    let mut query = Insert::into("Words");
+    for (index, &word) in words.iter().enumerate() {
+        query = query.row(vec![
+            Value::Int(FIRST_KEY + index as i32),
+            Value::from(word),
+        ]);
+    }
+    package.insert_rows(query).unwrap();
+    package.into_inner().unwrap()
+}
+
+/// Locates the "Words" table's data within the raw package data, and returns
+/// the offset of its first (two-byte) string reference.  Table data is stored
+/// column by column, so the string refs immediately follow the keys.
+fn string_refs_offset(cursor: &Cursor<Vec<u8>>, num_rows: usize) -> usize {
+    let keys: Vec<u8> = (0..num_rows)
+        .flat_map(|index| {
+            let key = (FIRST_KEY + index as i32) as u32 ^ 0x8000_0000;
+            key.to_le_bytes()
+        })
+        .collect();
+    let data = cursor.get_ref();
+    let position = data
+        .windows(keys.len())
+        .position(|window| window == keys.as_slice())
+        .expect("table data not found");
+    position + keys.len()
+}
+
+fn read_string_ref(cursor: &Cursor<Vec<u8>>, offset: usize) -> [u8; 2] {
+    [cursor.get_ref()[offset], cursor.get_ref()[offset + 1]]
+}
+
+fn write_string_ref(
+    cursor: &mut Cursor<Vec<u8>>,
+    offset: usize,
+    bytes: [u8; 2],
+) {
+    cursor.get_mut()[offset..][..2].copy_from_slice(&bytes);
+}
+
+fn select_words(package: &mut Package<Cursor<Vec<u8>>>) -> Vec<String> {
+    package
+        .select_rows(Select::table("Words"))
+        .unwrap()
+        .map(|row| {
+            let word = row.get_by_name("Word").unwrap();
+            word.as_str().unwrap().to_string()
+        })
+        .collect()
+}
+
+// ========================================================================= //
+
+#[test]
+fn dangling_string_reference() {
+    let mut cursor = create_words_package(&["Alpha", "Beta"]);
+    let offset = string_refs_offset(&cursor, 2);
+    write_string_ref(&mut cursor, offset, [0xff, 0x7f]);
+
+    // Reading the damaged table should report the corruption, rather than
+    // panicking or quietly returning an empty string.
+    let mut package = Package::open(cursor).unwrap();
+    let error = match package.select_rows(Select::table("Words")) {
+        Ok(_) => panic!("Expected reading the damaged table to fail"),
+        Err(error) => error,
+    };
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(error.to_string().starts_with(
+        "Row 0 of table \"Words\" refers to string #32767 in column \"Word\""
+    ));
+    assert!(matches!(
+        msi::Error::from_io(&error),
+        Some(msi::Error::Corrupt { stream, .. }) if stream == "Words"
+    ));
+
+    // So should trying to delete or overwrite it.
+    let error = package.delete_rows(Delete::from("Words")).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    let query = Update::table("Words").set("Word", Value::from("Gamma"));
+    let error = package.update_rows(query).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    let error = package.drop_table("Words").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+    // Repairing the package drops the damaged row.
+    package.repair().unwrap();
+    assert_eq!(select_words(&mut package), vec!["Beta"]);
+}
+
+#[test]
+fn string_referenced_more_than_refcount() {
+    let mut cursor = create_words_package(&["Alpha", "Beta"]);
+    let offset = string_refs_offset(&cursor, 2);
+    let alpha = read_string_ref(&cursor, offset);
+    write_string_ref(&mut cursor, offset + 2, alpha);
+
+    let mut package = Package::open(cursor).unwrap();
+    assert_eq!(select_words(&mut package), vec!["Alpha", "Alpha"]);
+    let error = package.delete_rows(Delete::from("Words")).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(
+        error.to_string().ends_with("more references than its refcount (1)")
+    );
+    assert_eq!(select_words(&mut package), vec!["Alpha", "Alpha"]);
+
+    // Repairing the package fixes the refcount, after which the rows can be
+    // deleted.
+    package.repair().unwrap();
+    package.delete_rows(Delete::from("Words")).unwrap();
+    assert!(select_words(&mut package).is_empty());
+}
+
+#[test]
+fn validate_package_with_nonstandard_validation_table() {
+    // Strip the _Validation table down to just its first two columns.
+    let mut package = testutil::create_package();
+    package.delete_rows(Delete::from("_Validation")).unwrap();
+    package
+        .delete_rows(
+            Delete::from("_Columns").with(
+                Expr::col("Table")
+                    .eq(Expr::string("_Validation"))
+                    .and(Expr::col("Number").gt(Expr::integer(2))),
+            ),
+        )
+        .unwrap();
+    let cursor = package.into_inner().unwrap();
+
+    let mut package = OpenOptions::new().lenient().open(cursor).unwrap();
+    let table = package.get_table("_Validation").unwrap();
+    assert_eq!(table.columns().len(), 2);
+    package
+        .insert_rows(
+            Insert::into("_Validation")
+                .row(vec![Value::from("_Columns"), Value::from("Name")]),
+        )
+        .unwrap();
+    assert!(package.validate().is_ok());
+}
+
+// ========================================================================= //
@@ -0,0 +1,195 @@
+#[macro_use]
+mod testutil;
+
+use msi::{Expr, Insert, OpenOptions, Package, Select, Update, Value};
+use std::io::{Cursor, ErrorKind};
+
+// ========================================================================= //
+
+#[test]
+fn open_undamaged_package_in_every_mode() {
+    let cursor = testutil::create_numbers_package().into_inner().unwrap();
+    let package = Package::open(cursor).unwrap();
+    assert!(package.open_warnings().is_empty());
+    let cursor = package.into_inner().unwrap();
+    let package = OpenOptions::new().strict().open(cursor).unwrap();
+    assert!(package.open_warnings().is_empty());
+    let cursor = package.into_inner().unwrap();
+    let package = OpenOptions::new().lenient().open(cursor).unwrap();
+    assert!(package.open_warnings().is_empty());
+    assert!(package.has_table("Numbers"));
+}
+
+/// Replaces every occurrence of `from` in the raw package data with `to`,
+/// which must have the same length.
+fn patch_bytes(cursor: &mut Cursor<Vec<u8>>, from: &[u8], to: &[u8]) {
+    assert_eq!(from.len(), to.len());
+    let data = cursor.get_mut();
+    let mut position = 0;
+    while position + from.len() <= data.len() {
+        if &data[position..][..from.len()] == from {
+            data[position..][..to.len()].copy_from_slice(to);
+            position += from.len();
+        } else {
+            position += 1;
+        }
+    }
+}
+
+#[test]
+fn unknown_category() {
+    let mut package = testutil::create_numbers_package();
+    package
+        .update_rows(
+            Update::table("_Validation")
+                .set("Category", Value::from("UpperCase"))
+                .with(Expr::col("Column").eq(Expr::string("Word"))),
+        )
+        .unwrap();
+    let mut cursor = package.into_inner().unwrap();
+    patch_bytes(&mut cursor, b"UpperCase", b"BogusCase");
+
+    assert_error!(
+        OpenOptions::new().strict().open(cursor.clone()),
+        ErrorKind::InvalidData,
+        "Unknown category \"BogusCase\" for column \"Word\" of table \"Numbers\""
+    );
+
+    let package = Package::open(cursor.clone()).unwrap();
+    assert!(package.open_warnings().is_empty());
+    let column = package.get_table("Numbers").unwrap().get_column("Word");
+    assert_eq!(column.unwrap().category(), None);
+
+    let package = OpenOptions::new().lenient().open(cursor).unwrap();
+    assert_eq!(package.open_warnings().len(), 1);
+    assert!(package.has_table("Numbers"));
+}
+
+#[test]
+fn copied_columns_keep_original_metadata() {
+    let mut package = testutil::create_numbers_package();
+    package
+        .update_rows(
+            Update::table("_Validation")
+                .set("Category", Value::from("UpperCase"))
+                .set("Description", Value::from("A number word"))
+                .with(Expr::col("Column").eq(Expr::string("Word"))),
+        )
+        .unwrap();
+    // Set a type bit that this library doesn't know the meaning of.
+    let type_bits = package
+        .select_rows(
+            Select::table("_Columns")
+                .with(Expr::col("Name").eq(Expr::string("Word"))),
+        )
+        .unwrap()
+        .next()
+        .unwrap()["Type"]
+        .as_int()
+        .unwrap();
+    package
+        .update_rows(
+            Update::table("_Columns")
+                .set("Type", Value::Int(type_bits | 0x4000))
+                .with(Expr::col("Name").eq(Expr::string("Word"))),
+        )
+        .unwrap();
+    let mut cursor = package.into_inner().unwrap();
+    patch_bytes(&mut cursor, b"UpperCase", b"BogusCase");
+
+    let package = Package::open(cursor).unwrap();
+    let columns = package.get_table("Numbers").unwrap().columns().to_vec();
+    assert_eq!(columns[1].category(), None);
+    assert_eq!(columns[1].unknown_category(), Some("BogusCase"));
+
+    let mut copy = testutil::create_package();
+    copy.create_table("Numbers", columns).unwrap();
+    let row = copy
+        .select_rows(
+            Select::table("_Columns")
+                .with(Expr::col("Name").eq(Expr::string("Word"))),
+        )
+        .unwrap()
+        .next()
+        .unwrap();
+    assert_eq!(row["Type"], Value::Int(type_bits | 0x4000));
+    let row = copy
+        .select_rows(
+            Select::table("_Validation")
+                .with(Expr::col("Column").eq(Expr::string("Word"))),
+        )
+        .unwrap()
+        .next()
+        .unwrap();
+    assert_eq!(row["Category"], Value::from("BogusCase"));
+    assert_eq!(row["Description"], Value::from("A number word"));
+}
+
+#[test]
+fn validation_entry_for_missing_column() {
+    let mut package = testutil::create_numbers_package();
+    package
+        .insert_rows(Insert::into("_Validation").row(vec![
+            Value::from("Numbers"),
+            Value::from("Missing"),
+            Value::from("N"),
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+        ]))
+        .unwrap();
+    let cursor = package.into_inner().unwrap();
+
+    assert_error!(
+        OpenOptions::new().strict().open(cursor.clone()),
+        ErrorKind::InvalidData,
+        "_Validation mentions column \"Missing\" of table \"Numbers\", which \
+         doesn't exist"
+    );
+    let package = Package::open(cursor.clone()).unwrap();
+    assert!(package.open_warnings().is_empty());
+    let package = OpenOptions::new().lenient().open(cursor).unwrap();
+    assert_eq!(package.open_warnings().len(), 1);
+}
+
+#[test]
+fn columns_for_missing_table() {
+    let mut package = testutil::create_numbers_package();
+    package
+        .insert_rows(Insert::into("_Columns").row(vec![
+            Value::from("Ghost"),
+            Value::Int(1),
+            Value::from("Boo"),
+            Value::Int(0x2d02),
+        ]))
+        .unwrap();
+    let cursor = package.into_inner().unwrap();
+
+    assert_error!(
+        Package::open(cursor.clone()),
+        ErrorKind::InvalidData,
+        "_Columns mentions table \"Ghost\", which isn't in _Tables"
+    );
+    let package = OpenOptions::new().lenient().open(cursor).unwrap();
+    assert_eq!(
+        package.open_warnings(),
+        &["_Columns mentions table \"Ghost\", which isn't in _Tables"]
+    );
+    assert!(package.has_table("Numbers"));
+    assert!(!package.has_table("Ghost"));
+}
+
+#[test]
+fn missing_string_pool() {
+    let comp = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+    let cursor = comp.into_inner();
+    assert!(Package::open(cursor.clone()).is_err());
+    let package = OpenOptions::new().lenient().open(cursor).unwrap();
+    assert!(!package.open_warnings().is_empty());
+}
+
+// ========================================================================= //
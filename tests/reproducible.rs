@@ -0,0 +1,122 @@
+#[macro_use]
+mod testutil;
+
+use msi::{Column, Expr, Insert, Package, PackageType, Update, Value};
+use std::io::{Cursor, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// ========================================================================= //
+
+fn build_package(creation_time: SystemTime) -> Package<Cursor<Vec<u8>>> {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    package.summary_info_mut().set_creation_time(creation_time);
+    package.summary_info_mut().set_last_save_time_to_now();
+    let columns = vec![
+        Column::build("Number").primary_key().int16(),
+        Column::build("Word").nullable().string(50),
+    ];
+    package.create_table("Numbers", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Numbers")
+                .row(vec![Value::Int(1), Value::from("One")])
+                .row(vec![Value::Int(2), Value::from("Two")]),
+        )
+        .unwrap();
+    package
+}
+
+// ========================================================================= //
+
+#[test]
+fn reproducible_output_is_byte_identical() {
+    let build_time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut package1 = build_package(SystemTime::now());
+    package1.set_reproducible_with_time(build_time);
+    assert!(package1.is_reproducible());
+    let mut package2 = build_package(UNIX_EPOCH);
+    package2.set_reproducible_with_time(build_time);
+    let bytes1 = package1.into_inner().unwrap().into_inner();
+    let bytes2 = package2.into_inner().unwrap().into_inner();
+    assert!(bytes1 == bytes2, "reproducible packages differ");
+
+    let package = Package::open(Cursor::new(bytes1)).unwrap();
+    assert_eq!(package.summary_info().creation_time(), Some(build_time));
+    assert_eq!(package.summary_info().last_saved_time(), Some(build_time));
+    assert_eq!(package.summary_info().last_printed(), None);
+    let comp = cfb::CompoundFile::open(package.into_inner().unwrap()).unwrap();
+    assert_eq!(comp.root_entry().modified(), build_time);
+    assert_eq!(comp.root_entry().created(), build_time);
+}
+
+#[test]
+fn reproducible_output_after_reopening() {
+    let build_time = UNIX_EPOCH + Duration::from_secs(1_234_567_890);
+    let cursor = build_package(SystemTime::now()).into_inner().unwrap();
+    let mut comp = cfb::CompoundFile::open(cursor).unwrap();
+    comp.set_modified_time("/", SystemTime::now()).unwrap();
+    let template = comp.into_inner().into_inner();
+
+    let outputs: Vec<Vec<u8>> = ["Uno", "Dos"]
+        .into_iter()
+        .map(|word| {
+            let cursor = Cursor::new(template.clone());
+            let mut package = Package::open(cursor).unwrap();
+            package.set_reproducible_with_time(build_time);
+            package
+                .update_rows(
+                    Update::table("Numbers")
+                        .set("Word", Value::from(word))
+                        .with(Expr::col("Number").eq(Expr::integer(1))),
+                )
+                .unwrap();
+            package
+                .update_rows(
+                    Update::table("Numbers")
+                        .set("Word", Value::from("Uno"))
+                        .with(Expr::col("Number").eq(Expr::integer(1))),
+                )
+                .unwrap();
+            package.into_inner().unwrap().into_inner()
+        })
+        .collect();
+    assert!(outputs[0] == outputs[1], "reproducible packages differ");
+}
+
+#[test]
+fn save_as_is_independent_of_build_history() {
+    let build_time = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+    let mut package1 = build_package(UNIX_EPOCH);
+    package1.set_reproducible_with_time(build_time);
+    let saved1 = package1.save_as(Cursor::new(Vec::new())).unwrap();
+
+    // Build the same contents by a roundabout route, leaving unused sectors
+    // and directory entries behind, and with stray state bits.
+    let mut package2 = build_package(SystemTime::now());
+    package2.write_stream("Junk").unwrap().write_all(&[0; 5000]).unwrap();
+    let columns = vec![Column::build("Key").primary_key().int16()];
+    package2.create_table("Temp", columns).unwrap();
+    package2.flush().unwrap();
+    package2.drop_table("Temp").unwrap();
+    package2.remove_stream("Junk").unwrap();
+    let mut comp =
+        cfb::CompoundFile::open(package2.into_inner().unwrap()).unwrap();
+    comp.set_state_bits("/", 7).unwrap();
+    let mut package2 = Package::open(comp.into_inner()).unwrap();
+    package2.set_reproducible_with_time(build_time);
+    let saved2 = package2.save_as(Cursor::new(Vec::new())).unwrap();
+
+    assert!(
+        saved1.get_ref() == saved2.get_ref(),
+        "reproducible packages differ"
+    );
+    let comp = cfb::CompoundFile::open(saved2).unwrap();
+    assert_eq!(
+        comp.root_entry().clsid().hyphenated().to_string(),
+        "000c1084-0000-0000-c000-000000000046"
+    );
+    assert_eq!(comp.root_entry().state_bits(), 0);
+}
+
+// ========================================================================= //
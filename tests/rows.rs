@@ -0,0 +1,197 @@
+#[macro_use]
+mod testutil;
+
+use msi::rows::{
+    ComponentAttributes, ComponentRow, CustomActionRow, CustomActionType,
+    FileAttributes, FileRow, PropertyRow, ShortcutRow, TypedRow,
+};
+use msi::schema::SchemaVersion;
+use msi::{Column, Insert, Package, Select, Value};
+use std::io::ErrorKind;
+
+// ========================================================================= //
+
+fn component_row() -> ComponentRow {
+    ComponentRow {
+        component: "MainComponent".to_string(),
+        component_id: Some(
+            "{5D6C1F2E-9C8B-4B5A-8E3D-2F1A0B9C8D7E}".to_string(),
+        ),
+        directory: "INSTALLDIR".to_string(),
+        attributes: ComponentAttributes::WIN64
+            | ComponentAttributes::PERMANENT,
+        condition: None,
+        key_path: Some("MainExe".to_string()),
+    }
+}
+
+fn file_row() -> FileRow {
+    FileRow {
+        file: "MainExe".to_string(),
+        component: "MainComponent".to_string(),
+        file_name: "MAIN~1.EXE|Main Program.exe".to_string(),
+        file_size: 12345,
+        version: Some("1.2.3.4".to_string()),
+        language: Some("1033".to_string()),
+        attributes: FileAttributes::VITAL,
+        sequence: 1,
+    }
+}
+
+// ========================================================================= //
+
+#[test]
+fn typed_rows_round_trip() {
+    let mut package = testutil::create_package();
+    package.create_standard_table("Component").unwrap();
+    package.create_standard_table("File").unwrap();
+    package.insert_typed_rows(vec![component_row()]).unwrap();
+    package.insert_typed_rows(vec![file_row()]).unwrap();
+
+    let cursor = package.into_inner().unwrap();
+    let mut package = Package::open(cursor).unwrap();
+    assert_eq!(package.components().unwrap(), vec![component_row()]);
+    assert_eq!(package.files().unwrap(), vec![file_row()]);
+
+    let row = package.select_rows(Select::table("File")).unwrap().next();
+    let file = FileRow::try_from(row.unwrap()).unwrap();
+    assert!(file.attributes.contains(FileAttributes::VITAL));
+    assert!(!file.attributes.contains(FileAttributes::COMPRESSED));
+}
+
+#[test]
+fn empty_file_attributes_are_null() {
+    let mut package = testutil::create_package();
+    package.create_standard_table("File").unwrap();
+    let file = FileRow { attributes: FileAttributes::empty(), ..file_row() };
+    package.insert_typed_rows(vec![file.clone()]).unwrap();
+    let row = package.select_rows(Select::table("File")).unwrap().next();
+    assert!(row.unwrap()["Attributes"].is_null());
+    assert_eq!(package.files().unwrap(), vec![file]);
+}
+
+#[test]
+fn custom_action_type_flags() {
+    let mut package = testutil::create_package();
+    package.create_standard_table("CustomAction").unwrap();
+    package
+        .insert_rows(Insert::into("CustomAction").row(vec![
+            Value::from("DoSomething"),
+            Value::Int(0x0c01),
+            Value::from("CustomActionsDll"),
+            Value::from("DoSomething"),
+            Value::Null,
+        ]))
+        .unwrap();
+    let actions = package.custom_actions().unwrap();
+    assert_eq!(actions.len(), 1);
+    let action_type = actions[0].action_type;
+    assert_eq!(action_type.base_type(), 1);
+    assert_eq!(action_type.source_type(), 0);
+    assert!(action_type.contains(CustomActionType::IN_SCRIPT));
+    assert!(action_type.contains(CustomActionType::NO_IMPERSONATE));
+    assert_eq!(action_type.bits(), 0x0c01);
+    assert_eq!(actions[0].extended_type, None);
+}
+
+#[test]
+fn columns_from_later_schema_versions() {
+    let mut package = testutil::create_package();
+    package.summary_info_mut().set_page_count(SchemaVersion::V300.number());
+    package.create_standard_table("CustomAction").unwrap();
+    package.create_standard_table("Shortcut").unwrap();
+
+    // Rows whose newer columns are null can be inserted into older tables.
+    let action = CustomActionRow {
+        action: "SetFoo".to_string(),
+        action_type: CustomActionType::from_bits(51),
+        source: Some("FOO".to_string()),
+        target: Some("bar".to_string()),
+        extended_type: None,
+    };
+    package.insert_typed_rows(vec![action.clone()]).unwrap();
+    assert_eq!(package.custom_actions().unwrap(), vec![action.clone()]);
+
+    // But rows whose newer columns have values cannot.
+    let action = CustomActionRow {
+        action: "SetBar".to_string(),
+        extended_type: Some(0),
+        ..action
+    };
+    assert_error!(
+        package.insert_typed_rows(vec![action]),
+        ErrorKind::InvalidInput,
+        "Table \"CustomAction\" has no column named \"ExtendedType\""
+    );
+    assert_eq!(package.custom_actions().unwrap().len(), 1);
+
+    let shortcuts = package.typed_rows::<ShortcutRow>().unwrap();
+    assert!(shortcuts.is_empty());
+}
+
+#[test]
+fn typed_rows_errors() {
+    let mut package = testutil::create_package();
+    assert_error!(
+        package.files(),
+        ErrorKind::NotFound,
+        "Table \"File\" does not exist"
+    );
+    assert_error!(
+        package.insert_typed_rows(vec![file_row()]),
+        ErrorKind::NotFound,
+        "Table \"File\" does not exist"
+    );
+
+    // A non-standard table with the same name as a standard one.
+    package
+        .create_table(
+            FileRow::TABLE_NAME,
+            vec![
+                Column::build("File").primary_key().id_string(72),
+                Column::build("Sequence").string(16),
+            ],
+        )
+        .unwrap();
+    package
+        .insert_rows(
+            Insert::into("File")
+                .row(vec![Value::from("Foo"), Value::from("Bar")]),
+        )
+        .unwrap();
+    assert_error!(
+        package.files(),
+        ErrorKind::InvalidInput,
+        "Table \"File\" has no column named \"Component_\""
+    );
+
+    // A column with the wrong type of value.
+    package
+        .create_table(
+            PropertyRow::TABLE_NAME,
+            vec![
+                Column::build("Property").primary_key().id_string(72),
+                Column::build("Value").int16(),
+            ],
+        )
+        .unwrap();
+    package
+        .insert_rows(
+            Insert::into("Property")
+                .row(vec![Value::from("Answer"), Value::Int(42)]),
+        )
+        .unwrap();
+    let error = package.typed_rows::<PropertyRow>().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert_eq!(
+        msi::Error::from_io(&error),
+        Some(&msi::Error::InvalidValue {
+            table: "Property".to_string(),
+            column: "Value".to_string(),
+            value: Value::Int(42),
+            row: None,
+        })
+    );
+}
+
+// ========================================================================= //
@@ -0,0 +1,140 @@
+#[macro_use]
+mod testutil;
+
+use cfb::CompoundFile;
+use msi::{Column, Insert, Package, PackageType, SaveOptions, Select, Value};
+use std::cell::Cell;
+use std::io::{Cursor, ErrorKind, Read, Seek, Write};
+use std::rc::Rc;
+
+// ========================================================================= //
+
+fn read_stream<F: Read + Seek>(
+    package: &mut Package<F>,
+    stream_name: &str,
+) -> Vec<u8> {
+    let mut data = Vec::new();
+    package.read_stream(stream_name).unwrap().read_to_end(&mut data).unwrap();
+    data
+}
+
+// ========================================================================= //
+
+#[test]
+fn save_as_copies_contents() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Patch, cursor).unwrap();
+    package.summary_info_mut().set_author("Jane Doe".to_string());
+    let columns = vec![
+        Column::build("Key").primary_key().int16(),
+        Column::build("Value").nullable().string(32),
+    ];
+    package.create_table("Foobar", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Foobar")
+                .row(vec![Value::Int(1), Value::from("One")])
+                .row(vec![Value::Int(2), Value::Null]),
+        )
+        .unwrap();
+    package.write_stream("Blob").unwrap().write_all(b"Hello!").unwrap();
+
+    // Don't flush first; save_as() should take care of that.
+    let copy = package.save_as(Cursor::new(Vec::new())).unwrap();
+    let mut copy = Package::open(copy).unwrap();
+    assert_eq!(copy.package_type(), PackageType::Patch);
+    assert_eq!(copy.summary_info().author(), Some("Jane Doe"));
+    let rows: Vec<(i32, Option<String>)> = copy
+        .select_rows(Select::table("Foobar"))
+        .unwrap()
+        .map(|row| {
+            (row[0].as_int().unwrap(), row[1].as_str().map(str::to_string))
+        })
+        .collect();
+    assert_eq!(rows, vec![(1, Some("One".to_string())), (2, None)]);
+    assert_eq!(read_stream(&mut copy, "Blob"), b"Hello!");
+    assert!(copy.check_integrity().unwrap().is_empty());
+}
+
+#[test]
+fn save_as_reclaims_removed_streams() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    package.write_stream("Big").unwrap().write_all(&[0x55; 100_000]).unwrap();
+    package.write_stream("Small").unwrap().write_all(b"small").unwrap();
+    package.flush().unwrap();
+    package.remove_stream("Big").unwrap();
+    let original = package.into_inner().unwrap().into_inner();
+
+    let mut package = Package::open(Cursor::new(original.clone())).unwrap();
+    let copy = package.save_as(Cursor::new(Vec::new())).unwrap().into_inner();
+    assert!(copy.len() < original.len() / 2);
+    let mut copy = Package::open(Cursor::new(copy)).unwrap();
+    assert!(!copy.has_stream("Big"));
+    assert_eq!(read_stream(&mut copy, "Small"), b"small");
+}
+
+#[test]
+fn save_as_strips_digital_signature() {
+    // Create a new package and edit it to add a (bogus) digital signature.
+    let cursor = Cursor::new(Vec::new());
+    let package = Package::create(PackageType::Installer, cursor).unwrap();
+    let mut comp = CompoundFile::open(package.into_inner().unwrap()).unwrap();
+    comp.create_stream("\u{5}DigitalSignature")
+        .unwrap()
+        .write_all(b"foo")
+        .unwrap();
+    let mut package = Package::open(comp.into_inner()).unwrap();
+    assert!(package.has_digital_signature());
+
+    let copy = package.save_as(Cursor::new(Vec::new())).unwrap();
+    assert!(Package::open(copy).unwrap().has_digital_signature());
+
+    let copy = SaveOptions::new()
+        .strip_digital_signature()
+        .save(&mut package, Cursor::new(Vec::new()))
+        .unwrap();
+    assert!(!Package::open(copy).unwrap().has_digital_signature());
+}
+
+#[test]
+fn failed_save_as_keeps_changes_buffered() {
+    let mut package = testutil::create_numbers_package();
+    package.flush().unwrap();
+    let cursor = package.into_inner().unwrap();
+    let fail = Rc::new(Cell::new(false));
+    let writer = testutil::FailingWriter { inner: cursor, fail: fail.clone() };
+    let mut package = Package::open(writer).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Numbers")
+                .row(vec![Value::Int(1), Value::from("One")]),
+        )
+        .unwrap();
+    fail.set(true);
+    assert_error!(
+        package.save_as(Cursor::new(Vec::new())),
+        ErrorKind::Other,
+        "Disk is full"
+    );
+    fail.set(false);
+    let copy = package.save_as(Cursor::new(Vec::new())).unwrap();
+    let mut copy = Package::open(copy).unwrap();
+    let rows = copy.select_rows(Select::table("Numbers")).unwrap();
+    assert_eq!(rows.len(), 1);
+}
+
+#[test]
+fn cannot_save_during_transaction() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    let result = package
+        .transaction(|package| package.save_as(Cursor::new(Vec::new())));
+    assert_error!(
+        result,
+        ErrorKind::InvalidInput,
+        "Cannot save a package during a transaction"
+    );
+}
+
+// ========================================================================= //
@@ -0,0 +1,106 @@
+#[macro_use]
+mod testutil;
+
+use msi::schema::{self, SchemaVersion};
+use msi::{Category, ColumnType, Package, PackageType};
+use std::io::{Cursor, ErrorKind};
+
+// ========================================================================= //
+
+#[test]
+fn create_every_standard_table() {
+    for version in SchemaVersion::all() {
+        let cursor = Cursor::new(Vec::new());
+        let mut package =
+            Package::create(PackageType::Installer, cursor).unwrap();
+        package.summary_info_mut().set_page_count(version.number());
+        let table_names = schema::table_names(version);
+        for &table_name in &table_names {
+            package.create_standard_table(table_name).unwrap();
+        }
+        assert!(package.check_integrity().unwrap().is_empty());
+
+        let cursor = package.into_inner().unwrap();
+        let package = Package::open(cursor).unwrap();
+        assert!(package.open_warnings().is_empty());
+        for table_name in table_names {
+            assert!(package.has_table(table_name));
+        }
+    }
+}
+
+#[test]
+fn standard_table_columns() {
+    let columns = schema::table_columns("Component", SchemaVersion::V500);
+    let columns = columns.unwrap();
+    let names: Vec<&str> =
+        columns.iter().map(|column| column.name()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "Component",
+            "ComponentId",
+            "Directory_",
+            "Attributes",
+            "Condition",
+            "KeyPath"
+        ]
+    );
+    assert!(columns[0].is_primary_key());
+    assert_eq!(columns[0].coltype(), ColumnType::Str(72));
+    assert_eq!(columns[1].category(), Some(Category::Guid));
+    assert!(columns[1].is_nullable());
+    assert!(!columns[2].is_nullable());
+    assert_eq!(columns[3].coltype(), ColumnType::Int16);
+    assert_eq!(columns[4].category(), Some(Category::Condition));
+
+    assert!(schema::is_standard_table("Registry"));
+    assert!(!schema::is_standard_table("Foobar"));
+    assert!(schema::table_columns("Foobar", SchemaVersion::V500).is_none());
+}
+
+#[test]
+fn schema_versions() {
+    assert!(schema::table_names(SchemaVersion::V200).contains(&"File"));
+    assert!(
+        !schema::table_names(SchemaVersion::V400).contains(&"MsiEmbeddedUI")
+    );
+    assert!(
+        schema::table_names(SchemaVersion::V450).contains(&"MsiEmbeddedUI")
+    );
+    let shortcut =
+        |version| schema::table_columns("Shortcut", version).unwrap().len();
+    assert_eq!(shortcut(SchemaVersion::V300), 12);
+    assert_eq!(shortcut(SchemaVersion::V400), 16);
+    assert_eq!(SchemaVersion::from_number(450), SchemaVersion::V450);
+    assert_eq!(SchemaVersion::from_number(405), SchemaVersion::V400);
+}
+
+#[test]
+fn create_standard_table_uses_package_schema_version() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    package.summary_info_mut().set_page_count(200);
+    assert_error!(
+        package.create_standard_table("MsiEmbeddedUI"),
+        ErrorKind::InvalidInput,
+        "\"MsiEmbeddedUI\" is not a standard table in schema version 2.0"
+    );
+    assert_error!(
+        package.create_standard_table("Foobar"),
+        ErrorKind::InvalidInput,
+        "\"Foobar\" is not a standard table in schema version 2.0"
+    );
+    package.create_standard_table("Shortcut").unwrap();
+    assert_eq!(package.get_table("Shortcut").unwrap().columns().len(), 12);
+    assert_error!(
+        package.create_standard_table("Shortcut"),
+        ErrorKind::AlreadyExists,
+        "Table \"Shortcut\" already exists"
+    );
+
+    package.summary_info_mut().set_page_count(500);
+    package.create_standard_table("MsiEmbeddedUI").unwrap();
+}
+
+// ========================================================================= //
@@ -1,7 +1,7 @@
 #[macro_use]
 mod testutil;
 
-use msi::{Column, Expr, Insert, Package, PackageType, Select, Value};
+use msi::{Column, Expr, Insert, Package, PackageType, Select, Update, Value};
 use std::io::{Cursor, ErrorKind};
 
 //===========================================================================//
@@ -203,4 +203,94 @@ fn nested_inner_join() {
     assert_eq!(values, vec![(6, 3, 2), (7, 4, 1)]);
 }
 
+#[test]
+fn select_rows_inserted_one_at_a_time() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    let columns = vec![
+        Column::build("Foo").primary_key().int32(),
+        Column::build("Bar").string(16),
+    ];
+    package.create_table("Quux", columns).unwrap();
+    for index in 0..1000 {
+        let query = Insert::into("Quux")
+            .row(vec![Value::Int(index), Value::Str(format!("bar{}", index))]);
+        package.insert_rows(query).unwrap();
+    }
+    let query = Insert::into("Quux")
+        .row(vec![Value::Int(500), Value::Str("dup".to_string())]);
+    assert_error!(
+        package.insert_rows(query),
+        ErrorKind::AlreadyExists,
+        "Table \"Quux\" already contains a row with key [Int(500)]"
+    );
+
+    let query =
+        Select::table("Quux").with(Expr::col("Foo").lt(Expr::integer(3)));
+    let rows = package.select_rows(query).unwrap();
+    let values: Vec<(i32, String)> = rows
+        .map(|row| {
+            (row[0].as_int().unwrap(), row[1].as_str().unwrap().to_string())
+        })
+        .collect();
+    let expected = vec![
+        (0, "bar0".to_string()),
+        (1, "bar1".to_string()),
+        (2, "bar2".to_string()),
+    ];
+    assert_eq!(values, expected);
+
+    let cursor = package.into_inner().unwrap();
+    let mut package = Package::open(cursor).unwrap();
+    let rows = package.select_rows(Select::table("Quux")).unwrap();
+    assert_eq!(rows.len(), 1000);
+    let query =
+        Select::table("Quux").with(Expr::col("Foo").lt(Expr::integer(3)));
+    let rows = package.select_rows(query).unwrap();
+    let values: Vec<(i32, String)> = rows
+        .map(|row| {
+            (row[0].as_int().unwrap(), row[1].as_str().unwrap().to_string())
+        })
+        .collect();
+    assert_eq!(values, expected);
+}
+
+#[test]
+fn rows_stay_in_key_order_across_flush() {
+    fn keys<F: std::io::Read + std::io::Seek>(
+        package: &mut Package<F>,
+    ) -> Vec<i32> {
+        let rows = package.select_rows(Select::table("Quux")).unwrap();
+        rows.map(|row| row[0].as_int().unwrap()).collect()
+    }
+
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    let columns = vec![
+        Column::build("Foo").primary_key().int32(),
+        Column::build("Bar").string(16),
+    ];
+    package.create_table("Quux", columns).unwrap();
+    for index in [5, 1, 3] {
+        let query = Insert::into("Quux")
+            .row(vec![Value::Int(index), Value::Str(format!("bar{}", index))]);
+        package.insert_rows(query).unwrap();
+    }
+    assert_eq!(keys(&mut package), vec![1, 3, 5]);
+    package.flush().unwrap();
+    assert_eq!(keys(&mut package), vec![1, 3, 5]);
+
+    let query = Update::table("Quux")
+        .set("Foo", Value::Int(9))
+        .with(Expr::col("Foo").eq(Expr::integer(1)));
+    package.update_rows(query).unwrap();
+    assert_eq!(keys(&mut package), vec![3, 5, 9]);
+    package.flush().unwrap();
+    assert_eq!(keys(&mut package), vec![3, 5, 9]);
+
+    let cursor = package.into_inner().unwrap();
+    let mut package = Package::open(cursor).unwrap();
+    assert_eq!(keys(&mut package), vec![3, 5, 9]);
+}
+
 //===========================================================================//
@@ -0,0 +1,189 @@
+use msi::{
+    Category, CodePage, Column, ColumnType, Insert, LanguageId, Package,
+    PackageType, Select, SummaryInfo, Value,
+};
+use serde_json::json;
+use std::io::Cursor;
+use std::time::{Duration, UNIX_EPOCH};
+
+// ========================================================================= //
+
+#[test]
+fn values() {
+    let values = vec![
+        Value::Null,
+        Value::Int(-42),
+        Value::from("Hello"),
+        Value::Binary,
+    ];
+    let json = serde_json::to_value(&values).unwrap();
+    assert_eq!(json, json!([null, -42, "Hello", []]));
+    let round_trip: Vec<Value> = serde_json::from_value(json).unwrap();
+    assert_eq!(round_trip, values);
+
+    assert!(serde_json::from_value::<Value>(json!(1u64 << 40)).is_err());
+    assert!(serde_json::from_value::<Value>(json!([1])).is_err());
+}
+
+#[test]
+fn simple_types() {
+    assert_eq!(serde_json::to_value(Category::Guid).unwrap(), json!("GUID"));
+    let category: Category =
+        serde_json::from_value(json!("Filename")).unwrap();
+    assert_eq!(category, Category::Filename);
+    assert!(serde_json::from_value::<Category>(json!("Bogus")).is_err());
+
+    assert_eq!(
+        serde_json::to_value(CodePage::Windows1252).unwrap(),
+        json!(1252)
+    );
+    let codepage: CodePage = serde_json::from_value(json!(932)).unwrap();
+    assert_eq!(codepage, CodePage::Windows932);
+    assert!(serde_json::from_value::<CodePage>(json!(12345)).is_err());
+
+    let language = LanguageId::from_id(1033);
+    assert_eq!(serde_json::to_value(language).unwrap(), json!(1033));
+    let round_trip: LanguageId = serde_json::from_value(json!(1033)).unwrap();
+    assert_eq!(round_trip, language);
+
+    assert_eq!(
+        serde_json::to_value(ColumnType::Int16).unwrap(),
+        json!("Int16")
+    );
+    assert_eq!(
+        serde_json::to_value(ColumnType::Str(72)).unwrap(),
+        json!({"Str": 72})
+    );
+}
+
+#[test]
+fn columns() {
+    let column = Column::build("Directory_")
+        .nullable()
+        .foreign_key("Directory", 1)
+        .category(Category::Identifier)
+        .description("The parent directory")
+        .string(72);
+    let json = serde_json::to_value(&column).unwrap();
+    assert_eq!(
+        json,
+        json!({
+            "name": "Directory_",
+            "type": {"Str": 72},
+            "localizable": false,
+            "nullable": true,
+            "primary_key": false,
+            "foreign_key": ["Directory", 1],
+            "category": "Identifier",
+            "description": "The parent directory",
+        })
+    );
+    let round_trip: Column = serde_json::from_value(json).unwrap();
+    assert_eq!(round_trip.name(), "Directory_");
+    assert_eq!(round_trip.coltype(), ColumnType::Str(72));
+    assert!(round_trip.is_nullable());
+    assert_eq!(round_trip.validation(), column.validation());
+
+    let column: Column = serde_json::from_value(json!({
+        "name": "Number",
+        "type": "Int32",
+    }))
+    .unwrap();
+    assert_eq!(column.coltype(), ColumnType::Int32);
+    assert!(!column.is_primary_key());
+
+    // Deserializing goes through the column builder's checks.
+    let result = serde_json::from_value::<Column>(json!({
+        "name": "Not a name",
+        "type": "Int16",
+    }));
+    assert!(result.is_err());
+
+    // Columns read from a package serialize just like built ones, without
+    // any internal details such as the raw `_Columns` type bits.
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    let columns =
+        vec![Column::build("Key").primary_key().int16(), round_trip.clone()];
+    package.create_table("Things", columns).unwrap();
+    let package = Package::open(package.into_inner().unwrap()).unwrap();
+    let read_column =
+        package.get_table("Things").unwrap().columns()[1].clone();
+    assert_eq!(
+        serde_json::to_value(&read_column).unwrap(),
+        serde_json::to_value(&round_trip).unwrap()
+    );
+}
+
+#[test]
+fn summary_info() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    let summary = package.summary_info_mut();
+    summary.set_title("Widget Installer");
+    summary.set_author("Jane Doe");
+    summary.set_arch("x64");
+    summary.set_languages(&[LanguageId::from_id(1033)]);
+    summary.set_page_count(500);
+    summary.set_creation_time(UNIX_EPOCH + Duration::from_secs(1_000_000));
+    summary.set_codepage(CodePage::Windows1252);
+    let summary = package.summary_info().clone();
+
+    let json = serde_json::to_value(&summary).unwrap();
+    assert_eq!(json["title"], json!("Widget Installer"));
+    assert_eq!(json["arch"], json!("x64"));
+    assert_eq!(json["languages"], json!([1033]));
+    assert_eq!(json["codepage"], json!(1252));
+    assert!(json.get("comments").is_none());
+
+    let round_trip: SummaryInfo =
+        serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(round_trip.title(), Some("Widget Installer"));
+    assert_eq!(round_trip.author(), Some("Jane Doe"));
+    assert_eq!(round_trip.arch(), Some("x64"));
+    assert_eq!(round_trip.languages(), vec![LanguageId::from_id(1033)]);
+    assert_eq!(round_trip.page_count(), Some(500));
+    assert_eq!(round_trip.creation_time(), summary.creation_time());
+    assert_eq!(round_trip.codepage(), CodePage::Windows1252);
+    assert_eq!(serde_json::to_value(&round_trip).unwrap(), json);
+}
+
+#[test]
+fn rows() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    let columns = vec![
+        Column::build("Key").primary_key().int16(),
+        Column::build("Value").nullable().string(32),
+    ];
+    package.create_table("Pairs", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Pairs")
+                .row(vec![Value::Int(1), Value::from("One")])
+                .row(vec![Value::Int(2), Value::Null]),
+        )
+        .unwrap();
+
+    let rows = package.select_rows(Select::table("Pairs")).unwrap();
+    assert_eq!(
+        serde_json::to_value(&rows).unwrap(),
+        json!([
+            {"Key": 1, "Value": "One"},
+            {"Key": 2, "Value": null},
+        ])
+    );
+
+    let mut rows = package.select_rows(Select::table("Pairs")).unwrap();
+    let first = rows.next().unwrap();
+    assert_eq!(
+        serde_json::to_value(&first).unwrap(),
+        json!({"Key": 1, "Value": "One"})
+    );
+    assert_eq!(
+        serde_json::to_value(&rows).unwrap(),
+        json!([{"Key": 2, "Value": null}])
+    );
+}
+
+// ========================================================================= //
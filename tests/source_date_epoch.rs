@@ -0,0 +1,58 @@
+#[macro_use]
+mod testutil;
+
+use msi::Package;
+use std::io::ErrorKind;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// ========================================================================= //
+
+// Changing the environment while another thread might be reading it is a
+// data race, so this test must stay the only one in this binary.
+#[test]
+fn source_date_epoch_from_environment() {
+    let set_epoch = |value: Option<&str>| {
+        // SAFETY: This is the only test in this binary, so no other thread
+        // can be reading the environment at the same time.
+        unsafe {
+            match value {
+                Some(value) => std::env::set_var("SOURCE_DATE_EPOCH", value),
+                None => std::env::remove_var("SOURCE_DATE_EPOCH"),
+            }
+        }
+    };
+    let build_package = || {
+        let mut package = testutil::create_numbers_package();
+        package.summary_info_mut().set_creation_time(SystemTime::now());
+        package.summary_info_mut().set_last_save_time_to_now();
+        package.set_reproducible(true);
+        package
+    };
+
+    set_epoch(Some("1500000000"));
+    let package = build_package();
+    let package = Package::open(package.into_inner().unwrap()).unwrap();
+    assert_eq!(
+        package.summary_info().creation_time(),
+        Some(UNIX_EPOCH + Duration::from_secs(1_500_000_000))
+    );
+
+    // Without SOURCE_DATE_EPOCH, times are pinned to the Unix epoch rather
+    // than removed.
+    set_epoch(None);
+    let package = build_package();
+    let package = Package::open(package.into_inner().unwrap()).unwrap();
+    assert_eq!(package.summary_info().creation_time(), Some(UNIX_EPOCH));
+    assert_eq!(package.summary_info().last_saved_time(), Some(UNIX_EPOCH));
+
+    set_epoch(Some("last Tuesday"));
+    let mut package = build_package();
+    assert_error!(
+        package.flush(),
+        ErrorKind::InvalidInput,
+        "Invalid SOURCE_DATE_EPOCH value: \"last Tuesday\""
+    );
+    set_epoch(None);
+}
+
+// ========================================================================= //
@@ -0,0 +1,179 @@
+#[macro_use]
+mod testutil;
+
+use msi::{CodePage, Column, Insert, LanguageId, Package, PackageType, Value};
+use rusqlite::Connection;
+use std::fs;
+use std::io::{Cursor, ErrorKind, Read, Write};
+
+// ========================================================================= //
+
+fn create_patch_package() -> Package<Cursor<Vec<u8>>> {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Patch, cursor).unwrap();
+    package.set_database_codepage(CodePage::Windows1252);
+    let summary_info = package.summary_info_mut();
+    summary_info.set_author("Jane Doe");
+    summary_info.set_languages(&[LanguageId::from_id(1033)]);
+
+    let columns = vec![
+        Column::build("Property").primary_key().id_string(72),
+        Column::build("Value").nullable().text_string(0),
+    ];
+    package.create_table("Property", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Property")
+                .row(vec![Value::from("Greeting"), Value::from("H\u{e9}llo")])
+                .row(vec![Value::from("Empty"), Value::Null]),
+        )
+        .unwrap();
+    let columns = vec![
+        Column::build("Name").primary_key().id_string(72),
+        Column::build("Size").int16(),
+        Column::build("Data").binary(),
+    ];
+    package.create_table("Binary", columns).unwrap();
+    package
+        .insert_rows(Insert::into("Binary").row(vec![
+            Value::from("Icon"),
+            Value::Int(4),
+            Value::Binary,
+        ]))
+        .unwrap();
+    package.write_stream("Binary.Icon").unwrap().write_all(b"icon").unwrap();
+    package.write_stream("data1.cab").unwrap().write_all(b"cabinet").unwrap();
+    package
+}
+
+// ========================================================================= //
+
+#[test]
+fn export_sqlite() {
+    let dir = testutil::temp_dir("export_sqlite");
+    let path = dir.join("package.db");
+    let mut package = create_patch_package();
+    package.export_sqlite(&path).unwrap();
+
+    let connection = Connection::open(&path).unwrap();
+    let sql: String = connection
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE name = 'Binary'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(
+        sql,
+        "CREATE TABLE \"Binary\" (\"Name\" TEXT NOT NULL, \"Size\" INTEGER \
+         NOT NULL, \"Data\" BLOB NOT NULL, PRIMARY KEY (\"Name\"))"
+    );
+    let (size, data): (i64, Vec<u8>) = connection
+        .query_row("SELECT Size, Data FROM Binary", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .unwrap();
+    assert_eq!((size, data.as_slice()), (4, b"icon".as_slice()));
+    let value: Option<String> = connection
+        .query_row(
+            "SELECT Value FROM Property WHERE Property = 'Greeting'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(value.as_deref(), Some("H\u{e9}llo"));
+    let author: String = connection
+        .query_row(
+            "SELECT Value FROM _SummaryInformation WHERE PropertyId = 4",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(author, "Jane Doe");
+    let types: Vec<String> = connection
+        .prepare(
+            "SELECT Type FROM _Columns WHERE \"Table\" = 'Binary' \
+             ORDER BY Number",
+        )
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(types, vec!["s72", "i2", "v0"]);
+    // Only streams that aren't binary values of a table go in _Streams.
+    let streams: Vec<String> = connection
+        .prepare("SELECT Name FROM _Streams")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(streams, vec!["data1.cab"]);
+    drop(connection);
+
+    // Exporting again into the same file fails.
+    assert_eq!(
+        package.export_sqlite(&path).unwrap_err().kind(),
+        ErrorKind::Other
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn import_sqlite_round_trip() {
+    let dir = testutil::temp_dir("import_sqlite_round_trip");
+    let path = dir.join("package.db");
+    let mut package = create_patch_package();
+    package.export_sqlite(&path).unwrap();
+
+    let cursor = Cursor::new(Vec::new());
+    let package2 = Package::import_sqlite(&path, cursor).unwrap();
+    let cursor = package2.into_inner().unwrap();
+    let mut package2 = Package::open(cursor).unwrap();
+    assert_eq!(package2.package_type(), PackageType::Patch);
+    assert_eq!(package2.database_codepage(), CodePage::Windows1252);
+    assert_eq!(package2.summary_info().author(), Some("Jane Doe"));
+    assert_eq!(
+        package2.summary_info().languages(),
+        vec![LanguageId::from_id(1033)]
+    );
+    let columns = package2.get_table("Binary").unwrap().columns();
+    assert_eq!(columns[1].coltype(), msi::ColumnType::Int16);
+    // Rows may come back in a different order, since the tables were
+    // recreated from scratch.
+    for table_name in ["Binary", "Property", "_Validation"] {
+        let mut rows1 = testutil::select_values(&mut package, table_name);
+        let mut rows2 = testutil::select_values(&mut package2, table_name);
+        rows1.sort_by_key(|row| format!("{row:?}"));
+        rows2.sort_by_key(|row| format!("{row:?}"));
+        assert_eq!(rows2, rows1);
+    }
+    let mut data = Vec::new();
+    package2
+        .read_stream("Binary.Icon")
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, b"icon");
+    let mut data = Vec::new();
+    package2.read_stream("data1.cab").unwrap().read_to_end(&mut data).unwrap();
+    assert_eq!(data, b"cabinet");
+}
+
+#[test]
+fn import_invalid_rows() {
+    let dir = testutil::temp_dir("import_invalid_rows");
+    let path = dir.join("package.db");
+    let mut package = create_patch_package();
+    package.export_sqlite(&path).unwrap();
+    let connection = Connection::open(&path).unwrap();
+    connection.execute("UPDATE Binary SET Size = 100000", []).unwrap();
+    drop(connection);
+
+    let cursor = Cursor::new(Vec::new());
+    let error = Package::import_sqlite(&path, cursor).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
+
+// ========================================================================= //
@@ -127,6 +127,41 @@ fn create_valid_table() {
     assert!(column.is_nullable());
 }
 
+#[test]
+fn failed_create_table_leaves_no_metadata() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    // A leftover _Validation row for the new table's column makes the last
+    // step of creating the table fail.
+    package
+        .insert_rows(Insert::into("_Validation").row(vec![
+            Value::from("Numbers"),
+            Value::from("Word"),
+            Value::from("Y"),
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::from("Text"),
+            Value::Null,
+            Value::Null,
+        ]))
+        .unwrap();
+    let columns = vec![
+        Column::build("Number").primary_key().int16(),
+        Column::build("Word").nullable().string(50),
+    ];
+    let result = package.create_table("Numbers", columns);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::AlreadyExists);
+    assert!(!package.has_table("Numbers"));
+    let query = Select::table("_Columns")
+        .with(Expr::col("Table").eq(Expr::string("Numbers")));
+    assert_eq!(package.select_rows(query).unwrap().len(), 0);
+    let query = Select::table("_Tables")
+        .with(Expr::col("Name").eq(Expr::string("Numbers")));
+    assert_eq!(package.select_rows(query).unwrap().len(), 0);
+}
+
 #[test]
 fn drop_table_with_invalid_name() {
     let cursor = Cursor::new(Vec::new());
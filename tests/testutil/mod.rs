@@ -1,5 +1,14 @@
+use msi::{Column, Insert, Package, PackageType, Select, Value};
+use std::cell::Cell;
+use std::fs;
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
 // ========================================================================= //
 
+#[allow(unused_macros)]
 macro_rules! assert_error {
     ($e:expr, $k:expr, $d:expr) => {
         let kind = $k;
@@ -31,3 +40,145 @@ macro_rules! assert_error {
 }
 
 // ========================================================================= //
+
+/// Creates a new, empty installer package in memory.
+#[allow(dead_code)]
+pub fn create_package() -> Package<Cursor<Vec<u8>>> {
+    let cursor = Cursor::new(Vec::new());
+    Package::create(PackageType::Installer, cursor).unwrap()
+}
+
+/// Creates a new, in-memory installer package with an empty `Numbers` table
+/// (keyed by an integer, with a nullable string column).
+#[allow(dead_code)]
+pub fn create_numbers_package() -> Package<Cursor<Vec<u8>>> {
+    let mut package = create_package();
+    let columns = vec![
+        Column::build("Number").primary_key().int16(),
+        Column::build("Word").nullable().string(50),
+    ];
+    package.create_table("Numbers", columns).unwrap();
+    package
+}
+
+/// Creates a new, in-memory installer package with an empty `Notes` table
+/// (keyed by a string, with a nullable integer column and a nullable,
+/// localizable text column).
+#[allow(dead_code)]
+pub fn create_notes_package() -> Package<Cursor<Vec<u8>>> {
+    let mut package = create_package();
+    let columns = vec![
+        Column::build("Note").primary_key().id_string(72),
+        Column::build("Priority").nullable().int16(),
+        Column::build("Text").localizable().nullable().text_string(0),
+    ];
+    package.create_table("Notes", columns).unwrap();
+    package
+}
+
+/// Adds two rows to the `Notes` table from `create_notes_package()`: one
+/// with a value in every column (including text that most file formats need
+/// to escape), and one that is null wherever it can be.
+#[allow(dead_code)]
+pub fn insert_notes(package: &mut Package<Cursor<Vec<u8>>>) {
+    package
+        .insert_rows(
+            Insert::into("Notes")
+                .row(vec![
+                    Value::from("First"),
+                    Value::Int(-1),
+                    Value::from("Say \"hi\",\r\nthen\tleave"),
+                ])
+                .row(vec![Value::from("Second"), Value::Null, Value::Null]),
+        )
+        .unwrap();
+}
+
+/// Returns the values of every row in the given table.
+#[allow(dead_code)]
+pub fn select_values(
+    package: &mut Package<Cursor<Vec<u8>>>,
+    table_name: &str,
+) -> Vec<Vec<Value>> {
+    package
+        .select_rows(Select::table(table_name))
+        .unwrap()
+        .map(|row| (0..row.len()).map(|index| row[index].clone()).collect())
+        .collect()
+}
+
+// ========================================================================= //
+
+/// A temporary directory for a test, which is removed when dropped (even if
+/// the test fails).
+#[allow(dead_code)]
+pub struct TempDir(PathBuf);
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Returns a fresh, empty temporary directory for a test.
+#[allow(dead_code)]
+pub fn temp_dir(test_name: &str) -> TempDir {
+    let dir = std::env::temp_dir().join(format!(
+        "msi-test-{}-{}",
+        std::process::id(),
+        test_name
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    TempDir(dir)
+}
+
+// ========================================================================= //
+
+/// An in-memory reader/writer whose writes start failing once `fail` is set.
+#[allow(dead_code)]
+pub struct FailingWriter {
+    pub inner: Cursor<Vec<u8>>,
+    pub fail: Rc<Cell<bool>>,
+}
+
+impl Read for FailingWriter {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.fail.get() {
+            return Err(io::Error::other("Disk is full"));
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Seek for FailingWriter {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+// ========================================================================= //
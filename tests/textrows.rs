@@ -0,0 +1,312 @@
+#[macro_use]
+mod testutil;
+
+use msi::{Column, Delete, Insert, Package, PackageType, Select, Value};
+use std::io::{Cursor, ErrorKind};
+
+// ========================================================================= //
+
+#[test]
+fn write_csv() {
+    let mut package = testutil::create_notes_package();
+    testutil::insert_notes(&mut package);
+    let mut output = Vec::new();
+    package
+        .select_rows(Select::table("Notes"))
+        .unwrap()
+        .write_csv(&mut output)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "Note,Priority,Text\r\n\
+         s72,I2,L0\r\n\
+         First,-1,\"Say \"\"hi\"\",\r\nthen\tleave\"\r\n\
+         Second,,\r\n"
+    );
+}
+
+#[test]
+fn write_json_lines() {
+    let mut package = testutil::create_notes_package();
+    testutil::insert_notes(&mut package);
+    let mut output = Vec::new();
+    package
+        .select_rows(Select::table("Notes"))
+        .unwrap()
+        .write_json_lines(&mut output)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "{\"columns\":[{\"name\":\"Note\",\"type\":\"s72\"},\
+         {\"name\":\"Priority\",\"type\":\"I2\"},\
+         {\"name\":\"Text\",\"type\":\"L0\"}]}\n\
+         {\"Note\":\"First\",\"Priority\":-1,\
+         \"Text\":\"Say \\\"hi\\\",\\r\\nthen\\tleave\"}\n\
+         {\"Note\":\"Second\",\"Priority\":null,\"Text\":null}\n"
+    );
+}
+
+#[test]
+fn csv_round_trip() {
+    let mut package = testutil::create_notes_package();
+    testutil::insert_notes(&mut package);
+    let mut output = Vec::new();
+    package
+        .select_rows(Select::table("Notes"))
+        .unwrap()
+        .write_csv(&mut output)
+        .unwrap();
+
+    let mut package2 = testutil::create_notes_package();
+    assert_eq!(package2.import_csv("Notes", output.as_slice()).unwrap(), 2);
+    assert_eq!(
+        testutil::select_values(&mut package2, "Notes"),
+        testutil::select_values(&mut package, "Notes")
+    );
+}
+
+#[test]
+fn json_lines_round_trip() {
+    let mut package = testutil::create_notes_package();
+    testutil::insert_notes(&mut package);
+    let mut output = Vec::new();
+    package
+        .select_rows(Select::table("Notes"))
+        .unwrap()
+        .write_json_lines(&mut output)
+        .unwrap();
+
+    let mut package2 = testutil::create_notes_package();
+    assert_eq!(
+        package2.import_json_lines("Notes", output.as_slice()).unwrap(),
+        2
+    );
+    assert_eq!(
+        testutil::select_values(&mut package2, "Notes"),
+        testutil::select_values(&mut package, "Notes")
+    );
+}
+
+#[test]
+fn binary_columns() {
+    let mut package =
+        Package::create(PackageType::Installer, Cursor::new(Vec::new()))
+            .unwrap();
+    package
+        .create_table(
+            "Icons",
+            vec![
+                Column::build("Name").primary_key().id_string(72),
+                Column::build("Data").binary(),
+            ],
+        )
+        .unwrap();
+    package
+        .insert_rows(
+            Insert::into("Icons").row(vec![Value::from("App"), Value::Binary]),
+        )
+        .unwrap();
+
+    let mut csv = Vec::new();
+    package
+        .select_rows(Select::table("Icons"))
+        .unwrap()
+        .write_csv(&mut csv)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(csv.clone()).unwrap(),
+        "Name,Data\r\ns72,v0\r\nApp,BINARY_STREAM\r\n"
+    );
+    let mut json = Vec::new();
+    package
+        .select_rows(Select::table("Icons"))
+        .unwrap()
+        .write_json_lines(&mut json)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(json.clone()).unwrap(),
+        "{\"columns\":[{\"name\":\"Name\",\"type\":\"s72\"},\
+         {\"name\":\"Data\",\"type\":\"v0\"}]}\n\
+         {\"Name\":\"App\",\"Data\":[]}\n"
+    );
+
+    package.delete_rows(Delete::from("Icons")).unwrap();
+    assert_eq!(package.import_csv("Icons", csv.as_slice()).unwrap(), 1);
+    package.delete_rows(Delete::from("Icons")).unwrap();
+    assert_eq!(
+        package.import_json_lines("Icons", json.as_slice()).unwrap(),
+        1
+    );
+    assert_eq!(
+        testutil::select_values(&mut package, "Icons"),
+        vec![vec![Value::from("App"), Value::Binary]]
+    );
+}
+
+#[test]
+fn import_subset_of_columns() {
+    let mut package = testutil::create_notes_package();
+    let csv = "Text,Note\nL0,s72\nHello,A\n";
+    assert_eq!(package.import_csv("Notes", csv.as_bytes()).unwrap(), 1);
+    let json = "{\"columns\":[{\"name\":\"Note\",\"type\":\"s72\"},\
+                {\"name\":\"Priority\",\"type\":\"I2\"}]}\n\
+                \n\
+                {\"Note\":\"B\",\"Priority\":2}\n";
+    assert_eq!(
+        package.import_json_lines("Notes", json.as_bytes()).unwrap(),
+        1
+    );
+    assert_eq!(
+        testutil::select_values(&mut package, "Notes"),
+        vec![
+            vec![Value::from("A"), Value::Null, Value::from("Hello")],
+            vec![Value::from("B"), Value::Int(2), Value::Null],
+        ]
+    );
+}
+
+#[test]
+fn import_csv_errors() {
+    let mut package = testutil::create_notes_package();
+    assert_error!(
+        package.import_csv("Nonexistent", "Note\ns72\n".as_bytes()),
+        ErrorKind::NotFound,
+        "Table \"Nonexistent\" does not exist"
+    );
+    assert_error!(
+        package.import_csv("Notes", "Note\n".as_bytes()),
+        ErrorKind::InvalidData,
+        "CSV file must start with records of column names and types"
+    );
+    assert_error!(
+        package.import_csv("Notes", "Note,Size\ns72,i2\n".as_bytes()),
+        ErrorKind::InvalidInput,
+        "Table \"Notes\" has no column named \"Size\""
+    );
+    assert_error!(
+        package.import_csv("Notes", "Note\ns255\n".as_bytes()),
+        ErrorKind::InvalidData,
+        "Column \"Note\" of table \"Notes\" has type s72, but the file says \
+         s255"
+    );
+    assert_error!(
+        package.import_csv("Notes", "Note\ns72\nA\n\"B\n".as_bytes()),
+        ErrorKind::InvalidData,
+        "CSV record on line 4 has an unterminated quoted field"
+    );
+    assert_error!(
+        package.import_csv(
+            "Notes",
+            "Note,Priority\ns72,I2\nA,1\nB,2,3\n".as_bytes()
+        ),
+        ErrorKind::InvalidData,
+        "Line 4 of CSV file has 3 fields, but its header has 2 columns"
+    );
+    assert_error!(
+        package
+            .import_csv("Notes", "Note,Priority\ns72,I2\nA,many\n".as_bytes()),
+        ErrorKind::InvalidData,
+        "Line 3 of CSV file has non-integer value \"many\" in column \
+         \"Priority\""
+    );
+    // Rows that are invalid for the table are reported with the line they
+    // started on, and none of the rows are inserted.
+    let error = package
+        .import_csv(
+            "Notes",
+            "Note,Text\ns72,L0\nA,\"two\nlines\"\nB,\n,missing key\n"
+                .as_bytes(),
+        )
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert!(error.to_string().starts_with("Line 6: "), "{error}");
+    assert!(matches!(
+        msi::Error::from_io(&error),
+        Some(msi::Error::InvalidValue { column, .. }) if column == "Note"
+    ));
+    assert_eq!(testutil::select_values(&mut package, "Notes").len(), 0);
+}
+
+#[test]
+fn import_json_lines_errors() {
+    let mut package = testutil::create_notes_package();
+    assert_error!(
+        package.import_json_lines("Notes", "".as_bytes()),
+        ErrorKind::InvalidData,
+        "JSON lines file is missing its column list"
+    );
+    assert_error!(
+        package.import_json_lines("Notes", "{\"columns\":[]}".as_bytes()),
+        ErrorKind::InvalidData,
+        "JSON lines file must start with a line listing its columns"
+    );
+    let header = "{\"columns\":[{\"name\":\"Note\",\"type\":\"s72\"},\
+                  {\"name\":\"Priority\",\"type\":\"I2\"}]}\n";
+    assert_error!(
+        package.import_json_lines(
+            "Notes",
+            format!("{header}{{\"Note\":\"A\",}}\n").as_bytes()
+        ),
+        ErrorKind::InvalidData,
+        "Line 2 of JSON lines file is not valid JSON: expected a member name \
+         at column 13"
+    );
+    assert_error!(
+        package.import_json_lines(
+            "Notes",
+            format!("{header}{{\"Note\":\"A\",\"Text\":\"Hi\"}}\n").as_bytes()
+        ),
+        ErrorKind::InvalidData,
+        "Line 2 of JSON lines file has member \"Text\", which is not one of \
+         its columns"
+    );
+    assert_error!(
+        package.import_json_lines(
+            "Notes",
+            format!("{header}{{\"Note\":\"A\",\"Priority\":\"1\"}}\n")
+                .as_bytes()
+        ),
+        ErrorKind::InvalidData,
+        "Line 2 of JSON lines file has a string in column \"Priority\", \
+         which should hold a number or null"
+    );
+    assert_error!(
+        package.import_json_lines(
+            "Notes",
+            format!("{header}{{\"Note\":\"A\",\"Priority\":1.5}}\n")
+                .as_bytes()
+        ),
+        ErrorKind::InvalidData,
+        "Line 2 of JSON lines file has non-integer value 1.5 in column \
+         \"Priority\""
+    );
+    let error = package
+        .import_json_lines(
+            "Notes",
+            format!(
+                "{header}{{\"Note\":\"A\"}}\n\
+                 {{\"Note\":\"B\",\"Priority\":40000}}\n"
+            )
+            .as_bytes(),
+        )
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert!(error.to_string().starts_with("Line 3: "), "{error}");
+    assert!(matches!(
+        msi::Error::from_io(&error),
+        Some(msi::Error::InvalidValue { column, .. }) if column == "Priority"
+    ));
+    assert_eq!(testutil::select_values(&mut package, "Notes").len(), 0);
+    // Deeply nested values are rejected rather than overflowing the stack.
+    assert_error!(
+        package.import_json_lines(
+            "Notes",
+            format!("{header}{}\n", "[".repeat(100_000)).as_bytes()
+        ),
+        ErrorKind::InvalidData,
+        "Line 2 of JSON lines file is not valid JSON: values are nested too \
+         deeply at column 129"
+    );
+}
+
+// ========================================================================= //
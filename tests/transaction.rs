@@ -0,0 +1,242 @@
+#[macro_use]
+mod testutil;
+
+use msi::{Column, Insert, Package, Select, Update, Value};
+use std::cell::Cell;
+use std::io::{self, Cursor, ErrorKind, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+
+// ========================================================================= //
+
+fn create_package_with_table() -> Package<Cursor<Vec<u8>>> {
+    let mut package = testutil::create_package();
+    let columns = vec![
+        Column::build("Key").primary_key().int16(),
+        Column::build("Value").string(32),
+    ];
+    package.create_table("Foobar", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Foobar")
+                .row(vec![Value::Int(1), Value::from("One")]),
+        )
+        .unwrap();
+    package.write_stream("Blob").unwrap().write_all(b"original").unwrap();
+    package.flush().unwrap();
+    package
+}
+
+fn keys(package: &mut Package<Cursor<Vec<u8>>>) -> Vec<i32> {
+    package
+        .select_rows(Select::table("Foobar"))
+        .unwrap()
+        .map(|row| row[0].as_int().unwrap())
+        .collect()
+}
+
+fn read_blob(package: &mut Package<Cursor<Vec<u8>>>) -> Vec<u8> {
+    let mut data = Vec::new();
+    package.read_stream("Blob").unwrap().read_to_end(&mut data).unwrap();
+    data
+}
+
+// ========================================================================= //
+
+#[test]
+fn commit_transaction() {
+    let mut package = create_package_with_table();
+    let answer = package
+        .transaction(|package| {
+            package.insert_rows(
+                Insert::into("Foobar")
+                    .row(vec![Value::Int(2), Value::from("Two")]),
+            )?;
+            package.summary_info_mut().set_author("Jane Doe".to_string());
+            package.write_stream("Blob")?.write_all(b"changed")?;
+            Ok(42)
+        })
+        .unwrap();
+    assert_eq!(answer, 42);
+    assert_eq!(keys(&mut package), vec![1, 2]);
+
+    let cursor = package.into_inner().unwrap();
+    let mut package = Package::open(cursor).unwrap();
+    assert_eq!(keys(&mut package), vec![1, 2]);
+    assert_eq!(package.summary_info().author(), Some("Jane Doe"));
+    assert_eq!(read_blob(&mut package), b"changed");
+}
+
+#[test]
+fn roll_back_transaction() {
+    let mut package = create_package_with_table();
+    let result: io::Result<()> = package.transaction(|package| {
+        package.insert_rows(
+            Insert::into("Foobar")
+                .row(vec![Value::Int(2), Value::from("Two")]),
+        )?;
+        package.summary_info_mut().set_author("Jane Doe".to_string());
+        package.write_stream("Blob")?.write_all(b"changed")?;
+        package.write_stream("NewBlob")?.write_all(b"new")?;
+        package.drop_table("Foobar")?;
+        package.insert_rows(
+            Insert::into("Foobar")
+                .row(vec![Value::Int(3), Value::from("Three")]),
+        )
+    });
+    assert_error!(
+        result,
+        ErrorKind::NotFound,
+        "Table \"Foobar\" does not exist"
+    );
+    assert!(package.has_table("Foobar"));
+    assert_eq!(keys(&mut package), vec![1]);
+    assert_eq!(package.summary_info().author(), None);
+    assert_eq!(read_blob(&mut package), b"original");
+    assert!(!package.has_stream("NewBlob"));
+
+    let cursor = package.into_inner().unwrap();
+    let mut package = Package::open(cursor).unwrap();
+    assert_eq!(keys(&mut package), vec![1]);
+    assert_eq!(package.summary_info().author(), None);
+    assert_eq!(read_blob(&mut package), b"original");
+    assert!(!package.has_stream("NewBlob"));
+    assert!(package.check_integrity().unwrap().is_empty());
+}
+
+#[test]
+fn roll_back_nested_transaction() {
+    let mut package = create_package_with_table();
+    package
+        .transaction(|package| {
+            package.insert_rows(
+                Insert::into("Foobar")
+                    .row(vec![Value::Int(2), Value::from("Two")]),
+            )?;
+            let result = package.transaction(|package| {
+                package.insert_rows(
+                    Insert::into("Foobar")
+                        .row(vec![Value::Int(3), Value::from("Three")]),
+                )?;
+                package.remove_stream("Blob")?;
+                package.insert_rows(
+                    Insert::into("Foobar")
+                        .row(vec![Value::Int(2), Value::from("Again")]),
+                )
+            });
+            assert!(result.is_err());
+            assert_eq!(keys(package), vec![1, 2]);
+            assert_eq!(read_blob(package), b"original");
+            Ok(())
+        })
+        .unwrap();
+
+    let cursor = package.into_inner().unwrap();
+    let mut package = Package::open(cursor).unwrap();
+    assert_eq!(keys(&mut package), vec![1, 2]);
+    assert_eq!(read_blob(&mut package), b"original");
+}
+
+#[test]
+fn roll_back_outer_transaction_after_nested_commit() {
+    let mut package = create_package_with_table();
+    let result: io::Result<()> = package.transaction(|package| {
+        package.transaction(|package| {
+            package.write_stream("Blob")?.write_all(b"changed")?;
+            package.insert_rows(
+                Insert::into("Foobar")
+                    .row(vec![Value::Int(2), Value::from("Two")]),
+            )
+        })?;
+        invalid_data_error()
+    });
+    assert!(result.is_err());
+    assert_eq!(keys(&mut package), vec![1]);
+    assert_eq!(read_blob(&mut package), b"original");
+}
+
+#[test]
+fn roll_back_transaction_on_panic() {
+    let mut package = create_package_with_table();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _: io::Result<()> = package.transaction(|package| {
+            package.update_rows(
+                Update::table("Foobar").set("Value", Value::from("Uno")),
+            )?;
+            package.insert_rows(
+                Insert::into("Foobar")
+                    .row(vec![Value::Int(2), Value::from("Two")]),
+            )?;
+            package.write_stream("Blob")?.write_all(b"changed")?;
+            panic!("Oops");
+        });
+    }));
+    assert!(result.is_err());
+    assert_eq!(keys(&mut package), vec![1]);
+    assert_eq!(read_blob(&mut package), b"original");
+    // The transaction is over, so flushing works again.
+    package.flush().unwrap();
+
+    let cursor = package.into_inner().unwrap();
+    let mut package = Package::open(cursor).unwrap();
+    let rows: Vec<String> = package
+        .select_rows(Select::table("Foobar"))
+        .unwrap()
+        .map(|row| row[1].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(rows, vec!["One".to_string()]);
+    assert_eq!(read_blob(&mut package), b"original");
+    assert!(package.check_integrity().unwrap().is_empty());
+}
+
+#[test]
+fn cannot_flush_during_transaction() {
+    let mut package = create_package_with_table();
+    let result = package.transaction(|package| package.flush());
+    assert_error!(
+        result,
+        ErrorKind::InvalidInput,
+        "Cannot flush a package during a transaction"
+    );
+    let result = package.transaction(|package| package.repair());
+    assert_error!(
+        result,
+        ErrorKind::InvalidInput,
+        "Cannot repair a package during a transaction"
+    );
+    package.flush().unwrap();
+}
+
+#[test]
+fn rollback_failure_keeps_original_error() {
+    let cursor = create_package_with_table().into_inner().unwrap();
+    let fail = Rc::new(Cell::new(false));
+    let writer = testutil::FailingWriter { inner: cursor, fail: fail.clone() };
+    let mut package = Package::open(writer).unwrap();
+    let result: io::Result<()> = package.transaction(|package| {
+        package.write_stream("NewBlob")?.write_all(b"new")?;
+        fail.set(true);
+        package.insert_rows(
+            Insert::into("Foobar")
+                .row(vec![Value::Int(1), Value::from("Again")]),
+        )
+    });
+    let error = result.unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+    assert!(matches!(
+        msi::Error::from_io(&error),
+        Some(msi::Error::DuplicateKey { .. })
+    ));
+    let message = error.to_string();
+    assert!(message.starts_with(
+        "Table \"Foobar\" already contains a row with key [Int(1)] (and \
+         then rolling back the transaction failed: "
+    ));
+    assert!(message.ends_with("Disk is full)"));
+}
+
+fn invalid_data_error() -> io::Result<()> {
+    Err(io::Error::new(ErrorKind::InvalidData, "Something went wrong"))
+}
+
+// ========================================================================= //
@@ -0,0 +1,268 @@
+#[macro_use]
+mod testutil;
+
+use msi::{
+    CodePage, Column, ColumnValidation, Insert, LanguageId, Package,
+    PackageType, Value,
+};
+use std::fs;
+use std::io::{Cursor, ErrorKind, Read, Write};
+use std::time::{Duration, UNIX_EPOCH};
+
+// ========================================================================= //
+
+fn create_sample_package() -> Package<Cursor<Vec<u8>>> {
+    let mut package = testutil::create_package();
+    package.set_database_codepage(CodePage::Windows1252);
+    let summary_info = package.summary_info_mut();
+    summary_info.set_author("Jane Doe");
+    summary_info.set_arch("x64");
+    summary_info.set_languages(&[LanguageId::from_id(1033)]);
+    summary_info.set_page_count(500);
+    summary_info
+        .set_creation_time(UNIX_EPOCH + Duration::from_secs(1489862796));
+
+    let columns = vec![
+        Column::build("Property").primary_key().id_string(72),
+        Column::build("Value").nullable().text_string(0),
+    ];
+    package.create_table("Property", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Property")
+                .row(vec![Value::from("Greeting"), Value::from("H\u{e9}llo")])
+                .row(vec![Value::from("Empty"), Value::Null]),
+        )
+        .unwrap();
+    package
+        .set_validation(
+            "Property",
+            "Value",
+            ColumnValidation::new().description("The value"),
+        )
+        .unwrap();
+
+    let columns = vec![
+        Column::build("Name").primary_key().id_string(72),
+        Column::build("Data").binary(),
+    ];
+    package.create_table("Binary", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Binary")
+                .row(vec![Value::from("Icon"), Value::Binary]),
+        )
+        .unwrap();
+    package.write_stream("Binary.Icon").unwrap().write_all(b"icon").unwrap();
+    package.write_stream("data1.cab").unwrap().write_all(b"cabinet").unwrap();
+    package
+}
+
+fn read_stream(
+    package: &mut Package<Cursor<Vec<u8>>>,
+    stream_name: &str,
+) -> Vec<u8> {
+    let mut data = Vec::new();
+    package.read_stream(stream_name).unwrap().read_to_end(&mut data).unwrap();
+    data
+}
+
+// ========================================================================= //
+
+#[test]
+fn unpack_layout() {
+    let dir = testutil::temp_dir("unpack_layout");
+    let mut package = create_sample_package();
+    package.unpack(&dir).unwrap();
+
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            "Binary",
+            "Binary.idt",
+            "Property.idt",
+            "_ForceCodepage.idt",
+            "_Streams",
+            "_SummaryInformation.idt",
+            "_Validation.idt",
+        ]
+    );
+    assert_eq!(
+        fs::read(dir.join("_ForceCodepage.idt")).unwrap(),
+        b"\r\n\r\n1252\t_ForceCodepage\r\n"
+    );
+    assert_eq!(
+        fs::read(dir.join("Property.idt")).unwrap(),
+        b"Property\tValue\r\ns72\tS0\r\n1252\tProperty\tProperty\r\n\
+          Greeting\tH\xe9llo\r\nEmpty\t\r\n"
+    );
+    assert_eq!(
+        String::from_utf8(
+            fs::read(dir.join("_SummaryInformation.idt")).unwrap()
+        )
+        .unwrap(),
+        "PropertyId\tValue\r\ni2\tl255\r\n_SummaryInformation\tPropertyId\r\n\
+         1\t65001\r\n\
+         2\tInstallation Database\r\n\
+         4\tJane Doe\r\n\
+         7\tx64;1033\r\n\
+         12\t2017/03/18 18:46:36\r\n\
+         14\t500\r\n"
+    );
+    assert_eq!(
+        fs::read(dir.join("Binary").join("Icon.ibd")).unwrap(),
+        b"icon"
+    );
+    // Only streams that aren't binary values of a table go in _Streams.
+    let streams: Vec<_> =
+        fs::read_dir(dir.join("_Streams")).unwrap().collect();
+    assert_eq!(streams.len(), 1);
+    assert_eq!(
+        fs::read(dir.join("_Streams").join("data1.cab")).unwrap(),
+        b"cabinet"
+    );
+}
+
+#[test]
+fn unpack_and_pack_round_trip() {
+    let dir = testutil::temp_dir("unpack_and_pack_round_trip");
+    let mut package = create_sample_package();
+    package.unpack(&dir).unwrap();
+
+    let cursor = Cursor::new(Vec::new());
+    let package2 =
+        Package::pack(PackageType::Installer, &dir, cursor).unwrap();
+    let cursor = package2.into_inner().unwrap();
+    let mut package2 = Package::open(cursor).unwrap();
+
+    assert_eq!(package2.package_type(), PackageType::Installer);
+    assert_eq!(package2.database_codepage(), CodePage::Windows1252);
+    let summary_info = package2.summary_info();
+    assert_eq!(summary_info.title(), Some("Installation Database"));
+    assert_eq!(summary_info.author(), Some("Jane Doe"));
+    assert_eq!(summary_info.arch(), Some("x64"));
+    assert_eq!(summary_info.languages(), vec![LanguageId::from_id(1033)]);
+    assert_eq!(summary_info.page_count(), Some(500));
+    assert_eq!(
+        summary_info.creation_time(),
+        package.summary_info().creation_time()
+    );
+
+    let table_names: Vec<&str> =
+        package2.tables().map(|table| table.name()).collect();
+    assert_eq!(
+        table_names,
+        vec!["Binary", "Property", "_Columns", "_Tables", "_Validation"]
+    );
+    // Rows may come back in a different order, since the tables were
+    // recreated from scratch.
+    for table_name in ["Binary", "Property", "_Validation"] {
+        let mut rows1 = testutil::select_values(&mut package, table_name);
+        let mut rows2 = testutil::select_values(&mut package2, table_name);
+        rows1.sort_by_key(|row| format!("{row:?}"));
+        rows2.sort_by_key(|row| format!("{row:?}"));
+        assert_eq!(rows2, rows1);
+    }
+    let table = package2.get_table("Property").unwrap();
+    assert_eq!(
+        table.get_column("Value").unwrap().description(),
+        Some("The value")
+    );
+
+    let mut streams: Vec<String> = package2.streams().collect();
+    streams.sort();
+    assert_eq!(streams, vec!["Binary.Icon", "data1.cab"]);
+    assert_eq!(read_stream(&mut package2, "Binary.Icon"), b"icon");
+    assert_eq!(read_stream(&mut package2, "data1.cab"), b"cabinet");
+}
+
+#[test]
+fn unpack_rejects_unsafe_file_names() {
+    let dir = testutil::temp_dir("unpack_rejects_unsafe_file_names");
+    let out_dir = dir.join("out");
+    let mut package = testutil::create_package();
+    package.write_stream("..").unwrap().write_all(b"parent").unwrap();
+    assert_error!(
+        package.unpack(&out_dir),
+        ErrorKind::InvalidData,
+        "\"..\" is not a valid file name"
+    );
+    assert!(!out_dir.exists());
+
+    let mut package = testutil::create_package();
+    let columns = vec![
+        Column::build("Name").primary_key().string(72),
+        Column::build("Data").binary(),
+    ];
+    package.create_table("Binary", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("Binary")
+                .row(vec![Value::from("../../escaped"), Value::Binary]),
+        )
+        .unwrap();
+    assert_error!(
+        package.unpack(&out_dir),
+        ErrorKind::InvalidData,
+        "\"../../escaped.ibd\" is not a valid file name"
+    );
+    assert!(!out_dir.exists());
+    assert!(!dir.join("escaped.ibd").exists());
+}
+
+#[test]
+fn pack_embedded_storage() {
+    let dir = testutil::temp_dir("pack_embedded_storage");
+    let mut comp = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+    comp.create_storage("/Inner").unwrap();
+    comp.create_stream("/Inner/Data").unwrap().write_all(b"nested").unwrap();
+    let data = comp.into_inner().into_inner();
+    fs::create_dir_all(dir.join("_Storages")).unwrap();
+    fs::write(dir.join("_Storages").join("Nested"), data).unwrap();
+
+    let cursor = Cursor::new(Vec::new());
+    let mut package =
+        Package::pack(PackageType::Transform, &dir, cursor).unwrap();
+    assert_eq!(package.package_type(), PackageType::Transform);
+    assert_eq!(package.streams().count(), 0);
+
+    let dir2 = testutil::temp_dir("pack_embedded_storage_2");
+    package.unpack(&dir2).unwrap();
+    let data = fs::read(dir2.join("_Storages").join("Nested")).unwrap();
+    let mut comp = cfb::CompoundFile::open(Cursor::new(data)).unwrap();
+    let mut nested = Vec::new();
+    comp.open_stream("/Inner/Data").unwrap().read_to_end(&mut nested).unwrap();
+    assert_eq!(nested, b"nested");
+}
+
+#[test]
+fn pack_invalid_idt_file() {
+    let dir = testutil::temp_dir("pack_invalid_idt_file");
+    fs::write(dir.join("Broken.idt"), "Key\ns72\n").unwrap();
+    let cursor = Cursor::new(Vec::new());
+    assert_error!(
+        Package::pack(PackageType::Installer, &dir, cursor),
+        ErrorKind::InvalidData,
+        "IDT file must start with three header lines"
+    );
+
+    fs::write(
+        dir.join("Broken.idt"),
+        "PropertyId\tValue\ni2\tl255\n_SummaryInformation\tPropertyId\n\
+         12\tyesterday\n",
+    )
+    .unwrap();
+    let cursor = Cursor::new(Vec::new());
+    assert_error!(
+        Package::pack(PackageType::Installer, &dir, cursor),
+        ErrorKind::InvalidData,
+        "Invalid summary info timestamp \"yesterday\" for property 12"
+    );
+}
+
+// ========================================================================= //
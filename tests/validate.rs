@@ -0,0 +1,370 @@
+#[macro_use]
+mod testutil;
+
+use msi::{Column, Insert, Package, Rule, Severity, Value};
+use std::io::Cursor;
+
+// ========================================================================= //
+
+fn create_package_with_tables() -> Package<Cursor<Vec<u8>>> {
+    let mut package = testutil::create_package();
+    package
+        .create_table(
+            "Directory",
+            vec![
+                Column::build("Directory").primary_key().id_string(72),
+                Column::build("Directory_Parent")
+                    .nullable()
+                    .foreign_key("Directory", 1)
+                    .id_string(72),
+                Column::build("DefaultDir").localizable().string(255),
+            ],
+        )
+        .unwrap();
+    package
+        .create_table(
+            "Component",
+            vec![
+                Column::build("Component").primary_key().id_string(72),
+                Column::build("ComponentId")
+                    .nullable()
+                    .category(msi::Category::Guid)
+                    .string(38),
+                Column::build("Directory_")
+                    .foreign_key("Directory", 1)
+                    .id_string(72),
+                Column::build("Attributes").int16(),
+                Column::build("Condition").nullable().string(255),
+                Column::build("KeyPath").nullable().id_string(72),
+            ],
+        )
+        .unwrap();
+    package
+        .create_table(
+            "File",
+            vec![
+                Column::build("File").primary_key().id_string(72),
+                Column::build("Component_")
+                    .foreign_key("Component", 1)
+                    .id_string(72),
+                Column::build("FileName").localizable().string(255),
+                Column::build("FileSize").range(0, 0x7fff_ffff).int32(),
+            ],
+        )
+        .unwrap();
+    package
+        .create_table(
+            "InstallExecuteSequence",
+            vec![
+                Column::build("Action").primary_key().id_string(72),
+                Column::build("Condition").nullable().string(255),
+                Column::build("Sequence").nullable().int16(),
+            ],
+        )
+        .unwrap();
+    package
+        .insert_rows(Insert::into("Directory").row(vec![
+            Value::from("TARGETDIR"),
+            Value::Null,
+            Value::from("SourceDir"),
+        ]))
+        .unwrap();
+    package
+}
+
+fn rules_hit(package: &mut Package<Cursor<Vec<u8>>>) -> Vec<Rule> {
+    package.validate().unwrap().iter().map(|d| d.rule()).collect()
+}
+
+// ========================================================================= //
+
+#[test]
+fn valid_package_has_no_diagnostics() {
+    let mut package = create_package_with_tables();
+    package
+        .insert_rows(Insert::into("Component").row(vec![
+            Value::from("Main"),
+            Value::from("{34AB5C53-9B30-4E14-AEF0-2C1C7BA826C0}"),
+            Value::from("TARGETDIR"),
+            Value::Int(0),
+            Value::Null,
+            Value::from("foo.txt"),
+        ]))
+        .unwrap();
+    package
+        .insert_rows(Insert::into("File").row(vec![
+            Value::from("foo.txt"),
+            Value::from("Main"),
+            Value::from("foo.txt"),
+            Value::Int(123),
+        ]))
+        .unwrap();
+    assert_eq!(package.validate().unwrap(), vec![]);
+}
+
+#[test]
+fn missing_validation_entry() {
+    let mut package = create_package_with_tables();
+    package
+        .delete_rows(
+            msi::Delete::from("_Validation")
+                .with(msi::Expr::col("Table").eq(msi::Expr::string("File")))
+                .with(
+                    msi::Expr::col("Column").eq(msi::Expr::string("FileSize")),
+                ),
+        )
+        .unwrap();
+    let diagnostics = package.validate_rules(&[Rule::Ice06]).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity(), Severity::Error);
+    assert_eq!(diagnostics[0].table(), Some("File"));
+    assert_eq!(diagnostics[0].column(), Some("FileSize"));
+}
+
+#[test]
+fn invalid_foreign_key() {
+    let mut package = create_package_with_tables();
+    package
+        .insert_rows(Insert::into("File").row(vec![
+            Value::from("foo.txt"),
+            Value::from("NoSuchComponent"),
+            Value::from("foo.txt"),
+            Value::Int(123),
+        ]))
+        .unwrap();
+    let diagnostics = package.validate_rules(&[Rule::Ice03]).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].table(), Some("File"));
+    assert_eq!(diagnostics[0].column(), Some("Component_"));
+    assert_eq!(diagnostics[0].primary_key(), &[Value::from("foo.txt")]);
+}
+
+#[test]
+fn duplicate_component_guids_and_bad_key_path() {
+    let mut package = create_package_with_tables();
+    let guid = "{34AB5C53-9B30-4E14-AEF0-2C1C7BA826C0}";
+    package
+        .insert_rows(
+            Insert::into("Component")
+                .row(vec![
+                    Value::from("Alpha"),
+                    Value::from(guid),
+                    Value::from("TARGETDIR"),
+                    Value::Int(0),
+                    Value::Null,
+                    Value::from("beta.txt"),
+                ])
+                .row(vec![
+                    Value::from("Beta"),
+                    Value::from(guid),
+                    Value::from("TARGETDIR"),
+                    Value::Int(0),
+                    Value::Null,
+                    Value::from("beta.txt"),
+                ]),
+        )
+        .unwrap();
+    package
+        .insert_rows(Insert::into("File").row(vec![
+            Value::from("beta.txt"),
+            Value::from("Beta"),
+            Value::from("beta.txt"),
+            Value::Int(0),
+        ]))
+        .unwrap();
+    assert_eq!(rules_hit(&mut package), vec![Rule::Ice02, Rule::Ice08]);
+}
+
+#[test]
+fn orphaned_directory_and_bad_sequence() {
+    let mut package = create_package_with_tables();
+    package
+        .insert_rows(Insert::into("Directory").row(vec![
+            Value::from("Orphan"),
+            Value::from("NoSuchParent"),
+            Value::from("orphan"),
+        ]))
+        .unwrap();
+    package
+        .insert_rows(
+            Insert::into("InstallExecuteSequence")
+                .row(vec![
+                    Value::from("CostInitialize"),
+                    Value::Null,
+                    Value::Int(800),
+                ])
+                .row(vec![
+                    Value::from("CostFinalize"),
+                    Value::Null,
+                    Value::Int(700),
+                ]),
+        )
+        .unwrap();
+    let diagnostics =
+        package.validate_rules(&[Rule::Ice27, Rule::DirectoryTree]).unwrap();
+    let rules: Vec<Rule> = diagnostics.iter().map(|d| d.rule()).collect();
+    assert_eq!(rules, vec![Rule::Ice27, Rule::DirectoryTree]);
+    assert_eq!(diagnostics[1].primary_key(), &[Value::from("Orphan")]);
+}
+
+#[test]
+fn directory_cycles() {
+    let mut package = create_package_with_tables();
+    let directory = |id: &str, parent: &str| {
+        vec![Value::from(id), Value::from(parent), Value::from("dir")]
+    };
+    package
+        .insert_rows(
+            Insert::into("Directory")
+                .row(directory("Alpha", "Beta"))
+                .row(directory("Beta", "Alpha"))
+                .row(directory("Gamma", "Alpha")),
+        )
+        .unwrap();
+    let diagnostics = package.validate_rules(&[Rule::DirectoryTree]).unwrap();
+    let problems: Vec<(Value, &str)> = diagnostics
+        .iter()
+        .map(|d| (d.primary_key()[0].clone(), d.message()))
+        .collect();
+    assert_eq!(
+        problems,
+        vec![
+            (Value::from("Alpha"), "Directory is part of a cycle"),
+            (Value::from("Beta"), "Directory is part of a cycle"),
+            (
+                Value::from("Gamma"),
+                "Directory's parent chain leads into a cycle"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn run_rules_from_cub_file() {
+    let mut package = create_package_with_tables();
+    package
+        .insert_rows(Insert::into("Directory").row(vec![
+            Value::from("Oops"),
+            Value::from("TARGETDIR"),
+            Value::from("oops"),
+        ]))
+        .unwrap();
+    package
+        .delete_rows(
+            msi::Delete::from("_Validation").with(
+                msi::Expr::col("Table").eq(msi::Expr::string("Directory")),
+            ),
+        )
+        .unwrap();
+    // Without any _Validation entries, ICE03 has nothing to check against.
+    assert_eq!(package.validate_rules(&[Rule::Ice03]).unwrap(), vec![]);
+
+    let mut cub = testutil::create_package();
+    cub.create_table(
+        "_ICESequence",
+        vec![
+            Column::build("Action").primary_key().id_string(72),
+            Column::build("Condition").nullable().string(255),
+            Column::build("Sequence").nullable().int16(),
+        ],
+    )
+    .unwrap();
+    cub.create_table(
+        "CustomAction",
+        vec![
+            Column::build("Action").primary_key().id_string(72),
+            Column::build("Type").int16(),
+            Column::build("Source").nullable().string(72),
+            Column::build("Target").nullable().string(255),
+        ],
+    )
+    .unwrap();
+    cub.insert_rows(
+        Insert::into("_ICESequence")
+            .row(vec![Value::from("ICE03"), Value::Null, Value::Int(30)])
+            .row(vec![
+                Value::from("ICE06"),
+                Value::from("ICE_LEVEL >= 2 AND NOT NOICE06"),
+                Value::Int(60),
+            ])
+            .row(vec![Value::from("ICE08"), Value::from("X"), Value::Int(80)])
+            .row(vec![
+                Value::from("ICE27"),
+                Value::from("?Main = 3"),
+                Value::Int(270),
+            ])
+            .row(vec![Value::from("ICE33"), Value::Null, Value::Int(330)])
+            .row(vec![Value::from("ICE99"), Value::Null, Value::Int(990)]),
+    )
+    .unwrap();
+    cub.create_table(
+        "Property",
+        vec![
+            Column::build("Property").primary_key().id_string(72),
+            Column::build("Value").string(0),
+        ],
+    )
+    .unwrap();
+    cub.insert_rows(
+        Insert::into("Property")
+            .row(vec![Value::from("ICE_LEVEL"), Value::from("2")]),
+    )
+    .unwrap();
+    cub.insert_rows(Insert::into("CustomAction").row(vec![
+        Value::from("ICE33"),
+        Value::Int(1),
+        Value::from("darice"),
+        Value::from("ICE33"),
+    ]))
+    .unwrap();
+    cub.insert_rows(Insert::into("_Validation").row(vec![
+        Value::from("Directory"),
+        Value::from("DefaultDir"),
+        Value::from("N"),
+        Value::Null,
+        Value::Null,
+        Value::Null,
+        Value::Null,
+        Value::from("UpperCase"),
+        Value::Null,
+        Value::Null,
+    ]))
+    .unwrap();
+
+    let report = package.validate_rules_from_cub(&mut cub).unwrap();
+    assert_eq!(report.executed(), &[Rule::Ice03, Rule::Ice06]);
+    let skipped: Vec<&str> =
+        report.skipped().iter().map(|skipped| skipped.name()).collect();
+    assert_eq!(skipped, vec!["ICE08", "ICE27", "ICE33", "ICE99"]);
+    let reasons: Vec<&str> =
+        report.skipped().iter().map(|skipped| skipped.reason()).collect();
+    assert_eq!(
+        reasons,
+        vec![
+            "condition \"X\" is false",
+            "condition \"?Main = 3\" can't be evaluated: '?' references (to \
+             environment variables or component or feature states) are not \
+             supported",
+            "requires running a DLL custom action",
+            "no built-in implementation of this rule",
+        ]
+    );
+    let found: Vec<(Rule, Option<&str>, Option<&str>)> = report
+        .diagnostics()
+        .iter()
+        .map(|d| (d.rule(), d.table(), d.column()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (Rule::Ice03, Some("Directory"), Some("DefaultDir")),
+            (Rule::Ice03, Some("Directory"), Some("DefaultDir")),
+            (Rule::Ice06, Some("Directory"), Some("Directory")),
+            (Rule::Ice06, Some("Directory"), Some("Directory_Parent")),
+        ]
+    );
+    // The package itself should be unaffected by the merge.
+    assert!(!package.has_table("_ICESequence"));
+}
+
+// ========================================================================= //
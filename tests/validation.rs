@@ -1,7 +1,10 @@
 #[macro_use]
 mod testutil;
 
-use msi::{Category, Column, Insert, Package, PackageType, Value};
+use msi::{
+    Category, Column, ColumnValidation, Expr, Insert, Package, PackageType,
+    Select, Value,
+};
 use std::io::{Cursor, ErrorKind};
 
 // ========================================================================= //
@@ -109,4 +112,110 @@ fn string_column_enum_values() {
     package.insert_rows(query).unwrap();
 }
 
+#[test]
+fn column_description() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    let columns = vec![
+        Column::build("Key").primary_key().description("The key").int16(),
+        Column::build("Note").nullable().string(0),
+    ];
+    package.create_table("Notes", columns).unwrap();
+
+    let cursor = package.into_inner().unwrap();
+    let mut package = Package::open(cursor).unwrap();
+    {
+        let table = package.get_table("Notes").unwrap();
+        assert_eq!(table.columns()[0].description(), Some("The key"));
+        assert_eq!(table.columns()[1].description(), None);
+    }
+    let query = Select::table("_Validation")
+        .columns(&["Column", "Description"])
+        .with(Expr::col("Table").eq(Expr::string("Notes")));
+    let rows: Vec<(Value, Value)> = package
+        .select_rows(query)
+        .unwrap()
+        .map(|row| (row[0].clone(), row[1].clone()))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (Value::from("Key"), Value::from("The key")),
+            (Value::from("Note"), Value::Null),
+        ]
+    );
+}
+
+#[test]
+fn set_validation_of_existing_column() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    let columns = vec![
+        Column::build("Key").primary_key().int16(),
+        Column::build("Color").description("Paint color").string(16),
+    ];
+    package.create_table("Paint", columns).unwrap();
+
+    let validation = {
+        let table = package.get_table("Paint").unwrap();
+        table.get_column("Color").unwrap().validation()
+    };
+    let validation = validation
+        .category(Some(Category::Identifier))
+        .enum_values(&["Red", "Green", "Blue"]);
+    package.set_validation("Paint", "Color", validation).unwrap();
+
+    let cursor = package.into_inner().unwrap();
+    let mut package = Package::open(cursor).unwrap();
+    {
+        let table = package.get_table("Paint").unwrap();
+        let column = table.get_column("Color").unwrap();
+        assert_eq!(column.category(), Some(Category::Identifier));
+        assert_eq!(column.description(), Some("Paint color"));
+        assert_eq!(column.enum_values().unwrap().len(), 3);
+    }
+    let query =
+        Insert::into("Paint").row(vec![Value::Int(1), Value::from("Pink")]);
+    assert_error!(
+        package.insert_rows(query),
+        ErrorKind::InvalidInput,
+        "\"Pink\" is not a valid value for column \"Color\""
+    );
+    let query =
+        Insert::into("Paint").row(vec![Value::Int(1), Value::from("Red")]);
+    package.insert_rows(query).unwrap();
+
+    // Clearing the validation removes the restrictions.
+    package.set_validation("Paint", "Color", ColumnValidation::new()).unwrap();
+    let query =
+        Insert::into("Paint").row(vec![Value::Int(2), Value::from("Pink")]);
+    package.insert_rows(query).unwrap();
+    let query = Select::table("_Validation")
+        .with(Expr::col("Table").eq(Expr::string("Paint")));
+    assert_eq!(package.select_rows(query).unwrap().len(), 2);
+}
+
+#[test]
+fn set_validation_errors() {
+    let cursor = Cursor::new(Vec::new());
+    let mut package = Package::create(PackageType::Installer, cursor).unwrap();
+    let columns = vec![Column::build("Key").primary_key().int16()];
+    package.create_table("Things", columns).unwrap();
+    assert_error!(
+        package.set_validation("Stuff", "Key", ColumnValidation::new()),
+        ErrorKind::NotFound,
+        "Table \"Stuff\" does not exist"
+    );
+    assert_error!(
+        package.set_validation("Things", "Foo", ColumnValidation::new()),
+        ErrorKind::InvalidInput,
+        "Table \"Things\" has no column named \"Foo\""
+    );
+    assert_error!(
+        package.set_validation("_Validation", "Set", ColumnValidation::new()),
+        ErrorKind::InvalidInput,
+        "Cannot change validation of special \"_Validation\" table"
+    );
+}
+
 // ========================================================================= //
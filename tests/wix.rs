@@ -0,0 +1,375 @@
+#[macro_use]
+mod testutil;
+
+use msi::rows::{
+    ComponentAttributes, ComponentRow, CustomActionRow, CustomActionType,
+    DirectoryRow, FeatureAttributes, FeatureComponentsRow, FeatureRow,
+    FileAttributes, FileRow, MediaRow, PropertyRow, RegistryRow, ShortcutRow,
+};
+use msi::{Column, Insert, Package, Value};
+use std::fs;
+use std::io::{Cursor, Write};
+
+// ========================================================================= //
+
+fn create_widget_package() -> Package<Cursor<Vec<u8>>> {
+    let mut package = testutil::create_package();
+    let summary_info = package.summary_info_mut();
+    summary_info.set_subject("Widget Installer");
+    summary_info.set_author("Widgets & Co");
+    summary_info.set_page_count(500);
+    summary_info.set_word_count(2);
+    for table_name in [
+        "Binary",
+        "Component",
+        "CustomAction",
+        "Directory",
+        "Feature",
+        "FeatureComponents",
+        "File",
+        "InstallExecuteSequence",
+        "Media",
+        "Property",
+        "Registry",
+        "Shortcut",
+    ] {
+        package.create_standard_table(table_name).unwrap();
+    }
+    let property = |name: &str, value: &str| PropertyRow {
+        property: name.to_string(),
+        value: value.to_string(),
+    };
+    package
+        .insert_typed_rows(vec![
+            property("ProductName", "Widget"),
+            property("Manufacturer", "Widgets & Co"),
+            property("ProductVersion", "1.2.3"),
+            property("ProductLanguage", "1033"),
+            property("ProductCode", "{8E2D0F6C-4B1A-4C43-9C4B-2D9C9B7E1F01}"),
+            property("UpgradeCode", "{0A6D4C1E-55B2-4E1F-8E2B-6F3D2C1B0A02}"),
+            property("ALLUSERS", "1"),
+            property("Greeting", "Say \"hi\""),
+        ])
+        .unwrap();
+    let directory =
+        |id: &str, parent: Option<&str>, default_dir: &str| DirectoryRow {
+            directory: id.to_string(),
+            parent: parent.map(str::to_string),
+            default_dir: default_dir.to_string(),
+        };
+    package
+        .insert_typed_rows(vec![
+            directory("TARGETDIR", None, "SourceDir"),
+            directory("ProgramFilesFolder", Some("TARGETDIR"), "PFiles"),
+            directory(
+                "INSTALLDIR",
+                Some("ProgramFilesFolder"),
+                "WIDGET|Widget",
+            ),
+            directory("ProgramMenuFolder", Some("TARGETDIR"), "."),
+        ])
+        .unwrap();
+    package
+        .insert_typed_rows(vec![
+            ComponentRow {
+                component: "MainExe".to_string(),
+                component_id: Some(
+                    "{3F2504E0-4F89-11D3-9A0C-0305E82C3301}".to_string(),
+                ),
+                directory: "INSTALLDIR".to_string(),
+                attributes: ComponentAttributes::WIN64,
+                condition: None,
+                key_path: Some("widget.exe".to_string()),
+            },
+            ComponentRow {
+                component: "Settings".to_string(),
+                component_id: Some(
+                    "{3F2504E0-4F89-11D3-9A0C-0305E82C3302}".to_string(),
+                ),
+                directory: "INSTALLDIR".to_string(),
+                attributes: ComponentAttributes::REGISTRY_KEY_PATH
+                    | ComponentAttributes::PERMANENT,
+                condition: Some("VersionNT >= 600".to_string()),
+                key_path: Some("InstallPath".to_string()),
+            },
+        ])
+        .unwrap();
+    package
+        .insert_typed_rows(vec![FileRow {
+            file: "widget.exe".to_string(),
+            component: "MainExe".to_string(),
+            file_name: "widget.exe".to_string(),
+            file_size: 1024,
+            version: Some("1.2.3.0".to_string()),
+            language: None,
+            attributes: FileAttributes::VITAL | FileAttributes::READ_ONLY,
+            sequence: 1,
+        }])
+        .unwrap();
+    let registry =
+        |id: &str, name: Option<&str>, value: Option<&str>| RegistryRow {
+            registry: id.to_string(),
+            root: 2,
+            key: "Software\\Widget".to_string(),
+            name: name.map(str::to_string),
+            value: value.map(str::to_string),
+            component: "Settings".to_string(),
+        };
+    package
+        .insert_typed_rows(vec![
+            registry("InstallPath", Some("Path"), Some("[INSTALLDIR]")),
+            registry("Count", Some("Count"), Some("#42")),
+            registry("Paths", Some("Paths"), Some("a[~]b")),
+            registry("WidgetKey", Some("*"), None),
+        ])
+        .unwrap();
+    package
+        .insert_typed_rows(vec![ShortcutRow {
+            shortcut: "WidgetShortcut".to_string(),
+            directory: "ProgramMenuFolder".to_string(),
+            name: "WIDGET|Widget".to_string(),
+            component: "MainExe".to_string(),
+            target: "[#widget.exe]".to_string(),
+            arguments: Some("--start".to_string()),
+            description: None,
+            hotkey: None,
+            icon: None,
+            icon_index: None,
+            show_cmd: Some(7),
+            working_dir: Some("INSTALLDIR".to_string()),
+            display_resource_dll: None,
+            display_resource_id: None,
+            description_resource_dll: None,
+            description_resource_id: None,
+        }])
+        .unwrap();
+    package
+        .insert_typed_rows(vec![
+            FeatureRow {
+                feature: "Complete".to_string(),
+                parent: None,
+                title: Some("Widget".to_string()),
+                description: None,
+                display: Some(1),
+                level: 1,
+                directory: Some("INSTALLDIR".to_string()),
+                attributes: FeatureAttributes::UI_DISALLOW_ABSENT,
+            },
+            FeatureRow {
+                feature: "Extras".to_string(),
+                parent: Some("Complete".to_string()),
+                title: None,
+                description: None,
+                display: Some(0),
+                level: 3,
+                directory: None,
+                attributes: FeatureAttributes::empty(),
+            },
+        ])
+        .unwrap();
+    package
+        .insert_typed_rows(vec![
+            FeatureComponentsRow {
+                feature: "Complete".to_string(),
+                component: "MainExe".to_string(),
+            },
+            FeatureComponentsRow {
+                feature: "Extras".to_string(),
+                component: "Settings".to_string(),
+            },
+        ])
+        .unwrap();
+    package
+        .insert_typed_rows(vec![
+            CustomActionRow {
+                action: "CheckLicense".to_string(),
+                action_type: CustomActionType::from_bits(1)
+                    | CustomActionType::CONTINUE,
+                source: Some("Helper".to_string()),
+                target: Some("CheckLicense".to_string()),
+                extended_type: None,
+            },
+            CustomActionRow {
+                action: "Cleanup".to_string(),
+                action_type: CustomActionType::from_bits(6 | 0x20)
+                    | CustomActionType::IN_SCRIPT
+                    | CustomActionType::NO_IMPERSONATE,
+                source: None,
+                target: Some("If a < b Then\nEnd If".to_string()),
+                extended_type: None,
+            },
+        ])
+        .unwrap();
+    package
+        .insert_rows(
+            Insert::into("InstallExecuteSequence")
+                .row(vec![
+                    Value::from("InstallFinalize"),
+                    Value::Null,
+                    Value::Int(6600),
+                ])
+                .row(vec![
+                    Value::from("CheckLicense"),
+                    Value::from("NOT Installed"),
+                    Value::Int(1100),
+                ])
+                .row(vec![
+                    Value::from("CostInitialize"),
+                    Value::Null,
+                    Value::Int(800),
+                ]),
+        )
+        .unwrap();
+    package
+        .insert_rows(
+            Insert::into("Binary")
+                .row(vec![Value::from("Helper"), Value::Binary]),
+        )
+        .unwrap();
+    package.write_stream("Binary.Helper").unwrap().write_all(b"dll").unwrap();
+    package
+        .insert_typed_rows(vec![MediaRow {
+            disk_id: 1,
+            last_sequence: 1,
+            disk_prompt: None,
+            cabinet: Some("#data1.cab".to_string()),
+            volume_label: None,
+            source: None,
+        }])
+        .unwrap();
+    package.write_stream("data1.cab").unwrap().write_all(b"MSCF").unwrap();
+    package
+}
+
+// ========================================================================= //
+
+#[test]
+fn decompile_wix() {
+    let dir = testutil::temp_dir("decompile_wix");
+    let mut package = create_widget_package();
+    let path = package.decompile_wix(&dir).unwrap();
+    assert_eq!(path, dir.join("Package.wxs"));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <Wix xmlns=\"http://wixtoolset.org/schemas/v4/wxs\">\n\
+         \x20 <Package Name=\"Widget\" Manufacturer=\"Widgets &amp; Co\" \
+         Version=\"1.2.3\" Language=\"1033\" \
+         ProductCode=\"{8E2D0F6C-4B1A-4C43-9C4B-2D9C9B7E1F01}\" \
+         UpgradeCode=\"{0A6D4C1E-55B2-4E1F-8E2B-6F3D2C1B0A02}\" \
+         Codepage=\"65001\" InstallerVersion=\"500\" Compressed=\"yes\" \
+         Scope=\"perMachine\">\n\
+         \x20   <SummaryInformation Description=\"Widget Installer\" \
+         Manufacturer=\"Widgets &amp; Co\" />\n\
+         \x20   <Media Id=\"1\" Cabinet=\"data1.cab\" EmbedCab=\"yes\" />\n\
+         \x20   <Property Id=\"Greeting\" Value=\"Say &quot;hi&quot;\" />\n\
+         \x20   <Binary Id=\"Helper\" SourceFile=\"Binary\\Helper\" />\n\
+         \x20   <StandardDirectory Id=\"ProgramFilesFolder\">\n\
+         \x20     <Directory Id=\"INSTALLDIR\" Name=\"Widget\">\n\
+         \x20       <Component Id=\"MainExe\" \
+         Guid=\"{3F2504E0-4F89-11D3-9A0C-0305E82C3301}\" \
+         Bitness=\"always64\">\n\
+         \x20         <File Id=\"widget.exe\" Name=\"widget.exe\" \
+         Source=\"File\\widget.exe\" KeyPath=\"yes\" ReadOnly=\"yes\" />\n\
+         \x20         <Shortcut Id=\"WidgetShortcut\" \
+         Directory=\"ProgramMenuFolder\" Name=\"Widget\" \
+         Target=\"[#widget.exe]\" Arguments=\"--start\" \
+         Show=\"minimized\" WorkingDirectory=\"INSTALLDIR\" />\n\
+         \x20       </Component>\n\
+         \x20       <Component Id=\"Settings\" \
+         Guid=\"{3F2504E0-4F89-11D3-9A0C-0305E82C3302}\" \
+         Condition=\"VersionNT &gt;= 600\" Permanent=\"yes\">\n\
+         \x20         <RegistryValue Id=\"InstallPath\" Root=\"HKLM\" \
+         Key=\"Software\\Widget\" Name=\"Path\" Type=\"string\" \
+         KeyPath=\"yes\" Value=\"[INSTALLDIR]\" />\n\
+         \x20         <RegistryValue Id=\"Count\" Root=\"HKLM\" \
+         Key=\"Software\\Widget\" Name=\"Count\" Type=\"integer\" \
+         Value=\"42\" />\n\
+         \x20         <RegistryValue Id=\"Paths\" Root=\"HKLM\" \
+         Key=\"Software\\Widget\" Name=\"Paths\" \
+         Type=\"multiString\">\n\
+         \x20           <MultiStringValue Value=\"a\" />\n\
+         \x20           <MultiStringValue Value=\"b\" />\n\
+         \x20         </RegistryValue>\n\
+         \x20         <RegistryKey Id=\"WidgetKey\" Root=\"HKLM\" \
+         Key=\"Software\\Widget\" ForceCreateOnInstall=\"yes\" \
+         ForceDeleteOnUninstall=\"yes\" />\n\
+         \x20       </Component>\n\
+         \x20     </Directory>\n\
+         \x20   </StandardDirectory>\n\
+         \x20   <StandardDirectory Id=\"ProgramMenuFolder\" />\n\
+         \x20   <Feature Id=\"Complete\" Title=\"Widget\" Display=\"1\" \
+         Level=\"1\" ConfigurableDirectory=\"INSTALLDIR\" \
+         AllowAbsent=\"no\">\n\
+         \x20     <ComponentRef Id=\"MainExe\" />\n\
+         \x20     <Feature Id=\"Extras\" Display=\"hidden\" Level=\"3\">\n\
+         \x20       <ComponentRef Id=\"Settings\" />\n\
+         \x20     </Feature>\n\
+         \x20   </Feature>\n\
+         \x20   <CustomAction Id=\"CheckLicense\" BinaryRef=\"Helper\" \
+         DllEntry=\"CheckLicense\" Return=\"ignore\" />\n\
+         \x20   <CustomAction Id=\"Cleanup\" Script=\"vbscript\" \
+         Execute=\"deferred\" Impersonate=\"no\">If a &lt; b Then\n\
+         End If</CustomAction>\n\
+         \x20   <InstallExecuteSequence>\n\
+         \x20     <CostInitialize Sequence=\"800\" />\n\
+         \x20     <Custom Action=\"CheckLicense\" Sequence=\"1100\" \
+         Condition=\"NOT Installed\" />\n\
+         \x20     <InstallFinalize Sequence=\"6600\" />\n\
+         \x20   </InstallExecuteSequence>\n\
+         \x20 </Package>\n\
+         </Wix>\n"
+    );
+    assert_eq!(fs::read(dir.join("Binary").join("Helper")).unwrap(), b"dll");
+    assert_eq!(fs::read(dir.join("data1.cab")).unwrap(), b"MSCF");
+}
+
+#[test]
+fn decompile_unusual_action_names() {
+    let dir = testutil::temp_dir("decompile_unusual_action_names");
+    let mut package = testutil::create_package();
+    let columns = vec![
+        Column::build("Action").primary_key().string(72),
+        Column::build("Condition").nullable().string(255),
+        Column::build("Sequence").nullable().int16(),
+    ];
+    package.create_table("InstallExecuteSequence", columns).unwrap();
+    package
+        .insert_rows(
+            Insert::into("InstallExecuteSequence")
+                .row(vec![Value::from("x---y z"), Value::Null, Value::Int(1)])
+                .row(vec![Value::from("Fine"), Value::Null, Value::Int(2)]),
+        )
+        .unwrap();
+    let path = package.decompile_wix(&dir).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <Wix xmlns=\"http://wixtoolset.org/schemas/v4/wxs\">\n\
+         \x20 <Package Codepage=\"65001\" Compressed=\"no\" \
+         Scope=\"perUser\">\n\
+         \x20   <InstallExecuteSequence>\n\
+         \x20     <!-- Action \"x- - -y z\" is not a valid element name -->\n\
+         \x20     <Fine Sequence=\"2\" />\n\
+         \x20   </InstallExecuteSequence>\n\
+         \x20 </Package>\n\
+         </Wix>\n"
+    );
+}
+
+#[test]
+fn decompile_empty_package() {
+    let dir = testutil::temp_dir("decompile_empty_package");
+    let mut package = testutil::create_package();
+    let path = package.decompile_wix(&dir).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <Wix xmlns=\"http://wixtoolset.org/schemas/v4/wxs\">\n\
+         \x20 <Package Codepage=\"65001\" Compressed=\"no\" \
+         Scope=\"perUser\" />\n\
+         </Wix>\n"
+    );
+}
+
+// ========================================================================= //